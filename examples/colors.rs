@@ -1,4 +1,4 @@
-use core::iter::repeat;
+use core::iter::repeat_n;
 use tyrminell::*;
 
 fn main() {
@@ -18,8 +18,8 @@ fn main() {
 	plainln!();
 
 	for range in
-		repeat(0..36u8)
-			.take(6).enumerate()
+		repeat_n(0..36u8, 6)
+			.enumerate()
 			.map(move |(which, mut range)| {
 				let offset = 16 + (which as u8) * 36u8;
 				range.start += offset;
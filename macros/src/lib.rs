@@ -0,0 +1,258 @@
+//! Procedural macros for `tyrminell`, which are re-exported by it with the
+//! `macros` feature.
+
+use proc_macro::{
+	Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream,
+	TokenTree,
+};
+
+/// Format string with style placeholders, which are turned into escape
+/// sequences at compile time.
+/// 
+/// The template is a format string like that of [`format_args!`], followed
+/// by the arguments for its placeholders. Style placeholders are replaced
+/// with SGR sequences, and consecutive ones are combined into one sequence,
+/// so only the arguments are formatted at runtime. Without any other
+/// placeholders or arguments, this expands to a `&'static str`, and otherwise
+/// to [`format_args!`].
+/// 
+/// These style placeholders are recognized, and take precedence over
+/// captured variables with the same name:
+/// - `{reset}`, `{bold}`, `{thin}` and `{regular}`.
+/// - `{italic}` and `{no_italic}`.
+/// - `{underline}`, `{double_underline}` and `{no_underline}`.
+/// - `{invert}` and `{no_invert}`.
+/// - `{strikethrough}` and `{no_strikethrough}`.
+/// - `{fg:COLOR}` and `{bg:COLOR}`, where `COLOR` is `default`, one of the
+///   names of the 8 basic colors (such as `red`), optionally prefixed with
+///   `bright_`, an index into the 256-color table, or `#rrggbb`.
+#[proc_macro]
+pub fn ansi_template(input: TokenStream) -> TokenStream {
+	match expand(input) {
+		Ok(output) => output,
+		Err((message, span)) => compile_error(&message, span),
+	}
+}
+
+type Error = (String, Span);
+
+fn expand(input: TokenStream) -> Result<TokenStream, Error> {
+	let mut tokens = input.into_iter();
+	let Some(TokenTree::Literal(literal)) = tokens.next() else {
+		return Err(("expected a string literal".into(), Span::call_site()))
+	};
+	let span = literal.span();
+	let template = parse_str_literal(&literal.to_string())
+		.ok_or_else(|| ("expected a string literal".into(), span))?;
+	let args: TokenStream = tokens.collect();
+
+	let (format, has_placeholders) = expand_template(&template)
+		.map_err(|message| (message, span))?;
+
+	if args.is_empty() && !has_placeholders {
+		// Nothing is formatted at runtime, so this can be a `&'static str`.
+		let text = format.replace("{{", "{").replace("}}", "}");
+		let mut literal = Literal::string(&text);
+		literal.set_span(span);
+		return Ok(TokenTree::Literal(literal).into())
+	}
+
+	let mut literal = Literal::string(&format);
+	literal.set_span(span);
+	let mut inner = TokenStream::from(TokenTree::Literal(literal));
+	inner.extend(args);
+	Ok(path(&["core", "format_args"]).into_iter()
+		.chain([
+			TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+			TokenTree::Group(Group::new(Delimiter::Parenthesis, inner)),
+		])
+		.collect())
+}
+
+/// Replace the style placeholders in `template` with escape sequences,
+/// returning the format string and whether any other placeholders are left.
+fn expand_template(template: &str) -> Result<(String, bool), String> {
+	let mut format = String::new();
+	let mut has_placeholders = false;
+	// Parameters of the SGR sequence for consecutive style placeholders.
+	let mut params = String::new();
+	let mut rest = template;
+	while let Some(idx) = rest.find(['{', '}']) {
+		let (text, tail) = rest.split_at(idx);
+		if !text.is_empty() {
+			flush_sgr(&mut format, &mut params);
+			format.push_str(text);
+		}
+		if tail.starts_with("{{") || tail.starts_with("}}") {
+			flush_sgr(&mut format, &mut params);
+			format.push_str(&tail[..2]);
+			rest = &tail[2..];
+			continue
+		}
+		if tail.starts_with('}') {
+			return Err("unmatched `}` in template".into())
+		}
+		let end = tail.find('}').ok_or("unmatched `{` in template")?;
+		let placeholder = &tail[1..end];
+		rest = &tail[end + 1..];
+		match style_params(placeholder)? {
+			Some(style) => {
+				if !params.is_empty() {
+					params.push(';');
+				}
+				params.push_str(&style);
+			}
+			None => {
+				flush_sgr(&mut format, &mut params);
+				format.push('{');
+				format.push_str(placeholder);
+				format.push('}');
+				has_placeholders = true;
+			}
+		}
+	}
+	flush_sgr(&mut format, &mut params);
+	format.push_str(rest);
+	Ok((format, has_placeholders))
+}
+
+/// Write an SGR sequence with `params` to `format`, if there are any.
+fn flush_sgr(format: &mut String, params: &mut String) {
+	if !params.is_empty() {
+		format.push_str("\x1b[");
+		format.push_str(params);
+		format.push('m');
+		params.clear();
+	}
+}
+
+/// Return the SGR parameters of the style placeholder `placeholder`, or
+/// `None` if it isn't a style placeholder.
+fn style_params(placeholder: &str) -> Result<Option<String>, String> {
+	let (name, arg) = match placeholder.split_once(':') {
+		Some((name, arg)) => (name.trim(), Some(arg.trim())),
+		None => (placeholder.trim(), None),
+	};
+	let param = match (name, arg) {
+		("reset", None) => "0",
+		("bold", None) => "1",
+		("thin", None) => "2",
+		("regular", None) => "22",
+		("italic", None) => "3",
+		("no_italic", None) => "23",
+		("underline", None) => "4",
+		("double_underline", None) => "21",
+		("no_underline", None) => "24",
+		("invert", None) => "7",
+		("no_invert", None) => "27",
+		("strikethrough", None) => "9",
+		("no_strikethrough", None) => "29",
+		("fg", Some(color)) => return color_params(color, 30).map(Some),
+		("bg", Some(color)) => return color_params(color, 40).map(Some),
+		_ => return Ok(None),
+	};
+	Ok(Some(param.into()))
+}
+
+/// Return the SGR parameters that set a color, where `base` is `30` for the
+/// foreground and `40` for the background.
+fn color_params(color: &str, base: u8) -> Result<String, String> {
+	const NAMES: [&str; 8] = [
+		"black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+	];
+	if color == "default" {
+		return Ok((base + 9).to_string())
+	}
+	if let Some(idx) = NAMES.iter().position(|&name| name == color) {
+		return Ok((base + idx as u8).to_string())
+	}
+	let bright = color.strip_prefix("bright_")
+		.and_then(|color| NAMES.iter().position(|&name| name == color));
+	if let Some(idx) = bright {
+		return Ok((base + 60 + idx as u8).to_string())
+	}
+	if let Ok(index) = color.parse::<u8>() {
+		return Ok(format!("{};5;{index}", base + 8))
+	}
+	let rgb = color.strip_prefix('#')
+		.filter(|hex| hex.len() == 6 && hex.is_ascii())
+		.and_then(|hex| {
+			let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+			Some((channel(0)?, channel(2)?, channel(4)?))
+		});
+	match rgb {
+		Some((r, g, b)) => Ok(format!("{};2;{r};{g};{b}", base + 8)),
+		None => Err(format!("unknown color `{color}`")),
+	}
+}
+
+/// Return the value of the string literal `source`, which may be raw.
+fn parse_str_literal(source: &str) -> Option<String> {
+	if let Some(raw) = source.strip_prefix('r') {
+		let hashes = raw.len() - raw.trim_start_matches('#').len();
+		let inner = raw.get(hashes..raw.len() - hashes)?;
+		return Some(inner.strip_prefix('"')?.strip_suffix('"')?.into())
+	}
+	let inner = source.strip_prefix('"')?.strip_suffix('"')?;
+	let mut value = String::with_capacity(inner.len());
+	let mut chars = inner.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			value.push(c);
+			continue
+		}
+		match chars.next()? {
+			'n' => value.push('\n'),
+			'r' => value.push('\r'),
+			't' => value.push('\t'),
+			'0' => value.push('\0'),
+			'\\' => value.push('\\'),
+			'\'' => value.push('\''),
+			'"' => value.push('"'),
+			'x' => {
+				let hex: String = chars.by_ref().take(2).collect();
+				value.push(u8::from_str_radix(&hex, 16).ok()? as char);
+			}
+			'u' => {
+				let rest = chars.as_str().strip_prefix('{')?;
+				let end = rest.find('}')?;
+				let code = u32::from_str_radix(&rest[..end].replace('_', ""), 16).ok()?;
+				value.push(char::from_u32(code)?);
+				chars = rest[end + 1..].chars();
+			}
+			'\n' => {
+				// A line continuation skips the whitespace at the start of the
+				// next line.
+				chars = chars.as_str().trim_start().chars();
+			}
+			_ => return None,
+		}
+	}
+	Some(value)
+}
+
+/// Return the tokens of the absolute path made of `segments`.
+fn path(segments: &[&str]) -> TokenStream {
+	segments.iter()
+		.flat_map(|segment| [
+			TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+			TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+			TokenTree::Ident(Ident::new(segment, Span::call_site())),
+		])
+		.collect()
+}
+
+/// Return the tokens of a `compile_error!` invocation with `message`.
+fn compile_error(message: &str, span: Span) -> TokenStream {
+	let mut literal = Literal::string(message);
+	literal.set_span(span);
+	path(&["core", "compile_error"]).into_iter()
+		.chain([
+			TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+			TokenTree::Group(Group::new(
+				Delimiter::Parenthesis,
+				TokenTree::Literal(literal).into(),
+			)),
+		])
+		.collect()
+}
@@ -0,0 +1,20 @@
+use core::fmt;
+
+/// Item that can be written to any [`fmt::Write`] through one function that
+/// isn't generic over the writer.
+/// 
+/// Every [`Display`](fmt::Display)able item implements this. Writing items
+/// through [`Self::write_ansi_dyn`] compiles the code that writes them once,
+/// instead of once for every type of writer, which keeps binaries small when
+/// several types of writers are used.
+pub trait WriteAnsi {
+	/// Write this item to `w`.
+	fn write_ansi_dyn(&self, w: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+impl<T: fmt::Display + ?Sized> WriteAnsi for T {
+	#[inline(always)]
+	fn write_ansi_dyn(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+		write!(w, "{self}")
+	}
+}
@@ -0,0 +1,102 @@
+use core::fmt::{
+	Result as FmtResult, Write,
+};
+
+/// Characters of the standard base64 alphabet, by value.
+const ALPHABET: &[u8; 64] =
+	b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Streaming encoder of the standard base64 encoding, with padding.
+/// 
+/// Bytes are encoded as they are written, keeping only the up to 2 bytes that
+/// don't form a complete group yet, so payloads of any size can be written
+/// without buffering them.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Base64Encoder {
+	pending: [u8; 3],
+	pending_len: u8,
+}
+
+impl Base64Encoder {
+	/// Number of encoded bytes that are written at once.
+	const BLOCK_LEN: usize = 64;
+
+	/// Create an encoder with no pending bytes.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			pending: [0; 3],
+			pending_len: 0,
+		}
+	}
+
+	/// Return the length of `len` bytes after encoding them, with padding.
+	#[inline(always)]
+	pub const fn encoded_len(len: usize) -> usize {
+		len.div_ceil(3) * 4
+	}
+
+	/// Return the largest number of bytes whose encoding, with padding, is at
+	/// most `encoded_len` bytes long.
+	#[inline(always)]
+	pub const fn max_input_len(encoded_len: usize) -> usize {
+		encoded_len / 4 * 3
+	}
+
+	/// Encode `bytes` to `w`.
+	/// 
+	/// Bytes that don't form a complete group of 3 are kept until more bytes
+	/// are written or [`Self::finish`] is called.
+	pub fn encode(&mut self, bytes: &[u8], w: &mut (impl Write + ?Sized)) -> FmtResult {
+		let mut block = [0; Self::BLOCK_LEN];
+		let mut len = 0;
+		for &byte in bytes {
+			self.pending[self.pending_len as usize] = byte;
+			self.pending_len += 1;
+			if self.pending_len == 3 {
+				block[len..len + 4].copy_from_slice(&encode_group(self.pending));
+				len += 4;
+				self.pending_len = 0;
+				if len == block.len() {
+					write_block(w, &block)?;
+					len = 0;
+				}
+			}
+		}
+		write_block(w, &block[..len])
+	}
+
+	/// Encode the pending bytes to `w` with padding, leaving the encoder
+	/// ready for a new payload.
+	pub fn finish(&mut self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		let len = self.pending_len as usize;
+		if len == 0 {
+			return Ok(())
+		}
+		self.pending[len..].fill(0);
+		self.pending_len = 0;
+		let mut group = encode_group(self.pending);
+		group[len + 1..].fill(b'=');
+		write_block(w, &group)
+	}
+}
+
+/// Encode a group of 3 bytes as 4 characters.
+#[inline(always)]
+const fn encode_group([a, b, c]: [u8; 3]) -> [u8; 4] {
+	let n = (a as u32) << 16 | (b as u32) << 8 | c as u32;
+	[
+		ALPHABET[(n >> 18) as usize & 0x3f],
+		ALPHABET[(n >> 12) as usize & 0x3f],
+		ALPHABET[(n >> 6) as usize & 0x3f],
+		ALPHABET[n as usize & 0x3f],
+	]
+}
+
+/// Write encoded characters to `w`.
+#[inline(always)]
+fn write_block(w: &mut (impl Write + ?Sized), block: &[u8]) -> FmtResult {
+	// SAFETY: `block` only contains characters of `ALPHABET` and `=`, which
+	// are ASCII.
+	w.write_str(unsafe { core::str::from_utf8_unchecked(block) })
+}
@@ -0,0 +1,430 @@
+use crate::key::{
+	KeyCode, KeyEvent, KeypadKey, MediaKey, Modifiers,
+};
+
+use core::{
+	fmt,
+	str::FromStr,
+	time::Duration,
+};
+
+/// Error that can occur when parsing a [`KeyBinding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBindingError {
+	/// The binding has no keys, or a chord has no key.
+	Empty,
+	/// A chord has a modifier that isn't known.
+	UnknownModifier,
+	/// A chord has a key that isn't known.
+	UnknownKey,
+	/// The binding has more than [`KeyBinding::MAX_KEYS`] keys.
+	TooManyKeys,
+}
+
+impl fmt::Display for KeyBindingError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Empty => "empty key binding",
+			Self::UnknownModifier => "unknown modifier",
+			Self::UnknownKey => "unknown key",
+			Self::TooManyKeys => "too many keys in key binding",
+		})
+	}
+}
+
+/// Return the modifier with the specified name, ignoring case.
+fn parse_modifier(name: &str) -> Option<Modifiers> {
+	const NAMES: [(&str, Modifiers); 10] = [
+		("ctrl", Modifiers::CTRL),
+		("control", Modifiers::CTRL),
+		("alt", Modifiers::ALT),
+		("option", Modifiers::ALT),
+		("shift", Modifiers::SHIFT),
+		("super", Modifiers::SUPER),
+		("cmd", Modifiers::SUPER),
+		("win", Modifiers::SUPER),
+		("hyper", Modifiers::HYPER),
+		("meta", Modifiers::META),
+	];
+	NAMES.iter()
+		.find(move |(n, _)| n.eq_ignore_ascii_case(name))
+		.map(move |&(_, modifier)| modifier)
+}
+
+/// Strip `prefix` from the start of `s`, ignoring case.
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+	let head = s.get(..prefix.len())?;
+	head.eq_ignore_ascii_case(prefix).then(|| &s[prefix.len()..])
+}
+
+impl FromStr for KeyCode {
+	type Err = KeyBindingError;
+
+	/// Parse the name of a key, as written by [`Display`](fmt::Display).
+	/// 
+	/// Names are case-insensitive, except for single characters. Some common
+	/// alternative names, such as `Escape` and `PgUp`, are accepted too.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut chars = s.chars();
+		if let (Some(c), None) = (chars.next(), chars.next()) {
+			return Ok(Self::Char(c))
+		}
+
+		const NAMES: [(&str, KeyCode); 27] = [
+			("space", KeyCode::Char(' ')),
+			("enter", KeyCode::Enter),
+			("return", KeyCode::Enter),
+			("tab", KeyCode::Tab),
+			("backspace", KeyCode::Backspace),
+			("esc", KeyCode::Escape),
+			("escape", KeyCode::Escape),
+			("up", KeyCode::Up),
+			("down", KeyCode::Down),
+			("left", KeyCode::Left),
+			("right", KeyCode::Right),
+			("home", KeyCode::Home),
+			("end", KeyCode::End),
+			("pageup", KeyCode::PageUp),
+			("pgup", KeyCode::PageUp),
+			("pagedown", KeyCode::PageDown),
+			("pgdn", KeyCode::PageDown),
+			("insert", KeyCode::Insert),
+			("ins", KeyCode::Insert),
+			("delete", KeyCode::Delete),
+			("del", KeyCode::Delete),
+			("capslock", KeyCode::CapsLock),
+			("scrolllock", KeyCode::ScrollLock),
+			("numlock", KeyCode::NumLock),
+			("printscreen", KeyCode::PrintScreen),
+			("pause", KeyCode::Pause),
+			("menu", KeyCode::Menu),
+		];
+		if let Some(&(_, code)) = NAMES.iter()
+			.find(move |(name, _)| name.eq_ignore_ascii_case(s))
+		{
+			return Ok(code)
+		}
+
+		if let Some(n) = strip_prefix_ignore_case(s, "f")
+			.and_then(|n| n.parse::<u8>().ok())
+			.filter(|n| (1..=35).contains(n))
+		{
+			return Ok(Self::F(n))
+		}
+		if let Some(name) = strip_prefix_ignore_case(s, "keypad") {
+			if let Ok(n @ 0..=9) = name.parse::<u8>() {
+				return Ok(Self::Keypad(KeypadKey::Digit(n)))
+			}
+			return KeypadKey::OTHERS.iter()
+				.find(move |key| key.name().eq_ignore_ascii_case(name))
+				.map(move |&key| Self::Keypad(key))
+				.ok_or(KeyBindingError::UnknownKey)
+		}
+		if let Some(name) = strip_prefix_ignore_case(s, "media") {
+			return MediaKey::ALL.iter()
+				.find(move |key| key.name().eq_ignore_ascii_case(name))
+				.map(move |&key| Self::Media(key))
+				.ok_or(KeyBindingError::UnknownKey)
+		}
+		Err(KeyBindingError::UnknownKey)
+	}
+}
+
+impl FromStr for KeyEvent {
+	type Err = KeyBindingError;
+
+	/// Parse a chord of modifiers and a key separated by `+`, such as
+	/// `Ctrl+Shift+F5`, as written by [`Display`](fmt::Display).
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		// The key itself may be `+`.
+		let (modifiers, key) = match s.strip_suffix("++") {
+			Some(modifiers) => (Some(modifiers), "+"),
+			None if s == "+" => (None, "+"),
+			None => match s.rsplit_once('+') {
+				Some((modifiers, key)) => (Some(modifiers), key),
+				None => (None, s),
+			},
+		};
+		if key.is_empty() {
+			return Err(KeyBindingError::Empty)
+		}
+
+		let mut event = KeyEvent::new(key.parse()?);
+		for name in modifiers.into_iter().flat_map(move |m| m.split('+')) {
+			event.modifiers |= parse_modifier(name)
+				.ok_or(KeyBindingError::UnknownModifier)?;
+		}
+		Ok(event)
+	}
+}
+
+impl KeyEvent {
+	/// Return `self` in a form that can be compared with other key events,
+	/// regardless of the state of Caps Lock and Num Lock, and of whether
+	/// Shift is reported as a modifier or as an uppercase letter.
+	pub fn normalized(self) -> Self {
+		let mut modifiers = self.modifiers
+			& !(Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK);
+		let code = match self.code {
+			KeyCode::Char(c) if c.is_ascii_uppercase() => {
+				modifiers |= Modifiers::SHIFT;
+				KeyCode::Char(c.to_ascii_lowercase())
+			}
+			code => code,
+		};
+		Self {
+			code,
+			modifiers,
+		}
+	}
+}
+
+/// Sequence of one or more key chords, such as `ctrl+x ctrl+s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+	keys: [KeyEvent; Self::MAX_KEYS],
+	len: u8,
+}
+
+impl KeyBinding {
+	/// Maximum number of chords in a binding.
+	pub const MAX_KEYS: usize = 4;
+
+	/// Create a binding for a single chord.
+	#[inline(always)]
+	pub const fn new(key: KeyEvent) -> Self {
+		Self {
+			keys: [key; Self::MAX_KEYS],
+			len: 1,
+		}
+	}
+
+	/// Create a binding from chords, which must not be empty or have more
+	/// than [`Self::MAX_KEYS`] chords.
+	pub fn from_keys(keys: &[KeyEvent]) -> Option<Self> {
+		let (&first, ..) = keys.split_first()?;
+		let mut binding = Self::new(first);
+		binding.keys.get_mut(..keys.len())?.copy_from_slice(keys);
+		binding.len = keys.len() as u8;
+		Some(binding)
+	}
+
+	/// Return the chords of the binding.
+	#[inline(always)]
+	pub fn keys(&self) -> &[KeyEvent] {
+		&self.keys[..self.len as usize]
+	}
+
+	/// Return `true` if `keys` are the first chords of the binding.
+	pub fn starts_with(&self, keys: &[KeyEvent]) -> bool {
+		keys.len() <= self.len as usize
+			&& self.keys().iter().zip(keys)
+				.all(move |(a, b)| a.normalized() == b.normalized())
+	}
+
+	/// Return `true` if `keys` are exactly the chords of the binding.
+	#[inline(always)]
+	pub fn matches(&self, keys: &[KeyEvent]) -> bool {
+		keys.len() == self.len as usize && self.starts_with(keys)
+	}
+}
+
+impl FromStr for KeyBinding {
+	type Err = KeyBindingError;
+
+	/// Parse chords separated by whitespace, such as `ctrl+x ctrl+s`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut keys = [KeyEvent::new(KeyCode::Escape); Self::MAX_KEYS];
+		let mut len = 0;
+		for chord in s.split_whitespace() {
+			let slot = keys.get_mut(len).ok_or(KeyBindingError::TooManyKeys)?;
+			*slot = chord.parse()?;
+			len += 1;
+		}
+		Self::from_keys(&keys[..len]).ok_or(KeyBindingError::Empty)
+	}
+}
+
+impl fmt::Display for KeyBinding {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (idx, key) in self.keys().iter().enumerate() {
+			if idx > 0 {
+				f.write_str(" ")?;
+			}
+			key.fmt(f)?;
+		}
+		Ok(())
+	}
+}
+
+/// Result of feeding a key to a [`KeyMatcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMatch {
+	/// The binding at this index was completed.
+	Matched(usize),
+	/// The keys so far are the start of at least one binding.
+	Pending,
+	/// The keys don't match any binding.
+	NoMatch,
+}
+
+/// Matcher of incoming key events against a set of [`KeyBinding`]s.
+/// 
+/// Keys of a multi-key binding must be pressed within a timeout of each other.
+/// Times are passed by the caller as the time since an arbitrary point, such as
+/// the start of the program, so that no clock is needed.
+#[derive(Debug, Clone)]
+pub struct KeyMatcher<'a> {
+	bindings: &'a [KeyBinding],
+	timeout: Duration,
+	pending: [KeyEvent; KeyBinding::MAX_KEYS],
+	len: usize,
+	last: Duration,
+}
+
+impl<'a> KeyMatcher<'a> {
+	/// Create a matcher for `bindings`, which waits at most `timeout` between
+	/// keys of a multi-key binding.
+	#[inline(always)]
+	pub const fn new(bindings: &'a [KeyBinding], timeout: Duration) -> Self {
+		Self {
+			bindings,
+			timeout,
+			pending: [KeyEvent::new(KeyCode::Escape); KeyBinding::MAX_KEYS],
+			len: 0,
+			last: Duration::ZERO,
+		}
+	}
+
+	/// Return the keys that were pressed so far as part of a binding.
+	#[inline(always)]
+	pub fn pending(&self) -> &[KeyEvent] {
+		&self.pending[..self.len]
+	}
+
+	/// Feed a key that was pressed at time `now`.
+	/// 
+	/// If the keys so far match a binding that is also the start of a longer
+	/// binding, then [`KeyMatch::Pending`] is returned, and [`Self::poll`]
+	/// reports the match after the timeout. If the next key doesn't continue
+	/// the longer binding, then the match is returned instead, and the key is
+	/// kept as the start of the next binding.
+	pub fn feed(&mut self, key: KeyEvent, now: Duration) -> KeyMatch {
+		if self.is_expired(now) || self.len == KeyBinding::MAX_KEYS {
+			self.len = 0;
+		}
+		let previous_len = self.len;
+		let previous_match = self.exact_match();
+		self.pending[self.len] = key;
+		self.len += 1;
+		self.last = now;
+
+		let result = self.check();
+		if result != KeyMatch::NoMatch || previous_len == 0 {
+			return result
+		}
+		// The key may be the start of another binding.
+		self.pending[0] = key;
+		self.len = 1;
+		match previous_match {
+			Some(idx) => {
+				let keys = self.pending();
+				if !self.bindings.iter().any(move |binding| binding.starts_with(keys)) {
+					self.len = 0;
+				}
+				KeyMatch::Matched(idx)
+			}
+			None => self.check(),
+		}
+	}
+
+	/// Resolve the pending keys if the timeout has passed at time `now`.
+	/// 
+	/// This returns [`KeyMatch::Matched`] if the pending keys match a binding
+	/// exactly, [`KeyMatch::NoMatch`] if they don't, and [`KeyMatch::Pending`]
+	/// if the timeout hasn't passed or there are no pending keys.
+	pub fn poll(&mut self, now: Duration) -> KeyMatch {
+		if self.len == 0 || !self.is_expired(now) {
+			return KeyMatch::Pending
+		}
+		let result = self.exact_match().map_or(KeyMatch::NoMatch, KeyMatch::Matched);
+		self.len = 0;
+		result
+	}
+
+	/// Forget the pending keys.
+	#[inline(always)]
+	pub fn reset(&mut self) {
+		self.len = 0;
+	}
+
+	/// Return the index of the first binding that the pending keys match
+	/// exactly, if any.
+	fn exact_match(&self) -> Option<usize> {
+		if self.len == 0 {
+			return None
+		}
+		let keys = self.pending();
+		self.bindings.iter().position(move |binding| binding.matches(keys))
+	}
+
+	fn is_expired(&self, now: Duration) -> bool {
+		self.len > 0 && now.saturating_sub(self.last) > self.timeout
+	}
+
+	fn check(&mut self) -> KeyMatch {
+		let keys = self.pending();
+		let mut exact = None;
+		let mut is_prefix = false;
+		for (idx, binding) in self.bindings.iter().enumerate() {
+			if binding.matches(keys) {
+				exact = exact.or(Some(idx));
+			} else if binding.starts_with(keys) {
+				is_prefix = true;
+			}
+		}
+		match (exact, is_prefix) {
+			(_, true) => KeyMatch::Pending,
+			(Some(idx), false) => {
+				self.len = 0;
+				KeyMatch::Matched(idx)
+			}
+			(None, false) => {
+				self.len = 0;
+				KeyMatch::NoMatch
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key(s: &str) -> KeyEvent {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn retries_from_the_last_key() {
+		let bindings = ["ctrl+x ctrl+s", "q"].map(move |s| s.parse().unwrap());
+		let mut matcher = KeyMatcher::new(&bindings, Duration::from_secs(1));
+		let now = Duration::ZERO;
+		assert_eq!(matcher.feed(key("ctrl+x"), now), KeyMatch::Pending);
+		assert_eq!(matcher.feed(key("q"), now), KeyMatch::Matched(1));
+		assert!(matcher.pending().is_empty());
+	}
+
+	#[test]
+	fn reports_a_pending_exact_match() {
+		let bindings = ["g", "g g", "d d"].map(move |s| s.parse().unwrap());
+		let mut matcher = KeyMatcher::new(&bindings, Duration::from_secs(1));
+		let now = Duration::ZERO;
+		assert_eq!(matcher.feed(key("g"), now), KeyMatch::Pending);
+		assert_eq!(matcher.feed(key("d"), now), KeyMatch::Matched(0));
+		assert_eq!(matcher.feed(key("d"), now), KeyMatch::Matched(2));
+		assert_eq!(matcher.feed(key("g"), now), KeyMatch::Pending);
+		assert_eq!(matcher.feed(key("x"), now), KeyMatch::Matched(0));
+		assert!(matcher.pending().is_empty());
+	}
+}
@@ -0,0 +1,247 @@
+/// Feature that terminals may or may not support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Feature {
+	/// 24-bit colors in SGR sequences.
+	TrueColor,
+	/// Synchronized output, or mode 2026.
+	SynchronizedOutput,
+	/// Bracketed paste, or mode 2004.
+	BracketedPaste,
+	/// kitty's keyboard protocol.
+	KittyKeyboard,
+	/// Hyperlinks written with OSC 8.
+	Hyperlinks,
+	/// Sixel graphics.
+	Sixel,
+	/// kitty's graphics protocol.
+	KittyGraphics,
+}
+
+impl Feature {
+	/// Every feature, in order.
+	pub const ALL: [Self; 7] = [
+		Self::TrueColor,
+		Self::SynchronizedOutput,
+		Self::BracketedPaste,
+		Self::KittyKeyboard,
+		Self::Hyperlinks,
+		Self::Sixel,
+		Self::KittyGraphics,
+	];
+
+	/// Guess whether the terminal supports this feature from environment
+	/// variables, which are looked up with `var`.
+	/// 
+	/// This recognizes the variables set by common terminals, such as `TERM`,
+	/// `TERM_PROGRAM` and `COLORTERM`, and returns `false` for unknown
+	/// terminals.
+	pub fn guess<S: AsRef<str>>(self, var: impl Fn(&str) -> Option<S>) -> bool {
+		let term = var("TERM");
+		let term = term.as_ref().map_or("", AsRef::as_ref);
+		let program = var("TERM_PROGRAM");
+		let program = program.as_ref().map_or("", AsRef::as_ref);
+		let is_program = move |names: &[&str]| names.contains(&program);
+		let is_kitty = term == "xterm-kitty" || var("KITTY_WINDOW_ID").is_some();
+		let is_foot = term.starts_with("foot");
+		match self {
+			Self::TrueColor => {
+				let colorterm = var("COLORTERM");
+				let colorterm = colorterm.as_ref().map_or("", AsRef::as_ref);
+				matches!(colorterm, "truecolor" | "24bit")
+					|| is_kitty
+					|| is_foot
+					|| is_program(&["iTerm.app", "WezTerm", "ghostty", "vscode"])
+			}
+			Self::SynchronizedOutput => {
+				is_kitty
+					|| is_foot
+					|| term.starts_with("contour")
+					|| is_program(&["iTerm.app", "WezTerm", "ghostty", "vscode"])
+			}
+			Self::BracketedPaste => !matches!(term, "" | "dumb" | "linux"),
+			Self::KittyKeyboard => {
+				is_kitty || is_foot || is_program(&["ghostty", "WezTerm"])
+			}
+			Self::Hyperlinks => {
+				let vte_version = var("VTE_VERSION")
+					.and_then(move |v| v.as_ref().parse::<u32>().ok());
+				is_kitty
+					|| is_foot
+					|| vte_version.is_some_and(move |v| v >= 5000)
+					|| is_program(&["iTerm.app", "WezTerm", "ghostty", "vscode"])
+			}
+			Self::Sixel => {
+				is_foot
+					|| term.contains("mlterm")
+					|| is_program(&["iTerm.app", "WezTerm"])
+			}
+			Self::KittyGraphics => {
+				is_kitty || is_program(&["ghostty", "WezTerm"])
+			}
+		}
+	}
+}
+
+/// Cache of the [`Feature`]s that the terminal supports.
+/// 
+/// Support for a feature is guessed from environment variables when it's
+/// first asked for, unless it was set or probed before.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+	cache: [Option<bool>; Feature::ALL.len()],
+}
+
+impl Capabilities {
+	/// Create a cache that doesn't know about any feature.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			cache: [None; Feature::ALL.len()],
+		}
+	}
+
+	/// Return whether the terminal supports `feature`, if it's known.
+	#[inline(always)]
+	pub const fn get(&self, feature: Feature) -> Option<bool> {
+		self.cache[feature as usize]
+	}
+
+	/// Set whether the terminal supports `feature`.
+	#[inline(always)]
+	pub fn set(&mut self, feature: Feature, supported: bool) {
+		self.cache[feature as usize] = Some(supported);
+	}
+
+	/// Forget whether the terminal supports `feature`.
+	#[inline(always)]
+	pub fn forget(&mut self, feature: Feature) {
+		self.cache[feature as usize] = None;
+	}
+
+	/// Return whether the terminal supports `feature`, guessing it from the
+	/// environment and caching the guess if it isn't known.
+	/// 
+	/// Without the `std` feature, unknown features are assumed to be
+	/// unsupported.
+	pub fn supports(&mut self, feature: Feature) -> bool {
+		if let Some(supported) = self.get(feature) {
+			return supported
+		}
+		#[cfg(feature = "std")]
+		{
+			let supported = feature.guess(|name| std::env::var(name).ok());
+			self.set(feature, supported);
+			supported
+		}
+		#[cfg(not(feature = "std"))]
+		false
+	}
+
+	/// Return [`Error::Unsupported`](crate::Error::Unsupported) unless
+	/// [`Self::supports`] says that the terminal supports `feature`.
+	#[inline(always)]
+	pub fn require(&mut self, feature: Feature) -> Result<(), crate::Error> {
+		match self.supports(feature) {
+			true => Ok(()),
+			false => Err(crate::Error::Unsupported(feature)),
+		}
+	}
+
+	/// Record the features that the terminfo entry of the terminal says it
+	/// supports, for terminals that can't be queried.
+	/// 
+	/// Terminfo only has capabilities for some features, and entries often
+	/// leave out the ones that the terminal does support, so features that the
+	/// entry doesn't mention are left as they are.
+	#[cfg(feature = "terminfo")]
+	pub fn apply_terminfo(&mut self, terminfo: &crate::Terminfo) {
+		if terminfo.flag("RGB") || terminfo.flag("Tc")
+			|| terminfo.number("colors").is_some_and(|colors| colors >= 1 << 24)
+		{
+			self.set(Feature::TrueColor, true);
+		}
+		if terminfo.string("Sync").is_some() {
+			self.set(Feature::SynchronizedOutput, true);
+		}
+		if terminfo.string("BE").is_some() {
+			self.set(Feature::BracketedPaste, true);
+		}
+	}
+
+	/// Ask the terminal whether it supports `feature`, caching the answer.
+	/// 
+	/// The query for the feature is written to `output` along with a
+	/// [`PrimaryAttributesQuery`](crate::PrimaryAttributesQuery), which every
+	/// terminal answers, so that a terminal that ignores the query doesn't
+	/// block this function. Other input that arrives before the answers is
+	/// discarded, and the terminal should be in raw mode.
+	/// 
+	/// [`Feature::Hyperlinks`] can't be queried, so it's guessed like in
+	/// [`Self::supports`]. Support for [`Feature::TrueColor`] is also guessed
+	/// if the terminal doesn't report it.
+	#[cfg(feature = "std")]
+	pub fn probe(
+		&mut self,
+		feature: Feature,
+		output: &mut impl std::io::Write,
+		input: &mut impl std::io::Read,
+	) -> Result<bool, crate::Error> {
+		use crate::{
+			Demux, Error, GraphicsQuery, KeyboardFlagsQuery, ModeQuery,
+			PrimaryAttributesQuery, TcapQuery,
+		};
+
+		const RGB: TcapQuery<'static> = TcapQuery { name: "RGB" };
+		let mode = match feature {
+			Feature::SynchronizedOutput => ModeQuery::private(2026),
+			_ => ModeQuery::private(2004),
+		};
+
+		let mut demux = Demux::new();
+		let mut query = String::new();
+		match feature {
+			Feature::TrueColor => demux.send(&RGB, &mut query),
+			Feature::SynchronizedOutput | Feature::BracketedPaste => {
+				demux.send(&mode, &mut query)
+			}
+			Feature::KittyKeyboard => demux.send(&KeyboardFlagsQuery, &mut query),
+			Feature::KittyGraphics => demux.send(&GraphicsQuery, &mut query),
+			Feature::Sixel => Ok(()),
+			Feature::Hyperlinks => {
+				self.forget(feature);
+				return Ok(self.supports(feature))
+			}
+		}.and_then(|_| demux.send(&PrimaryAttributesQuery, &mut query))?;
+		output.write_all(query.as_bytes())?;
+		output.flush()?;
+
+		let mut buf = [0; 256];
+		let attributes = loop {
+			let read = input.read(&mut buf)?;
+			if read == 0 {
+				return Err(Error::Incomplete)
+			}
+			demux.advance(&buf[..read], |_| {});
+			if let Some(attributes) = demux.take(&PrimaryAttributesQuery) {
+				break attributes
+			}
+		};
+
+		let supported = match feature {
+			Feature::TrueColor => {
+				demux.take(&RGB).flatten().is_some()
+					|| feature.guess(|name| std::env::var(name).ok())
+			}
+			Feature::SynchronizedOutput | Feature::BracketedPaste => {
+				demux.take(&mode).is_some_and(|status| status.is_recognized())
+			}
+			Feature::KittyKeyboard => demux.take(&KeyboardFlagsQuery).is_some(),
+			Feature::KittyGraphics => demux.take(&GraphicsQuery).unwrap_or(false),
+			Feature::Sixel => attributes.has(4),
+			Feature::Hyperlinks => unreachable!(),
+		};
+		self.set(feature, supported);
+		Ok(supported)
+	}
+}
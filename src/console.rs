@@ -0,0 +1,548 @@
+//! Output to Windows consoles that don't interpret escape sequences.
+
+use crate::{
+	csi::Csi,
+	scan::{
+		Scanner, Token
+	},
+	sgr::xterm_256_to_rgb,
+};
+
+use core::ffi::c_void;
+use std::io;
+
+type Handle = *mut c_void;
+
+const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+const INVALID_HANDLE_VALUE: Handle = -1isize as Handle;
+const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+const FOREGROUND_BLUE: u16 = 0x0001;
+const FOREGROUND_GREEN: u16 = 0x0002;
+const FOREGROUND_RED: u16 = 0x0004;
+const FOREGROUND_INTENSITY: u16 = 0x0008;
+const FOREGROUND_MASK: u16 = 0x000f;
+const BACKGROUND_MASK: u16 = 0x00f0;
+const COMMON_LVB_UNDERSCORE: u16 = 0x8000;
+
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+struct Coord {
+	x: i16,
+	y: i16,
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+struct SmallRect {
+	left: i16,
+	top: i16,
+	right: i16,
+	bottom: i16,
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+struct ScreenBufferInfo {
+	size: Coord,
+	cursor_position: Coord,
+	attributes: u16,
+	window: SmallRect,
+	maximum_window_size: Coord,
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Clone, Copy)]
+struct CursorInfo {
+	size: u32,
+	visible: i32,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+	fn GetStdHandle(std_handle: u32) -> Handle;
+	fn GetConsoleMode(console: Handle, mode: *mut u32) -> i32;
+	fn SetConsoleMode(console: Handle, mode: u32) -> i32;
+	fn GetConsoleScreenBufferInfo(console: Handle, info: *mut ScreenBufferInfo) -> i32;
+	fn SetConsoleTextAttribute(console: Handle, attributes: u16) -> i32;
+	fn SetConsoleCursorPosition(console: Handle, position: Coord) -> i32;
+	fn GetConsoleCursorInfo(console: Handle, info: *mut CursorInfo) -> i32;
+	fn SetConsoleCursorInfo(console: Handle, info: *const CursorInfo) -> i32;
+	fn FillConsoleOutputCharacterW(
+		console: Handle, character: u16, length: u32, start: Coord,
+		written: *mut u32,
+	) -> i32;
+	fn FillConsoleOutputAttribute(
+		console: Handle, attribute: u16, length: u32, start: Coord,
+		written: *mut u32,
+	) -> i32;
+	fn WriteConsoleW(
+		console: Handle, buffer: *const u16, length: u32, written: *mut u32,
+		reserved: *mut c_void,
+	) -> i32;
+}
+
+/// Turn the result of a console function into an [`io::Result`].
+#[inline(always)]
+fn check(result: i32) -> io::Result<()> {
+	match result {
+		0 => Err(io::Error::last_os_error()),
+		_ => Ok(()),
+	}
+}
+
+/// Return the handle of the standard output.
+fn stdout_handle() -> io::Result<Handle> {
+	// SAFETY: `GetStdHandle` has no preconditions.
+	let handle = unsafe { GetStdHandle(STD_OUTPUT_HANDLE) };
+	if handle == INVALID_HANDLE_VALUE {
+		return Err(io::Error::last_os_error())
+	}
+	if handle.is_null() {
+		return Err(io::Error::new(io::ErrorKind::NotFound, "no standard output"))
+	}
+	Ok(handle)
+}
+
+/// Make the console of the standard output interpret escape sequences.
+/// 
+/// This fails on versions of Windows before Windows 10, and if the standard
+/// output isn't a console.
+pub fn enable_virtual_terminal() -> io::Result<()> {
+	let handle = stdout_handle()?;
+	let mut mode = 0;
+	// SAFETY: `handle` is valid, and `mode` is a valid pointer.
+	unsafe {
+		check(GetConsoleMode(handle, &mut mode))?;
+		check(SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING))
+	}
+}
+
+/// Writer to a console that doesn't interpret escape sequences, which
+/// translates them into calls to the console API.
+/// 
+/// Cursor movement, erasing and SGR colors are translated. Colors are reduced
+/// to the 16 colors of the console, and the only other attributes that are
+/// kept are bold (as bright colors), underline and inverse. Other sequences are
+/// dropped.
+/// 
+/// Cursor positions are relative to the visible window of the console, like
+/// on a terminal.
+#[derive(Debug)]
+pub struct LegacyConsole {
+	handle: Handle,
+	default_attributes: u16,
+	foreground: u16,
+	background: u16,
+	is_bold: bool,
+	is_underlined: bool,
+	is_inverse: bool,
+	saved: Coord,
+	scanner: Scanner,
+}
+
+impl LegacyConsole {
+	/// Create a writer to the console of the standard output.
+	/// 
+	/// The attributes that the console has now are used as the default colors.
+	pub fn stdout() -> io::Result<Self> {
+		let handle = stdout_handle()?;
+		let mut info = ScreenBufferInfo::default();
+		// SAFETY: `handle` is valid, and `info` is a valid pointer.
+		check(unsafe { GetConsoleScreenBufferInfo(handle, &mut info) })?;
+		let default_attributes = info.attributes & (FOREGROUND_MASK | BACKGROUND_MASK);
+		Ok(Self {
+			handle,
+			default_attributes,
+			foreground: default_attributes & FOREGROUND_MASK,
+			background: default_attributes & BACKGROUND_MASK,
+			is_bold: false,
+			is_underlined: false,
+			is_inverse: false,
+			saved: Coord::default(),
+			scanner: Scanner::new(),
+		})
+	}
+
+	/// Interpret `bytes` as output written to a terminal.
+	pub fn feed(&mut self, bytes: &[u8]) -> io::Result<()> {
+		let mut scanner = core::mem::take(&mut self.scanner);
+		let mut result = Ok(());
+		scanner.feed(bytes, |token| {
+			if result.is_ok() {
+				result = self.apply(token);
+			}
+		});
+		self.scanner = scanner;
+		result
+	}
+
+	fn info(&self) -> io::Result<ScreenBufferInfo> {
+		let mut info = ScreenBufferInfo::default();
+		// SAFETY: `self.handle` is valid, and `info` is a valid pointer.
+		check(unsafe { GetConsoleScreenBufferInfo(self.handle, &mut info) })?;
+		Ok(info)
+	}
+
+	fn write_wide(&mut self, text: &str) -> io::Result<()> {
+		let wide: Vec<u16> = text.encode_utf16().collect();
+		let mut rest = &wide[..];
+		while !rest.is_empty() {
+			let mut written = 0;
+			// SAFETY: `self.handle` is valid, and the pointers are valid for
+			// the lengths passed.
+			check(unsafe {
+				WriteConsoleW(
+					self.handle, rest.as_ptr(), rest.len() as u32, &mut written,
+					core::ptr::null_mut(),
+				)
+			})?;
+			rest = &rest[(written as usize).min(rest.len())..];
+		}
+		Ok(())
+	}
+
+	fn apply(&mut self, token: Token<'_>) -> io::Result<()> {
+		match token {
+			Token::Text(text) => self.write_wide(text),
+			Token::Control(byte @ (b'\r' | b'\n' | b'\t' | 0x07 | 0x08)) => {
+				self.write_wide(char::from(byte).encode_utf8(&mut [0; 4]))
+			}
+			Token::Csi(csi) => self.apply_csi(&csi),
+			Token::Escape(b"\x1b7") => {
+				self.saved = self.info()?.cursor_position;
+				Ok(())
+			}
+			Token::Escape(b"\x1b8") => self.set_cursor(self.saved),
+			_ => Ok(()),
+		}
+	}
+
+	fn apply_csi(&mut self, csi: &Csi<'_>) -> io::Result<()> {
+		if !csi.intermediate_bytes.is_empty() {
+			return Ok(())
+		}
+		if csi.private_marker() == Some(b'?') {
+			return match csi.final_byte.byte() {
+				b'h' | b'l' if csi.params().any(|param| param == Some(25)) => {
+					self.set_cursor_visible(csi.final_byte.byte() == b'h')
+				}
+				_ => Ok(()),
+			}
+		}
+		if csi.private_marker().is_some() {
+			return Ok(())
+		}
+		if csi.final_byte.byte() == b'm' {
+			self.apply_sgr(csi);
+			return self.update_attributes()
+		}
+
+		let info = self.info()?;
+		let window = info.window;
+		let cursor = info.cursor_position;
+		let mut params = csi.params();
+		let first = params.next().flatten();
+		let n = first.unwrap_or(1).max(1).min(i16::MAX as u16) as i16;
+		let column = move |x: i16| Coord {
+			x: x.clamp(window.left, window.right),
+			y: cursor.y,
+		};
+		let row = move |y: i16| Coord {
+			x: cursor.x,
+			y: y.clamp(window.top, window.bottom),
+		};
+
+		match csi.final_byte.byte() {
+			b'A' => self.set_cursor(row(cursor.y.saturating_sub(n))),
+			b'B' => self.set_cursor(row(cursor.y.saturating_add(n))),
+			b'C' => self.set_cursor(column(cursor.x.saturating_add(n))),
+			b'D' => self.set_cursor(column(cursor.x.saturating_sub(n))),
+			b'E' => self.set_cursor(Coord {
+				x: window.left,
+				..row(cursor.y.saturating_add(n))
+			}),
+			b'F' => self.set_cursor(Coord {
+				x: window.left,
+				..row(cursor.y.saturating_sub(n))
+			}),
+			b'G' | b'`' => self.set_cursor(column(window.left.saturating_add(n - 1))),
+			b'd' => self.set_cursor(row(window.top.saturating_add(n - 1))),
+			b'H' | b'f' => {
+				let x = params.next().flatten().unwrap_or(1).max(1).min(i16::MAX as u16) as i16;
+				self.set_cursor(Coord {
+					x: window.left.saturating_add(x - 1).clamp(window.left, window.right),
+					y: window.top.saturating_add(n - 1).clamp(window.top, window.bottom),
+				})
+			}
+			b'J' => {
+				let width = info.size.x.max(0) as u32;
+				let offset = |c: Coord| c.y.max(0) as u32 * width + c.x.max(0) as u32;
+				let begin = Coord {
+					x: 0,
+					y: window.top,
+				};
+				let end = offset(Coord {
+					x: 0,
+					y: window.bottom.saturating_add(1),
+				});
+				match first.unwrap_or(0) {
+					0 => self.fill(cursor, end.saturating_sub(offset(cursor))),
+					1 => self.fill(begin, (offset(cursor) + 1).saturating_sub(offset(begin))),
+					_ => self.fill(begin, end.saturating_sub(offset(begin))),
+				}
+			}
+			b'K' => {
+				let width = info.size.x.max(0) as u32;
+				let begin = Coord {
+					x: 0,
+					y: cursor.y,
+				};
+				let x = cursor.x.max(0) as u32;
+				match first.unwrap_or(0) {
+					0 => self.fill(cursor, width.saturating_sub(x)),
+					1 => self.fill(begin, x + 1),
+					_ => self.fill(begin, width),
+				}
+			}
+			b'X' => {
+				let width = info.size.x.max(0) as u32;
+				let x = cursor.x.max(0) as u32;
+				self.fill(cursor, (n as u32).min(width.saturating_sub(x)))
+			}
+			b's' => {
+				self.saved = cursor;
+				Ok(())
+			}
+			b'u' => self.set_cursor(self.saved),
+			_ => Ok(()),
+		}
+	}
+
+	fn apply_sgr(&mut self, csi: &Csi<'_>) {
+		// An empty parameter, which is the only one of `CSI m`, resets.
+		let mut params = csi.params();
+		while let Some(param) = params.next() {
+			match param.unwrap_or(0) {
+				0 => {
+					self.foreground = self.default_attributes & FOREGROUND_MASK;
+					self.background = self.default_attributes & BACKGROUND_MASK;
+					self.is_bold = false;
+					self.is_underlined = false;
+					self.is_inverse = false;
+				}
+				1 => self.is_bold = true,
+				22 => self.is_bold = false,
+				4 | 21 => self.is_underlined = true,
+				24 => self.is_underlined = false,
+				7 => self.is_inverse = true,
+				27 => self.is_inverse = false,
+				n @ 30..=37 => self.foreground = table_attributes((n - 30) as u8),
+				n @ 90..=97 => self.foreground = table_attributes((n - 90 + 8) as u8),
+				n @ 40..=47 => self.background = table_attributes((n - 40) as u8) << 4,
+				n @ 100..=107 => {
+					self.background = table_attributes((n - 100 + 8) as u8) << 4;
+				}
+				39 => self.foreground = self.default_attributes & FOREGROUND_MASK,
+				49 => self.background = self.default_attributes & BACKGROUND_MASK,
+				n @ (38 | 48 | 58) => {
+					let color = match params.next().flatten() {
+						Some(5) => params.next().flatten().map(|n| table_attributes(n as u8)),
+						Some(2) => {
+							let mut channel = || params.next().flatten().unwrap_or(0) as u8;
+							let (r, g, b) = (channel(), channel(), channel());
+							Some(rgb_attributes(r, g, b))
+						}
+						_ => None,
+					};
+					match (n, color) {
+						(38, Some(color)) => self.foreground = color,
+						(48, Some(color)) => self.background = color << 4,
+						_ => {}
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	/// Return the attributes that the current SGR settings are displayed with.
+	fn attributes(&self) -> u16 {
+		let mut foreground = self.foreground;
+		let mut background = self.background >> 4;
+		if self.is_inverse {
+			core::mem::swap(&mut foreground, &mut background);
+		}
+		if self.is_bold {
+			foreground |= FOREGROUND_INTENSITY;
+		}
+		let mut attributes = foreground | background << 4;
+		if self.is_underlined {
+			attributes |= COMMON_LVB_UNDERSCORE;
+		}
+		attributes
+	}
+
+	fn update_attributes(&mut self) -> io::Result<()> {
+		// SAFETY: `self.handle` is valid.
+		check(unsafe { SetConsoleTextAttribute(self.handle, self.attributes()) })
+	}
+
+	fn set_cursor(&mut self, position: Coord) -> io::Result<()> {
+		// SAFETY: `self.handle` is valid.
+		check(unsafe { SetConsoleCursorPosition(self.handle, position) })
+	}
+
+	fn set_cursor_visible(&mut self, visible: bool) -> io::Result<()> {
+		let mut info = CursorInfo::default();
+		// SAFETY: `self.handle` is valid, and `info` is a valid pointer.
+		unsafe {
+			check(GetConsoleCursorInfo(self.handle, &mut info))?;
+			info.visible = visible as i32;
+			check(SetConsoleCursorInfo(self.handle, &info))
+		}
+	}
+
+	/// Erase `length` cells from `start`, wrapping at the end of each row,
+	/// with the current background color.
+	fn fill(&mut self, start: Coord, length: u32) -> io::Result<()> {
+		let attributes = self.attributes() & !COMMON_LVB_UNDERSCORE;
+		let mut written = 0;
+		// SAFETY: `self.handle` is valid, and `written` is a valid pointer.
+		unsafe {
+			check(FillConsoleOutputCharacterW(
+				self.handle, b' ' as u16, length, start, &mut written,
+			))?;
+			check(FillConsoleOutputAttribute(
+				self.handle, attributes, length, start, &mut written,
+			))
+		}
+	}
+}
+
+impl io::Write for LegacyConsole {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.feed(buf)?;
+		Ok(buf.len())
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Drop for LegacyConsole {
+	fn drop(&mut self) {
+		// SAFETY: `self.handle` is valid.
+		unsafe {
+			SetConsoleTextAttribute(self.handle, self.default_attributes);
+		}
+	}
+}
+
+/// Return the console attributes of the foreground color `n` of the
+/// 256-color table.
+fn table_attributes(n: u8) -> u16 {
+	match n {
+		0..=15 => {
+			// The table is ordered by red, green and blue bits, which are in
+			// the opposite order in console attributes.
+			let mut attributes = 0;
+			if n & 1 != 0 {
+				attributes |= FOREGROUND_RED;
+			}
+			if n & 2 != 0 {
+				attributes |= FOREGROUND_GREEN;
+			}
+			if n & 4 != 0 {
+				attributes |= FOREGROUND_BLUE;
+			}
+			if n >= 8 {
+				attributes |= FOREGROUND_INTENSITY;
+			}
+			attributes
+		}
+		16.. => {
+			let (r, g, b) = xterm_256_to_rgb(n);
+			rgb_attributes(r, g, b)
+		}
+	}
+}
+
+/// Return the console attributes of the foreground color that is closest to
+/// the color `r`, `g`, `b`.
+fn rgb_attributes(r: u8, g: u8, b: u8) -> u16 {
+	let max = r.max(g).max(b);
+	if max < 64 {
+		return 0
+	}
+	// Channels that are at least half as bright as the brightest one are on.
+	let mut attributes = 0;
+	if r as u16 * 2 >= max as u16 {
+		attributes |= FOREGROUND_RED;
+	}
+	if g as u16 * 2 >= max as u16 {
+		attributes |= FOREGROUND_GREEN;
+	}
+	if b as u16 * 2 >= max as u16 {
+		attributes |= FOREGROUND_BLUE;
+	}
+	if max > 191 {
+		attributes |= FOREGROUND_INTENSITY;
+	}
+	attributes
+}
+
+/// Writer to the standard output that uses escape sequences if the console
+/// interprets them, or translates them with a [`LegacyConsole`] if it
+/// doesn't.
+#[derive(Debug)]
+pub enum ConsoleOutput {
+	/// The console interprets escape sequences.
+	Vt(io::Stdout),
+	/// The console doesn't interpret escape sequences.
+	Legacy(Box<LegacyConsole>),
+}
+
+impl ConsoleOutput {
+	/// Create a writer to the standard output, trying to make the console
+	/// interpret escape sequences with [`enable_virtual_terminal`] first.
+	/// 
+	/// If the standard output isn't a console, escape sequences are written
+	/// as they are.
+	pub fn stdout() -> Self {
+		if enable_virtual_terminal().is_ok() {
+			return Self::Vt(io::stdout())
+		}
+		match LegacyConsole::stdout() {
+			Ok(console) => Self::Legacy(Box::new(console)),
+			Err(..) => Self::Vt(io::stdout()),
+		}
+	}
+
+	/// Return `true` if escape sequences are being translated.
+	#[inline(always)]
+	pub const fn is_legacy(&self) -> bool {
+		matches!(self, Self::Legacy(..))
+	}
+}
+
+impl io::Write for ConsoleOutput {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match self {
+			Self::Vt(stdout) => stdout.write(buf),
+			Self::Legacy(console) => console.write(buf),
+		}
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		match self {
+			Self::Vt(stdout) => stdout.flush(),
+			Self::Legacy(console) => console.flush(),
+		}
+	}
+}
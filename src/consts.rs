@@ -0,0 +1,148 @@
+//! Common sequences as string constants, which are encoded at compile time.
+//! 
+//! Writing these avoids formatting the sequence every time, which matters in
+//! code that writes many of them, such as when drawing every frame.
+
+use crate::{
+	csi::Csi,
+	dec_len,
+	fe_seq::ESC_STR,
+	sgr::Sgr,
+};
+
+/// Copy `bytes` into `out` at `at`, returning the index after them.
+const fn copy<const N: usize>(out: &mut [u8; N], mut at: usize, bytes: &[u8]) -> usize {
+	let mut i = 0;
+	while i < bytes.len() {
+		out[at] = bytes[i];
+		at += 1;
+		i += 1;
+	}
+	at
+}
+
+/// Encode a CSI sequence with `params`, followed by `final_str`.
+const fn csi<const N: usize>(params: &[u8], final_str: &str) -> [u8; N] {
+	let mut out = [0; N];
+	let mut len = copy(&mut out, 0, ESC_STR.as_bytes());
+	len = copy(&mut out, len, Csi::INTRO_STR.as_bytes());
+	len = copy(&mut out, len, params);
+	len = copy(&mut out, len, final_str.as_bytes());
+	assert!(len == N, "wrong length of sequence");
+	out
+}
+
+/// Encode `sgr`, which must not carry a color.
+const fn sgr<const N: usize>(sgr: Sgr) -> [u8; N] {
+	let mut code = sgr.code().get();
+	let len = dec_len(code as u32);
+	let mut digits = [0; 3];
+	let mut i = len;
+	while i > 0 {
+		i -= 1;
+		digits[i] = b'0' + code % 10;
+		code /= 10;
+	}
+	csi(digits.split_at(len).0, Csi::FINAL_STR)
+}
+
+/// Return `bytes` as a [`str`], which they must be.
+const fn as_str(bytes: &[u8]) -> &str {
+	match core::str::from_utf8(bytes) {
+		Ok(s) => s,
+		Err(..) => panic!("sequence isn't valid UTF-8"),
+	}
+}
+
+macro_rules! sequences {
+	($(
+		$(#[$attr:meta])*
+		$name:ident = $kind:ident($($arg:expr),*);
+	)*) => {$(
+		$(#[$attr])*
+		pub const $name: &str = {
+			const BYTES: [u8; sequences!(@len $kind($($arg),*))] = $kind($($arg),*);
+			as_str(&BYTES)
+		};
+	)*};
+	(@len sgr($sgr:expr)) => { $sgr.encoded_len() };
+	(@len csi($params:expr, $final_str:expr)) => {
+		Csi::BEGIN_LEN + $params.len() + $final_str.len()
+	};
+}
+
+sequences! {
+	/// [`Sgr::Reset`].
+	RESET = sgr(Sgr::Reset);
+	/// [`Sgr::WeightBoldOn`].
+	BOLD = sgr(Sgr::WeightBoldOn);
+	/// [`Sgr::WeightThinOn`].
+	THIN = sgr(Sgr::WeightThinOn);
+	/// [`Sgr::WeightAllOff`].
+	REGULAR = sgr(Sgr::WeightAllOff);
+	/// [`Sgr::ItalicOn`].
+	ITALIC = sgr(Sgr::ItalicOn);
+	/// [`Sgr::ItalicOff`].
+	NO_ITALIC = sgr(Sgr::ItalicOff);
+	/// [`Sgr::UnderlineSingle`].
+	UNDERLINE = sgr(Sgr::UnderlineSingle);
+	/// [`Sgr::UnderlineNone`].
+	NO_UNDERLINE = sgr(Sgr::UnderlineNone);
+	/// [`Sgr::Invert`].
+	INVERT = sgr(Sgr::Invert);
+	/// [`Sgr::ReversedOff`].
+	NO_INVERT = sgr(Sgr::ReversedOff);
+	/// [`Sgr::StrikethroughOn`].
+	STRIKETHROUGH = sgr(Sgr::StrikethroughOn);
+	/// [`Sgr::StrikethroughOff`].
+	NO_STRIKETHROUGH = sgr(Sgr::StrikethroughOff);
+
+	/// [`Sgr::Foreground1`], which is usually black.
+	FOREGROUND_1 = sgr(Sgr::Foreground1);
+	/// [`Sgr::Foreground2`], which is usually red.
+	FOREGROUND_2 = sgr(Sgr::Foreground2);
+	/// [`Sgr::Foreground3`], which is usually green.
+	FOREGROUND_3 = sgr(Sgr::Foreground3);
+	/// [`Sgr::Foreground4`], which is usually yellow.
+	FOREGROUND_4 = sgr(Sgr::Foreground4);
+	/// [`Sgr::Foreground5`], which is usually blue.
+	FOREGROUND_5 = sgr(Sgr::Foreground5);
+	/// [`Sgr::Foreground6`], which is usually magenta.
+	FOREGROUND_6 = sgr(Sgr::Foreground6);
+	/// [`Sgr::Foreground7`], which is usually cyan.
+	FOREGROUND_7 = sgr(Sgr::Foreground7);
+	/// [`Sgr::Foreground8`], which is usually white.
+	FOREGROUND_8 = sgr(Sgr::Foreground8);
+	/// [`Sgr::ForegroundDefault`].
+	FOREGROUND_DEFAULT = sgr(Sgr::ForegroundDefault);
+
+	/// [`Sgr::Background1`], which is usually black.
+	BACKGROUND_1 = sgr(Sgr::Background1);
+	/// [`Sgr::Background2`], which is usually red.
+	BACKGROUND_2 = sgr(Sgr::Background2);
+	/// [`Sgr::Background3`], which is usually green.
+	BACKGROUND_3 = sgr(Sgr::Background3);
+	/// [`Sgr::Background4`], which is usually yellow.
+	BACKGROUND_4 = sgr(Sgr::Background4);
+	/// [`Sgr::Background5`], which is usually blue.
+	BACKGROUND_5 = sgr(Sgr::Background5);
+	/// [`Sgr::Background6`], which is usually magenta.
+	BACKGROUND_6 = sgr(Sgr::Background6);
+	/// [`Sgr::Background7`], which is usually cyan.
+	BACKGROUND_7 = sgr(Sgr::Background7);
+	/// [`Sgr::Background8`], which is usually white.
+	BACKGROUND_8 = sgr(Sgr::Background8);
+	/// [`Sgr::BackgroundDefault`].
+	BACKGROUND_DEFAULT = sgr(Sgr::BackgroundDefault);
+
+	/// Hide the cursor, by resetting the private mode `25`.
+	HIDE_CURSOR = csi(b"?25", "l");
+	/// Show the cursor, by setting the private mode `25`.
+	SHOW_CURSOR = csi(b"?25", "h");
+	/// Erase the whole display, without moving the cursor.
+	CLEAR = csi(b"2", "J");
+	/// Erase the line of the cursor, without moving the cursor.
+	CLEAR_LINE = csi(b"2", "K");
+	/// Move the cursor to the top left of the screen.
+	HOME = csi(b"", "H");
+}
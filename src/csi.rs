@@ -22,9 +22,13 @@ impl<'a> Csi<'a> {
 	pub const FINAL_BYTE: CsiFinal = unsafe { CsiFinal::new_unchecked(b'm') };
 	pub const FINAL_CHAR: char = 'm';
 	pub const FINAL_STR: &'static str = "m";
+	/// Length of the beginning of a CSI sequence, as written by
+	/// [`Self::write_begin`].
+	pub const BEGIN_LEN: usize =
+		crate::fe_seq::ESC_STR.len() + Self::INTRO_STR.len();
 
 	/// Write the beginning of a CSI sequence.
-	pub fn write_begin(w: &mut impl fmt::Write) -> fmt::Result {
+	pub fn write_begin(w: &mut (impl fmt::Write + ?Sized)) -> fmt::Result {
 		w.write_str(crate::fe_seq::ESC_STR)?;
 		w.write_str(Self::INTRO_STR)
 	}
@@ -40,6 +44,87 @@ impl<'a> fmt::Display for Csi<'a> {
 	}
 }
 
+/// Operation that is written as one CSI sequence.
+/// 
+/// Writing the parameters separately from the rest of the sequence allows
+/// operations to be embedded in larger sequences, and to share one introducer
+/// in a [`CsiChain`].
+pub trait CsiSequence {
+	/// Write the parameter bytes of the sequence, without the introducer or
+	/// the final bytes.
+	fn write_params_to(&self, w: &mut dyn fmt::Write) -> fmt::Result;
+
+	/// Return the intermediate bytes and the final byte of the sequence.
+	fn final_str(&self) -> &'static str;
+
+	/// Return `true` if the sequence applies any number of parameters in
+	/// turn, so that its parameters can be joined with those of the next
+	/// sequence with the same final bytes.
+	/// 
+	/// This is the case for SGR, but not for sequences such as ED, which
+	/// ignore extra parameters.
+	#[inline(always)]
+	fn is_chainable(&self) -> bool {
+		false
+	}
+}
+
+/// Sequences that are written one after another, sharing one introducer
+/// between consecutive sequences that are [chainable](CsiSequence::is_chainable)
+/// and have the same final bytes.
+/// 
+/// For example, bold and a red foreground are written as `CSI 1;31m`.
+#[derive(Clone, Copy)]
+pub struct CsiChain<'a> {
+	pub sequences: &'a [&'a dyn CsiSequence],
+}
+
+impl<'a> CsiChain<'a> {
+	/// Create a [`CsiChain`] from its sequences.
+	#[inline(always)]
+	pub const fn new(sequences: &'a [&'a dyn CsiSequence]) -> Self {
+		Self {
+			sequences,
+		}
+	}
+}
+
+impl fmt::Debug for CsiChain<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CsiChain")
+			.field("len", &self.sequences.len())
+			.finish_non_exhaustive()
+	}
+}
+
+impl fmt::Display for CsiChain<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// Final bytes of the previous sequence if it's chainable, in which case
+		// it hasn't been finished yet.
+		let mut open: Option<&'static str> = None;
+		for sequence in self.sequences {
+			let final_str = sequence.final_str();
+			match open {
+				Some(open) if sequence.is_chainable() && open == final_str => {
+					f.write_str(";")?;
+				}
+				_ => {
+					if let Some(open) = open {
+						f.write_str(open)?;
+					}
+					Csi::write_begin(f)?;
+				}
+			}
+			sequence.write_params_to(f)?;
+			open = sequence.is_chainable().then_some(final_str);
+			if open.is_none() {
+				f.write_str(final_str)?;
+			}
+		}
+		open.map_or(Ok(()), move |open| f.write_str(open))
+	}
+}
+
 byte_wrapper! {
 	for 0x30..=0x3f =>
 	#[repr(transparent)]
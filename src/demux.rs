@@ -0,0 +1,330 @@
+use crate::{
+	csi::{
+		Csi, CsiInter,
+	},
+	event::{
+		Event, Parser,
+	},
+	query::TerminalQuery,
+};
+
+use core::fmt;
+
+/// Parser of terminal input that separates responses to queries from input
+/// by the user.
+/// 
+/// While queries are pending, sequences that look like responses (DSR, CPR,
+/// DA, DECRPM, OSC, DCS and APC replies) are kept in slots until they are taken
+/// with [`Self::take`], instead of being reported as [`Event`]s. Everything
+/// else, and every sequence while no query is pending, is reported as
+/// [`Event`]s like [`Parser`] does.
+/// 
+/// Cursor Position Reports look the same as some keys pressed with modifiers
+/// (such as `CSI 1;2R` for Shift+F3), so they are only kept while a query for
+/// the position of the cursor is pending.
+#[derive(Debug, Clone)]
+pub struct Demux {
+	parser: Parser,
+	slots: [[u8; Parser::CAPACITY]; Self::SLOTS],
+	slot_lens: [usize; Self::SLOTS],
+	responses: usize,
+	pending: usize,
+	positions: usize,
+}
+
+impl Default for Demux {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Demux {
+	/// Maximum number of responses that can be kept until they are taken.
+	pub const SLOTS: usize = 4;
+
+	/// Create a new [`Demux`] with no pending queries.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			parser: Parser::new(),
+			slots: [[0; Parser::CAPACITY]; Self::SLOTS],
+			slot_lens: [0; Self::SLOTS],
+			responses: 0,
+			pending: 0,
+			positions: 0,
+		}
+	}
+
+	/// Return the parser of the input.
+	#[inline(always)]
+	pub const fn parser_mut(&mut self) -> &mut Parser {
+		&mut self.parser
+	}
+
+	/// Return the number of queries that weren't answered yet.
+	#[inline(always)]
+	pub const fn pending(&self) -> usize {
+		self.pending
+	}
+
+	/// Return the number of responses that weren't taken yet.
+	#[inline(always)]
+	pub const fn responses(&self) -> usize {
+		self.responses
+	}
+
+	/// Write `query` to `w` and expect a response to it.
+	#[inline(always)]
+	pub fn send<Q: TerminalQuery + ?Sized>(
+		&mut self, query: &Q, w: &mut impl fmt::Write,
+	) -> fmt::Result {
+		query.write_query(w)?;
+		match query.is_position_query() {
+			true => self.expect_position(),
+			false => self.expect(),
+		}
+		Ok(())
+	}
+
+	/// Expect a response to a query that was written without
+	/// [`Self::send`].
+	/// 
+	/// Use [`Self::expect_position`] if the response is a Cursor Position
+	/// Report.
+	#[inline(always)]
+	pub fn expect(&mut self) {
+		self.pending += 1;
+	}
+
+	/// Expect a Cursor Position Report in response to a query that was written
+	/// without [`Self::send`].
+	#[inline(always)]
+	pub fn expect_position(&mut self) {
+		self.pending += 1;
+		self.positions += 1;
+	}
+
+	/// Stop expecting responses and discard the ones that weren't taken, such
+	/// as when the terminal doesn't answer in time.
+	#[inline(always)]
+	pub fn cancel(&mut self) {
+		self.pending = 0;
+		self.responses = 0;
+		self.positions = 0;
+	}
+
+	/// Feed `bytes` to the parser, keeping responses and calling `f` with
+	/// every other complete event.
+	pub fn advance(&mut self, bytes: &[u8], mut f: impl FnMut(Event<'_>)) {
+		let Self { parser, slots, slot_lens, responses, pending, positions } = self;
+		parser.advance_with_bytes(bytes, |event, raw| {
+			let counts = (&mut *responses, &mut *pending, &mut *positions);
+			route(slots, slot_lens, counts, event, raw, &mut f);
+		});
+	}
+
+	/// Interpret any buffered bytes as if no more bytes are going to arrive.
+	/// 
+	/// See [`Parser::flush`].
+	pub fn flush(&mut self, mut f: impl FnMut(Event<'_>)) {
+		let Self { parser, slots, slot_lens, responses, pending, positions } = self;
+		parser.flush_with_bytes(|event, raw| {
+			let counts = (&mut *responses, &mut *pending, &mut *positions);
+			route(slots, slot_lens, counts, event, raw, &mut f);
+		});
+	}
+
+	/// Take the first kept response that is a response to `query`, parsing it.
+	pub fn take<Q: TerminalQuery + ?Sized>(
+		&mut self, query: &Q,
+	) -> Option<Q::Response> {
+		(0..self.responses).find_map(|i| {
+			let bytes = &self.slots[i][..self.slot_lens[i]];
+			let (response, ..) = query.parse_response(bytes).ok()?;
+			self.slots.copy_within(i + 1..self.responses, i);
+			self.slot_lens.copy_within(i + 1..self.responses, i);
+			self.responses -= 1;
+			Some(response)
+		})
+	}
+}
+
+/// Keep `raw` in a slot if it's a response that is expected, or call `f`
+/// with `event` otherwise.
+fn route(
+	slots: &mut [[u8; Parser::CAPACITY]; Demux::SLOTS],
+	slot_lens: &mut [usize; Demux::SLOTS],
+	(responses, pending, positions): (&mut usize, &mut usize, &mut usize),
+	event: Event<'_>,
+	raw: &[u8],
+	f: &mut impl FnMut(Event<'_>),
+) {
+	let kind = match *pending > 0
+		&& *responses < Demux::SLOTS
+		&& !matches!(event, Event::Paste(..))
+	{
+		true => response_kind(raw),
+		false => None,
+	};
+	let is_kept = match kind {
+		Some(Response::Position) => *positions > 0,
+		Some(Response::Other) => true,
+		None => false,
+	};
+	if is_kept {
+		slots[*responses][..raw.len()].copy_from_slice(raw);
+		slot_lens[*responses] = raw.len();
+		*responses += 1;
+		*pending -= 1;
+		if kind == Some(Response::Position) {
+			*positions -= 1;
+		}
+	} else {
+		f(event);
+	}
+}
+
+/// Kind of a sequence that terminals send in response to queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Response {
+	/// Cursor Position Report, which may also be a key.
+	Position,
+	/// Any other response.
+	Other,
+}
+
+/// Return the kind of response that `bytes` is, if it's a complete sequence
+/// that terminals send in response to queries.
+fn response_kind(bytes: &[u8]) -> Option<Response> {
+	let is_response = match bytes {
+		[0x1b, b']' | b'P' | b'_', ..] => {
+			bytes.ends_with(b"\x1b\\") || bytes.ends_with(b"\x07")
+		}
+		[0x1b, b'[', ..] => match Csi::parse(bytes) {
+			Ok((csi, len)) if len == bytes.len() => {
+				let inter = CsiInter::slice_as_bytes(csi.intermediate_bytes);
+				match (csi.private_marker(), inter, csi.final_byte.byte()) {
+					// CPR.
+					(None, [], b'R') => return Some(Response::Position),
+					// DSR and DECXCPR.
+					(None | Some(b'?'), [], b'n') | (Some(b'?'), [], b'R') => true,
+					// Primary, secondary and tertiary DA.
+					(Some(b'?' | b'>' | b'='), [], b'c') => true,
+					// DECRPM.
+					(None | Some(b'?'), [b'$'], b'y') => true,
+					// Kitty keyboard flags.
+					(Some(b'?'), [], b'u') => true,
+					_ => false,
+				}
+			}
+			_ => false,
+		},
+		_ => false,
+	};
+	is_response.then_some(Response::Other)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		key::{
+			KeyCode, KeyEvent,
+		},
+		pos::Pos,
+		query::{
+			ColorQuery, ColorTarget, CursorPositionQuery, DeviceStatus,
+			PrimaryAttributesQuery, Rgb16, StatusQuery,
+		},
+	};
+
+	use alloc::{
+		string::String,
+		vec::Vec,
+	};
+
+	/// Feed `bytes` to `demux` one byte at a time, and collect the keys that
+	/// are delivered, along with a `?` for every other event.
+	fn keys(demux: &mut Demux, bytes: &[u8]) -> String {
+		let mut keys = String::new();
+		for byte in bytes {
+			demux.advance(core::slice::from_ref(byte), |event| {
+				keys.push(match event {
+					Event::Key(KeyEvent { code: KeyCode::Char(c), .. }) => c,
+					_ => '?',
+				});
+			});
+		}
+		keys
+	}
+
+	#[test]
+	fn keeps_responses_between_keys() {
+		let mut demux = Demux::new();
+		let mut written = String::new();
+		demux.send(&ColorQuery(ColorTarget::Background), &mut written).unwrap();
+		demux.send(&CursorPositionQuery, &mut written).unwrap();
+		demux.send(&PrimaryAttributesQuery, &mut written).unwrap();
+		assert_eq!(demux.pending(), 3);
+
+		let input = concat!(
+			"a\x1b]11;rgb:1111/2222/3333\x1b\\b",
+			"\x1b[3;4Rc\x1b[?62;22cd",
+		);
+		assert_eq!(keys(&mut demux, input.as_bytes()), "abcd");
+		assert_eq!((demux.pending(), demux.responses()), (0, 3));
+
+		// Responses can be taken in any order.
+		let attributes = demux.take(&PrimaryAttributesQuery).unwrap();
+		assert_eq!(attributes.attributes(), [62, 22]);
+		assert_eq!(demux.take(&CursorPositionQuery), Some(Pos::new(2, 3)));
+		assert_eq!(demux.take(&CursorPositionQuery), None);
+		assert_eq!(
+			demux.take(&ColorQuery(ColorTarget::Background)),
+			Some(Rgb16 { r: 0x1111, g: 0x2222, b: 0x3333 }),
+		);
+		assert_eq!(demux.responses(), 0);
+	}
+
+	#[test]
+	fn keeps_cursor_positions_only_when_expected() {
+		let mut demux = Demux::new();
+		// Without a pending query, this is Shift+F3.
+		assert_eq!(keys(&mut demux, b"\x1b[1;2R"), "?");
+		demux.expect();
+		assert_eq!(keys(&mut demux, b"\x1b[1;2R\x1b[0n"), "?");
+		assert_eq!(demux.take(&StatusQuery), Some(DeviceStatus::Ok));
+
+		demux.expect_position();
+		assert_eq!(keys(&mut demux, b"\x1b[1;2R"), "");
+		assert_eq!(demux.take(&CursorPositionQuery), Some(Pos::new(0, 1)));
+	}
+
+	#[test]
+	fn delivers_responses_that_do_not_fit() {
+		let mut demux = Demux::new();
+		for _ in 0..Demux::SLOTS + 1 {
+			demux.expect();
+		}
+		let mut input = Vec::new();
+		for _ in 0..Demux::SLOTS + 1 {
+			input.extend_from_slice(b"\x1b[0n");
+		}
+		assert_eq!(keys(&mut demux, &input), "?");
+		assert_eq!(demux.responses(), Demux::SLOTS);
+
+		demux.cancel();
+		assert_eq!((demux.pending(), demux.responses()), (0, 0));
+		assert_eq!(demux.take(&StatusQuery), None);
+		assert_eq!(keys(&mut demux, b"\x1b[0n"), "?");
+	}
+
+	#[test]
+	fn does_not_keep_pastes() {
+		let mut demux = Demux::new();
+		demux.expect();
+		assert_eq!(keys(&mut demux, b"\x1b[200~\x1b[0n\x1b[201~x"), "?x");
+		assert_eq!((demux.pending(), demux.responses()), (1, 0));
+	}
+}
@@ -0,0 +1,112 @@
+use crate::{
+	capabilities::Feature,
+	parse::ParseError,
+	InvalidByte,
+};
+
+use core::fmt;
+
+/// Error returned when writing sequences, parsing input or querying the
+/// terminal fails.
+#[derive(Debug)]
+pub enum Error {
+	/// A formatter returned an error.
+	Fmt,
+	/// Reading from or writing to the terminal failed.
+	#[cfg(feature = "std")]
+	Io(std::io::Error),
+	/// Input isn't valid where it should be.
+	InvalidByte {
+		/// Position of the byte at which the input stopped being valid, which
+		/// is the start of the sequence for invalid sequences.
+		pos: usize,
+	},
+	/// Input isn't the sequence that it should be.
+	Invalid,
+	/// Input ended before it was complete.
+	Incomplete,
+	/// A response from the terminal is too long to be read.
+	TooLong,
+	/// The terminal doesn't support a feature that is needed.
+	Unsupported(Feature),
+	/// The terminal didn't respond in time.
+	Timeout,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Fmt => f.write_str("formatter error"),
+			#[cfg(feature = "std")]
+			Self::Io(error) => error.fmt(f),
+			Self::InvalidByte { pos } => write!(f, "invalid byte at position {pos}"),
+			Self::Invalid => f.write_str("invalid sequence"),
+			Self::Incomplete => f.write_str("incomplete input"),
+			Self::TooLong => f.write_str("response too long"),
+			Self::Unsupported(feature) => write!(f, "{feature:?} isn't supported"),
+			Self::Timeout => f.write_str("timed out"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Io(error) => Some(error),
+			_ => None,
+		}
+	}
+}
+
+impl From<fmt::Error> for Error {
+	#[inline(always)]
+	fn from(_: fmt::Error) -> Self {
+		Self::Fmt
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+	#[inline(always)]
+	fn from(value: std::io::Error) -> Self {
+		Self::Io(value)
+	}
+}
+
+impl From<ParseError> for Error {
+	#[inline(always)]
+	fn from(value: ParseError) -> Self {
+		match value {
+			ParseError::Incomplete => Self::Incomplete,
+			ParseError::Invalid => Self::Invalid,
+		}
+	}
+}
+
+impl From<InvalidByte> for Error {
+	#[inline(always)]
+	fn from(value: InvalidByte) -> Self {
+		Self::InvalidByte {
+			pos: value.position,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+	fn from(value: Error) -> Self {
+		use std::io::ErrorKind;
+		match value {
+			Error::Io(error) => error,
+			Error::Fmt => Self::other(value),
+			Error::InvalidByte { .. } => Self::new(ErrorKind::InvalidData, value),
+			Error::Invalid | Error::TooLong => {
+				Self::new(ErrorKind::InvalidData, value)
+			}
+			Error::Incomplete => Self::new(ErrorKind::UnexpectedEof, value),
+			Error::Unsupported(..) => Self::new(ErrorKind::Unsupported, value),
+			Error::Timeout => Self::new(ErrorKind::TimedOut, value),
+		}
+	}
+}
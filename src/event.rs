@@ -0,0 +1,891 @@
+use crate::{
+	csi::{
+		Csi, CsiParam
+	},
+	key::{
+		KeyCode, KeyEvent, Modifiers
+	},
+	mouse::MouseEvent,
+	parse::{
+		parse_string, ParamLimits, ParseError, BEL
+	},
+	pos::Pos,
+	query::{
+		ColorScheme, CursorPositionQuery, DeviceAttributes
+	},
+};
+
+/// Input received from a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'a> {
+	Key(KeyEvent),
+	Mouse(MouseEvent),
+	/// Text pasted while bracketed paste mode was enabled.
+	/// 
+	/// Pastes longer than [`Parser::CAPACITY`] are delivered as several
+	/// consecutive events.
+	Paste(&'a [u8]),
+	FocusGained,
+	FocusLost,
+	/// Size of the terminal, as reported by xterm's in-band resize
+	/// notifications.
+	Resize {
+		rows: u16,
+		columns: u16,
+	},
+	/// Position of the cursor, sent in response to a
+	/// [`CursorPositionQuery`].
+	CursorPositionReport(Pos),
+	DeviceAttributes(DeviceAttributes),
+	/// Color scheme that the user prefers, sent in response to a
+	/// [`ColorSchemeQuery`](crate::ColorSchemeQuery), and when it changes while
+	/// the terminal's private mode `2031` is set.
+	ColorSchemeChanged(ColorScheme),
+	/// Original bytes of a sequence that isn't otherwise recognized.
+	/// 
+	/// Writing these bytes back reproduces the input exactly. Sequences longer
+	/// than [`Parser::CAPACITY`] are delivered as several consecutive events.
+	Unrecognized(&'a [u8]),
+}
+
+/// Result of decoding bytes at the start of the parser's buffer.
+enum Decoded<'a> {
+	Event(Event<'a>),
+	PasteStart,
+}
+
+/// Kind of sequence that is being passed through after it overflowed the
+/// parser's buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overflow {
+	Csi,
+	String {
+		after_esc: bool,
+		/// Whether the string exceeded [`Parser::set_string_limit`], so the
+		/// rest of it is discarded instead of delivered.
+		discard: bool,
+	},
+}
+
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Parser of terminal input, which turns bytes into [`Event`]s.
+/// 
+/// Bytes can be fed in chunks of any size. Sequences that are split between
+/// chunks are kept until the rest of them arrive.
+#[derive(Debug, Clone)]
+pub struct Parser {
+	buf: [u8; Self::CAPACITY],
+	len: usize,
+	in_paste: bool,
+	overflow: Option<Overflow>,
+	pixel_mouse: bool,
+	param_limits: ParamLimits,
+	sanitize_paste: bool,
+	wheel_arrows: u8,
+	string_limit: usize,
+	string_len: usize,
+}
+
+impl Default for Parser {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Parser {
+	/// Maximum length of a sequence that can be buffered.
+	pub const CAPACITY: usize = 256;
+
+	/// Create a new [`Parser`] with no buffered bytes.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			buf: [0; Self::CAPACITY],
+			len: 0,
+			in_paste: false,
+			overflow: None,
+			pixel_mouse: false,
+			param_limits: ParamLimits::NONE,
+			sanitize_paste: false,
+			wheel_arrows: 0,
+			string_limit: usize::MAX,
+			string_len: 0,
+		}
+	}
+
+	/// Set whether SGR mouse reports are in pixels, which is the case while
+	/// [`MouseEncoding::SgrPixels`](crate::MouseEncoding::SgrPixels) is
+	/// enabled.
+	/// 
+	/// The terminal uses the same sequences for both encodings, so this must
+	/// be kept in sync with the enabled encoding for
+	/// [`MouseEvent::pixels`] to be correct.
+	#[inline(always)]
+	pub fn set_pixel_mouse(&mut self, pixel_mouse: bool) {
+		self.pixel_mouse = pixel_mouse;
+	}
+
+	/// Set the limits on the numeric parameters of CSI sequences, which are
+	/// [`ParamLimits::NONE`] by default.
+	/// 
+	/// Parameters after [`ParamLimits::max_count`] are ignored, and larger
+	/// values are clamped, except for the Unicode code points of keys in the
+	/// kitty keyboard protocol. [`ParamLimits::XTERM`] can be used to read
+	/// parameters like xterm does.
+	#[inline(always)]
+	pub fn set_param_limits(&mut self, limits: ParamLimits) {
+		self.param_limits = limits;
+	}
+
+	/// Set whether control characters are removed from pasted text before it
+	/// is delivered as [`Event::Paste`].
+	/// 
+	/// Pasted text may contain escape sequences, which would be run by a line
+	/// editor that writes it back to the terminal, or control characters that
+	/// are bound to commands. When this is enabled, every C0 and C1 control
+	/// character other than tab, line feed and carriage return is removed,
+	/// including escape characters.
+	#[inline(always)]
+	pub fn set_sanitize_paste(&mut self, sanitize_paste: bool) {
+		self.sanitize_paste = sanitize_paste;
+	}
+
+	/// Set how many arrow key events each turn of the mouse wheel is delivered
+	/// as, instead of [`Event::Mouse`].
+	/// 
+	/// This is meant for applications such as pagers that don't handle the
+	/// mouse, but still run while mouse reporting is enabled, where users
+	/// expect the wheel to scroll like it does in alternate scroll mode
+	/// (`1007`) when mouse reporting is disabled. The arrow keys have the
+	/// same modifiers as the wheel event. A value of `0` disables this, which
+	/// is the default.
+	#[inline(always)]
+	pub fn set_wheel_arrows(&mut self, wheel_arrows: u8) {
+		self.wheel_arrows = wheel_arrows;
+	}
+
+	/// Set the maximum number of bytes of each control string (OSC, DCS, SOS,
+	/// PM and APC) that are delivered, which is unlimited by default.
+	/// 
+	/// Control strings are delivered as [`Event::Unrecognized`], in several
+	/// events if they're longer than [`Self::CAPACITY`], until they're
+	/// terminated. With a limit, the bytes of a string after the first `limit`
+	/// are discarded until its terminator arrives, so input that never
+	/// terminates a string can't flood the application with events. Limits
+	/// below `2` act like `2`, because strings are only recognized by their
+	/// first two bytes.
+	#[inline(always)]
+	pub fn set_string_limit(&mut self, limit: usize) {
+		self.string_limit = limit.max(2);
+	}
+
+	/// Return `true` if the parser is in the middle of a sequence or paste, so
+	/// it needs more bytes before it can deliver the rest of the event.
+	/// 
+	/// Unlike [`Self::has_pending`], this includes the contents of a paste
+	/// whose end hasn't arrived yet, and a control string whose rest is being
+	/// discarded after [`Self::set_string_limit`].
+	#[inline(always)]
+	pub const fn needs_more_bytes(&self) -> bool {
+		self.in_paste || self.len > 0 || self.overflow.is_some()
+	}
+
+	/// Return `true` if the bytes of an incomplete sequence are buffered.
+	/// 
+	/// A lone escape character is buffered until the next byte arrives,
+	/// because it may be the Escape key or the start of a sequence. Calling
+	/// [`Self::flush`] when no more bytes arrive in time delivers it as the
+	/// key. The contents of a paste don't count.
+	#[inline(always)]
+	pub const fn has_pending(&self) -> bool {
+		!self.in_paste && (self.len > 0 || self.overflow.is_some())
+	}
+
+	/// Feed `bytes` to the parser, calling `f` with every complete event.
+	#[inline(always)]
+	pub fn advance(&mut self, bytes: &[u8], mut f: impl FnMut(Event<'_>)) {
+		self.advance_with_bytes(bytes, move |event, _| f(event));
+	}
+
+	/// Feed `bytes` to the parser, calling `f` with every complete event and
+	/// the bytes that it was decoded from.
+	/// 
+	/// For [`Event::Paste`], the bytes are the pasted text.
+	pub fn advance_with_bytes(
+		&mut self, bytes: &[u8], mut f: impl FnMut(Event<'_>, &[u8]),
+	) {
+		for &byte in bytes {
+			self.push(byte, &mut f);
+		}
+	}
+
+	/// Interpret any buffered bytes as if no more bytes are going to arrive.
+	/// 
+	/// This should be called when no input arrives for a short while after an
+	/// escape character, which is then reported as [`KeyCode::Escape`].
+	/// Incomplete sequences are reported as [`Event::Unrecognized`].
+	#[inline(always)]
+	pub fn flush(&mut self, mut f: impl FnMut(Event<'_>)) {
+		self.flush_with_bytes(move |event, _| f(event));
+	}
+
+	/// Interpret any buffered bytes like [`Self::flush`], calling `f` with
+	/// every event and the bytes that it was decoded from.
+	pub fn flush_with_bytes(&mut self, mut f: impl FnMut(Event<'_>, &[u8])) {
+		if self.in_paste && self.sanitize_paste {
+			self.len = sanitize_paste(&mut self.buf[..self.len]);
+		}
+		if self.len > 0 {
+			let bytes = &self.buf[..self.len];
+			f(match (self.in_paste, bytes) {
+				(true, ..) => Event::Paste(bytes),
+				(false, [0x1b]) => Event::Key(KeyEvent::new(KeyCode::Escape)),
+				(false, ..) => Event::Unrecognized(bytes),
+			}, bytes);
+		}
+		self.len = 0;
+		self.overflow = None;
+		self.string_len = 0;
+	}
+
+	fn push(&mut self, byte: u8, f: &mut impl FnMut(Event<'_>, &[u8])) {
+		if let Some(Overflow::String { after_esc, discard: true }) = self.overflow {
+			self.overflow = Some(Overflow::String {
+				after_esc: byte == 0x1b,
+				discard: true,
+			});
+			if byte == BEL || (after_esc && byte == b'\\') {
+				self.overflow = None;
+				self.string_len = 0;
+			}
+			return
+		}
+
+		if self.len == Self::CAPACITY {
+			self.make_room(f);
+		}
+		self.buf[self.len] = byte;
+		self.len += 1;
+
+		if self.in_paste {
+			if self.buf[..self.len].ends_with(PASTE_END) {
+				let mut paste_len = self.len - PASTE_END.len();
+				if self.sanitize_paste {
+					paste_len = sanitize_paste(&mut self.buf[..paste_len]);
+				}
+				if paste_len > 0 {
+					let paste = &self.buf[..paste_len];
+					f(Event::Paste(paste), paste);
+				}
+				self.len = 0;
+				self.in_paste = false;
+			}
+			return
+		}
+
+		if let Some(overflow) = self.overflow {
+			let is_end = match overflow {
+				Overflow::Csi => matches!(byte, 0x40..=0x7e),
+				Overflow::String { after_esc, .. } => {
+					self.overflow = Some(Overflow::String {
+						after_esc: byte == 0x1b,
+						discard: false,
+					});
+					byte == BEL || (after_esc && byte == b'\\')
+				}
+			};
+			if is_end {
+				let bytes = &self.buf[..self.len];
+				f(Event::Unrecognized(bytes), bytes);
+				self.len = 0;
+				self.overflow = None;
+				self.string_len = 0;
+			} else if let Some(Overflow::String { .. }) = self.overflow {
+				self.limit_string(f);
+			}
+			return
+		}
+
+		while self.len > 0 {
+			let len = match decode(
+				&self.buf[..self.len], self.pixel_mouse, self.param_limits,
+			) {
+				Ok((Decoded::Event(event), len)) => {
+					let bytes = &self.buf[..len];
+					match event {
+						Event::Mouse(mouse) if self.wheel_arrows > 0 => {
+							match mouse.wheel_key() {
+								Some(key) => for _ in 0..self.wheel_arrows {
+									f(Event::Key(key), bytes);
+								},
+								None => f(event, bytes),
+							}
+						}
+						_ => f(event, bytes),
+					}
+					len
+				}
+				Ok((Decoded::PasteStart, len)) => {
+					self.in_paste = true;
+					len
+				}
+				Err(..) => break,
+			};
+			self.consume(len);
+			if self.in_paste {
+				// Bytes after the start of the paste are now its contents.
+				break
+			}
+		}
+		if self.in_paste {
+			return
+		}
+		if let [0x1b, b']' | b'P' | b'X' | b'^' | b'_', ..] = self.buf[..self.len] {
+			self.limit_string(f);
+		}
+	}
+
+	/// Start discarding the incomplete control string that is buffered if it
+	/// reached [`Self::set_string_limit`], delivering what is buffered of it.
+	fn limit_string(&mut self, f: &mut impl FnMut(Event<'_>, &[u8])) {
+		if self.string_len + self.len < self.string_limit {
+			return
+		}
+		let bytes = &self.buf[..self.len];
+		f(Event::Unrecognized(bytes), bytes);
+		self.overflow = Some(Overflow::String {
+			after_esc: bytes.last() == Some(&0x1b),
+			discard: true,
+		});
+		self.len = 0;
+	}
+
+	/// Make room in the full buffer by delivering what it contains so far.
+	fn make_room(&mut self, f: &mut impl FnMut(Event<'_>, &[u8])) {
+		if self.in_paste {
+			// Keep the bytes that may be the start of the end of the paste.
+			let mut keep = (1..PASTE_END.len()).rev()
+				.find(|&n| self.buf[..self.len].ends_with(&PASTE_END[..n]))
+				.unwrap_or(0);
+			// Keep the first byte of a C1 control character too, so that it's
+			// still removed once the second byte arrives.
+			if self.sanitize_paste && self.buf[..self.len - keep].ends_with(&[0xc2]) {
+				keep += 1;
+			}
+			let mut paste_len = self.len - keep;
+			if self.sanitize_paste {
+				paste_len = sanitize_paste(&mut self.buf[..paste_len]);
+				self.buf.copy_within(self.len - keep..self.len, paste_len);
+				self.len = paste_len + keep;
+			}
+			if paste_len > 0 {
+				let paste = &self.buf[..paste_len];
+				f(Event::Paste(paste), paste);
+			}
+			self.consume(paste_len);
+			return
+		}
+
+		let bytes = &self.buf[..self.len];
+		if self.overflow.is_none() {
+			self.overflow = match bytes {
+				[0x1b, b'[', ..] => Some(Overflow::Csi),
+				[.., last] => Some(Overflow::String {
+					after_esc: *last == 0x1b,
+					discard: false,
+				}),
+				[] => None,
+			};
+		} else if let Some(Overflow::String { .. }) = self.overflow {
+			self.overflow = Some(Overflow::String {
+				after_esc: bytes.last() == Some(&0x1b),
+				discard: false,
+			});
+		}
+		if let Some(Overflow::String { .. }) = self.overflow {
+			self.string_len += self.len;
+		}
+		f(Event::Unrecognized(bytes), bytes);
+		self.len = 0;
+	}
+
+	fn consume(&mut self, len: usize) {
+		self.buf.copy_within(len..self.len, 0);
+		self.len -= len;
+	}
+}
+
+/// Remove the control characters that [`Parser::set_sanitize_paste`] removes
+/// from `bytes` in place, returning the length of what is left.
+fn sanitize_paste(bytes: &mut [u8]) -> usize {
+	let mut len = 0;
+	let mut i = 0;
+	while i < bytes.len() {
+		let remove = match bytes[i] {
+			b'\t' | b'\n' | b'\r' => 0,
+			0x00..=0x1f | 0x7f => 1,
+			// C1 control characters are encoded as U+0080 to U+009F.
+			0xc2 if matches!(bytes.get(i + 1), Some(0x80..=0x9f)) => 2,
+			_ => 0,
+		};
+		if remove > 0 {
+			i += remove;
+			continue
+		}
+		bytes[len] = bytes[i];
+		len += 1;
+		i += 1;
+	}
+	len
+}
+
+/// Decode one event from the start of `bytes`.
+/// 
+/// This only fails if `bytes` is incomplete. Invalid bytes are decoded as
+/// [`Event::Unrecognized`].
+fn decode(
+	bytes: &[u8], pixel_mouse: bool, limits: ParamLimits,
+) -> Result<(Decoded<'_>, usize), ParseError> {
+	match decode_event(bytes, pixel_mouse, limits) {
+		Err(ParseError::Invalid) => {
+			let len = invalid_len(bytes);
+			Ok((Decoded::Event(Event::Unrecognized(&bytes[..len])), len))
+		}
+		result => result,
+	}
+}
+
+/// Return the length of the invalid sequence at the start of `bytes`.
+fn invalid_len(bytes: &[u8]) -> usize {
+	match bytes {
+		[0x1b, b'[', rest @ ..] => match Csi::parse(bytes) {
+			Ok((.., len)) => len,
+			Err(..) => {
+				2 + rest.iter().take_while(|&&b| matches!(b, 0x20..=0x3f)).count()
+			}
+		},
+		[0x1b, b'O', 0x40..=0x7e, ..] => 3,
+		[0x1b, b'O', ..] => 2,
+		_ => 1,
+	}
+}
+
+/// Decode one event from the start of `bytes`, failing if it's invalid.
+fn decode_event(
+	bytes: &[u8], pixel_mouse: bool, limits: ParamLimits,
+) -> Result<(Decoded<'_>, usize), ParseError> {
+	match bytes {
+		[0x1b, rest @ ..] => match rest {
+			[] => Err(ParseError::Incomplete),
+			[b'[', ..] => decode_csi(bytes, pixel_mouse, limits),
+			[b'O', rest @ ..] => {
+				let &byte = rest.first().ok_or(ParseError::Incomplete)?;
+				let code = ss3_key(byte).ok_or(ParseError::Invalid)?;
+				Ok((Decoded::Event(Event::Key(KeyEvent::new(code))), 3))
+			}
+			[intro @ (b']' | b'P' | b'X' | b'^' | b'_'), ..] => {
+				let (.., len) = parse_string(bytes, *intro)?;
+				Ok((unrecognized(bytes, len), len))
+			}
+			[0x1b, ..] => Ok((key_event(KeyEvent::new(KeyCode::Escape)), 1)),
+			_ => {
+				let (key, len) = decode_key(rest)?;
+				let key = key.with_modifiers(key.modifiers | Modifiers::ALT);
+				Ok((key_event(key), len + 1))
+			}
+		},
+		_ => decode_key(bytes).map(move |(key, len)| (key_event(key), len)),
+	}
+}
+
+#[inline(always)]
+fn key_event<'a>(key: KeyEvent) -> Decoded<'a> {
+	Decoded::Event(Event::Key(key))
+}
+
+#[inline(always)]
+fn unrecognized(bytes: &[u8], len: usize) -> Decoded<'_> {
+	Decoded::Event(Event::Unrecognized(&bytes[..len]))
+}
+
+/// Decode a key press that isn't part of an escape sequence.
+fn decode_key(bytes: &[u8]) -> Result<(KeyEvent, usize), ParseError> {
+	let &first = bytes.first().ok_or(ParseError::Incomplete)?;
+	let ctrl = move |c: u8| {
+		KeyEvent::new(KeyCode::Char(c as char)).with_modifiers(Modifiers::CTRL)
+	};
+	let key = match first {
+		b'\r' | b'\n' => KeyEvent::new(KeyCode::Enter),
+		b'\t' => KeyEvent::new(KeyCode::Tab),
+		0x08 | 0x7f => KeyEvent::new(KeyCode::Backspace),
+		0x1b => KeyEvent::new(KeyCode::Escape),
+		0x00 => ctrl(b' '),
+		0x01..=0x1a => ctrl(b'a' + first - 1),
+		0x1c..=0x1f => ctrl(b'4' + first - 0x1c),
+		0x20..=0x7e => KeyEvent::new(KeyCode::Char(first as char)),
+		_ => {
+			let len = match first {
+				0xc0..=0xdf => 2,
+				0xe0..=0xef => 3,
+				0xf0..=0xf7 => 4,
+				_ => return Err(ParseError::Invalid),
+			};
+			let bytes = bytes.get(..len).ok_or(ParseError::Incomplete)?;
+			let c = core::str::from_utf8(bytes).ok()
+				.and_then(move |s| s.chars().next())
+				.ok_or(ParseError::Invalid)?;
+			return Ok((KeyEvent::new(KeyCode::Char(c)), len))
+		}
+	};
+	Ok((key, 1))
+}
+
+/// Return the key for an SS3 sequence (`ESC O`) with the specified final
+/// byte.
+const fn ss3_key(byte: u8) -> Option<KeyCode> {
+	Some(match byte {
+		b'A' => KeyCode::Up,
+		b'B' => KeyCode::Down,
+		b'C' => KeyCode::Right,
+		b'D' => KeyCode::Left,
+		b'H' => KeyCode::Home,
+		b'F' => KeyCode::End,
+		b'M' => KeyCode::Enter,
+		b'P' => KeyCode::F(1),
+		b'Q' => KeyCode::F(2),
+		b'R' => KeyCode::F(3),
+		b'S' => KeyCode::F(4),
+		_ => return None,
+	})
+}
+
+/// Return the key for a `CSI Ps ~` sequence.
+const fn tilde_key(code: u16) -> Option<KeyCode> {
+	Some(match code {
+		1 | 7 => KeyCode::Home,
+		2 => KeyCode::Insert,
+		3 => KeyCode::Delete,
+		4 | 8 => KeyCode::End,
+		5 => KeyCode::PageUp,
+		6 => KeyCode::PageDown,
+		11..=15 => KeyCode::F((code - 10) as u8),
+		17..=21 => KeyCode::F((code - 11) as u8),
+		23..=26 => KeyCode::F((code - 12) as u8),
+		28 | 29 => KeyCode::F((code - 15) as u8),
+		31..=34 => KeyCode::F((code - 17) as u8),
+		_ => return None,
+	})
+}
+
+/// Return the key code of a `CSI u` sequence, which is its first parameter,
+/// without limiting it to a [`u16`] like [`CsiParams`](crate::CsiParams) does,
+/// since it can be a code point outside of the Basic Multilingual Plane.
+fn kitty_key_code(params: &[u8]) -> u32 {
+	params.iter()
+		.take_while(move |&&b| b != b';' && b != b':')
+		.filter(move |b| b.is_ascii_digit())
+		.fold(0, move |code: u32, &b| {
+			code.saturating_mul(10).saturating_add((b - b'0') as u32)
+		})
+}
+
+fn decode_csi(
+	bytes: &[u8], pixel_mouse: bool, limits: ParamLimits,
+) -> Result<(Decoded<'_>, usize), ParseError> {
+	let (csi, len) = Csi::parse(bytes)?;
+	let unknown = move || unrecognized(bytes, len);
+	if !csi.intermediate_bytes.is_empty() {
+		return Ok((unknown(), len))
+	}
+
+	let mut params = csi.params_with(limits);
+	let first = params.next().flatten();
+	let second = params.next().flatten();
+	let with_mods = move |code| {
+		let modifiers = second.map_or(Modifiers::NONE, Modifiers::from_param);
+		key_event(KeyEvent::new(code).with_modifiers(modifiers))
+	};
+
+	let decoded = match (csi.private_marker(), csi.final_byte.byte()) {
+		(None, b'M') if csi.parameter_bytes.is_empty() => {
+			// X10-compatible mouse report, encoded as three raw bytes.
+			let raw = bytes.get(len..len + 3).ok_or(ParseError::Incomplete)?;
+			let [code, column, row] = [0, 1, 2].map(move |i| {
+				(raw[i] as u16).saturating_sub(32)
+			});
+			let event = MouseEvent::from_code(code, row, column, false)
+				.ok_or(ParseError::Invalid)?;
+			return Ok((Decoded::Event(Event::Mouse(event)), len + 3))
+		}
+		(Some(b'<'), final_byte @ (b'M' | b'm')) => {
+			let row = params.next().flatten().ok_or(ParseError::Invalid)?;
+			let event = MouseEvent::from_code(
+				first.unwrap_or(0), row, second.unwrap_or(1), final_byte == b'm',
+			).ok_or(ParseError::Invalid)?;
+			Decoded::Event(Event::Mouse(MouseEvent {
+				pixels: pixel_mouse,
+				..event
+			}))
+		}
+		(None, b'M') => {
+			// urxvt (1015) mouse report, with the code offset like X10's.
+			let row = params.next().flatten().ok_or(ParseError::Invalid)?;
+			let code = first.ok_or(ParseError::Invalid)?.saturating_sub(32);
+			let event = MouseEvent::from_code(
+				code, row, second.unwrap_or(1), false,
+			).ok_or(ParseError::Invalid)?;
+			Decoded::Event(Event::Mouse(event))
+		}
+		(Some(b'?'), b'c') => {
+			let (attributes, ..) = DeviceAttributes::parse(bytes)?;
+			Decoded::Event(Event::DeviceAttributes(attributes))
+		}
+		(Some(b'?'), b'n') => match ColorScheme::parse_report(bytes) {
+			Ok((scheme, ..)) => Decoded::Event(Event::ColorSchemeChanged(scheme)),
+			Err(..) => unknown(),
+		},
+		(None, b'R') => match CursorPositionQuery::parse_report(bytes) {
+			Ok((pos, ..)) => Decoded::Event(Event::CursorPositionReport(pos)),
+			Err(..) => with_mods(KeyCode::F(3)),
+		},
+		(None, b'~') => match first {
+			Some(200) => Decoded::PasteStart,
+			// End of a paste that was never started.
+			Some(201) => unknown(),
+			_ => match tilde_key(first.unwrap_or(0)) {
+				Some(code) => with_mods(code),
+				None => unknown(),
+			},
+		},
+		(None, b'u') => {
+			let code = kitty_key_code(CsiParam::slice_as_bytes(csi.parameter_bytes));
+			match KeyCode::from_kitty_code(code) {
+				Some(code) => with_mods(code),
+				None => unknown(),
+			}
+		}
+		(None, b'Z') => key_event(
+			KeyEvent::new(KeyCode::Tab).with_modifiers(Modifiers::SHIFT)
+		),
+		(None, b'I') => Decoded::Event(Event::FocusGained),
+		(None, b'O') => Decoded::Event(Event::FocusLost),
+		(None, b't') if first == Some(48) => Decoded::Event(Event::Resize {
+			rows: second.unwrap_or(0),
+			columns: params.next().flatten().unwrap_or(0),
+		}),
+		(None, final_byte) => match ss3_key(final_byte) {
+			Some(code) => with_mods(code),
+			None => unknown(),
+		},
+		_ => unknown(),
+	};
+	Ok((decoded, len))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mouse::{
+		MouseButton, MouseEventKind,
+	};
+
+	use alloc::vec::Vec;
+
+	/// Event with the bytes that it borrows copied, so that it can be kept.
+	#[derive(Debug, PartialEq)]
+	enum Input {
+		Event(Event<'static>),
+		Paste(Vec<u8>),
+		Unrecognized(Vec<u8>),
+	}
+
+	/// Copy the bytes that `event` borrows.
+	fn to_input(event: Event<'_>) -> Input {
+		match event {
+			Event::Paste(bytes) => Input::Paste(bytes.to_vec()),
+			Event::Unrecognized(bytes) => Input::Unrecognized(bytes.to_vec()),
+			Event::Key(key) => Input::Event(Event::Key(key)),
+			Event::Mouse(mouse) => Input::Event(Event::Mouse(mouse)),
+			Event::FocusGained => Input::Event(Event::FocusGained),
+			Event::FocusLost => Input::Event(Event::FocusLost),
+			Event::Resize { rows, columns } => {
+				Input::Event(Event::Resize { rows, columns })
+			}
+			Event::CursorPositionReport(pos) => {
+				Input::Event(Event::CursorPositionReport(pos))
+			}
+			Event::DeviceAttributes(attributes) => {
+				Input::Event(Event::DeviceAttributes(attributes))
+			}
+			Event::ColorSchemeChanged(scheme) => {
+				Input::Event(Event::ColorSchemeChanged(scheme))
+			}
+		}
+	}
+
+	/// Feed `bytes` to `parser`, in one chunk or one byte at a time, and
+	/// collect the events, joining the parts of each paste.
+	fn feed(
+		parser: &mut Parser, bytes: &[u8], byte_at_a_time: bool,
+	) -> Vec<Input> {
+		let mut inputs = Vec::new();
+		let mut push = |event: Event<'_>| {
+			if let (Event::Paste(bytes), Some(Input::Paste(paste)))
+				= (event, inputs.last_mut())
+			{
+				return paste.extend_from_slice(bytes)
+			}
+			inputs.push(to_input(event));
+		};
+		if byte_at_a_time {
+			for byte in bytes {
+				parser.advance(core::slice::from_ref(byte), &mut push);
+			}
+		} else {
+			parser.advance(bytes, &mut push);
+		}
+		inputs
+	}
+
+	fn key(code: KeyCode, modifiers: Modifiers) -> Input {
+		Input::Event(Event::Key(KeyEvent::new(code).with_modifiers(modifiers)))
+	}
+
+	fn mouse(kind: MouseEventKind, row: u16, column: u16) -> Input {
+		Input::Event(Event::Mouse(MouseEvent {
+			kind,
+			row,
+			column,
+			modifiers: Modifiers::NONE,
+			pixels: false,
+		}))
+	}
+
+	#[test]
+	fn decodes_the_same_events_byte_at_a_time() {
+		let input = concat!(
+			"a\x1bb\x1b[A\x1b[1;5C\x1bOP",
+			// SGR, X10 and urxvt mouse reports.
+			"\x1b[<0;12;5M\x1b[<0;12;5m\x1b[M !#\x1b[32;3;4M",
+			// A cursor position report, and F3 without modifiers, which
+			// would be a report if it had two parameters.
+			"\x1b[7;9R\x1b[R",
+			"\x1b[200~pasted\x1b[201~\x1b[I\x1b[99x",
+		).as_bytes();
+		let expected = [
+			key(KeyCode::Char('a'), Modifiers::NONE),
+			key(KeyCode::Char('b'), Modifiers::ALT),
+			key(KeyCode::Up, Modifiers::NONE),
+			key(KeyCode::Right, Modifiers::CTRL),
+			key(KeyCode::F(1), Modifiers::NONE),
+			mouse(MouseEventKind::Down(MouseButton::Left), 5, 12),
+			mouse(MouseEventKind::Up(Some(MouseButton::Left)), 5, 12),
+			mouse(MouseEventKind::Down(MouseButton::Left), 3, 1),
+			mouse(MouseEventKind::Down(MouseButton::Left), 4, 3),
+			Input::Event(Event::CursorPositionReport(Pos::new(6, 8))),
+			key(KeyCode::F(3), Modifiers::NONE),
+			Input::Paste(b"pasted".to_vec()),
+			Input::Event(Event::FocusGained),
+			Input::Unrecognized(b"\x1b[99x".to_vec()),
+		];
+		for byte_at_a_time in [false, true] {
+			let mut parser = Parser::new();
+			assert_eq!(feed(&mut parser, input, byte_at_a_time), expected);
+			assert!(!parser.needs_more_bytes());
+		}
+	}
+
+	#[test]
+	fn keeps_a_lone_escape_until_flushed() {
+		let mut parser = Parser::new();
+		assert!(feed(&mut parser, b"\x1b", false).is_empty());
+		assert!(parser.has_pending());
+		let mut events = Vec::new();
+		parser.flush(|event| events.push(to_input(event)));
+		assert_eq!(events, [key(KeyCode::Escape, Modifiers::NONE)]);
+		assert!(!parser.needs_more_bytes());
+	}
+
+	#[test]
+	fn delivers_pastes_longer_than_the_buffer() {
+		// The end of the paste is split by the point at which the buffer is
+		// full, so part of it must be kept.
+		for split in 0..PASTE_END.len() {
+			let mut input = b"\x1b[200~".to_vec();
+			let text: Vec<u8> = (0..Parser::CAPACITY * 2 - split)
+				.map(move |i| b'a' + (i % 26) as u8)
+				.collect();
+			input.extend_from_slice(&text);
+			input.extend_from_slice(PASTE_END);
+			input.push(b'z');
+			for byte_at_a_time in [false, true] {
+				let mut parser = Parser::new();
+				assert_eq!(feed(&mut parser, &input, byte_at_a_time), [
+					Input::Paste(text.clone()),
+					key(KeyCode::Char('z'), Modifiers::NONE),
+				]);
+			}
+		}
+	}
+
+	#[test]
+	fn sanitizes_pastes_across_the_buffer() {
+		let mut input = b"\x1b[200~".to_vec();
+		// The C1 control character NEL is split by the point at which the
+		// buffer is full.
+		let text = [b'x'; Parser::CAPACITY - 1];
+		input.extend_from_slice(&text);
+		input.extend_from_slice("\u{85}\x1b[31my\ty\x1b[201~".as_bytes());
+
+		let mut expected = text.to_vec();
+		expected.extend_from_slice(b"[31my\ty");
+		for byte_at_a_time in [false, true] {
+			let mut parser = Parser::new();
+			parser.set_sanitize_paste(true);
+			assert_eq!(
+				feed(&mut parser, &input, byte_at_a_time),
+				[Input::Paste(expected.clone())],
+			);
+		}
+	}
+
+	#[test]
+	fn passes_long_strings_through() {
+		let mut input = b"\x1b]52;c;".to_vec();
+		input.resize(Parser::CAPACITY * 3, b'A');
+		input.extend_from_slice(b"\x1b\\q");
+
+		let mut parser = Parser::new();
+		let inputs = feed(&mut parser, &input, false);
+		let (last, parts) = inputs.split_last().unwrap();
+		assert_eq!(*last, key(KeyCode::Char('q'), Modifiers::NONE));
+		let mut string = Vec::new();
+		for part in parts {
+			let Input::Unrecognized(bytes) = part else {
+				panic!("{part:?} isn't part of the string");
+			};
+			string.extend_from_slice(bytes);
+		}
+		assert_eq!(string, input[..input.len() - 1]);
+	}
+
+	#[test]
+	fn discards_strings_over_the_limit() {
+		let mut input = b"\x1b]0;".to_vec();
+		input.resize(Parser::CAPACITY * 3, b'A');
+		input.extend_from_slice(b"\x07q\x1b]0;ok\x07");
+
+		for byte_at_a_time in [false, true] {
+			let mut parser = Parser::new();
+			parser.set_string_limit(10);
+			assert_eq!(feed(&mut parser, &input, byte_at_a_time), [
+				Input::Unrecognized(input[..10].to_vec()),
+				key(KeyCode::Char('q'), Modifiers::NONE),
+				Input::Unrecognized(b"\x1b]0;ok\x07".to_vec()),
+			]);
+			assert!(!parser.needs_more_bytes());
+		}
+	}
+}
@@ -0,0 +1,312 @@
+use crate::{
+	csi::Csi,
+	scan::{
+		Scanner, Token
+	},
+};
+
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+
+/// Virtual terminal that interprets text, cursor movement and erase sequences
+/// written to it, keeping track of the text that would be visible.
+/// 
+/// Styles and other sequences are ignored. Lines that scroll off the top of the
+/// screen are kept, so the whole output can be retrieved with
+/// [`Self::plain_text`].
+#[derive(Debug, Clone)]
+pub struct FakeTerminal {
+	width: u16,
+	height: u16,
+	lines: Vec<Vec<char>>,
+	/// Index of the line the cursor is on in `lines`.
+	row: usize,
+	column: usize,
+	saved: (usize, usize),
+	scanner: Scanner,
+}
+
+impl FakeTerminal {
+	/// Create an empty [`FakeTerminal`] with the specified screen size.
+	pub fn new(width: u16, height: u16) -> Self {
+		Self {
+			width: width.max(1),
+			height: height.max(1),
+			lines: Vec::new(),
+			row: 0,
+			column: 0,
+			saved: (0, 0),
+			scanner: Scanner::new(),
+		}
+	}
+
+	/// Return the width of the screen, in columns.
+	#[inline(always)]
+	pub const fn width(&self) -> u16 {
+		self.width
+	}
+
+	/// Return the height of the screen, in rows.
+	#[inline(always)]
+	pub const fn height(&self) -> u16 {
+		self.height
+	}
+
+	/// Return the 0-based row and column of the cursor on the screen.
+	#[inline(always)]
+	pub fn cursor(&self) -> (usize, usize) {
+		(self.row.saturating_sub(self.top()), self.column)
+	}
+
+	/// Return the number of lines, including those scrolled off the screen.
+	#[inline(always)]
+	pub fn line_count(&self) -> usize {
+		self.lines.len().max(self.row + 1)
+	}
+
+	/// Return the characters on a line, without trailing blanks.
+	pub fn line(&self, idx: usize) -> &[char] {
+		let line = self.lines.get(idx).map_or(&[][..], Vec::as_slice);
+		let len = line.iter().rposition(move |&c| c != ' ').map_or(0, |i| i + 1);
+		&line[..len]
+	}
+
+	/// Return the text of every line, joined with newlines.
+	/// 
+	/// Trailing blanks on each line and trailing empty lines are removed.
+	pub fn plain_text(&self) -> String {
+		let count = (0..self.lines.len()).rev()
+			.find(|&idx| !self.line(idx).is_empty())
+			.map_or(0, |idx| idx + 1);
+		let mut text = String::new();
+		for idx in 0..count {
+			text.extend(self.line(idx));
+			text.push('\n');
+		}
+		text
+	}
+
+	/// Interpret `bytes` as output written to the terminal.
+	pub fn feed(&mut self, bytes: &[u8]) {
+		let mut scanner = core::mem::take(&mut self.scanner);
+		scanner.feed(bytes, |token| self.apply(token));
+		self.scanner = scanner;
+	}
+
+	/// Index of the first line of the screen in `self.lines`.
+	#[inline(always)]
+	fn top(&self) -> usize {
+		self.line_count().saturating_sub(self.height as usize)
+	}
+
+	fn line_mut(&mut self, idx: usize) -> &mut Vec<char> {
+		if self.lines.len() <= idx {
+			self.lines.resize_with(idx + 1, Vec::new);
+		}
+		&mut self.lines[idx]
+	}
+
+	fn put(&mut self, c: char) {
+		if self.column >= self.width as usize {
+			self.column = 0;
+			self.row += 1;
+		}
+		let column = self.column;
+		let line = self.line_mut(self.row);
+		if line.len() <= column {
+			line.resize(column + 1, ' ');
+		}
+		line[column] = c;
+		self.column += 1;
+	}
+
+	fn apply(&mut self, token: Token<'_>) {
+		match token {
+			Token::Text(text) => text.chars().for_each(|c| self.put(c)),
+			Token::Control(b'\r') => self.column = 0,
+			Token::Control(b'\n' | 0x0b | 0x0c) => {
+				// Output is assumed to have newlines translated to CR LF.
+				self.column = 0;
+				self.row += 1;
+			}
+			Token::Control(0x08) => self.column = self.column.saturating_sub(1),
+			Token::Control(b'\t') => {
+				let stop = (self.column / 8 + 1) * 8;
+				self.column = stop.min(self.width as usize - 1);
+			}
+			Token::Csi(csi) => self.apply_csi(&csi),
+			Token::Escape(b"\x1b7") => self.saved = self.cursor(),
+			Token::Escape(b"\x1b8") => self.restore(),
+			Token::Escape(b"\x1bM") => self.reverse_index(),
+			Token::Escape(b"\x1bE") => {
+				self.column = 0;
+				self.row += 1;
+			}
+			_ => {}
+		}
+	}
+
+	/// Move the cursor up a line, scrolling the screen down if it's on the top
+	/// line.
+	fn reverse_index(&mut self) {
+		let top = self.top();
+		if self.row > top {
+			self.row -= 1;
+			return
+		}
+		// The bottom line of the screen is lost.
+		let bottom = top + self.height as usize;
+		if self.lines.len() < top {
+			self.lines.resize_with(top, Vec::new);
+		}
+		self.lines.insert(top, Vec::new());
+		self.lines.truncate(bottom);
+	}
+
+	fn restore(&mut self) {
+		let (row, column) = self.saved;
+		self.row = self.top() + row;
+		self.column = column;
+	}
+
+	fn apply_csi(&mut self, csi: &Csi<'_>) {
+		if csi.private_marker().is_some() || !csi.intermediate_bytes.is_empty() {
+			return
+		}
+
+		let mut params = csi.params();
+		let first = params.next().flatten();
+		let n = first.unwrap_or(1).max(1) as usize;
+		let top = self.top();
+		let max_column = self.width as usize - 1;
+		let max_row = top + self.height as usize - 1;
+
+		match csi.final_byte.byte() {
+			b'A' => self.row = self.row.saturating_sub(n).max(top),
+			b'B' => self.row = (self.row + n).min(max_row),
+			b'C' => self.column = (self.column + n).min(max_column),
+			b'D' => self.column = self.column.saturating_sub(n).min(max_column),
+			b'E' => {
+				self.row = (self.row + n).min(max_row);
+				self.column = 0;
+			}
+			b'F' => {
+				self.row = self.row.saturating_sub(n).max(top);
+				self.column = 0;
+			}
+			b'G' | b'`' => self.column = (n - 1).min(max_column),
+			b'd' => self.row = (top + n - 1).min(max_row),
+			b'H' | b'f' => {
+				let column = params.next().flatten().unwrap_or(1).max(1) as usize;
+				self.row = (top + n - 1).min(max_row);
+				self.column = (column - 1).min(max_column);
+			}
+			b'J' => self.erase_display(first.unwrap_or(0)),
+			b'K' => self.erase_line(first.unwrap_or(0)),
+			b'X' => {
+				let column = self.column;
+				let line = self.line_mut(self.row);
+				let end = (column + n).min(line.len());
+				if column < end {
+					line[column..end].fill(' ');
+				}
+			}
+			b'P' => {
+				let column = self.column;
+				let line = self.line_mut(self.row);
+				if column < line.len() {
+					line.drain(column..(column + n).min(line.len()));
+				}
+			}
+			b'@' => {
+				let (column, width) = (self.column, self.width as usize);
+				let line = self.line_mut(self.row);
+				if column < line.len() {
+					line.splice(column..column, core::iter::repeat_n(' ', n));
+					line.truncate(width);
+				}
+			}
+			b's' => self.saved = self.cursor(),
+			b'u' => self.restore(),
+			_ => {}
+		}
+	}
+
+	fn erase_line(&mut self, mode: u16) {
+		let column = self.column;
+		let line = self.line_mut(self.row);
+		match mode {
+			0 => line.truncate(column),
+			1 => {
+				let end = (column + 1).min(line.len());
+				line[..end].fill(' ');
+			}
+			_ => line.clear(),
+		}
+	}
+
+	fn erase_display(&mut self, mode: u16) {
+		let top = self.top();
+		let end = self.lines.len();
+		match mode {
+			0 => {
+				self.erase_line(0);
+				self.lines.get_mut(self.row + 1..end)
+					.unwrap_or_default()
+					.iter_mut().for_each(Vec::clear);
+			}
+			1 => {
+				self.erase_line(1);
+				self.lines.get_mut(top..self.row)
+					.unwrap_or_default()
+					.iter_mut().for_each(Vec::clear);
+			}
+			2 => {
+				self.lines.get_mut(top..end)
+					.unwrap_or_default()
+					.iter_mut().for_each(Vec::clear);
+			}
+			_ => {
+				// Only lines that scrolled off the screen are erased.
+				self.lines.drain(..top.min(end));
+				self.row = self.row.saturating_sub(top);
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for FakeTerminal {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.feed(buf);
+		Ok(buf.len())
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+impl core::fmt::Write for FakeTerminal {
+	#[inline(always)]
+	fn write_str(&mut self, s: &str) -> core::fmt::Result {
+		self.feed(s.as_bytes());
+		Ok(())
+	}
+}
+
+/// Convert terminal output into the text that it would leave on a terminal
+/// with the specified screen size.
+/// 
+/// Unlike stripping escape sequences, this applies cursor movement and
+/// erasing, so output that redraws lines (such as progress bars) is reduced to
+/// its final state.
+pub fn to_plain_text(bytes: &[u8], width: u16, height: u16) -> String {
+	let mut terminal = FakeTerminal::new(width, height);
+	terminal.feed(bytes);
+	terminal.plain_text()
+}
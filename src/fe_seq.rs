@@ -1,8 +1,13 @@
 use super::{
-	byte_wrapper, byte_utf8able
+	byte_wrapper, byte_utf8able, byte_from_str
 };
 
-use crate::csi::Csi;
+use crate::{
+	csi::Csi,
+	parse::{
+		expect_intro, parse_string, FromSequence, ParseError
+	},
+};
 
 /// ASCII escape character string.
 pub const ESC_STR: &str = "\x1b";
@@ -39,11 +44,17 @@ pub enum FeSeq<'a> {
 	/// 
 	/// Intended to allow an arbitrary Unicode character to be printed.
 	/// This is to be followed by that character, most likely encoded in UTF-1.
+	/// 
+	/// This only represents the introducer. Use [`SingleGraphicCharacter`] to
+	/// write it along with the character.
 	Sgci,
 	/// Single Character Introducer.
 	/// 
 	/// To be followed by a [`Printable`] or [`FormatEffector`], which will be
 	/// printed as ASCII no matter what graphic or control sets are in use.
+	/// 
+	/// This only represents the introducer. Use [`SingleCharacter`] to write it
+	/// along with the character.
 	Sci,
 	Csi(Csi<'a>),
 	StringTerminator,
@@ -57,69 +68,510 @@ impl<'a> FeSeq<'a> {
 	/// sequence.
 	pub fn kind_byte(&self) -> u8 {
 		match self {
-			Self::Pad => 0x80,
-			Self::HighOctetPreset => 0x81,
-			Self::BreakPermittedHere => 0x82,
-			Self::NoBreakHere => 0x83,
-			Self::Index => 0x84,
-			Self::NextLine => 0x85,
-			Self::StartOfSelArea => 0x86,
-			Self::EndOfSelArea => 0x87,
-			Self::HorizTabSet => 0x88,
-			Self::RightJustify => 0x89,
-			Self::VertTabSet => 0x8a,
-			Self::PartLineDown => 0x8b,
-			Self::PartLineUp => 0x8c,
-			Self::ReverseIndex => 0x8d,
-			Self::SingleShift2 => 0x8e,
-			Self::SingleShift3 => 0x8f,
-			Self::DeviceControlString(..) => 0x90,
-			Self::PrivateUse1 => 0x91,
-			Self::PrivateUse2 => 0x92,
-			Self::SetTransmitState => 0x93,
-			Self::CancelCharacter => 0x94,
-			Self::MessageWaiting => 0x95,
-			Self::StartOfProtArea => 0x96,
-			Self::EndOfProtArea => 0x97,
-			Self::StartOfString(..) => 0x97,
-			Self::Sgci => 0x99,
-			Self::Sci => 0x9a,
+			Self::Pad => 0x40,
+			Self::HighOctetPreset => 0x41,
+			Self::BreakPermittedHere => 0x42,
+			Self::NoBreakHere => 0x43,
+			Self::Index => 0x44,
+			Self::NextLine => 0x45,
+			Self::StartOfSelArea => 0x46,
+			Self::EndOfSelArea => 0x47,
+			Self::HorizTabSet => 0x48,
+			Self::RightJustify => 0x49,
+			Self::VertTabSet => 0x4a,
+			Self::PartLineDown => 0x4b,
+			Self::PartLineUp => 0x4c,
+			Self::ReverseIndex => 0x4d,
+			Self::SingleShift2 => 0x4e,
+			Self::SingleShift3 => 0x4f,
+			Self::DeviceControlString(..) => 0x50,
+			Self::PrivateUse1 => 0x51,
+			Self::PrivateUse2 => 0x52,
+			Self::SetTransmitState => 0x53,
+			Self::CancelCharacter => 0x54,
+			Self::MessageWaiting => 0x55,
+			Self::StartOfProtArea => 0x56,
+			Self::EndOfProtArea => 0x57,
+			Self::StartOfString(..) => 0x58,
+			Self::Sgci => 0x59,
+			Self::Sci => 0x5a,
 			Self::Csi(..) => Csi::INTRO_BYTE,
-			Self::StringTerminator => 0x9c,
-			Self::OsCommand(..) => 0x9d,
-			Self::PrivacyMessage(..) => 0x9e,
-			Self::AppProgramCommand(..) => 0x9f,
+			Self::StringTerminator => 0x5c,
+			Self::OsCommand(..) => 0x5d,
+			Self::PrivacyMessage(..) => 0x5e,
+			Self::AppProgramCommand(..) => 0x5f,
 		}
 	}
 
 	#[cfg(feature = "std")]
 	/// Write this `Fe` Escape sequence to a destination.
-	pub fn write_to(&self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+	/// 
+	/// String sequences are terminated with [`Terminator::St`].
+	#[inline(always)]
+	pub fn write_to(
+		&self, w: &mut impl std::io::Write,
+	) -> Result<(), crate::Error> {
+		self.write_terminated(w, Terminator::St)
+	}
+
+	#[cfg(feature = "std")]
+	/// Write this `Fe` Escape sequence to a destination, terminating string
+	/// sequences with `terminator`.
+	pub fn write_terminated(
+		&self, w: &mut impl std::io::Write, terminator: Terminator,
+	) -> Result<(), crate::Error> {
 		w.write_all(ESC_STR.as_bytes())?;
 		w.write_all(&[self.kind_byte()])?;
 		match self {
 			Self::DeviceControlString(chars) => {
 				w.write_all(DcsChar::slice_as_bytes(chars))?;
-				Self::StringTerminator.write_to(w)
+				w.write_all(terminator.as_str().as_bytes())?;
 			}
 			Self::StartOfString(chars) => {
 				w.write_all(SosChar::slice_as_bytes(chars))?;
-				Self::StringTerminator.write_to(w)
+				w.write_all(terminator.as_str().as_bytes())?;
+			}
+			Self::Csi(seq) => {
+				// The introducer was already written as the kind byte.
+				w.write_all(crate::csi::CsiParam::slice_as_bytes(seq.parameter_bytes))?;
+				w.write_all(crate::csi::CsiInter::slice_as_bytes(seq.intermediate_bytes))?;
+				w.write_all(&[seq.final_byte.byte()])?;
 			}
-			Self::Csi(seq) => write!(w, "{seq}"),
 			Self::OsCommand(chars) => {
 				w.write_all(Printable::slice_as_bytes(chars))?;
-				Self::StringTerminator.write_to(w)
+				w.write_all(terminator.as_str().as_bytes())?;
 			}
 			Self::PrivacyMessage(chars) => {
 				w.write_all(Printable::slice_as_bytes(chars))?;
-				Self::StringTerminator.write_to(w)
+				w.write_all(terminator.as_str().as_bytes())?;
 			}
 			Self::AppProgramCommand(chars) => {
 				w.write_all(Printable::slice_as_bytes(chars))?;
-				Self::StringTerminator.write_to(w)
+				w.write_all(terminator.as_str().as_bytes())?;
+			}
+			_ => {}
+		}
+		Ok(())
+	}
+}
+
+impl<'a> FromSequence<'a> for FeSeq<'a> {
+	/// Parse a 7-bit `Fe` Escape sequence, whose string sequences may be
+	/// terminated by either [`Terminator`].
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let kind = match bytes {
+			[] | [0x1b] => return Err(ParseError::Incomplete),
+			[0x1b, kind, ..] => *kind,
+			_ => return Err(ParseError::Invalid),
+		};
+		let string = move || parse_string(bytes, kind);
+		let printable = move |payload| {
+			Printable::slice_from_bytes(payload).map_err(|_| ParseError::Invalid)
+		};
+		let seq = match kind {
+			0x40 => Self::Pad,
+			0x41 => Self::HighOctetPreset,
+			0x42 => Self::BreakPermittedHere,
+			0x43 => Self::NoBreakHere,
+			0x44 => Self::Index,
+			0x45 => Self::NextLine,
+			0x46 => Self::StartOfSelArea,
+			0x47 => Self::EndOfSelArea,
+			0x48 => Self::HorizTabSet,
+			0x49 => Self::RightJustify,
+			0x4a => Self::VertTabSet,
+			0x4b => Self::PartLineDown,
+			0x4c => Self::PartLineUp,
+			0x4d => Self::ReverseIndex,
+			0x4e => Self::SingleShift2,
+			0x4f => Self::SingleShift3,
+			0x50 => {
+				let (payload, len) = string()?;
+				let chars = DcsChar::slice_from_bytes(payload)
+					.map_err(|_| ParseError::Invalid)?;
+				return Ok((Self::DeviceControlString(chars), len))
+			}
+			0x51 => Self::PrivateUse1,
+			0x52 => Self::PrivateUse2,
+			0x53 => Self::SetTransmitState,
+			0x54 => Self::CancelCharacter,
+			0x55 => Self::MessageWaiting,
+			0x56 => Self::StartOfProtArea,
+			0x57 => Self::EndOfProtArea,
+			0x58 => {
+				let (payload, len) = string()?;
+				let chars = SosChar::slice_from_bytes(payload)
+					.map_err(|_| ParseError::Invalid)?;
+				return Ok((Self::StartOfString(chars), len))
+			}
+			0x59 => Self::Sgci,
+			0x5a => Self::Sci,
+			Csi::INTRO_BYTE => {
+				let (csi, len) = Csi::parse(bytes)?;
+				return Ok((Self::Csi(csi), len))
+			}
+			0x5c => Self::StringTerminator,
+			0x5d => {
+				let (payload, len) = string()?;
+				return Ok((Self::OsCommand(printable(payload)?), len))
+			}
+			0x5e => {
+				let (payload, len) = string()?;
+				return Ok((Self::PrivacyMessage(printable(payload)?), len))
+			}
+			0x5f => {
+				let (payload, len) = string()?;
+				return Ok((Self::AppProgramCommand(printable(payload)?), len))
+			}
+			_ => return Err(ParseError::Invalid),
+		};
+		Ok((seq, 2))
+	}
+}
+
+/// [`FeSeq::Sgci`] followed by the character that it introduces.
+/// 
+/// ECMA-48 leaves the encoding of the character up to the terminal. It's
+/// written as UTF-8, which is what terminals that decode UTF-8 expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SingleGraphicCharacter(pub char);
+
+impl SingleGraphicCharacter {
+	/// Maximum length of the sequence displayed by [`SingleGraphicCharacter`].
+	pub const MAX_LEN: usize = ESC_STR.len() + 1 + 4;
+}
+
+impl core::fmt::Display for SingleGraphicCharacter {
+	#[inline(always)]
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		use core::fmt::Write;
+		f.write_str(ESC_STR)?;
+		f.write_char('Y')?;
+		f.write_char(self.0)
+	}
+}
+
+impl<'a> FromSequence<'a> for SingleGraphicCharacter {
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let rest = expect_intro(bytes, b'Y')?;
+		let len = rest.first().map_or(1, |&b| match b {
+			0xf0.. => 4,
+			0xe0.. => 3,
+			0xc0.. => 2,
+			_ => 1,
+		});
+		let c = rest.get(..len).ok_or(ParseError::Incomplete)?;
+		let c = core::str::from_utf8(c).map_err(|_| ParseError::Invalid)?;
+		let c = c.chars().next().ok_or(ParseError::Invalid)?;
+		Ok((Self(c), bytes.len() - rest.len() + len))
+	}
+}
+
+/// [`FeSeq::Sci`] followed by the character that it introduces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SingleCharacter(pub SciChar);
+
+impl SingleCharacter {
+	/// Maximum length of the sequence displayed by [`SingleCharacter`].
+	pub const MAX_LEN: usize = ESC_STR.len() + 2;
+
+	/// Create a [`SingleCharacter`] for `c`, or [`None`] if it isn't a
+	/// [`Printable`] or a [`FormatEffector`].
+	#[inline(always)]
+	pub const fn new(c: char) -> Option<Self> {
+		if !c.is_ascii() {
+			return None
+		}
+		match SciChar::new(c as u8) {
+			Some(c) => Some(Self(c)),
+			None => None,
+		}
+	}
+}
+
+impl From<Printable> for SingleCharacter {
+	#[inline(always)]
+	fn from(value: Printable) -> Self {
+		Self(value.into())
+	}
+}
+
+impl From<FormatEffector> for SingleCharacter {
+	#[inline(always)]
+	fn from(value: FormatEffector) -> Self {
+		Self(value.into())
+	}
+}
+
+impl core::fmt::Display for SingleCharacter {
+	#[inline(always)]
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		use core::fmt::Write;
+		f.write_str(ESC_STR)?;
+		f.write_char('Z')?;
+		f.write_char(self.0.as_char())
+	}
+}
+
+impl<'a> FromSequence<'a> for SingleCharacter {
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let rest = expect_intro(bytes, b'Z')?;
+		let c = rest.first().ok_or(ParseError::Incomplete)?;
+		let c = SciChar::new(*c).ok_or(ParseError::Invalid)?;
+		Ok((Self(c), bytes.len() - rest.len() + 1))
+	}
+}
+
+/// Sequence that ends a string sequence, such as an OSC or DCS sequence.
+/// 
+/// Some terminals only accept OSC sequences that are terminated with
+/// [`Self::Bel`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Terminator {
+	/// [`FeSeq::StringTerminator`], as defined by ECMA-48.
+	#[default]
+	St,
+	/// ASCII bell character, as accepted by xterm.
+	Bel,
+}
+
+impl Terminator {
+	/// Return the string that is written for this terminator.
+	#[inline(always)]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::St => "\x1b\\",
+			Self::Bel => "\x07",
+		}
+	}
+}
+
+impl core::fmt::Display for Terminator {
+	#[inline(always)]
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl<'a> FromSequence<'a> for Terminator {
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		match bytes {
+			[] | [0x1b] => Err(ParseError::Incomplete),
+			[crate::parse::BEL, ..] => Ok((Self::Bel, 1)),
+			[0x1b, b'\\', ..] => Ok((Self::St, 2)),
+			_ => Err(ParseError::Invalid),
+		}
+	}
+}
+
+/// What a writer of a string sequence does with the payload that is written
+/// after its limit is reached.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PayloadOverflow {
+	/// Discard the rest of the payload, keeping the part that fits.
+	#[default]
+	Truncate,
+	/// Return an error from any write that doesn't fit, without writing any
+	/// of it.
+	Reject,
+}
+
+/// Limit on the number of bytes of the payload of a string sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PayloadLimit {
+	limit: usize,
+	len: usize,
+	overflow: PayloadOverflow,
+}
+
+impl PayloadLimit {
+	/// Limit that never overflows.
+	pub const NONE: Self = Self::new(usize::MAX, PayloadOverflow::Truncate);
+
+	#[inline(always)]
+	pub const fn new(limit: usize, overflow: PayloadOverflow) -> Self {
+		Self {
+			limit,
+			len: 0,
+			overflow,
+		}
+	}
+
+	/// Fail if writing `len` more bytes overflows the limit and overflowing
+	/// payload is rejected.
+	pub fn check(&self, len: usize) -> core::fmt::Result {
+		let left = self.limit.saturating_sub(self.len);
+		match self.overflow {
+			PayloadOverflow::Reject if len > left => Err(core::fmt::Error),
+			_ => Ok(()),
+		}
+	}
+
+	/// Count `s` towards the limit, returning the part of it that fits.
+	pub fn take<'s>(&mut self, s: &'s str) -> &'s str {
+		let mut fits = s.len().min(self.limit.saturating_sub(self.len));
+		while !s.is_char_boundary(fits) {
+			fits -= 1;
+		}
+		self.len += fits;
+		&s[..fits]
+	}
+
+	/// Count `bytes` towards the limit, returning the part of them that fits.
+	#[cfg(feature = "helpers")]
+	pub fn take_bytes<'b>(&mut self, bytes: &'b [u8]) -> &'b [u8] {
+		let fits = bytes.len().min(self.limit.saturating_sub(self.len));
+		self.len += fits;
+		&bytes[..fits]
+	}
+}
+
+/// Writer of the payload of an OSC sequence, which writes the sequence
+/// introducer when it's created and the terminator when it's finished.
+/// 
+/// Control characters in the payload are skipped so that they can't end the
+/// sequence early. The payload is unlimited unless [`Self::with_limit`] is
+/// used. The sequence is terminated on drop if [`Self::finish`] wasn't
+/// called, ignoring errors.
+#[derive(Debug)]
+pub struct OscWriter<'w, W: core::fmt::Write + ?Sized> {
+	inner: &'w mut W,
+	terminator: Terminator,
+	limit: PayloadLimit,
+	is_finished: bool,
+}
+
+impl<'w, W: core::fmt::Write + ?Sized> OscWriter<'w, W> {
+	/// Write the introducer of an OSC sequence to `inner`, which is terminated
+	/// with [`Terminator::St`].
+	#[inline(always)]
+	pub fn new(inner: &'w mut W) -> Result<Self, core::fmt::Error> {
+		Self::with_terminator(inner, Terminator::St)
+	}
+
+	/// Write the introducer of an OSC sequence to `inner`, which is terminated
+	/// with `terminator`.
+	pub fn with_terminator(
+		inner: &'w mut W, terminator: Terminator,
+	) -> Result<Self, core::fmt::Error> {
+		inner.write_str(ESC_STR)?;
+		inner.write_char(']')?;
+		Ok(Self {
+			inner,
+			terminator,
+			limit: PayloadLimit::NONE,
+			is_finished: false,
+		})
+	}
+
+	/// Return `self` with at most `limit` bytes of payload written after this
+	/// call, handling the rest as specified by `overflow`.
+	#[inline(always)]
+	pub const fn with_limit(mut self, limit: usize, overflow: PayloadOverflow) -> Self {
+		self.limit = PayloadLimit::new(limit, overflow);
+		self
+	}
+
+	/// Write the terminator of the sequence.
+	#[inline(always)]
+	pub fn finish(mut self) -> core::fmt::Result {
+		self.terminate()
+	}
+
+	/// Write the terminator of the sequence if it wasn't written yet.
+	pub(crate) fn terminate(&mut self) -> core::fmt::Result {
+		if self.is_finished {
+			return Ok(())
+		}
+		self.is_finished = true;
+		self.inner.write_str(self.terminator.as_str())
+	}
+}
+
+impl<W: core::fmt::Write + ?Sized> core::fmt::Write for OscWriter<'_, W> {
+	fn write_str(&mut self, s: &str) -> core::fmt::Result {
+		self.limit.check(s.split(char::is_control).map(str::len).sum())?;
+		s.split(char::is_control).try_for_each(|part| {
+			self.inner.write_str(self.limit.take(part))
+		})
+	}
+}
+
+impl<W: core::fmt::Write + ?Sized> Drop for OscWriter<'_, W> {
+	#[inline(always)]
+	fn drop(&mut self) {
+		let _ = self.terminate();
+	}
+}
+
+/// Writer of the payload of a DCS sequence, which writes the sequence
+/// introducer when it's created and [`Terminator::St`] when it's finished.
+/// 
+/// Characters that aren't [`DcsChar`]s are replaced with
+/// [`DcsChar::REPLACEMENT`]. The payload is unlimited unless
+/// [`Self::with_limit`] is used. The sequence is terminated on drop if
+/// [`Self::finish`] wasn't called, ignoring errors.
+#[derive(Debug)]
+pub struct DcsWriter<'w, W: core::fmt::Write + ?Sized> {
+	inner: &'w mut W,
+	limit: PayloadLimit,
+	is_finished: bool,
+}
+
+impl<'w, W: core::fmt::Write + ?Sized> DcsWriter<'w, W> {
+	/// Write the introducer of a DCS sequence to `inner`.
+	pub fn new(inner: &'w mut W) -> Result<Self, core::fmt::Error> {
+		inner.write_str(ESC_STR)?;
+		inner.write_char('P')?;
+		Ok(Self {
+			inner,
+			limit: PayloadLimit::NONE,
+			is_finished: false,
+		})
+	}
+
+	/// Return `self` with at most `limit` bytes of payload written after this
+	/// call, handling the rest as specified by `overflow`.
+	#[inline(always)]
+	pub const fn with_limit(mut self, limit: usize, overflow: PayloadOverflow) -> Self {
+		self.limit = PayloadLimit::new(limit, overflow);
+		self
+	}
+
+	/// Write the terminator of the sequence.
+	#[inline(always)]
+	pub fn finish(mut self) -> core::fmt::Result {
+		self.is_finished = true;
+		self.inner.write_str(Terminator::St.as_str())
+	}
+}
+
+impl<W: core::fmt::Write + ?Sized> core::fmt::Write for DcsWriter<'_, W> {
+	fn write_str(&mut self, s: &str) -> core::fmt::Result {
+		// Replacements are ASCII, so they're one byte each.
+		self.limit.check(s.chars().map(|c| match DcsChar::try_from(c) {
+			Ok(..) => c.len_utf8(),
+			Err(..) => 1,
+		}).sum())?;
+		let mut replacement = [0; 4];
+		let replacement = (DcsChar::REPLACEMENT.byte() as char)
+			.encode_utf8(&mut replacement);
+		let mut start = 0;
+		for (i, c) in s.char_indices() {
+			if DcsChar::try_from(c).is_err() {
+				self.inner.write_str(self.limit.take(&s[start..i]))?;
+				self.inner.write_str(self.limit.take(replacement))?;
+				start = i + c.len_utf8();
 			}
-			_ => Ok(())
+		}
+		self.inner.write_str(self.limit.take(&s[start..]))
+	}
+}
+
+impl<W: core::fmt::Write + ?Sized> Drop for DcsWriter<'_, W> {
+	fn drop(&mut self) {
+		if !self.is_finished {
+			let _ = self.inner.write_str(Terminator::St.as_str());
 		}
 	}
 }
@@ -132,6 +584,7 @@ byte_wrapper! {
 	pub struct DcsChar(u8);
 }
 byte_utf8able!(DcsChar);
+byte_from_str!(DcsChar, lossy = b'?');
 
 byte_wrapper! {
 	for 0x0..=0x96 | 0x98..=0x9b | 0x9d.. =>
@@ -153,6 +606,28 @@ impl From<FormatEffector> for DcsChar {
 	}
 }
 
+byte_wrapper! {
+	for 0x08..=0x0d | 0x20..=0x7e =>
+	#[repr(transparent)]
+	/// Character type for [`SingleCharacter`], which is a [`Printable`] or a
+	/// [`FormatEffector`].
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	pub struct SciChar(u8);
+}
+byte_utf8able!(SciChar);
+
+impl From<Printable> for SciChar {
+	fn from(value: Printable) -> Self {
+		unsafe { Self::new_unchecked(value.byte()) }
+	}
+}
+
+impl From<FormatEffector> for SciChar {
+	fn from(value: FormatEffector) -> Self {
+		unsafe { Self::new_unchecked(value.byte()) }
+	}
+}
+
 byte_wrapper! {
 	for 0x08..=0x0d =>
 	#[repr(transparent)]
@@ -160,6 +635,8 @@ byte_wrapper! {
 	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 	pub struct FormatEffector(u8);
 }
+byte_utf8able!(FormatEffector);
+byte_from_str!(FormatEffector);
 
 byte_wrapper! {
 	for 0x20..=0x7e =>
@@ -168,3 +645,5 @@ byte_wrapper! {
 	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 	pub struct Printable(u8);
 }
+byte_utf8able!(Printable);
+byte_from_str!(Printable, lossy = b'?');
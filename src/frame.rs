@@ -0,0 +1,135 @@
+use std::time::{
+	Duration, Instant,
+};
+
+/// Scheduler that limits how often the screen is redrawn.
+/// 
+/// Changes that need a redraw are reported with [`Self::mark_dirty`], and any
+/// number of them are drawn in one frame. Frames start at most once per
+/// interval, and the time spent drawing counts towards the interval, so a
+/// slow frame shortens the wait for the next one instead of lowering the frame
+/// rate further.
+/// 
+/// In an event loop, wait for input until [`Self::deadline`] (for example,
+/// with [`TimedParser::read_until`](crate::TimedParser::read_until)), and
+/// draw a frame when [`Self::should_render`] returns `true`. Nothing is
+/// scheduled while nothing is dirty, so an idle loop doesn't wake up.
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+	interval: Duration,
+	dirty: bool,
+	last_frame: Option<Instant>,
+}
+
+impl Default for FrameLimiter {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new(Self::DEFAULT_FPS)
+	}
+}
+
+impl FrameLimiter {
+	/// Frame rate that is smooth on most displays.
+	pub const DEFAULT_FPS: u32 = 60;
+
+	/// Create a limiter that draws at most `max_fps` frames per second.
+	/// 
+	/// A rate of `0` doesn't limit the frame rate.
+	#[inline(always)]
+	pub const fn new(max_fps: u32) -> Self {
+		Self::from_interval(interval(max_fps))
+	}
+
+	/// Create a limiter that starts frames at least `interval` apart.
+	#[inline(always)]
+	pub const fn from_interval(interval: Duration) -> Self {
+		Self {
+			interval,
+			dirty: false,
+			last_frame: None,
+		}
+	}
+
+	/// Return the shortest time between the starts of two frames.
+	#[inline(always)]
+	pub const fn interval(&self) -> Duration {
+		self.interval
+	}
+
+	/// Set the shortest time between the starts of two frames.
+	#[inline(always)]
+	pub fn set_interval(&mut self, interval: Duration) {
+		self.interval = interval;
+	}
+
+	/// Draw at most `max_fps` frames per second, or any number if it's `0`.
+	#[inline(always)]
+	pub fn set_max_fps(&mut self, max_fps: u32) {
+		self.interval = interval(max_fps);
+	}
+
+	/// Report that the screen needs to be redrawn.
+	#[inline(always)]
+	pub fn mark_dirty(&mut self) {
+		self.dirty = true;
+	}
+
+	/// Return `true` if the screen needs to be redrawn.
+	#[inline(always)]
+	pub const fn is_dirty(&self) -> bool {
+		self.dirty
+	}
+
+	/// Return the time at which the next frame should be drawn, or [`None`]
+	/// if nothing needs to be redrawn.
+	#[inline(always)]
+	pub fn deadline(&self) -> Option<Instant> {
+		if !self.dirty {
+			return None
+		}
+		match self.last_frame {
+			Some(last_frame) => last_frame.checked_add(self.interval),
+			None => Some(Instant::now()),
+		}
+	}
+
+	/// Return how long to wait from `now` before drawing the next frame, or
+	/// [`None`] to wait indefinitely.
+	#[inline(always)]
+	pub fn remaining(&self, now: Instant) -> Option<Duration> {
+		self.deadline().map(move |deadline| deadline.saturating_duration_since(now))
+	}
+
+	/// Return `true` if a frame should be drawn at `now`, in which case the
+	/// screen is assumed to be drawn and is no longer dirty.
+	pub fn should_render(&mut self, now: Instant) -> bool {
+		let due = self.dirty && self.last_frame
+			.and_then(|last_frame| last_frame.checked_add(self.interval))
+			.is_none_or(move |deadline| now >= deadline);
+		if due {
+			self.dirty = false;
+			self.last_frame = Some(now);
+		}
+		due
+	}
+
+	/// Sleep until the next frame should be drawn, returning immediately if
+	/// nothing needs to be redrawn.
+	/// 
+	/// This is for loops that don't wait for input, such as animations that
+	/// draw every frame.
+	pub fn sleep(&self) {
+		if let Some(remaining) = self.remaining(Instant::now()) {
+			std::thread::sleep(remaining);
+		}
+	}
+}
+
+/// Return the interval between frames at `fps` frames per second.
+#[inline(always)]
+const fn interval(fps: u32) -> Duration {
+	match fps {
+		0 => Duration::ZERO,
+		fps => Duration::from_nanos(1_000_000_000 / fps as u64),
+	}
+}
@@ -0,0 +1,228 @@
+use crate::{
+	key::Modifiers,
+	mouse::{
+		MouseButton, MouseEvent, MouseEventKind
+	},
+	pos::Pos,
+};
+
+use std::time::{
+	Duration, Instant,
+};
+
+/// Higher-level mouse action recognized by [`MouseGestures`].
+/// 
+/// Positions are 0-based, or in pixels if the events were reported in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gesture {
+	/// Button was pressed and released without dragging.
+	Click {
+		button: MouseButton,
+		pos: Pos,
+		/// Number of consecutive clicks at the same position, which is `2` for
+		/// a double click.
+		count: u8,
+		modifiers: Modifiers,
+	},
+	/// Pointer moved far enough while a button is held down to start a drag.
+	DragStart {
+		button: MouseButton,
+		/// Position where the button was pressed.
+		pos: Pos,
+		modifiers: Modifiers,
+	},
+	/// Rectangle between the start of a drag and the pointer changed.
+	/// 
+	/// This follows [`Self::DragStart`] and every move during the drag.
+	Selection {
+		button: MouseButton,
+		/// Top left corner of the rectangle.
+		start: Pos,
+		/// Bottom right corner of the rectangle, which is included in it.
+		end: Pos,
+	},
+	/// Button was released, ending a drag.
+	DragEnd {
+		button: MouseButton,
+		/// Position where the button was pressed.
+		start: Pos,
+		/// Position where the button was released.
+		end: Pos,
+		modifiers: Modifiers,
+	},
+}
+
+/// Button that is held down.
+#[derive(Debug, Clone, Copy)]
+struct Press {
+	button: MouseButton,
+	pos: Pos,
+	modifiers: Modifiers,
+	count: u8,
+	is_drag: bool,
+}
+
+/// Last click, which a following press may continue.
+#[derive(Debug, Clone, Copy)]
+struct LastClick {
+	button: MouseButton,
+	pos: Pos,
+	time: Instant,
+	count: u8,
+}
+
+/// Recognizer of clicks, multiple clicks and drags in [`MouseEvent`]s.
+/// 
+/// Every mouse event is passed to [`Self::process`], which reports the
+/// [`Gesture`]s that it completes. Drags can only be recognized if the
+/// terminal reports motion while a button is held down, as it does in xterm
+/// mode `1002` or `1003`.
+#[derive(Debug, Clone)]
+pub struct MouseGestures {
+	click_interval: Duration,
+	drag_threshold: u16,
+	press: Option<Press>,
+	last_click: Option<LastClick>,
+}
+
+impl Default for MouseGestures {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl MouseGestures {
+	/// Longest time between two presses that count as a multiple click by
+	/// default.
+	pub const DEFAULT_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+	/// Create a recognizer with [`Self::DEFAULT_CLICK_INTERVAL`] and a drag
+	/// threshold of one cell.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			click_interval: Self::DEFAULT_CLICK_INTERVAL,
+			drag_threshold: 1,
+			press: None,
+			last_click: None,
+		}
+	}
+
+	/// Return `self` with the longest time between two presses that count as
+	/// a multiple click.
+	#[inline(always)]
+	pub const fn with_click_interval(mut self, click_interval: Duration) -> Self {
+		self.click_interval = click_interval;
+		self
+	}
+
+	/// Return `self` with the distance that the pointer has to move while a
+	/// button is held down to start a drag, in cells (or pixels) along either
+	/// axis.
+	#[inline(always)]
+	pub const fn with_drag_threshold(mut self, drag_threshold: u16) -> Self {
+		self.drag_threshold = drag_threshold;
+		self
+	}
+
+	/// Return `true` if a drag is in progress.
+	#[inline(always)]
+	pub const fn is_dragging(&self) -> bool {
+		matches!(self.press, Some(Press { is_drag: true, .. }))
+	}
+
+	/// Process `event`, which was received at `now`, calling `f` with every
+	/// [`Gesture`] that it completes.
+	pub fn process(
+		&mut self, event: &MouseEvent, now: Instant, mut f: impl FnMut(Gesture),
+	) {
+		let pos = Pos::from_one_based(event.row, event.column);
+		match event.kind {
+			MouseEventKind::Down(button) => {
+				let count = match self.last_click {
+					Some(last) if last.button == button && last.pos == pos
+						&& now.saturating_duration_since(last.time)
+							<= self.click_interval
+					=> last.count.saturating_add(1),
+					_ => 1,
+				};
+				self.last_click = Some(LastClick {
+					button,
+					pos,
+					time: now,
+					count,
+				});
+				self.press = Some(Press {
+					button,
+					pos,
+					modifiers: event.modifiers,
+					count,
+					is_drag: false,
+				});
+			}
+			MouseEventKind::Drag(button) => {
+				let press = self.press.as_mut()
+					.filter(move |press| press.button == button);
+				let Some(press) = press else {
+					return
+				};
+				if !press.is_drag {
+					let distance = press.pos.row.abs_diff(pos.row)
+						.max(press.pos.col.abs_diff(pos.col));
+					if distance < self.drag_threshold.max(1) {
+						return
+					}
+					press.is_drag = true;
+					self.last_click = None;
+					f(Gesture::DragStart {
+						button,
+						pos: press.pos,
+						modifiers: press.modifiers,
+					});
+				}
+				let (from, to) = (press.pos, pos);
+				f(Gesture::Selection {
+					button,
+					start: Pos::new(from.row.min(to.row), from.col.min(to.col)),
+					end: Pos::new(from.row.max(to.row), from.col.max(to.col)),
+				});
+			}
+			MouseEventKind::Up(button) => {
+				// Encodings that don't report which button was released end any
+				// press.
+				let press = self.press.filter(move |press| {
+					button.is_none_or(move |button| button == press.button)
+				});
+				let Some(press) = press else {
+					return
+				};
+				self.press = None;
+				f(if press.is_drag {
+					Gesture::DragEnd {
+						button: press.button,
+						start: press.pos,
+						end: pos,
+						modifiers: press.modifiers,
+					}
+				} else {
+					Gesture::Click {
+						button: press.button,
+						pos: press.pos,
+						count: press.count,
+						modifiers: press.modifiers,
+					}
+				});
+			}
+			_ => {}
+		}
+	}
+
+	/// Forget any button that is held down and the last click, for example
+	/// after mouse reporting was disabled.
+	#[inline(always)]
+	pub fn reset(&mut self) {
+		self.press = None;
+		self.last_click = None;
+	}
+}
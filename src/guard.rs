@@ -0,0 +1,301 @@
+use crate::{
+	csi::Csi,
+	fe_seq::Terminator,
+	profile::{
+		AltScreenMode, Profile,
+	},
+};
+
+use std::io::{
+	self, Write,
+};
+
+/// Write the beginning of a CSI sequence to `w`.
+fn write_csi_begin(w: &mut impl Write) -> io::Result<()> {
+	w.write_all(crate::fe_seq::ESC_STR.as_bytes())?;
+	w.write_all(Csi::INTRO_STR.as_bytes())
+}
+
+/// Guard that limits scrolling to a region of the screen, or **DECSTBM**,
+/// until it is dropped.
+/// 
+/// The guard passes writes through to the inner writer. When it is dropped,
+/// the scrolling region is reset to the whole screen, and origin mode is
+/// disabled if the guard enabled it.
+#[derive(Debug)]
+pub struct ScrollRegionGuard<W: Write> {
+	inner: W,
+	origin_mode: bool,
+}
+
+impl<W: Write> ScrollRegionGuard<W> {
+	/// Limit scrolling to the rows from `top` to `bottom`, which are 1-based
+	/// and inclusive.
+	#[inline(always)]
+	pub fn new(inner: W, top: u16, bottom: u16) -> io::Result<Self> {
+		Self::with_origin_mode(inner, top, bottom, false)
+	}
+
+	/// Limit scrolling to the rows from `top` to `bottom`, which are 1-based
+	/// and inclusive, enabling origin mode if `origin_mode` is `true`.
+	/// 
+	/// While origin mode is enabled, cursor positions are relative to the top
+	/// of the region.
+	pub fn with_origin_mode(
+		mut inner: W, top: u16, bottom: u16, origin_mode: bool,
+	) -> io::Result<Self> {
+		write_csi_begin(&mut inner)?;
+		write!(inner, "{top};{bottom}r")?;
+		if origin_mode {
+			write_csi_begin(&mut inner)?;
+			inner.write_all(b"?6h")?;
+		}
+		inner.flush()?;
+		Ok(Self {
+			inner,
+			origin_mode,
+		})
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	fn reset(&mut self) -> io::Result<()> {
+		if self.origin_mode {
+			write_csi_begin(&mut self.inner)?;
+			self.inner.write_all(b"?6l")?;
+		}
+		write_csi_begin(&mut self.inner)?;
+		self.inner.write_all(b"r")?;
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Write for ScrollRegionGuard<W> {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.inner.write(buf)
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Drop for ScrollRegionGuard<W> {
+	fn drop(&mut self) {
+		// Errors can't be reported from `drop`.
+		let _ = self.reset();
+	}
+}
+
+/// Write an OSC sequence that sets the window title to `title`.
+/// 
+/// Control characters in `title` are skipped, so that they can't end the
+/// sequence early.
+fn write_title(
+	w: &mut impl Write, title: &str, terminator: Terminator,
+) -> io::Result<()> {
+	w.write_all(crate::fe_seq::ESC_STR.as_bytes())?;
+	w.write_all(b"]2;")?;
+	for part in title.split(char::is_control) {
+		w.write_all(part.as_bytes())?;
+	}
+	w.write_all(terminator.as_str().as_bytes())
+}
+
+/// Guard that changes the window title until it is dropped.
+/// 
+/// The previous title is saved on the xterm title stack with **XTWINOPS**, and
+/// restored from it when the guard is dropped. The guard passes writes through
+/// to the inner writer.
+#[derive(Debug)]
+pub struct TitleGuard<W: Write> {
+	inner: W,
+	fallback: Option<String>,
+	terminator: Terminator,
+}
+
+impl<W: Write> TitleGuard<W> {
+	/// Set the window title to `title`.
+	/// 
+	/// If `fallback` is specified, then the title is set to it when the guard
+	/// is dropped, before restoring the previous title from the title stack.
+	/// Terminals without a title stack are left with the fallback title.
+	#[inline(always)]
+	pub fn new(inner: W, title: &str, fallback: Option<&str>) -> io::Result<Self> {
+		Self::with_profile(inner, title, fallback, &Profile::new())
+	}
+
+	/// Set the window title to `title`, writing sequences as appropriate for
+	/// `profile`.
+	/// 
+	/// See [`Self::new`].
+	pub fn with_profile(
+		mut inner: W, title: &str, fallback: Option<&str>, profile: &Profile,
+	) -> io::Result<Self> {
+		write_csi_begin(&mut inner)?;
+		inner.write_all(b"22;2t")?;
+		write_title(&mut inner, title, profile.terminator)?;
+		inner.flush()?;
+		Ok(Self {
+			inner,
+			fallback: fallback.map(String::from),
+			terminator: profile.terminator,
+		})
+	}
+
+	/// Change the title again, keeping the saved title.
+	pub fn set_title(&mut self, title: &str) -> io::Result<()> {
+		write_title(&mut self.inner, title, self.terminator)?;
+		self.inner.flush()
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	fn restore(&mut self) -> io::Result<()> {
+		if let Some(fallback) = self.fallback.as_deref() {
+			write_title(&mut self.inner, fallback, self.terminator)?;
+		}
+		write_csi_begin(&mut self.inner)?;
+		self.inner.write_all(b"23;2t")?;
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Write for TitleGuard<W> {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.inner.write(buf)
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Drop for TitleGuard<W> {
+	fn drop(&mut self) {
+		// Errors can't be reported from `drop`.
+		let _ = self.restore();
+	}
+}
+
+/// Guard that shows the alternate screen until it is dropped.
+/// 
+/// The alternate screen is cleared when it's shown, and the cursor position
+/// is saved and restored with it. The sequences that are used depend on the
+/// [`AltScreenMode`] of the profile. The guard passes writes through to the
+/// inner writer.
+#[derive(Debug)]
+pub struct AlternateScreen<W: Write> {
+	inner: W,
+	mode: AltScreenMode,
+}
+
+impl<W: Write> AlternateScreen<W> {
+	/// Switch to the alternate screen with [`AltScreenMode::Combined`].
+	#[inline(always)]
+	pub fn new(inner: W) -> io::Result<Self> {
+		Self::with_profile(inner, &Profile::new())
+	}
+
+	/// Switch to the alternate screen, writing sequences as appropriate for
+	/// `profile`.
+	pub fn with_profile(mut inner: W, profile: &Profile) -> io::Result<Self> {
+		let mode = profile.alt_screen;
+		match mode {
+			AltScreenMode::Combined => {
+				write_csi_begin(&mut inner)?;
+				inner.write_all(b"?1049h")?;
+			}
+			AltScreenMode::Decomposed => {
+				inner.write_all(crate::fe_seq::ESC_STR.as_bytes())?;
+				inner.write_all(b"7")?;
+				write_csi_begin(&mut inner)?;
+				inner.write_all(b"?47h")?;
+				write_csi_begin(&mut inner)?;
+				inner.write_all(b"2J")?;
+			}
+		}
+		inner.flush()?;
+		Ok(Self {
+			inner,
+			mode,
+		})
+	}
+
+	/// Return the sequences that are used for the alternate screen.
+	#[inline(always)]
+	pub const fn mode(&self) -> AltScreenMode {
+		self.mode
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	fn leave(&mut self) -> io::Result<()> {
+		match self.mode {
+			AltScreenMode::Combined => {
+				write_csi_begin(&mut self.inner)?;
+				self.inner.write_all(b"?1049l")?;
+			}
+			AltScreenMode::Decomposed => {
+				write_csi_begin(&mut self.inner)?;
+				self.inner.write_all(b"?47l")?;
+				self.inner.write_all(crate::fe_seq::ESC_STR.as_bytes())?;
+				self.inner.write_all(b"8")?;
+			}
+		}
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Write for AlternateScreen<W> {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.inner.write(buf)
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Drop for AlternateScreen<W> {
+	fn drop(&mut self) {
+		// Errors can't be reported from `drop`.
+		let _ = self.leave();
+	}
+}
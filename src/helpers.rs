@@ -1,7 +1,15 @@
 use crate::{
-	csi::Csi,
+	csi::{
+		Csi, CsiSequence
+	},
+	mouse::MouseEncoding,
+	parse::{
+		FromSequence, ParseError
+	},
+	pos::Pos,
+	profile::Profile,
 	sgr::{
-		Sgr, SgrColor
+		xterm_256_to_rgb, Sgr, SgrColor
 	}
 };
 
@@ -12,18 +20,199 @@ use core::{
 		Result as FmtResult,
 		Write,
 	},
-	num::NonZeroU8
+	num::{
+		NonZeroU8, NonZeroU16,
+	},
 };
 
+#[cfg(feature = "alloc")]
+mod ansi_string;
+#[cfg(feature = "alloc")]
+pub use ansi_string::*;
+mod badge;
+pub use badge::*;
+#[cfg(feature = "alloc")]
+mod braille;
+#[cfg(feature = "alloc")]
+pub use braille::*;
+#[cfg(feature = "alloc")]
+mod buffer;
+#[cfg(feature = "alloc")]
+pub use buffer::*;
+mod chart;
+pub use chart::*;
+#[cfg(feature = "alloc")]
+mod columns;
+#[cfg(feature = "alloc")]
+pub use columns::*;
+mod diff;
+pub use diff::*;
+mod graphics;
+pub use graphics::*;
+mod hexdump;
+pub use hexdump::*;
+mod highlight;
+pub use highlight::*;
+mod human;
+pub use human::*;
+mod layout;
+pub use layout::*;
+mod lines;
+pub use lines::*;
+#[cfg(feature = "std")]
+mod live;
+#[cfg(feature = "std")]
+pub use live::*;
+#[cfg(feature = "markdown")]
+mod markdown;
+#[cfg(feature = "markdown")]
+pub use markdown::*;
+mod osc;
+pub use osc::*;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "remote")]
+pub use remote::*;
+#[cfg(feature = "alloc")]
+mod render;
+#[cfg(feature = "alloc")]
+pub use render::*;
+mod roles;
+pub use roles::*;
+mod rule;
+pub use rule::*;
+#[cfg(feature = "alloc")]
+mod search;
+#[cfg(feature = "alloc")]
+pub use search::*;
+#[cfg(feature = "alloc")]
+mod selection;
+#[cfg(feature = "alloc")]
+pub use selection::*;
+#[cfg(feature = "std")]
+mod streams;
+#[cfg(feature = "std")]
+pub use streams::*;
+mod style;
+pub use style::*;
+mod text;
+pub use text::*;
+mod theme;
+pub use theme::*;
+mod truncate;
+pub use truncate::*;
+#[cfg(feature = "alloc")]
+mod viewport;
+#[cfg(feature = "alloc")]
+pub use viewport::*;
+
+#[inline(always)]
+const fn max_len(a: usize, b: usize) -> usize {
+	if a > b { a } else { b }
+}
+
+/// Implement [`CsiSequence`] and [`Display`] for a type with a
+/// `write_params_to` method and a `FINAL_STR` constant, or [`CsiSequence`] for
+/// a type with an `into_sgr` method.
+macro_rules! csi_sequence {
+	(sgr $name:ty) => {
+		impl CsiSequence for $name {
+			#[inline(always)]
+			fn write_params_to(&self, w: &mut dyn Write) -> FmtResult {
+				self.into_sgr().write_params_to(w)
+			}
+
+			#[inline(always)]
+			fn final_str(&self) -> &'static str {
+				Csi::FINAL_STR
+			}
+
+			#[inline(always)]
+			fn is_chainable(&self) -> bool {
+				true
+			}
+		}
+	};
+	($name:ty) => {
+		impl CsiSequence for $name {
+			#[inline(always)]
+			fn write_params_to(&self, w: &mut dyn Write) -> FmtResult {
+				Self::write_params_to(self, w)
+			}
+
+			#[inline(always)]
+			fn final_str(&self) -> &'static str {
+				Self::FINAL_STR
+			}
+		}
+
+		impl Display for $name {
+			#[inline(always)]
+			fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+				Csi::write_begin(f)?;
+				self.write_params_to(f)?;
+				f.write_str(Self::FINAL_STR)
+			}
+		}
+	};
+}
+
+/// Implement [`FromSequence`] for a type with a `from_csi` method, or for a
+/// type with a `from_sgr` method.
+macro_rules! from_sequence {
+	(sgr $name:ty) => {
+		impl<'a> FromSequence<'a> for $name {
+			#[inline(always)]
+			fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+				let (sgr, len) = Sgr::parse_sequence(bytes)?;
+				Self::from_sgr(sgr).map(move |value| (value, len)).ok_or(ParseError::Invalid)
+			}
+		}
+	};
+	($name:ty) => {
+		impl<'a> FromSequence<'a> for $name {
+			#[inline(always)]
+			fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+				let (csi, len) = Csi::parse(bytes)?;
+				Self::from_csi(&csi).map(move |value| (value, len)).ok_or(ParseError::Invalid)
+			}
+		}
+	};
+}
+
+/// Return `Some(true)` if `csi` sets the private mode `mode`, or `Some(false)`
+/// if it resets it.
+fn private_mode_change(csi: &Csi<'_>, mode: u16) -> Option<bool> {
+	let set = csi.single_param(Some(b'?'), "h").map(move |param| (param, true));
+	let (param, set) = set.or_else(|| {
+		csi.single_param(Some(b'?'), "l").map(move |param| (param, false))
+	})?;
+	(param == mode).then_some(set)
+}
+
+/// Parse an escape sequence of the escape character followed by `s` from the
+/// start of `bytes`.
+fn parse_escape(bytes: &[u8], s: &str) -> Result<usize, ParseError> {
+	let esc = crate::fe_seq::ESC_STR.as_bytes();
+	let len = esc.len() + s.len();
+	let expected = esc.iter().chain(s.as_bytes());
+	if bytes.iter().zip(expected).any(|(a, b)| a != b) {
+		Err(ParseError::Invalid)
+	} else if bytes.len() < len {
+		Err(ParseError::Incomplete)
+	} else {
+		Ok(len)
+	}
+}
+
 #[inline(always)]
-fn move_cursor_delta(
-	w: &mut impl Write,
-	neg: char, pos: char,
-	delta: i8
-) -> FmtResult {
-	Csi::write_begin(w)?;
-	write!(w, "{}", delta.abs())?;
-	w.write_char(if delta < 0 { neg } else { pos })
+fn write_separator(f: &mut Formatter<'_>, is_first: &mut bool) -> FmtResult {
+	if *is_first {
+		*is_first = false;
+		Ok(())
+	} else {
+		f.write_str(";")
+	}
 }
 
 /// Mode of erasing characters on the display.
@@ -32,23 +221,44 @@ pub enum EraseDisplay {
 	CurToEnd,
 	CurToBegin,
 	All,
+	/// xterm's extension that erases the lines scrolled off the top of the
+	/// screen, leaving the screen as it is.
 	XtermAllNoScrollback,
 }
 
-impl Display for EraseDisplay {
+impl EraseDisplay {
+	/// Maximum length of the sequence displayed by [`EraseDisplay`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + Self::FINAL_STR.len();
+	/// Final byte of the sequence.
+	pub const FINAL_STR: &'static str = "J";
+
+	/// Write the parameter of this sequence, without the introducer or the
+	/// final byte.
 	#[inline(always)]
-	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-		Csi::write_begin(f)?;
-		f.write_str(match self {
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		w.write_str(match self {
 			EraseDisplay::CurToEnd => "0",
 			EraseDisplay::CurToBegin => "1",
 			EraseDisplay::All => "2",
 			EraseDisplay::XtermAllNoScrollback => "3",
-		})?;
-		f.write_str("J")
+		})
+	}
+
+	/// Return the [`EraseDisplay`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		match csi.single_param(None, Self::FINAL_STR)? {
+			0 => Some(Self::CurToEnd),
+			1 => Some(Self::CurToBegin),
+			2 => Some(Self::All),
+			3 => Some(Self::XtermAllNoScrollback),
+			_ => None,
+		}
 	}
 }
 
+csi_sequence!(EraseDisplay);
+from_sequence!(EraseDisplay);
+
 /// Mode of erasing characters in a line.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EraseLine {
@@ -57,19 +267,683 @@ pub enum EraseLine {
 	All,
 }
 
-impl Display for EraseLine {
+impl EraseLine {
+	/// Maximum length of the sequence displayed by [`EraseLine`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + Self::FINAL_STR.len();
+	/// Final byte of the sequence.
+	pub const FINAL_STR: &'static str = "K";
+
+	/// Write the parameter of this sequence, without the introducer or the
+	/// final byte.
 	#[inline(always)]
-	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-		Csi::write_begin(f)?;
-		f.write_str(match self {
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		w.write_str(match self {
 			EraseLine::CurToEnd => "0",
 			EraseLine::CurToBegin => "1",
 			EraseLine::All => "2",
-		})?;
-		f.write_str("K")
+		})
+	}
+
+	/// Return the [`EraseLine`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		match csi.single_param(None, Self::FINAL_STR)? {
+			0 => Some(Self::CurToEnd),
+			1 => Some(Self::CurToBegin),
+			2 => Some(Self::All),
+			_ => None,
+		}
+	}
+}
+
+csi_sequence!(EraseLine);
+from_sequence!(EraseLine);
+
+/// Character protection attribute, which is changed by the Select Character
+/// Protection Attribute, or **DECSCA**, sequence.
+/// 
+/// Characters written while [`Self::Protected`] is in effect are not erased by
+/// [`SelectiveEraseDisplay`] and [`SelectiveEraseLine`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+	#[default]
+	Unprotected,
+	Protected,
+}
+
+impl Protection {
+	/// Maximum length of the sequence displayed by [`Protection`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + Self::FINAL_STR.len();
+	/// Intermediate and final bytes of the sequence.
+	pub const FINAL_STR: &'static str = "\"q";
+
+	/// Write the parameter of this sequence, without the introducer or the
+	/// final bytes.
+	#[inline(always)]
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		w.write_str(match self {
+			Protection::Unprotected => "0",
+			Protection::Protected => "1",
+		})
+	}
+
+	/// Return the [`Protection`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		match csi.single_param(None, Self::FINAL_STR)? {
+			0 | 2 => Some(Self::Unprotected),
+			1 => Some(Self::Protected),
+			_ => None,
+		}
+	}
+}
+
+csi_sequence!(Protection);
+from_sequence!(Protection);
+
+/// Mode of erasing unprotected characters on the display, or **DECSED**.
+/// 
+/// See [`Protection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectiveEraseDisplay {
+	CurToEnd,
+	CurToBegin,
+	All,
+}
+
+impl SelectiveEraseDisplay {
+	/// Maximum length of the sequence displayed by [`SelectiveEraseDisplay`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 2 + Self::FINAL_STR.len();
+	/// Final byte of the sequence.
+	pub const FINAL_STR: &'static str = "J";
+
+	/// Write the parameters of this sequence, including the private marker,
+	/// without the introducer or the final byte.
+	#[inline(always)]
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		w.write_str(match self {
+			SelectiveEraseDisplay::CurToEnd => "?0",
+			SelectiveEraseDisplay::CurToBegin => "?1",
+			SelectiveEraseDisplay::All => "?2",
+		})
+	}
+
+	/// Return the [`SelectiveEraseDisplay`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		match csi.single_param(Some(b'?'), Self::FINAL_STR)? {
+			0 => Some(Self::CurToEnd),
+			1 => Some(Self::CurToBegin),
+			2 => Some(Self::All),
+			_ => None,
+		}
+	}
+}
+
+csi_sequence!(SelectiveEraseDisplay);
+from_sequence!(SelectiveEraseDisplay);
+
+/// Mode of erasing unprotected characters in a line, or **DECSEL**.
+/// 
+/// See [`Protection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectiveEraseLine {
+	CurToEnd,
+	CurToBegin,
+	All,
+}
+
+impl SelectiveEraseLine {
+	/// Maximum length of the sequence displayed by [`SelectiveEraseLine`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 2 + Self::FINAL_STR.len();
+	/// Final byte of the sequence.
+	pub const FINAL_STR: &'static str = "K";
+
+	/// Write the parameters of this sequence, including the private marker,
+	/// without the introducer or the final byte.
+	#[inline(always)]
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		w.write_str(match self {
+			SelectiveEraseLine::CurToEnd => "?0",
+			SelectiveEraseLine::CurToBegin => "?1",
+			SelectiveEraseLine::All => "?2",
+		})
+	}
+
+	/// Return the [`SelectiveEraseLine`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		match csi.single_param(Some(b'?'), Self::FINAL_STR)? {
+			0 => Some(Self::CurToEnd),
+			1 => Some(Self::CurToBegin),
+			2 => Some(Self::All),
+			_ => None,
+		}
+	}
+}
+
+csi_sequence!(SelectiveEraseLine);
+from_sequence!(SelectiveEraseLine);
+
+/// Screen Alignment Pattern, or **DECALN**, which fills the screen with `E`
+/// characters.
+/// 
+/// This is mostly useful for testing terminals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenAlignment;
+
+impl ScreenAlignment {
+	/// Length of the sequence displayed by [`ScreenAlignment`].
+	pub const MAX_LEN: usize = crate::fe_seq::ESC_STR.len() + 2;
+}
+
+impl Display for ScreenAlignment {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_str(crate::fe_seq::ESC_STR)?;
+		f.write_str("#8")
+	}
+}
+
+impl<'a> FromSequence<'a> for ScreenAlignment {
+	#[inline(always)]
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		parse_escape(bytes, "#8").map(move |len| (Self, len))
+	}
+}
+
+/// Size of the characters on the line of the cursor (DECDHL, DECSWL and
+/// DECDWL).
+/// 
+/// Characters on a double-width line occupy two columns each, so only the
+/// first half of the columns can be used.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineSize {
+	/// Top half of a line of double-width, double-height characters. The same
+	/// text should be written to the line below it with
+	/// [`LineSize::DoubleHeightBottom`].
+	DoubleHeightTop,
+	/// Bottom half of a line of double-width, double-height characters.
+	DoubleHeightBottom,
+	/// Normal line.
+	#[default]
+	SingleWidth,
+	/// Line of double-width characters.
+	DoubleWidth,
+}
+
+impl LineSize {
+	/// Length of the sequence displayed by [`LineSize`].
+	pub const MAX_LEN: usize = crate::fe_seq::ESC_STR.len() + 2;
+
+	/// Return `true` if characters on the line occupy two columns each.
+	#[inline(always)]
+	pub const fn is_double_width(self) -> bool {
+		!matches!(self, Self::SingleWidth)
+	}
+}
+
+impl Display for LineSize {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_str(crate::fe_seq::ESC_STR)?;
+		f.write_str(match self {
+			Self::DoubleHeightTop => "#3",
+			Self::DoubleHeightBottom => "#4",
+			Self::SingleWidth => "#5",
+			Self::DoubleWidth => "#6",
+		})
+	}
+}
+
+impl<'a> FromSequence<'a> for LineSize {
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let len = parse_escape(bytes, "#")? + 1;
+		let size = match bytes.get(len - 1) {
+			Some(b'3') => Self::DoubleHeightTop,
+			Some(b'4') => Self::DoubleHeightBottom,
+			Some(b'5') => Self::SingleWidth,
+			Some(b'6') => Self::DoubleWidth,
+			Some(..) => return Err(ParseError::Invalid),
+			None => return Err(ParseError::Incomplete),
+		};
+		Ok((size, len))
+	}
+}
+
+/// Bell character, which makes the terminal beep or flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bell;
+
+impl Bell {
+	/// Length of the sequence displayed by [`Bell`].
+	pub const MAX_LEN: usize = 1;
+}
+
+impl Display for Bell {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_char(crate::parse::BEL as char)
+	}
+}
+
+impl<'a> FromSequence<'a> for Bell {
+	#[inline(always)]
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		match bytes.first() {
+			Some(&crate::parse::BEL) => Ok((Self, 1)),
+			Some(..) => Err(ParseError::Invalid),
+			None => Err(ParseError::Incomplete),
+		}
+	}
+}
+
+/// Volume of a bell, as set by [`WarningBellVolume`] and [`MarginBellVolume`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BellVolume {
+	Off,
+	Low,
+	High,
+}
+
+impl BellVolume {
+	#[inline(always)]
+	const fn param(self) -> &'static str {
+		match self {
+			Self::Off => "1",
+			Self::Low => "4",
+			Self::High => "8",
+		}
+	}
+
+	#[inline(always)]
+	const fn from_param(param: u16) -> Option<Self> {
+		match param {
+			0 | 1 => Some(Self::Off),
+			2..=4 => Some(Self::Low),
+			5..=8 => Some(Self::High),
+			_ => None,
+		}
+	}
+}
+
+/// Set Warning Bell Volume, or **DECSWBV**, which changes the volume of
+/// [`Bell`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarningBellVolume(pub BellVolume);
+
+impl WarningBellVolume {
+	/// Maximum length of the sequence displayed by [`WarningBellVolume`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + Self::FINAL_STR.len();
+	/// Intermediate and final bytes of the sequence.
+	pub const FINAL_STR: &'static str = " t";
+}
+
+impl Display for WarningBellVolume {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Csi::write_begin(f)?;
+		f.write_str(self.0.param())?;
+		f.write_str(Self::FINAL_STR)
+	}
+}
+
+impl WarningBellVolume {
+	/// Return the [`WarningBellVolume`] that `csi` represents, if any.
+	#[inline(always)]
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		csi.single_param(None, Self::FINAL_STR)
+			.and_then(BellVolume::from_param)
+			.map(Self)
+	}
+}
+
+from_sequence!(WarningBellVolume);
+
+/// Set Margin Bell Volume, or **DECSMBV**, which changes the volume of the bell
+/// that rings when the cursor nears the right margin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarginBellVolume(pub BellVolume);
+
+impl MarginBellVolume {
+	/// Maximum length of the sequence displayed by [`MarginBellVolume`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + Self::FINAL_STR.len();
+	/// Intermediate and final bytes of the sequence.
+	pub const FINAL_STR: &'static str = " u";
+}
+
+impl Display for MarginBellVolume {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Csi::write_begin(f)?;
+		f.write_str(self.0.param())?;
+		f.write_str(Self::FINAL_STR)
+	}
+}
+
+impl MarginBellVolume {
+	/// Return the [`MarginBellVolume`] that `csi` represents, if any.
+	#[inline(always)]
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		csi.single_param(None, Self::FINAL_STR)
+			.and_then(BellVolume::from_param)
+			.map(Self)
+	}
+}
+
+from_sequence!(MarginBellVolume);
+
+/// Character written several times in a row, such as for horizontal rules and
+/// fills.
+/// 
+/// If the terminal supports Repeat, or **REP**, according to the [`Profile`],
+/// the character is written once, followed by a sequence that repeats it as
+/// many times as needed. Otherwise, or if that would be longer, it's written
+/// `count` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeated {
+	/// Character to write.
+	pub ch: char,
+	/// Number of times to write the character.
+	pub count: u16,
+	/// Whether to use REP.
+	pub use_rep: bool,
+}
+
+impl Repeated {
+	/// Final byte of REP.
+	pub const FINAL_STR: &'static str = "b";
+
+	/// Create a [`Repeated`] that writes `ch` `count` times without REP.
+	#[inline(always)]
+	pub const fn new(ch: char, count: u16) -> Self {
+		Self {
+			ch,
+			count,
+			use_rep: false,
+		}
+	}
+
+	/// Return `self` with REP used if `profile` says that the terminal
+	/// supports it.
+	#[inline(always)]
+	pub const fn with_profile(mut self, profile: &Profile) -> Self {
+		self.use_rep = profile.repeat;
+		self
+	}
+}
+
+impl Display for Repeated {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let Some(repeats) = self.count.checked_sub(1) else {
+			return Ok(())
+		};
+		f.write_char(self.ch)?;
+		// REP only repeats graphic characters.
+		let rep_len = Csi::BEGIN_LEN + crate::dec_len(repeats as u32) + 1;
+		if self.use_rep && !self.ch.is_control()
+			&& rep_len < repeats as usize * self.ch.len_utf8()
+		{
+			Csi::write_begin(f)?;
+			write!(f, "{repeats}")?;
+			return f.write_str(Self::FINAL_STR)
+		}
+		(0..repeats).try_for_each(move |_| f.write_char(self.ch))
+	}
+}
+
+impl<'a> FromSequence<'a> for Repeated {
+	/// Parse a character followed by either REP or more of the same
+	/// character.
+	/// 
+	/// A character written `0` times, which is displayed as nothing, is
+	/// parsed from empty `bytes`.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let Some(&first) = bytes.first() else {
+			return Ok((Self::new('\0', 0), 0))
+		};
+		let ch_len = match first {
+			0x00..=0x7f => 1,
+			0xc0..=0xdf => 2,
+			0xe0..=0xef => 3,
+			0xf0..=0xf7 => 4,
+			_ => return Err(ParseError::Invalid),
+		};
+		let ch_bytes = bytes.get(..ch_len).ok_or(ParseError::Incomplete)?;
+		let ch = core::str::from_utf8(ch_bytes).ok()
+			.and_then(move |s| s.chars().next())
+			.filter(move |&ch| ch != '\x1b')
+			.ok_or(ParseError::Invalid)?;
+
+		let rest = &bytes[ch_len..];
+		let rep = match Csi::parse(rest) {
+			Ok((csi, len)) => csi.single_param(None, Self::FINAL_STR)
+				.map(move |repeats| (repeats, len)),
+			Err(ParseError::Incomplete) if !rest.is_empty() => {
+				return Err(ParseError::Incomplete)
+			}
+			Err(..) => None,
+		};
+		if let Some((repeats, len)) = rep {
+			// A missing or `0` parameter repeats the character once.
+			let count = repeats.max(1).checked_add(1)
+				.ok_or(ParseError::Invalid)?;
+			let repeated = Self {
+				ch,
+				count,
+				use_rep: true,
+			};
+			return Ok((repeated, ch_len + len))
+		}
+		let count = 1 + rest.chunks(ch_len)
+			.take_while(move |&chunk| chunk == ch_bytes)
+			.count();
+		let count = u16::try_from(count).map_err(|_| ParseError::Invalid)?;
+		Ok((Self::new(ch, count), count as usize * ch_len))
+	}
+}
+
+/// Shape of the cursor, as set by Set Cursor Style, or **DECSCUSR**.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+	/// Shape chosen by the user of the terminal.
+	#[default]
+	Default,
+	BlinkingBlock,
+	SteadyBlock,
+	BlinkingUnderline,
+	SteadyUnderline,
+	BlinkingBar,
+	SteadyBar,
+}
+
+impl CursorShape {
+	/// Maximum length of the sequence displayed by [`CursorShape`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + Self::FINAL_STR.len();
+	/// Intermediate and final bytes of the sequence.
+	pub const FINAL_STR: &'static str = " q";
+
+	/// Write the parameter of this sequence, without the introducer or the
+	/// final bytes.
+	#[inline(always)]
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		w.write_str(match self {
+			CursorShape::Default => "0",
+			CursorShape::BlinkingBlock => "1",
+			CursorShape::SteadyBlock => "2",
+			CursorShape::BlinkingUnderline => "3",
+			CursorShape::SteadyUnderline => "4",
+			CursorShape::BlinkingBar => "5",
+			CursorShape::SteadyBar => "6",
+		})
+	}
+
+	/// Return the [`CursorShape`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		match csi.single_param(None, Self::FINAL_STR)? {
+			0 => Some(Self::Default),
+			1 => Some(Self::BlinkingBlock),
+			2 => Some(Self::SteadyBlock),
+			3 => Some(Self::BlinkingUnderline),
+			4 => Some(Self::SteadyUnderline),
+			5 => Some(Self::BlinkingBar),
+			6 => Some(Self::SteadyBar),
+			_ => None,
+		}
+	}
+}
+
+csi_sequence!(CursorShape);
+from_sequence!(CursorShape);
+
+/// Change of whether the cursor blinks, using the att610 private mode `12`.
+/// 
+/// Unlike [`CursorShape`], this doesn't change the shape of the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorBlink {
+	On,
+	Off,
+}
+
+impl CursorBlink {
+	/// Maximum length of the sequence displayed by [`CursorBlink`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 3 + 1;
+}
+
+impl Display for CursorBlink {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Csi::write_begin(f)?;
+		f.write_str(match self {
+			CursorBlink::On => "?12h",
+			CursorBlink::Off => "?12l",
+		})
+	}
+}
+
+impl CursorBlink {
+	/// Return the [`CursorBlink`] that `csi` represents, if any.
+	#[inline(always)]
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		private_mode_change(csi, 12).map(move |on| if on { Self::On } else { Self::Off })
+	}
+}
+
+from_sequence!(CursorBlink);
+
+/// Change of xterm's alternate scroll mode, or private mode `1007`.
+/// 
+/// While it's on and the alternate screen is shown, the terminal sends the
+/// mouse wheel as the Up and Down keys, unless mouse reports are enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlternateScroll {
+	On,
+	Off,
+}
+
+impl AlternateScroll {
+	/// Maximum length of the sequence displayed by [`AlternateScroll`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 5 + 1;
+}
+
+impl Display for AlternateScroll {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Csi::write_begin(f)?;
+		f.write_str(match self {
+			AlternateScroll::On => "?1007h",
+			AlternateScroll::Off => "?1007l",
+		})
+	}
+}
+
+impl AlternateScroll {
+	/// Return the [`AlternateScroll`] that `csi` represents, if any.
+	#[inline(always)]
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		private_mode_change(csi, 1007).map(move |on| if on { Self::On } else { Self::Off })
+	}
+}
+
+from_sequence!(AlternateScroll);
+
+/// Change of the private mode `2031` of contour, which makes the terminal
+/// send a report whenever the color scheme that the user prefers changes.
+/// 
+/// The reports are delivered as
+/// [`Event::ColorSchemeChanged`](crate::Event::ColorSchemeChanged).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSchemeUpdates {
+	On,
+	Off,
+}
+
+impl ColorSchemeUpdates {
+	/// Maximum length of the sequence displayed by [`ColorSchemeUpdates`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 5 + 1;
+}
+
+impl Display for ColorSchemeUpdates {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Csi::write_begin(f)?;
+		f.write_str(match self {
+			ColorSchemeUpdates::On => "?2031h",
+			ColorSchemeUpdates::Off => "?2031l",
+		})
+	}
+}
+
+impl ColorSchemeUpdates {
+	/// Return the [`ColorSchemeUpdates`] that `csi` represents, if any.
+	#[inline(always)]
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		private_mode_change(csi, 2031).map(move |on| if on { Self::On } else { Self::Off })
 	}
 }
 
+from_sequence!(ColorSchemeUpdates);
+
+/// Change of the encoding of mouse reports.
+/// 
+/// Mouse reports must also be enabled separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEncodingChange {
+	Enable(MouseEncoding),
+	Disable(MouseEncoding),
+}
+
+impl MouseEncodingChange {
+	/// Maximum length of the sequence displayed by [`MouseEncodingChange`].
+	pub const MAX_LEN: usize = Csi::BEGIN_LEN + 1 + 4 + 1;
+}
+
+impl Display for MouseEncodingChange {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Csi::write_begin(f)?;
+		match self {
+			MouseEncodingChange::Enable(encoding) => {
+				write!(f, "?{}h", encoding.mode())
+			}
+			MouseEncodingChange::Disable(encoding) => {
+				write!(f, "?{}l", encoding.mode())
+			}
+		}
+	}
+}
+
+impl MouseEncodingChange {
+	/// Return the [`MouseEncodingChange`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		[MouseEncoding::Sgr, MouseEncoding::SgrPixels, MouseEncoding::Urxvt]
+			.into_iter()
+			.find_map(move |encoding| {
+				private_mode_change(csi, encoding.mode()).map(move |enable| {
+					if enable { Self::Enable(encoding) } else { Self::Disable(encoding) }
+				})
+			})
+	}
+}
+
+from_sequence!(MouseEncodingChange);
+
 /// Font weight change.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Weight {
@@ -80,6 +954,15 @@ pub enum Weight {
 }
 
 impl Weight {
+	/// Maximum length of the sequence displayed by [`Weight`].
+	pub const MAX_LEN: usize = max_len(
+		Self::Bold.into_sgr().encoded_len(),
+		max_len(
+			Self::Thin.into_sgr().encoded_len(),
+			Self::Regular.into_sgr().encoded_len(),
+		),
+	);
+
 	/// Convert `self` into an [`Sgr`].
 	#[inline(always)]
 	pub const fn into_sgr(self) -> Sgr {
@@ -91,6 +974,22 @@ impl Weight {
 	}
 }
 
+csi_sequence!(sgr Weight);
+from_sequence!(sgr Weight);
+
+impl Weight {
+	/// Return the [`Weight`] that `sgr` changes to, if any.
+	#[inline(always)]
+	pub const fn from_sgr(sgr: Sgr) -> Option<Self> {
+		match sgr {
+			Sgr::WeightBoldOn => Some(Self::Bold),
+			Sgr::WeightThinOn => Some(Self::Thin),
+			Sgr::WeightAllOff => Some(Self::Regular),
+			_ => None,
+		}
+	}
+}
+
 impl From<Weight> for Sgr {
 	#[inline(always)]
 	fn from(val: Weight) -> Self {
@@ -115,6 +1014,15 @@ pub enum Underline {
 }
 
 impl Underline {
+	/// Maximum length of the sequence displayed by [`Underline`].
+	pub const MAX_LEN: usize = max_len(
+		Self::Single.into_sgr().encoded_len(),
+		max_len(
+			Self::Double.into_sgr().encoded_len(),
+			Self::None.into_sgr().encoded_len(),
+		),
+	);
+
 	/// Convert `self` into an [`Sgr`].
 	#[inline(always)]
 	pub const fn into_sgr(self) -> Sgr {
@@ -126,6 +1034,22 @@ impl Underline {
 	}
 }
 
+csi_sequence!(sgr Underline);
+from_sequence!(sgr Underline);
+
+impl Underline {
+	/// Return the [`Underline`] that `sgr` changes to, if any.
+	#[inline(always)]
+	pub const fn from_sgr(sgr: Sgr) -> Option<Self> {
+		match sgr {
+			Sgr::UnderlineNone => Some(Self::None),
+			Sgr::UnderlineSingle => Some(Self::Single),
+			Sgr::UnderlineDouble => Some(Self::Double),
+			_ => None,
+		}
+	}
+}
+
 impl From<Underline> for Sgr {
 	#[inline(always)]
 	fn from(val: Underline) -> Self {
@@ -171,6 +1095,37 @@ impl Color {
 			Self::Rgb(r, g, b) => Sgr::Background(SgrColor::Rgb(r, g, b)),
 		}
 	}
+
+	/// Return black or white, whichever is more readable on the background
+	/// color `bg`, such as for badges whose color is chosen at runtime.
+	/// 
+	/// The colors are compared by their contrast ratio with `bg`, as defined
+	/// by WCAG, with the luminance of `bg` approximated. Table colors are
+	/// looked up with [`xterm_256_to_rgb`], and black and white are returned
+	/// as the table colors `16` and `231`, which themes don't change.
+	/// [`Self::Reset`] is returned for [`Self::Reset`], because the default
+	/// background is unknown.
+	pub const fn contrasting_foreground(bg: Color) -> Color {
+		let (r, g, b) = match bg {
+			Self::Reset => return Self::Reset,
+			Self::Table(n) => xterm_256_to_rgb(n),
+			Self::Rgb(r, g, b) => (r, g, b),
+		};
+		// Relative luminance, with the channels squared instead of
+		// linearized, scaled by 10000 * 255^2.
+		let luminance = 2126 * (r as u64 * r as u64)
+			+ 7152 * (g as u64 * g as u64)
+			+ 722 * (b as u64 * b as u64);
+		// Black and white contrast equally with a luminance of
+		// sqrt(1.05 * 0.05) - 0.05.
+		let is_light = luminance > 116_478_632;
+		match (bg, is_light) {
+			(Self::Table(..), true) => Self::Table(16),
+			(Self::Table(..), false) => Self::Table(231),
+			(_, true) => Self::Rgb(0, 0, 0),
+			(_, false) => Self::Rgb(255, 255, 255),
+		}
+	}
 }
 
 impl From<SgrColor> for Color {
@@ -183,6 +1138,120 @@ impl From<SgrColor> for Color {
 	}
 }
 
+/// Movement of the cursor that is written as one sequence, such as each of
+/// the sequences of a [`Movement`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMove {
+	/// Move by a number of rows, up if it's negative (**CUU** or **CUD**).
+	/// 
+	/// Moving by `0` rows is displayed as nothing.
+	Rows(i8),
+	/// Move by a number of columns, left if it's negative (**CUB** or
+	/// **CUF**).
+	/// 
+	/// Moving by `0` columns is displayed as nothing.
+	Columns(i8),
+	/// Move to a row and a column, counted from `1` (**CUP**).
+	To {
+		row: NonZeroU8,
+		column: NonZeroU8,
+	},
+	/// Move to a column of the same row, counted from `1` (**CHA**).
+	/// 
+	/// Terminals stop at the last column, so a large column moves the cursor
+	/// to the right edge of the screen.
+	Column(NonZeroU16),
+}
+
+impl CursorMove {
+	/// Maximum length of the sequence displayed by [`CursorMove`].
+	pub const MAX_LEN: usize = max_len(
+		Csi::BEGIN_LEN + "128".len() + 1,
+		Csi::BEGIN_LEN + "255;255".len() + 1,
+	);
+
+	/// Write the parameters of this sequence, without the introducer or the
+	/// final byte.
+	#[inline(always)]
+	pub fn write_params_to(&self, w: &mut (impl Write + ?Sized)) -> FmtResult {
+		match self {
+			Self::Rows(delta) | Self::Columns(delta) => {
+				write!(w, "{}", delta.unsigned_abs())
+			}
+			Self::To { row, column } => write!(w, "{row};{column}"),
+			Self::Column(column) => write!(w, "{column}"),
+		}
+	}
+
+	/// Return the final byte of the sequence.
+	#[inline(always)]
+	pub const fn final_str(&self) -> &'static str {
+		match self {
+			Self::Rows(i8::MIN..=-1) => "A",
+			Self::Rows(..) => "B",
+			Self::Columns(i8::MIN..=-1) => "D",
+			Self::Columns(..) => "C",
+			Self::To { .. } => "H",
+			Self::Column(..) => "G",
+		}
+	}
+
+	/// Return the [`CursorMove`] that `csi` represents, if any.
+	pub fn from_csi(csi: &Csi<'_>) -> Option<Self> {
+		if csi.final_byte.byte() == b'H' && csi.private_marker().is_none()
+			&& csi.intermediate_bytes.is_empty()
+		{
+			let mut params = csi.params().map(move |param| {
+				u8::try_from(param.unwrap_or(1).max(1)).ok().and_then(NonZeroU8::new)
+			});
+			let row = params.next().unwrap_or(NonZeroU8::new(1));
+			let column = params.next().unwrap_or(NonZeroU8::new(1));
+			return match (row, column, params.next()) {
+				(Some(row), Some(column), None) => Some(Self::To { row, column }),
+				_ => None,
+			}
+		}
+		if let Some(column) = csi.single_param(None, "G") {
+			return NonZeroU16::new(column.max(1)).map(Self::Column)
+		}
+
+		[("A", true, -1), ("B", true, 1), ("D", false, -1), ("C", false, 1)]
+			.into_iter()
+			.find_map(move |(final_str, is_rows, sign)| {
+				// A missing or `0` parameter moves by one.
+				let count = csi.single_param(None, final_str)?.max(1);
+				let delta = i8::try_from(count as i32 * sign).ok()?;
+				Some(if is_rows { Self::Rows(delta) } else { Self::Columns(delta) })
+			})
+	}
+}
+
+impl CsiSequence for CursorMove {
+	#[inline(always)]
+	fn write_params_to(&self, w: &mut dyn Write) -> FmtResult {
+		Self::write_params_to(self, w)
+	}
+
+	#[inline(always)]
+	fn final_str(&self) -> &'static str {
+		Self::final_str(self)
+	}
+}
+
+impl Display for CursorMove {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		if let Self::Rows(0) | Self::Columns(0) = self {
+			return Ok(())
+		}
+		Csi::write_begin(f)?;
+		self.write_params_to(f)?;
+		f.write_str(self.final_str())
+	}
+}
+
+from_sequence!(CursorMove);
+
 /// Relative or absolute movement of the cursor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Movement {
@@ -196,20 +1265,90 @@ pub enum Movement {
 	},
 }
 
+impl Movement {
+	/// Maximum length of the sequences displayed by [`Movement`].
+	pub const MAX_LEN: usize = max_len(
+		2 * (Csi::BEGIN_LEN + "128".len() + 1),
+		Csi::BEGIN_LEN + "255;255".len() + 1,
+	);
+
+	/// Create a [`Movement::Absolute`] to `pos`, or [`None`] if `pos` is
+	/// beyond the 255th row or column.
+	#[inline(always)]
+	pub const fn absolute(pos: Pos) -> Option<Self> {
+		if pos.row >= u8::MAX as u16 || pos.col >= u8::MAX as u16 {
+			return None
+		}
+		match (NonZeroU8::new(pos.row as u8 + 1), NonZeroU8::new(pos.col as u8 + 1)) {
+			(Some(row), Some(column)) => Some(Self::Absolute { row, column }),
+			_ => None,
+		}
+	}
+
+	/// Return the sequences that this movement is written as, in order.
+	/// 
+	/// A relative movement of both the rows and the columns is two sequences,
+	/// and one of neither is none, so each of them can be written on its own
+	/// or in a [`CsiChain`](crate::CsiChain). Moving by `0` is the same as not
+	/// moving.
+	#[inline(always)]
+	pub fn sequences(&self) -> [Option<CursorMove>; 2] {
+		match *self {
+			Self::Relative { rows, columns } => [
+				rows.filter(|&rows| rows != 0).map(CursorMove::Rows),
+				columns.filter(|&columns| columns != 0).map(CursorMove::Columns),
+			],
+			Self::Absolute { row, column } => [Some(CursorMove::To { row, column }), None],
+		}
+	}
+}
+
+impl TryFrom<Pos> for Movement {
+	type Error = Pos;
+
+	/// Convert `pos` with [`Movement::absolute`], returning it back if it's
+	/// out of range.
+	#[inline(always)]
+	fn try_from(pos: Pos) -> Result<Self, Self::Error> {
+		Self::absolute(pos).ok_or(pos)
+	}
+}
+
 impl Display for Movement {
 	#[inline(always)]
 	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-		match self {
-			Movement::Relative { rows, columns } => {
-				if let Some(x) = rows { move_cursor_delta(f, 'A', 'B', *x)?; }
-				if let Some(x) = columns { move_cursor_delta(f, 'D', 'C', *x)?; }
-				Ok(())
+		self.sequences().into_iter().flatten().try_for_each(move |seq| seq.fmt(f))
+	}
+}
+
+impl<'a> FromSequence<'a> for Movement {
+	/// Parse an absolute movement, or a relative movement of the rows and the
+	/// columns, which are two sequences if both of them move.
+	/// 
+	/// A relative movement of neither, which is displayed as nothing, is
+	/// parsed from empty `bytes`.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		if bytes.is_empty() {
+			return Ok((Self::Relative { rows: None, columns: None }, 0))
+		}
+		match CursorMove::parse_sequence(bytes)? {
+			(CursorMove::To { row, column }, len) => {
+				Ok((Self::Absolute { row, column }, len))
 			}
-			Movement::Absolute { row, column } => {
-				Csi::write_begin(f)?;
-				write!(f, "{row};{column}")?;
-				f.write_str("J")
+			(CursorMove::Rows(rows), len) => {
+				// The columns may follow in a second sequence.
+				let (columns, next_len) = match CursorMove::parse_sequence(&bytes[len..]) {
+					Ok((CursorMove::Columns(columns), next_len)) => {
+						(Some(columns), next_len)
+					}
+					_ => (None, 0),
+				};
+				Ok((Self::Relative { rows: Some(rows), columns }, len + next_len))
+			}
+			(CursorMove::Columns(columns), len) => {
+				Ok((Self::Relative { rows: None, columns: Some(columns) }, len))
 			}
+			(CursorMove::Column(..), ..) => Err(ParseError::Invalid),
 		}
 	}
 }
@@ -223,6 +1362,12 @@ pub enum Italic {
 }
 
 impl Italic {
+	/// Maximum length of the sequence displayed by [`Italic`].
+	pub const MAX_LEN: usize = max_len(
+		Self::On.into_sgr().encoded_len(),
+		Self::Off.into_sgr().encoded_len(),
+	);
+
 	/// Convert `self` into an [`Sgr`].
 	#[inline(always)]
 	pub const fn into_sgr(self) -> Sgr {
@@ -233,6 +1378,21 @@ impl Italic {
 	}
 }
 
+csi_sequence!(sgr Italic);
+from_sequence!(sgr Italic);
+
+impl Italic {
+	/// Return the [`Italic`] that `sgr` changes to, if any.
+	#[inline(always)]
+	pub const fn from_sgr(sgr: Sgr) -> Option<Self> {
+		match sgr {
+			Sgr::ItalicOn => Some(Self::On),
+			Sgr::ItalicOff => Some(Self::Off),
+			_ => None,
+		}
+	}
+}
+
 impl From<Italic> for Sgr {
 	#[inline(always)]
 	fn from(val: Italic) -> Self {
@@ -256,6 +1416,12 @@ pub enum Strikethrough {
 }
 
 impl Strikethrough {
+	/// Maximum length of the sequence displayed by [`Strikethrough`].
+	pub const MAX_LEN: usize = max_len(
+		Self::On.into_sgr().encoded_len(),
+		Self::Off.into_sgr().encoded_len(),
+	);
+
 	/// Convert `self` into an [`Sgr`].
 	#[inline(always)]
 	pub const fn into_sgr(self) -> Sgr {
@@ -266,6 +1432,21 @@ impl Strikethrough {
 	}
 }
 
+csi_sequence!(sgr Strikethrough);
+from_sequence!(sgr Strikethrough);
+
+impl Strikethrough {
+	/// Return the [`Strikethrough`] that `sgr` changes to, if any.
+	#[inline(always)]
+	pub const fn from_sgr(sgr: Sgr) -> Option<Self> {
+		match sgr {
+			Sgr::StrikethroughOn => Some(Self::On),
+			Sgr::StrikethroughOff => Some(Self::Off),
+			_ => None,
+		}
+	}
+}
+
 impl From<Strikethrough> for Sgr {
 	#[inline(always)]
 	fn from(val: Strikethrough) -> Self {
@@ -280,15 +1461,74 @@ impl Display for Strikethrough {
 	}
 }
 
+/// Inverse video state change, which swaps the foreground and background
+/// colors.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invert {
+	#[default]
+	Off,
+	On,
+}
+
+impl Invert {
+	/// Maximum length of the sequence displayed by [`Invert`].
+	pub const MAX_LEN: usize = max_len(
+		Self::On.into_sgr().encoded_len(),
+		Self::Off.into_sgr().encoded_len(),
+	);
+
+	/// Convert `self` into an [`Sgr`].
+	#[inline(always)]
+	pub const fn into_sgr(self) -> Sgr {
+		match self {
+			Self::On => Sgr::Invert,
+			Self::Off => Sgr::ReversedOff,
+		}
+	}
+}
+
+csi_sequence!(sgr Invert);
+from_sequence!(sgr Invert);
+
+impl Invert {
+	/// Return the [`Invert`] that `sgr` changes to, if any.
+	#[inline(always)]
+	pub const fn from_sgr(sgr: Sgr) -> Option<Self> {
+		match sgr {
+			Sgr::Invert => Some(Self::On),
+			Sgr::ReversedOff => Some(Self::Off),
+			_ => None,
+		}
+	}
+}
+
+impl From<Invert> for Sgr {
+	#[inline(always)]
+	fn from(val: Invert) -> Self {
+		val.into_sgr()
+	}
+}
+
+impl Display for Invert {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		self.into_sgr().fmt(f)
+	}
+}
+
 /// Graphics setting state change.
 /// 
 /// This structure combines multiple state changes into one escape sequence.
+/// A state change that doesn't change anything displays nothing, rather than
+/// an empty SGR sequence, which terminals treat as a reset of every setting.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct StateChange {
 	pub weight: Option<Weight>,
 	pub italic: Option<Italic>,
 	pub underline: Option<Underline>,
 	pub strikethrough: Option<Strikethrough>,
+	pub invert: Option<Invert>,
 	pub foreground: Option<Color>,
 	pub background: Option<Color>,
 }
@@ -300,7 +1540,34 @@ const fn opt_replace_some<T: Copy>(option: Option<T>, value: T) -> Option<T> {
 	}
 }
 
+const fn opt_or<T: Copy>(option: Option<T>, other: Option<T>) -> Option<T> {
+	match option {
+		Some(..) => option,
+		None => other,
+	}
+}
+
 impl StateChange {
+	/// Maximum length of the sequence displayed by [`StateChange`].
+	pub const MAX_LEN: usize = {
+		const fn params(max_len: usize) -> usize {
+			max_len - Csi::BEGIN_LEN - Csi::FINAL_STR.len()
+		}
+		Csi::BEGIN_LEN
+			// Bold and thin weights are preceded by a reset.
+			+ Sgr::WeightAllOff.params_len() + 1 + max_len(
+				Sgr::WeightBoldOn.params_len(),
+				Sgr::WeightThinOn.params_len(),
+			)
+			+ 1 + params(Italic::MAX_LEN)
+			+ 1 + params(Underline::MAX_LEN)
+			+ 1 + params(Strikethrough::MAX_LEN)
+			+ 1 + params(Invert::MAX_LEN)
+			+ 1 + Sgr::MAX_PARAMS_LEN
+			+ 1 + Sgr::MAX_PARAMS_LEN
+			+ Csi::FINAL_STR.len()
+	};
+
 	/// Create a [`StateChange`] that doesn't change any formatting.
 	#[inline(always)]
 	pub const fn new() -> Self {
@@ -309,11 +1576,24 @@ impl StateChange {
 			italic: None,
 			underline: None,
 			strikethrough: None,
+			invert: None,
 			foreground: None,
 			background: None,
 		}
 	}
 
+	/// Return `true` if `self` doesn't change any formatting.
+	#[inline(always)]
+	pub const fn is_empty(&self) -> bool {
+		self.weight.is_none()
+			&& self.italic.is_none()
+			&& self.underline.is_none()
+			&& self.strikethrough.is_none()
+			&& self.invert.is_none()
+			&& self.foreground.is_none()
+			&& self.background.is_none()
+	}
+
 	/// Create a [`StateChange`] that resets the effects of this one.
 	#[inline(always)]
 	pub const fn resetter(&self) -> Self {
@@ -324,11 +1604,48 @@ impl StateChange {
 			strikethrough: opt_replace_some(
 				self.strikethrough, Strikethrough::Off
 			),
+			invert: opt_replace_some(self.invert, Invert::Off),
 			foreground: opt_replace_some(self.foreground, Color::Reset),
 			background: opt_replace_some(self.background, Color::Reset),
 		}
 	}
 
+	/// Create a [`StateChange`] that has the effect of `self` followed by
+	/// `next`, so that they can be written as one sequence.
+	/// 
+	/// Settings that both change are only changed to the one in `next`.
+	#[inline(always)]
+	pub const fn then(&self, next: &Self) -> Self {
+		Self {
+			weight: opt_or(next.weight, self.weight),
+			italic: opt_or(next.italic, self.italic),
+			underline: opt_or(next.underline, self.underline),
+			strikethrough: opt_or(next.strikethrough, self.strikethrough),
+			invert: opt_or(next.invert, self.invert),
+			foreground: opt_or(next.foreground, self.foreground),
+			background: opt_or(next.background, self.background),
+		}
+	}
+
+	/// Return `self` without the settings that don't change anything when it's
+	/// written where the style is `style`.
+	pub fn normalized_from(&self, style: &Style) -> Self {
+		let mut target = *style;
+		target.apply(self);
+		style.diff_to(&target)
+	}
+
+	/// Return `self` without the settings that don't change anything when it's
+	/// written where the style is the default, such as at the start of output
+	/// or after [`Sgr::Reset`].
+	/// 
+	/// For example, [`Weight::Regular`] is removed, since no weight was set
+	/// before.
+	#[inline(always)]
+	pub fn normalized(&self) -> Self {
+		self.normalized_from(&Style::new())
+	}
+
 	/// Set [`Self::weight`].
 	#[inline(always)]
 	pub const fn with_weight(self, x: Weight) -> Self {
@@ -365,6 +1682,15 @@ impl StateChange {
 		}
 	}
 
+	/// Set [`Self::invert`].
+	#[inline(always)]
+	pub const fn with_invert(self, x: Invert) -> Self {
+		Self {
+			invert: Some(x),
+			..self
+		}
+	}
+
 	/// Set [`Self::foreground`].
 	#[inline(always)]
 	pub const fn with_foreground(self, x: Color) -> Self {
@@ -386,9 +1712,16 @@ impl StateChange {
 
 impl Display for StateChange {
 	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		if self.is_empty() {
+			// An empty SGR sequence would reset everything.
+			return Ok(())
+		}
 		Csi::write_begin(f)?;
 
+		let mut is_first = true;
+
 		if let Some(weight) = self.weight {
+			write_separator(f, &mut is_first)?;
 			// Somehow, terminals can have characters that are bold _and_ thin
 			// at the same time...?
 			match weight {
@@ -406,44 +1739,94 @@ impl Display for StateChange {
 			}?;
 		}
 
-		let mut is_first = true;
-
 		if let Some(state) = self.italic {
-			if is_first { f.write_str(";")?; is_first = false; }
+			write_separator(f, &mut is_first)?;
 			state.into_sgr().write_params_to(f)?;
 		}
 
 		if let Some(underline) = self.underline {
-			if is_first { f.write_str(";")?; is_first = false; }
-			match underline {
-				Underline::None => Sgr::UnderlineNone,
-				Underline::Single => Sgr::UnderlineSingle,
-				Underline::Double => Sgr::UnderlineDouble,
-			}.write_params_to(f)?;
+			write_separator(f, &mut is_first)?;
+			underline.into_sgr().write_params_to(f)?;
 		}
 
 		if let Some(state) = self.strikethrough {
-			if is_first { f.write_str(";")?; is_first = false; }
+			write_separator(f, &mut is_first)?;
+			state.into_sgr().write_params_to(f)?;
+		}
+
+		if let Some(state) = self.invert {
+			write_separator(f, &mut is_first)?;
 			state.into_sgr().write_params_to(f)?;
 		}
 
 		if let Some(color) = self.foreground {
-			if is_first { f.write_str(";")?; is_first = false; }
-			match color {
-				Color::Reset => Sgr::ForegroundDefault,
-				Color::Table(n) => Sgr::Foreground(SgrColor::Table(n)),
-				Color::Rgb(r, g, b) => Sgr::Foreground(SgrColor::Rgb(r, g, b)),
-			}.write_params_to(f)?;
+			write_separator(f, &mut is_first)?;
+			color.into_foreground().write_params_to(f)?;
 		}
 		if let Some(color) = self.background {
-			if is_first { f.write_str(";")?; }
-			match color {
-				Color::Reset => Sgr::BackgroundDefault,
-				Color::Table(n) => Sgr::Background(SgrColor::Table(n)),
-				Color::Rgb(r, g, b) => Sgr::Background(SgrColor::Rgb(r, g, b)),
-			}.write_params_to(f)?;
+			write_separator(f, &mut is_first)?;
+			color.into_background().write_params_to(f)?;
 		}
 
 		f.write_str(Csi::FINAL_STR)
 	}
 }
+
+impl<'a> FromSequence<'a> for StateChange {
+	/// Parse an SGR sequence that only changes the settings of a
+	/// [`StateChange`], in any order.
+	/// 
+	/// A state change that doesn't change anything, which is displayed as
+	/// nothing, is parsed from empty `bytes`.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		if bytes.is_empty() {
+			return Ok((Self::new(), 0))
+		}
+		let (csi, len) = Csi::parse(bytes)?;
+		let mut change = Self::new();
+		for sgr in Sgr::parse_csi(&csi).ok_or(ParseError::Invalid)? {
+			match sgr {
+				Sgr::Foreground(color) => change.foreground = Some(color.into()),
+				Sgr::ForegroundDefault => change.foreground = Some(Color::Reset),
+				Sgr::Background(color) => change.background = Some(color.into()),
+				Sgr::BackgroundDefault => change.background = Some(Color::Reset),
+				_ => if let Some(x) = Weight::from_sgr(sgr) {
+					change.weight = Some(x);
+				} else if let Some(x) = Italic::from_sgr(sgr) {
+					change.italic = Some(x);
+				} else if let Some(x) = Underline::from_sgr(sgr) {
+					change.underline = Some(x);
+				} else if let Some(x) = Strikethrough::from_sgr(sgr) {
+					change.strikethrough = Some(x);
+				} else if let Some(x) = Invert::from_sgr(sgr) {
+					change.invert = Some(x);
+				} else {
+					return Err(ParseError::Invalid)
+				},
+			}
+		}
+		Ok((change, len))
+	}
+}
+
+const _: () = {
+	assert!(EraseDisplay::MAX_LEN == "\x1b[0J".len());
+	assert!(EraseLine::MAX_LEN == "\x1b[0K".len());
+	assert!(Protection::MAX_LEN == "\x1b[0\"q".len());
+	assert!(SelectiveEraseDisplay::MAX_LEN == "\x1b[?0J".len());
+	assert!(SelectiveEraseLine::MAX_LEN == "\x1b[?0K".len());
+	assert!(ScreenAlignment::MAX_LEN == "\x1b#8".len());
+	assert!(LineSize::MAX_LEN == "\x1b#6".len());
+	assert!(WarningBellVolume::MAX_LEN == "\x1b[8 t".len());
+	assert!(MarginBellVolume::MAX_LEN == "\x1b[8 u".len());
+	assert!(CursorShape::MAX_LEN == "\x1b[0 q".len());
+	assert!(CursorBlink::MAX_LEN == "\x1b[?12h".len());
+	assert!(Invert::MAX_LEN == "\x1b[27m".len());
+	assert!(MouseEncodingChange::MAX_LEN == "\x1b[?1006h".len());
+	assert!(CursorMove::MAX_LEN == "\x1b[255;255H".len());
+	assert!(Movement::MAX_LEN == "\x1b[128A\x1b[128D".len());
+	assert!(
+		StateChange::MAX_LEN
+			== "\x1b[22;1;23;24;29;27;38;2;255;255;255;48;2;255;255;255m".len()
+	);
+};
@@ -0,0 +1,126 @@
+use super::{
+	Span, Style,
+};
+
+use alloc::string::String;
+use core::fmt::{
+	Arguments, Display, Formatter, Result as FmtResult, Write,
+};
+
+/// String of styled segments, which only writes the settings that change
+/// between adjacent segments.
+/// 
+/// Appending segments with the same style doesn't write any sequences
+/// between them, and segments with different styles are separated by the
+/// [`StateChange`](super::StateChange) between them instead of a reset and
+/// the whole next style. The string ends in the style of its last segment
+/// until it's finished with [`Self::into_string`], or displayed.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct AnsiString {
+	text: String,
+	style: Style,
+}
+
+impl AnsiString {
+	/// Create an empty [`AnsiString`].
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			text: String::new(),
+			style: Style::new(),
+		}
+	}
+
+	/// Create an empty [`AnsiString`] with at least `capacity` bytes of
+	/// storage.
+	#[inline(always)]
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self {
+			text: String::with_capacity(capacity),
+			style: Style::new(),
+		}
+	}
+
+	/// Return the style at the end of the string.
+	#[inline(always)]
+	pub const fn style(&self) -> Style {
+		self.style
+	}
+
+	/// Return the contents, which end in [`Self::style`].
+	#[inline(always)]
+	pub fn as_str(&self) -> &str {
+		&self.text
+	}
+
+	/// Return `true` if nothing was appended.
+	#[inline(always)]
+	pub fn is_empty(&self) -> bool {
+		self.text.is_empty()
+	}
+
+	/// Append `content` in `style`.
+	/// 
+	/// Nothing is written for empty content, so the style doesn't change.
+	pub fn push_str(&mut self, content: &str, style: Style) {
+		if content.is_empty() {
+			return
+		}
+		self.set_style(style);
+		self.text.push_str(content);
+	}
+
+	/// Append the content of `span` in its style.
+	#[inline(always)]
+	pub fn push_span(&mut self, span: &Span<'_>) {
+		self.push_str(span.content, span.style);
+	}
+
+	/// Append `args` formatted in `style`.
+	/// 
+	/// The style is changed even if `args` formats to nothing.
+	pub fn push_fmt(&mut self, args: Arguments<'_>, style: Style) {
+		self.set_style(style);
+		let _ = self.text.write_fmt(args);
+	}
+
+	/// Return the contents, followed by the sequence that resets the style if
+	/// it isn't the default.
+	pub fn into_string(mut self) -> String {
+		self.set_style(Style::new());
+		self.text
+	}
+
+	/// Write the settings that change the current style to `style`.
+	fn set_style(&mut self, style: Style) {
+		if self.style != style {
+			let _ = write!(self.text, "{}", self.style.diff_to(&style));
+			self.style = style;
+		}
+	}
+}
+
+impl<'a> Extend<Span<'a>> for AnsiString {
+	fn extend<I: IntoIterator<Item = Span<'a>>>(&mut self, iter: I) {
+		for span in iter {
+			self.push_span(&span);
+		}
+	}
+}
+
+impl<'a> FromIterator<Span<'a>> for AnsiString {
+	fn from_iter<I: IntoIterator<Item = Span<'a>>>(iter: I) -> Self {
+		let mut string = Self::new();
+		string.extend(iter);
+		string
+	}
+}
+
+impl Display for AnsiString {
+	/// Display the contents, followed by the sequence that resets the style if
+	/// it isn't the default.
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_str(&self.text)?;
+		self.style.diff_to(&Style::new()).fmt(f)
+	}
+}
@@ -0,0 +1,142 @@
+use super::{
+	Color, Role, StateChange, StyleRoles,
+};
+use crate::width::WidthPolicy;
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Short text on a colored background, padded with spaces, for status
+/// indicators such as ` PASS ` and ` FAIL `.
+/// 
+/// The text is black or white, whichever is more readable on the background,
+/// unless its color is specified. Edge glyphs in the background color, such
+/// as [`Self::ROUNDED`], can be drawn on either side on the default
+/// background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Badge<'a> {
+	/// Text of the badge, without the padding.
+	pub text: &'a str,
+	/// Background color of the badge.
+	pub background: Color,
+	/// Color of the text, or `None` for
+	/// [`Color::contrasting_foreground`] of the background.
+	pub foreground: Option<Color>,
+	/// Number of spaces on each side of the text.
+	pub padding: u8,
+	/// Glyphs on the left and right of the badge.
+	pub edges: Option<(char, char)>,
+}
+
+impl<'a> Badge<'a> {
+	/// Half circles from Powerline fonts, which need a patched font, such as
+	/// one of the Nerd Fonts.
+	pub const ROUNDED: (char, char) = ('\u{e0b6}', '\u{e0b4}');
+	/// Half blocks, which most fonts have.
+	pub const BLOCKS: (char, char) = ('▐', '▌');
+
+	/// Create a [`Badge`] of `text` on `background`, with one space on each
+	/// side and no edges.
+	#[inline(always)]
+	pub const fn new(text: &'a str, background: Color) -> Self {
+		Self {
+			text,
+			background,
+			foreground: None,
+			padding: 1,
+			edges: None,
+		}
+	}
+
+	/// Create a [`Badge`] of `text` in the colors of `role` in `roles`, such as
+	/// [`Role::Success`] for ` PASS `, with one space on each side and no
+	/// edges.
+	/// 
+	/// The background of the role is used as the background of the badge, and
+	/// its foreground as the color of the text. If the role has no background,
+	/// its foreground is used as the background instead.
+	pub const fn from_roles(
+		text: &'a str, role: Role, roles: &StyleRoles,
+	) -> Self {
+		let change = roles.get(role);
+		match (change.background, change.foreground) {
+			(Some(background), Some(foreground)) => {
+				Self::new(text, background).with_foreground(foreground)
+			}
+			(Some(background), None) | (None, Some(background)) => {
+				Self::new(text, background)
+			}
+			(None, None) => Self::new(text, Color::Reset),
+		}
+	}
+
+	/// Return `self` with the text in `foreground`.
+	#[inline(always)]
+	pub const fn with_foreground(mut self, foreground: Color) -> Self {
+		self.foreground = Some(foreground);
+		self
+	}
+
+	/// Return `self` with `padding` spaces on each side of the text.
+	#[inline(always)]
+	pub const fn with_padding(mut self, padding: u8) -> Self {
+		self.padding = padding;
+		self
+	}
+
+	/// Return `self` with the glyphs `left` and `right` on its sides.
+	#[inline(always)]
+	pub const fn with_edges(mut self, (left, right): (char, char)) -> Self {
+		self.edges = Some((left, right));
+		self
+	}
+
+	/// Return the number of cells that the badge occupies.
+	pub fn width(&self, policy: &WidthPolicy) -> usize {
+		let edges = self.edges.map_or(0, move |(left, right)| {
+			policy.char_width(left) + policy.char_width(right)
+		});
+		policy.str_width(self.text) + 2 * self.padding as usize + edges
+	}
+
+	/// Return the color of the text.
+	#[inline(always)]
+	pub const fn text_color(&self) -> Color {
+		match self.foreground {
+			Some(foreground) => foreground,
+			None => Color::contrasting_foreground(self.background),
+		}
+	}
+
+	/// Write `edge` in the background color on the default background.
+	fn write_edge(&self, f: &mut Formatter<'_>, edge: char) -> FmtResult {
+		let style = StateChange::new().with_foreground(self.background);
+		style.fmt(f)?;
+		f.write_char(edge)?;
+		style.resetter().fmt(f)
+	}
+}
+
+impl<'a> Display for Badge<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		if let Some((left, ..)) = self.edges {
+			self.write_edge(f, left)?;
+		}
+		let style = StateChange::new()
+			.with_foreground(self.text_color())
+			.with_background(self.background);
+		style.fmt(f)?;
+		let write_padding = move |f: &mut Formatter<'_>| {
+			(0..self.padding).try_for_each(move |_| f.write_char(' '))
+		};
+		write_padding(f)?;
+		f.write_str(self.text)?;
+		write_padding(f)?;
+		style.resetter().fmt(f)?;
+		if let Some((.., right)) = self.edges {
+			self.write_edge(f, right)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,172 @@
+use super::{
+	Buffer, Style,
+};
+
+use alloc::vec::Vec;
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Grid of dots that is displayed with Braille patterns, which have 2 columns
+/// and 4 rows of dots in each cell, for plots that are denser than the cells.
+/// 
+/// Dots are addressed by their column and row from the top left corner.
+/// Dots outside of the canvas are ignored, so lines and plots can extend past
+/// its edges.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct BrailleCanvas {
+	columns: u16,
+	rows: u16,
+	cells: Vec<u8>,
+}
+
+impl BrailleCanvas {
+	/// Create an empty canvas that occupies `columns` by `rows` cells.
+	pub fn new(columns: u16, rows: u16) -> Self {
+		Self {
+			columns,
+			rows,
+			cells: alloc::vec![0; columns as usize * rows as usize],
+		}
+	}
+
+	/// Return the number of cells in each row.
+	#[inline(always)]
+	pub const fn columns(&self) -> u16 {
+		self.columns
+	}
+
+	/// Return the number of rows of cells.
+	#[inline(always)]
+	pub const fn rows(&self) -> u16 {
+		self.rows
+	}
+
+	/// Return the number of dots in each row.
+	#[inline(always)]
+	pub const fn width(&self) -> u32 {
+		self.columns as u32 * 2
+	}
+
+	/// Return the number of rows of dots.
+	#[inline(always)]
+	pub const fn height(&self) -> u32 {
+		self.rows as u32 * 4
+	}
+
+	/// Return the index of the cell and the bit of the dot at `x`, `y`.
+	fn locate(&self, x: i32, y: i32) -> Option<(usize, u8)> {
+		let (x, y) = (u32::try_from(x).ok()?, u32::try_from(y).ok()?);
+		if x >= self.width() || y >= self.height() {
+			return None
+		}
+		let idx = (y / 4) as usize * self.columns as usize + (x / 2) as usize;
+		let bit = match (x % 2, y % 4) {
+			(0, 3) => 0x40,
+			(1, 3) => 0x80,
+			(0, row) => 1 << row,
+			(_, row) => 0x08 << row,
+		};
+		Some((idx, bit))
+	}
+
+	/// Return `true` if the dot at `x`, `y` is set.
+	pub fn get(&self, x: i32, y: i32) -> bool {
+		self.locate(x, y).is_some_and(|(idx, bit)| self.cells[idx] & bit != 0)
+	}
+
+	/// Set the dot at `x`, `y`.
+	pub fn set(&mut self, x: i32, y: i32) {
+		if let Some((idx, bit)) = self.locate(x, y) {
+			self.cells[idx] |= bit;
+		}
+	}
+
+	/// Clear the dot at `x`, `y`.
+	pub fn clear(&mut self, x: i32, y: i32) {
+		if let Some((idx, bit)) = self.locate(x, y) {
+			self.cells[idx] &= !bit;
+		}
+	}
+
+	/// Clear every dot.
+	#[inline(always)]
+	pub fn clear_all(&mut self) {
+		self.cells.fill(0);
+	}
+
+	/// Set the dots on the line from `x0`, `y0` to `x1`, `y1`, including both
+	/// ends.
+	pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+		// Bresenham's algorithm, which works in every octant.
+		let (dx, dy) = (x0.abs_diff(x1) as i64, -(y0.abs_diff(y1) as i64));
+		let step_x = if x0 < x1 { 1 } else { -1 };
+		let step_y = if y0 < y1 { 1 } else { -1 };
+		let (mut x, mut y) = (x0, y0);
+		let mut error = dx + dy;
+		loop {
+			self.set(x, y);
+			if (x, y) == (x1, y1) {
+				break
+			}
+			let double = error * 2;
+			if double >= dy {
+				error += dy;
+				x += step_x;
+			}
+			if double <= dx {
+				error += dx;
+				y += step_y;
+			}
+		}
+	}
+
+	/// Return the Braille pattern of the cell at `column`, `row`, or `None` if
+	/// it has no dots or is outside of the canvas.
+	pub fn char_at(&self, column: u16, row: u16) -> Option<char> {
+		if column >= self.columns {
+			return None
+		}
+		let idx = row as usize * self.columns as usize + column as usize;
+		match self.cells.get(idx) {
+			Some(0) | None => None,
+			Some(&dots) => char::from_u32(0x2800 + dots as u32),
+		}
+	}
+
+	/// Write the cells that have dots to `buffer` with `style`, with the top
+	/// left corner of the canvas at `x`, `y`.
+	/// 
+	/// Cells without dots are left as they are, so the canvas can be drawn
+	/// over other content.
+	pub fn draw(&self, buffer: &mut Buffer, x: u16, y: u16, style: Style) {
+		for row in 0..self.rows {
+			for column in 0..self.columns {
+				let Some(c) = self.char_at(column, row) else {
+					continue
+				};
+				let pos = (x.checked_add(column), y.checked_add(row));
+				let (Some(x), Some(y)) = pos else {
+					continue
+				};
+				buffer.set_str(x, y, c.encode_utf8(&mut [0; 4]), style);
+			}
+		}
+	}
+}
+
+impl Display for BrailleCanvas {
+	/// Display the rows of cells separated by line feeds, with spaces for the
+	/// cells without dots.
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		for row in 0..self.rows {
+			if row > 0 {
+				f.write_char('\n')?;
+			}
+			for column in 0..self.columns {
+				f.write_char(self.char_at(column, row).unwrap_or(' '))?;
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,780 @@
+use super::{
+	render::write_link, LineSize, Protection, Rect, Style,
+};
+use crate::{
+	pos::Pos,
+	sgr::Sgr,
+	width::{
+		graphemes, WidthPolicy,
+	},
+};
+
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+use core::{
+	fmt::{
+		Display, Formatter, Result as FmtResult, Write,
+	},
+	num::NonZeroU16,
+};
+
+/// Identifier of a hyperlink target that is stored in a [`Buffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct LinkId(NonZeroU16);
+
+impl LinkId {
+	/// Return the identifier as a number, which is never `0`.
+	#[inline(always)]
+	pub const fn get(self) -> u16 {
+		self.0.get()
+	}
+}
+
+impl Display for LinkId {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		self.0.fmt(f)
+	}
+}
+
+/// One cell of a [`Buffer`], which holds a grapheme cluster and its style.
+/// 
+/// A cluster that occupies several cells is stored in the first of them, and
+/// the cells after it are continuations, which have an empty symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+	symbol: [u8; Self::CAPACITY],
+	len: u8,
+	pub style: Style,
+	/// Hyperlink that the cell is part of.
+	/// 
+	/// See [`Buffer::add_link`].
+	pub link: Option<LinkId>,
+	/// Whether the cell is excluded from selective erases.
+	/// 
+	/// See [`Buffer::erase_unprotected`].
+	pub protection: Protection,
+}
+
+impl Default for Cell {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::BLANK
+	}
+}
+
+impl Cell {
+	/// Maximum length of a symbol, in bytes.
+	/// 
+	/// Longer clusters are stored as their first character.
+	pub const CAPACITY: usize = 22;
+
+	/// Cell with a space and the default style.
+	pub const BLANK: Self = Self::new(' ', Style::new());
+
+	/// Create a cell with the character `c` and `style`.
+	pub const fn new(c: char, style: Style) -> Self {
+		let mut symbol = [0; Self::CAPACITY];
+		let len = c.encode_utf8(&mut symbol).len();
+		Self {
+			symbol,
+			len: len as u8,
+			style,
+			link: None,
+			protection: Protection::Unprotected,
+		}
+	}
+
+	/// Return the grapheme cluster in this cell, which is empty if this cell
+	/// is a continuation of a wide cluster.
+	#[inline(always)]
+	pub fn symbol(&self) -> &str {
+		// SAFETY: `symbol` is only set from `str`s, cut at character
+		// boundaries.
+		unsafe {
+			core::str::from_utf8_unchecked(&self.symbol[..self.len as usize])
+		}
+	}
+
+	/// Set the grapheme cluster in this cell.
+	pub fn set_symbol(&mut self, symbol: &str) {
+		let symbol = match symbol.len() {
+			..=Self::CAPACITY => symbol,
+			_ => {
+				let c = symbol.chars().next().unwrap_or(' ');
+				&symbol[..c.len_utf8()]
+			}
+		};
+		self.symbol[..symbol.len()].copy_from_slice(symbol.as_bytes());
+		self.len = symbol.len() as u8;
+	}
+
+	/// Return `true` if this cell is a continuation of a wide cluster.
+	#[inline(always)]
+	pub const fn is_continuation(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Return the number of cells that the symbol occupies with `policy`.
+	#[inline(always)]
+	pub fn width(&self, policy: &WidthPolicy) -> usize {
+		policy.grapheme_width(self.symbol())
+	}
+
+	/// Turn this cell into a continuation of the cluster before it.
+	#[inline(always)]
+	pub(super) fn set_continuation(&mut self, style: Style) {
+		self.len = 0;
+		self.style = style;
+		self.link = None;
+		self.protection = Protection::Unprotected;
+	}
+}
+
+/// Copy of a region of a [`Buffer`], made with [`Buffer::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+	pos: Pos,
+	width: u16,
+	height: u16,
+	cells: Vec<Cell>,
+	/// Hyperlink targets of the cells, by [`LinkId`].
+	links: Vec<String>,
+}
+
+impl Snapshot {
+	/// Return the position of the top left corner of the region.
+	#[inline(always)]
+	pub const fn pos(&self) -> Pos {
+		self.pos
+	}
+
+	/// Return the width of the region, in columns.
+	#[inline(always)]
+	pub const fn width(&self) -> u16 {
+		self.width
+	}
+
+	/// Return the height of the region, in rows.
+	#[inline(always)]
+	pub const fn height(&self) -> u16 {
+		self.height
+	}
+
+	/// Return the cells of the region, row by row.
+	#[inline(always)]
+	pub fn cells(&self) -> &[Cell] {
+		&self.cells
+	}
+
+	/// Return the hyperlink target of `id`, which is the link of a cell in the
+	/// snapshot.
+	#[inline(always)]
+	pub fn link_uri(&self, id: LinkId) -> Option<&str> {
+		self.links.get(id.get() as usize - 1).map(String::as_str)
+	}
+}
+
+/// Grid of [`Cell`]s, which is drawn to and then rendered to the terminal.
+/// 
+/// Coordinates are 0-based, with `x` being the column and `y` the row.
+/// 
+/// Cells that are written to, including through [`Self::get_mut`], are found
+/// by comparing with the last frame. Cells that are drawn over on the terminal
+/// by other means have to be marked with [`Self::mark_dirty`] to be drawn
+/// again by the [`Renderer`](super::Renderer). Dirty flags aren't compared by
+/// [`PartialEq`].
+#[derive(Debug, Clone)]
+pub struct Buffer {
+	width: u16,
+	height: u16,
+	cells: Vec<Cell>,
+	dirty: Vec<bool>,
+	policy: WidthPolicy,
+	links: Vec<String>,
+	line_sizes: Vec<LineSize>,
+}
+
+impl PartialEq for Buffer {
+	fn eq(&self, other: &Self) -> bool {
+		self.width == other.width
+			&& self.height == other.height
+			&& self.cells == other.cells
+			&& self.policy == other.policy
+			&& self.links == other.links
+			&& self.line_sizes == other.line_sizes
+	}
+}
+
+impl Eq for Buffer {}
+
+impl Buffer {
+	/// Create a buffer of blank cells with the specified size.
+	#[inline(always)]
+	pub fn new(width: u16, height: u16) -> Self {
+		Self::with_policy(width, height, WidthPolicy::new())
+	}
+
+	/// Create a buffer of blank cells with the specified size, which measures
+	/// text with `policy`.
+	pub fn with_policy(width: u16, height: u16, policy: WidthPolicy) -> Self {
+		Self {
+			width,
+			height,
+			cells: alloc::vec![Cell::BLANK; width as usize * height as usize],
+			dirty: alloc::vec![false; width as usize * height as usize],
+			policy,
+			links: Vec::new(),
+			line_sizes: alloc::vec![LineSize::SingleWidth; height as usize],
+		}
+	}
+
+	/// Return the policy that is used to measure text.
+	#[inline(always)]
+	pub const fn width_policy(&self) -> WidthPolicy {
+		self.policy
+	}
+
+	/// Return the width of the buffer, in columns.
+	#[inline(always)]
+	pub const fn width(&self) -> u16 {
+		self.width
+	}
+
+	/// Return the height of the buffer, in rows.
+	#[inline(always)]
+	pub const fn height(&self) -> u16 {
+		self.height
+	}
+
+	/// Return every cell, row by row.
+	#[inline(always)]
+	pub fn cells(&self) -> &[Cell] {
+		&self.cells
+	}
+
+	/// Return the cells of row `y`.
+	pub fn row(&self, y: u16) -> Option<&[Cell]> {
+		let start = self.index(0, y)?;
+		Some(&self.cells[start..start + self.width as usize])
+	}
+
+	/// Return the cell at `x`, `y`.
+	#[inline(always)]
+	pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+		self.cells.get(self.index(x, y)?)
+	}
+
+	/// Return a mutable reference to the cell at `x`, `y`.
+	/// 
+	/// Writing to a cell directly doesn't keep wide clusters intact. See
+	/// [`Self::set_str`].
+	#[inline(always)]
+	pub fn get_mut(&mut self, x: u16, y: u16) -> Option<&mut Cell> {
+		let idx = self.index(x, y)?;
+		self.cells.get_mut(idx)
+	}
+
+	/// Return the cell at `pos`.
+	#[inline(always)]
+	pub fn cell(&self, pos: Pos) -> Option<&Cell> {
+		self.get(pos.col, pos.row)
+	}
+
+	/// Return a mutable reference to the cell at `pos`.
+	/// 
+	/// See [`Self::get_mut`].
+	#[inline(always)]
+	pub fn cell_mut(&mut self, pos: Pos) -> Option<&mut Cell> {
+		self.get_mut(pos.col, pos.row)
+	}
+
+	/// Return `true` if `pos` is inside the buffer.
+	#[inline(always)]
+	pub const fn contains(&self, pos: Pos) -> bool {
+		pos.is_within(self.width, self.height)
+	}
+
+	/// Reset every cell to [`Cell::BLANK`] and every row to
+	/// [`LineSize::SingleWidth`], and forget every hyperlink.
+	#[inline(always)]
+	pub fn clear(&mut self) {
+		self.cells.fill(Cell::BLANK);
+		self.links.clear();
+		self.line_sizes.fill(LineSize::SingleWidth);
+	}
+
+	/// Store the hyperlink target `uri`, returning its identifier.
+	/// 
+	/// The same identifier is returned for the same target. Returns [`None`]
+	/// if there are too many targets.
+	pub fn add_link(&mut self, uri: &str) -> Option<LinkId> {
+		if let Some(id) = self.find_link(uri) {
+			return Some(id)
+		}
+		self.links.push(uri.into());
+		match u16::try_from(self.links.len()).ok().and_then(NonZeroU16::new) {
+			Some(id) => Some(LinkId(id)),
+			None => {
+				self.links.pop();
+				None
+			}
+		}
+	}
+
+	/// Return the identifier of the hyperlink target `uri`, if it's stored.
+	pub(super) fn find_link(&self, uri: &str) -> Option<LinkId> {
+		let idx = self.links.iter().position(move |link| link == uri)?;
+		u16::try_from(idx + 1).ok().and_then(NonZeroU16::new).map(LinkId)
+	}
+
+	/// Return the number of hyperlink targets that are stored.
+	#[cfg(feature = "remote")]
+	#[inline(always)]
+	pub(super) fn link_count(&self) -> usize {
+		self.links.len()
+	}
+
+	/// Return the hyperlink target of `id`.
+	#[inline(always)]
+	pub fn link_uri(&self, id: LinkId) -> Option<&str> {
+		self.links.get(id.get() as usize - 1).map(String::as_str)
+	}
+
+	/// Set the protection of `width` cells starting at `x`, `y`.
+	pub fn set_protection(
+		&mut self, x: u16, y: u16, width: u16, protection: Protection,
+	) {
+		let Some(start) = self.index(x, y) else {
+			return
+		};
+		let end = start + width.min(self.width - x) as usize;
+		for cell in &mut self.cells[start..end] {
+			cell.protection = protection;
+		}
+	}
+
+	/// Reset every cell that is unprotected to [`Cell::BLANK`], like
+	/// [`SelectiveEraseDisplay::All`](super::SelectiveEraseDisplay::All) does
+	/// on the terminal.
+	pub fn erase_unprotected(&mut self) {
+		for cell in &mut self.cells {
+			if cell.protection == Protection::Unprotected {
+				*cell = Cell::BLANK;
+			}
+		}
+	}
+
+	/// Return the size of the characters on row `y`.
+	#[inline(always)]
+	pub fn line_size(&self, y: u16) -> LineSize {
+		self.line_sizes.get(y as usize).copied().unwrap_or_default()
+	}
+
+	/// Set the size of the characters on row `y`.
+	/// 
+	/// Only the first half of the columns of a row with double-width
+	/// characters is visible.
+	#[inline(always)]
+	pub fn set_line_size(&mut self, y: u16, size: LineSize) {
+		if let Some(x) = self.line_sizes.get_mut(y as usize) {
+			*x = size;
+		}
+	}
+
+	/// Make `width` cells starting at `x`, `y` part of the hyperlink `link`,
+	/// or remove them from any hyperlink if `link` is [`None`].
+	pub fn set_link(&mut self, x: u16, y: u16, width: u16, link: Option<LinkId>) {
+		let Some(start) = self.index(x, y) else {
+			return
+		};
+		let end = start + width.min(self.width - x) as usize;
+		for cell in &mut self.cells[start..end] {
+			cell.link = link;
+		}
+	}
+
+	/// Move every row up by `rows`, or down if `rows` is negative, filling
+	/// the rows that are uncovered with blank cells of
+	/// [`LineSize::SingleWidth`].
+	pub fn scroll(&mut self, rows: i32) {
+		let shifted_rows = rows.unsigned_abs().min(self.height as u32) as usize;
+		let shift = shifted_rows * self.width as usize;
+		let len = self.cells.len();
+		let height = self.height as usize;
+		if rows >= 0 {
+			self.cells.copy_within(shift.., 0);
+			self.cells[len - shift..].fill(Cell::BLANK);
+			self.line_sizes.copy_within(shifted_rows.., 0);
+			self.line_sizes[height - shifted_rows..].fill(LineSize::SingleWidth);
+		} else {
+			self.cells.copy_within(..len - shift, shift);
+			self.cells[..shift].fill(Cell::BLANK);
+			self.line_sizes.copy_within(..height - shifted_rows, shifted_rows);
+			self.line_sizes[..shifted_rows].fill(LineSize::SingleWidth);
+		}
+	}
+
+	/// Change the size of the buffer, keeping the cells that are still in
+	/// bounds.
+	pub fn resize(&mut self, width: u16, height: u16) {
+		let mut resized = Self::with_policy(width, height, self.policy);
+		resized.links = core::mem::take(&mut self.links);
+		let kept_rows = self.height.min(height) as usize;
+		resized.line_sizes[..kept_rows]
+			.copy_from_slice(&self.line_sizes[..kept_rows]);
+		let kept = self.width.min(width) as usize;
+		for y in 0..self.height.min(height) {
+			let from = y as usize * self.width as usize;
+			let to = y as usize * width as usize;
+			resized.cells[to..to + kept]
+				.copy_from_slice(&self.cells[from..from + kept]);
+			// Don't keep part of a wide cluster that crosses the new edge.
+			let is_cut = self.get(width, y).is_some_and(Cell::is_continuation);
+			if 0 < width && is_cut {
+				let row = &mut resized.cells[to..to + kept];
+				let start = row.iter()
+					.rposition(move |cell| !cell.is_continuation())
+					.unwrap_or(0);
+				for cell in &mut row[start..] {
+					*cell = Cell {
+						style: cell.style,
+						..Cell::BLANK
+					};
+				}
+			}
+		}
+		*self = resized;
+	}
+
+	/// Copy the cells of the region of `width` columns and `height` rows
+	/// with its top left corner at `pos`, cut to the bounds of the buffer,
+	/// which leaves no cells if the region is outside of the buffer.
+	/// 
+	/// The copy can be put back with [`Self::restore`], such as to remove a
+	/// popup that was drawn over the region. Rendering the buffer after that
+	/// only redraws the cells that the popup covered.
+	pub fn snapshot(&self, pos: Pos, width: u16, height: u16) -> Snapshot {
+		let mut width = width.min(self.width.saturating_sub(pos.col));
+		let mut height = height.min(self.height.saturating_sub(pos.row));
+		if width == 0 || height == 0 {
+			// The region is outside of the buffer.
+			(width, height) = (0, 0);
+		}
+		let mut snapshot = Snapshot {
+			pos,
+			width,
+			height,
+			cells: Vec::with_capacity(width as usize * height as usize),
+			links: Vec::new(),
+		};
+		for y in pos.row..pos.row + height {
+			let start = self.index(pos.col, y).unwrap();
+			for cell in &self.cells[start..start + width as usize] {
+				// Only the links in the region are kept.
+				let link = cell.link.and_then(|id| {
+					let uri = self.link_uri(id)?;
+					let links = &mut snapshot.links;
+					let idx = match links.iter().position(move |link| link == uri) {
+						Some(idx) => idx,
+						None => {
+							links.push(uri.into());
+							links.len() - 1
+						}
+					};
+					// There are at most as many links as in `self`.
+					NonZeroU16::new(idx as u16 + 1).map(LinkId)
+				});
+				snapshot.cells.push(Cell {
+					link,
+					..*cell
+				});
+			}
+		}
+		snapshot
+	}
+
+	/// Put the cells of `snapshot` back where they were copied from.
+	/// 
+	/// Hyperlinks are stored again if they were forgotten since. Wide
+	/// clusters that cross the edges of the region are replaced with blank
+	/// cells in the buffer, since the part outside the region may have
+	/// changed.
+	pub fn restore(&mut self, snapshot: &Snapshot) {
+		let Snapshot { pos, width, height, .. } = *snapshot;
+		let width = width.min(self.width.saturating_sub(pos.col));
+		let height = height.min(self.height.saturating_sub(pos.row));
+		if width == 0 {
+			return
+		}
+
+		// Identifiers of the links in this buffer, in the order of the links
+		// in the snapshot.
+		let link_ids: Vec<Option<LinkId>> = snapshot.links.iter()
+			.map(|uri| self.add_link(uri))
+			.collect();
+		let rows = snapshot.cells.chunks(snapshot.width as usize).take(height as usize);
+		for (y, row) in (pos.row..).zip(rows) {
+			self.split_wide(pos.col, y);
+			self.split_wide(pos.col + width - 1, y);
+
+			let start = self.index(pos.col, y).unwrap();
+			let cells = &mut self.cells[start..start + width as usize];
+			for (cell, saved) in cells.iter_mut().zip(row) {
+				*cell = Cell {
+					link: saved.link.and_then(|id| link_ids[id.get() as usize - 1]),
+					..*saved
+				};
+			}
+			self.blank_cut_clusters(start, width as usize);
+		}
+	}
+
+	/// Write `s` at `x`, `y` with `style`, stopping at the end of the row.
+	/// 
+	/// `s` is written by grapheme cluster, with wide clusters occupying
+	/// several cells. Control characters are skipped, and escape sequences
+	/// aren't recognized. The cells written are unprotected and aren't part of
+	/// any hyperlink.
+	/// Returns the column after the last cell written.
+	pub fn set_str(&mut self, mut x: u16, y: u16, s: &str, style: Style) -> u16 {
+		if y >= self.height {
+			return x
+		}
+		for g in graphemes(s) {
+			let width = self.policy.grapheme_width(g) as u16;
+			if width == 0 {
+				continue
+			}
+			if x as usize + width as usize > self.width as usize {
+				break
+			}
+
+			for dx in 0..width {
+				self.split_wide(x + dx, y);
+			}
+			let idx = self.index(x, y).unwrap();
+			let cell = &mut self.cells[idx];
+			cell.set_symbol(g);
+			cell.style = style;
+			cell.link = None;
+			cell.protection = Protection::Unprotected;
+			for cell in &mut self.cells[idx + 1..idx + width as usize] {
+				cell.set_continuation(style);
+			}
+			x += width;
+		}
+		x
+	}
+
+	/// Write the text of every row to `w`, without styles, separating rows with
+	/// line feeds.
+	/// 
+	/// Blank cells at the end of each row are left out, as are the columns
+	/// that rows with double-width characters don't show.
+	pub fn write_plain(&self, w: &mut dyn Write) -> FmtResult {
+		for y in 0..self.height {
+			if y > 0 {
+				w.write_char('\n')?;
+			}
+			let cells = self.visible_row(y);
+			let len = cells.iter()
+				.rposition(move |cell| !matches!(cell.symbol(), " " | ""))
+				.map_or(0, move |idx| idx + 1);
+			for cell in &cells[..len] {
+				w.write_str(cell.symbol())?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Return the text of every row, as written by [`Self::write_plain`].
+	pub fn to_plain_string(&self) -> String {
+		let mut text = String::new();
+		let _ = self.write_plain(&mut text);
+		text
+	}
+
+	/// Write every row to `w` with the escape sequences that reproduce its
+	/// styles, hyperlinks and [`LineSize`], separating rows with line feeds.
+	/// 
+	/// Writing the result to a terminal, or printing it with `cat`, shows the
+	/// buffer as it is drawn. Each row starts and ends in the default style and
+	/// outside of any hyperlink, and blank cells in the default style at the
+	/// end of each row are left out.
+	pub fn write_ansi(&self, w: &mut dyn Write) -> FmtResult {
+		for y in 0..self.height {
+			if y > 0 {
+				w.write_char('\n')?;
+			}
+			let line_size = self.line_size(y);
+			if line_size != LineSize::SingleWidth {
+				write!(w, "{line_size}")?;
+			}
+			let cells = self.visible_row(y);
+			let len = cells.iter()
+				.rposition(move |cell| {
+					!matches!(cell.symbol(), " " | "")
+						|| !cell.style.is_default()
+						|| cell.link.is_some()
+				})
+				.map_or(0, move |idx| idx + 1);
+			let mut style = Style::new();
+			let mut link = None;
+			for cell in &cells[..len] {
+				if cell.is_continuation() {
+					continue
+				}
+				if cell.style != style {
+					write!(w, "{}", style.diff_to(&cell.style))?;
+					style = cell.style;
+				}
+				if cell.link != link {
+					let uri = cell.link.and_then(|id| self.link_uri(id));
+					write_link(w, cell.link.zip(uri))?;
+					link = cell.link;
+				}
+				w.write_str(cell.symbol())?;
+			}
+			if !style.is_default() {
+				write!(w, "{}", Sgr::Reset)?;
+			}
+			if link.is_some() {
+				write_link(w, None)?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Return every row with the escape sequences that reproduce its styles,
+	/// as written by [`Self::write_ansi`].
+	pub fn to_ansi_string(&self) -> String {
+		let mut text = String::new();
+		let _ = self.write_ansi(&mut text);
+		text
+	}
+
+	/// Return the cells of row `y` that are shown with its [`LineSize`].
+	fn visible_row(&self, y: u16) -> &[Cell] {
+		let row = self.row(y).unwrap_or_default();
+		match self.line_size(y).is_double_width() {
+			true => &row[..row.len() / 2],
+			false => row,
+		}
+	}
+
+	/// Mark the cells in `area` as dirty, so that the next frame draws them
+	/// even if they look the same as in the last frame.
+	/// 
+	/// This is needed after drawing over the cells without the buffer, such
+	/// as with graphics or by writing to the terminal directly.
+	pub fn mark_dirty(&mut self, area: Rect) {
+		for y in area.y..area.bottom().min(self.height) {
+			let Some(start) = self.index(area.x, y) else {
+				continue
+			};
+			let end = start + area.width.min(self.width - area.x) as usize;
+			self.dirty[start..end].fill(true);
+		}
+	}
+
+	/// Return `true` if the cell at `x`, `y` was marked with
+	/// [`Self::mark_dirty`] since the last call to [`Self::clear_dirty`].
+	#[inline(always)]
+	pub fn is_dirty(&self, x: u16, y: u16) -> bool {
+		self.index(x, y).is_some_and(|idx| self.dirty[idx])
+	}
+
+	/// Return the smallest [`Rect`] that contains every dirty cell, if any.
+	pub fn dirty_area(&self) -> Option<Rect> {
+		let width = (self.width as usize).max(1);
+		let mut dirty = self.dirty.iter().enumerate()
+			.filter(move |(_, dirty)| **dirty)
+			.map(move |(idx, _)| ((idx % width) as u16, (idx / width) as u16));
+		let (x, y) = dirty.next()?;
+		let (left, top, right, bottom) = dirty.fold(
+			(x, y, x, y),
+			move |(left, top, right, bottom), (x, y)| {
+				(left.min(x), top, right.max(x), bottom.max(y))
+			},
+		);
+		Some(Rect::new(left, top, right - left + 1, bottom - top + 1))
+	}
+
+	/// Mark every cell as clean, as if it was just drawn.
+	/// 
+	/// Call this once the frame was drawn with
+	/// [`Renderer::render`](super::Renderer::render), so that the marked cells
+	/// aren't drawn again in the next frame.
+	#[inline(always)]
+	pub fn clear_dirty(&mut self) {
+		self.dirty.fill(false);
+	}
+
+	/// Return `true` if the cell at `idx` is marked as dirty.
+	#[inline(always)]
+	pub(super) fn dirty(&self, idx: usize) -> bool {
+		self.dirty[idx]
+	}
+
+	#[inline(always)]
+	fn index(&self, x: u16, y: u16) -> Option<usize> {
+		(x < self.width && y < self.height)
+			.then(|| y as usize * self.width as usize + x as usize)
+	}
+
+	/// Blank the wide clusters that cross the edges of the `len` cells
+	/// starting at `start`.
+	fn blank_cut_clusters(&mut self, start: usize, len: usize) {
+		let policy = self.policy;
+		let cells = &mut self.cells[start..start + len];
+		let blank = move |cells: &mut [Cell]| for cell in cells {
+			*cell = Cell {
+				style: cell.style,
+				..Cell::BLANK
+			};
+		};
+
+		// Continuations of a cluster that starts before the region.
+		let orphans = cells.iter().take_while(move |cell| cell.is_continuation()).count();
+		blank(&mut cells[..orphans]);
+		// Cluster that continues after the region.
+		if let Some(last) = cells.iter().rposition(move |cell| !cell.is_continuation()) {
+			if last + cells[last].width(&policy) > len {
+				blank(&mut cells[last..]);
+			}
+		}
+	}
+
+	/// Blank the rest of a wide cluster that the cell at `x`, `y` is part of,
+	/// so that the cell can be overwritten.
+	fn split_wide(&mut self, x: u16, y: u16) {
+		let Some(idx) = self.index(x, y) else {
+			return
+		};
+		let row_start = idx - x as usize;
+		let row_end = row_start + self.width as usize;
+
+		// Blank the start of the cluster and the continuations before `x`.
+		let mut start = idx;
+		while start > row_start && self.cells[start].is_continuation() {
+			start -= 1;
+		}
+		// Blank the continuations after `x`.
+		let mut end = idx + 1;
+		while end < row_end && self.cells[end].is_continuation() {
+			end += 1;
+		}
+		if end - start > 1 {
+			for cell in &mut self.cells[start..end] {
+				*cell = Cell {
+					style: cell.style,
+					..Cell::BLANK
+				};
+			}
+		}
+	}
+}
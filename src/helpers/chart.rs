@@ -0,0 +1,227 @@
+use super::{
+	Color, Role, StateChange, StyleRoles,
+};
+use crate::sgr::rgb_to_xterm_256;
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Return the smallest and largest finite values in `values`, if any.
+fn finite_range(values: &[f64]) -> Option<(f64, f64)> {
+	values.iter().copied()
+		.filter(move |value| value.is_finite())
+		.fold(None, move |range, value| match range {
+			Some((min, max)) => Some((value.min(min), value.max(max))),
+			None => Some((value, value)),
+		})
+}
+
+/// Return where `value` is between `min` and `max`, from `0.0` to `1.0`, or
+/// `None` if it isn't finite.
+/// 
+/// Every value is `0.5` if the range is empty.
+fn normalize(value: f64, (min, max): (f64, f64)) -> Option<f64> {
+	if !value.is_finite() {
+		return None
+	}
+	if max <= min {
+		return Some(0.5)
+	}
+	Some(((value - min) / (max - min)).clamp(0.0, 1.0))
+}
+
+/// Chart of numbers on one line, made of block glyphs of eight heights from
+/// `▁` to `█`, such as for recent values on a dashboard.
+/// 
+/// Values that aren't finite are displayed as spaces.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Sparkline<'a> {
+	/// Values of the chart, one for each cell.
+	pub values: &'a [f64],
+	/// Values at the bottom and top of the chart, or `None` for the smallest
+	/// and largest values.
+	pub range: Option<(f64, f64)>,
+	/// Style of the chart.
+	pub style: StateChange,
+}
+
+impl<'a> Sparkline<'a> {
+	/// Glyphs for values from the bottom of the chart to the top.
+	pub const GLYPHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+	/// Create a [`Sparkline`] of `values`, scaled from the smallest to the
+	/// largest.
+	#[inline(always)]
+	pub const fn new(values: &'a [f64]) -> Self {
+		Self {
+			values,
+			range: None,
+			style: StateChange::new(),
+		}
+	}
+
+	/// Return `self` with `min` at the bottom of the chart and `max` at the
+	/// top, clamping the values outside of them.
+	#[inline(always)]
+	pub const fn with_range(mut self, min: f64, max: f64) -> Self {
+		self.range = Some((min, max));
+		self
+	}
+
+	/// Return `self` displayed in `style`.
+	#[inline(always)]
+	pub const fn with_style(mut self, style: StateChange) -> Self {
+		self.style = style;
+		self
+	}
+
+	/// Create a [`Sparkline`] of `values` like [`Self::new`], displayed in the
+	/// settings of [`Role::Accent`] in `roles`.
+	#[inline(always)]
+	pub const fn from_roles(values: &'a [f64], roles: &StyleRoles) -> Self {
+		Self::new(values).with_style(roles.get(Role::Accent))
+	}
+}
+
+impl<'a> Display for Sparkline<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let range = self.range.or_else(move || finite_range(self.values))
+			.unwrap_or((0.0, 0.0));
+		self.style.fmt(f)?;
+		for &value in self.values {
+			f.write_char(match normalize(value, range) {
+				Some(t) => {
+					let top = (Self::GLYPHS.len() - 1) as f64;
+					Self::GLYPHS[(t * top + 0.5) as usize]
+				}
+				None => ' ',
+			})?;
+		}
+		self.style.resetter().fmt(f)
+	}
+}
+
+/// Chart of numbers as cells whose background colors go from one color for
+/// the smallest values to another for the largest, such as for activity over
+/// time.
+/// 
+/// Values that aren't finite have the default background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heatmap<'a> {
+	/// Values of the chart, one for each cell.
+	pub values: &'a [f64],
+	/// Values that have [`Self::low`] and [`Self::high`] as their colors, or
+	/// `None` for the smallest and largest values.
+	pub range: Option<(f64, f64)>,
+	/// RGB color of the smallest values.
+	pub low: (u8, u8, u8),
+	/// RGB color of the largest values.
+	pub high: (u8, u8, u8),
+	/// Whether to use 24-bit colors, instead of the nearest colors in the
+	/// 256-color table.
+	pub true_color: bool,
+	/// Character that is written in every cell.
+	pub glyph: char,
+	/// Number of cells on each line, after which a new line is started, or
+	/// `0` to display every value on one line.
+	pub columns: usize,
+}
+
+impl<'a> Heatmap<'a> {
+	/// Create a [`Heatmap`] of `values` from dark green to bright green,
+	/// scaled from the smallest to the largest, which uses 24-bit colors.
+	#[inline(always)]
+	pub const fn new(values: &'a [f64]) -> Self {
+		Self {
+			values,
+			range: None,
+			low: (14, 68, 41),
+			high: (57, 211, 83),
+			true_color: true,
+			glyph: ' ',
+			columns: 0,
+		}
+	}
+
+	/// Return `self` with `min` in the color for the smallest values and `max`
+	/// in the color for the largest, clamping the values outside of them.
+	#[inline(always)]
+	pub const fn with_range(mut self, min: f64, max: f64) -> Self {
+		self.range = Some((min, max));
+		self
+	}
+
+	/// Return `self` with the colors of the smallest and largest values.
+	#[inline(always)]
+	pub const fn with_colors(mut self, low: (u8, u8, u8), high: (u8, u8, u8)) -> Self {
+		self.low = low;
+		self.high = high;
+		self
+	}
+
+	/// Return `self` using 24-bit colors or the 256-color table.
+	#[inline(always)]
+	pub const fn with_true_color(mut self, true_color: bool) -> Self {
+		self.true_color = true_color;
+		self
+	}
+
+	/// Return `self` with `glyph` written in every cell.
+	#[inline(always)]
+	pub const fn with_glyph(mut self, glyph: char) -> Self {
+		self.glyph = glyph;
+		self
+	}
+
+	/// Return `self` with lines of `columns` cells.
+	#[inline(always)]
+	pub const fn with_columns(mut self, columns: usize) -> Self {
+		self.columns = columns;
+		self
+	}
+
+	/// Return the color of values at `t` between the smallest and the largest.
+	fn color(&self, t: f64) -> Color {
+		let mix = move |low: u8, high: u8| {
+			(low as f64 + (high as f64 - low as f64) * t + 0.5) as u8
+		};
+		let (r, g, b) = (
+			mix(self.low.0, self.high.0),
+			mix(self.low.1, self.high.1),
+			mix(self.low.2, self.high.2),
+		);
+		match self.true_color {
+			true => Color::Rgb(r, g, b),
+			false => Color::Table(rgb_to_xterm_256(r, g, b)),
+		}
+	}
+}
+
+impl<'a> Display for Heatmap<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let range = self.range.or_else(move || finite_range(self.values))
+			.unwrap_or((0.0, 0.0));
+		let mut current = Color::Reset;
+		for (i, &value) in self.values.iter().enumerate() {
+			if self.columns > 0 && i > 0 && i % self.columns == 0 {
+				if current != Color::Reset {
+					current = Color::Reset;
+					current.into_background().fmt(f)?;
+				}
+				f.write_char('\n')?;
+			}
+			let color = normalize(value, range)
+				.map_or(Color::Reset, move |t| self.color(t));
+			if color != current {
+				current = color;
+				current.into_background().fmt(f)?;
+			}
+			f.write_char(self.glyph)?;
+		}
+		if current != Color::Reset {
+			Color::Reset.into_background().fmt(f)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,179 @@
+use crate::width::WidthPolicy;
+
+use alloc::vec::Vec;
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Order in which [`Columns`] places items.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnOrder {
+	/// Fill each column from top to bottom before the next one, like `ls`.
+	#[default]
+	Down,
+	/// Fill each row from left to right before the next one, like `ls -x`.
+	Across,
+}
+
+/// Layout of items in as few rows as fit in a width, as done by `ls`.
+/// 
+/// Items may contain escape sequences, which don't count towards their
+/// width. The widths of the items and columns are kept in one buffer, so
+/// rendering doesn't allocate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Columns<'a, S> {
+	items: &'a [S],
+	width: usize,
+	spacing: usize,
+	order: ColumnOrder,
+	/// Widths of the items, followed by the widths of the columns.
+	widths: Vec<usize>,
+	rows: usize,
+}
+
+impl<'a, S: AsRef<str>> Columns<'a, S> {
+	/// Default number of spaces between columns.
+	pub const DEFAULT_SPACING: usize = 2;
+
+	/// Lay out `items` in at most `width` columns of cells, ordered
+	/// [`ColumnOrder::Down`] and measured with the default [`WidthPolicy`].
+	pub fn new(items: &'a [S], width: usize) -> Self {
+		let mut columns = Self {
+			items,
+			width,
+			spacing: Self::DEFAULT_SPACING,
+			order: ColumnOrder::Down,
+			widths: Vec::with_capacity(items.len() * 2),
+			rows: 0,
+		};
+		columns.measure(&WidthPolicy::new());
+		columns
+	}
+
+	/// Return `self` with items placed in `order`.
+	pub fn with_order(mut self, order: ColumnOrder) -> Self {
+		self.order = order;
+		self.layout();
+		self
+	}
+
+	/// Return `self` with `spacing` spaces between columns.
+	pub fn with_spacing(mut self, spacing: usize) -> Self {
+		self.spacing = spacing;
+		self.layout();
+		self
+	}
+
+	/// Return `self` with items measured according to `policy`.
+	pub fn with_width_policy(mut self, policy: &WidthPolicy) -> Self {
+		self.measure(policy);
+		self
+	}
+
+	/// Return the number of rows.
+	#[inline(always)]
+	pub const fn rows(&self) -> usize {
+		self.rows
+	}
+
+	/// Return the number of columns.
+	#[inline(always)]
+	pub fn columns(&self) -> usize {
+		self.widths.len() - self.items.len()
+	}
+
+	/// Return the width of each column, in cells.
+	#[inline(always)]
+	pub fn column_widths(&self) -> &[usize] {
+		&self.widths[self.items.len()..]
+	}
+
+	/// Return the index of the item in the cell at `row` and `column`, if
+	/// there is one.
+	pub fn item_index(&self, row: usize, column: usize) -> Option<usize> {
+		let idx = match self.order {
+			ColumnOrder::Down => column * self.rows + row,
+			ColumnOrder::Across => row * self.columns() + column,
+		};
+		(row < self.rows && column < self.columns() && idx < self.items.len())
+			.then_some(idx)
+	}
+
+	fn measure(&mut self, policy: &WidthPolicy) {
+		self.widths.clear();
+		self.widths.extend(self.items.iter().map(move |item| {
+			policy.visible_width(item.as_ref().as_bytes())
+		}));
+		self.layout();
+	}
+
+	/// Find the smallest number of rows that the items fit in, and the widths
+	/// of the resulting columns.
+	fn layout(&mut self) {
+		let len = self.items.len();
+		self.widths.truncate(len);
+		if len == 0 {
+			self.rows = 0;
+			return
+		}
+		let this = &*self;
+		self.rows = (1..len)
+			.find(move |&rows| {
+				let columns = this.columns_for(rows);
+				let total = (0..columns)
+					.map(move |column| this.column_width(rows, columns, column))
+					.sum::<usize>();
+				total + (columns - 1) * this.spacing <= this.width
+			})
+			.unwrap_or(len);
+
+		let columns = self.columns_for(self.rows);
+		if self.order == ColumnOrder::Across {
+			// Filling rows may need fewer of them than were tried.
+			self.rows = len.div_ceil(columns);
+		}
+		for column in 0..columns {
+			let width = self.column_width(self.rows, columns, column);
+			self.widths.push(width);
+		}
+	}
+
+	/// Return the number of columns that are needed for `rows` rows.
+	#[inline(always)]
+	fn columns_for(&self, rows: usize) -> usize {
+		self.items.len().div_ceil(rows)
+	}
+
+	/// Return the width of the widest item in `column`.
+	fn column_width(&self, rows: usize, columns: usize, column: usize) -> usize {
+		let item_widths = &self.widths[..self.items.len()];
+		match self.order {
+			ColumnOrder::Down => item_widths.iter().skip(column * rows).take(rows)
+				.copied().max().unwrap_or(0),
+			ColumnOrder::Across => item_widths.iter().skip(column).step_by(columns)
+				.copied().max().unwrap_or(0),
+		}
+	}
+}
+
+impl<'a, S: AsRef<str>> Display for Columns<'a, S> {
+	/// Display the rows, each followed by a newline.
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let column_widths = self.column_widths();
+		for row in 0..self.rows {
+			// Padding is only written before the next item, so that rows don't
+			// end with spaces.
+			let mut padding = 0;
+			for (column, &column_width) in column_widths.iter().enumerate() {
+				let Some(idx) = self.item_index(row, column) else {
+					break
+				};
+				(0..padding).try_for_each(|_| f.write_char(' '))?;
+				f.write_str(self.items[idx].as_ref())?;
+				padding = column_width - self.widths[idx] + self.spacing;
+			}
+			f.write_char('\n')?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,223 @@
+use super::{
+	Color, Role, StateChange, Style, StyleRoles, Underline, Weight,
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult,
+};
+
+/// Kind of a line of a unified diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffKind {
+	/// Header that names a file, such as `+++ b/src/lib.rs` or
+	/// `diff --git a/src/lib.rs b/src/lib.rs`.
+	FileHeader,
+	/// Header of a hunk, such as `@@ -1,4 +1,5 @@`.
+	HunkHeader,
+	/// Line that was added.
+	Addition,
+	/// Line that was removed.
+	Deletion,
+	/// Unchanged line, or anything else.
+	Context,
+}
+
+impl DiffKind {
+	/// Return the kind of `line`, which is a line of a unified diff without
+	/// the line terminator.
+	pub fn classify(line: &str) -> Self {
+		const FILE_HEADERS: &[&str] = &[
+			"+++ ", "--- ", "diff ", "index ", "new file mode ", "deleted file mode ",
+			"similarity index ", "rename from ", "rename to ",
+		];
+		if FILE_HEADERS.iter().any(move |header| line.starts_with(header)) {
+			Self::FileHeader
+		} else if line.starts_with("@@") {
+			Self::HunkHeader
+		} else if line.starts_with('+') {
+			Self::Addition
+		} else if line.starts_with('-') {
+			Self::Deletion
+		} else {
+			Self::Context
+		}
+	}
+}
+
+/// Styles used by [`DiffLine`] for each [`DiffKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffTheme {
+	pub file_header: StateChange,
+	pub hunk_header: StateChange,
+	pub addition: StateChange,
+	pub deletion: StateChange,
+	/// Change applied on top of the style of an addition or a deletion to the
+	/// words that differ from the other line.
+	pub changed: StateChange,
+}
+
+impl Default for DiffTheme {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl DiffTheme {
+	/// Create the default [`DiffTheme`], which is similar to that of `git`.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			file_header: StateChange::new().with_weight(Weight::Bold),
+			hunk_header: StateChange::new().with_foreground(Color::Table(6)),
+			addition: StateChange::new().with_foreground(Color::Table(2)),
+			deletion: StateChange::new().with_foreground(Color::Table(1)),
+			changed: StateChange::new()
+				.with_weight(Weight::Bold)
+				.with_underline(Underline::Single),
+		}
+	}
+
+	/// Create a [`DiffTheme`] with the settings of the roles in `roles`.
+	/// 
+	/// File headers use [`Role::Heading`], hunk headers [`Role::Info`],
+	/// additions [`Role::Success`], deletions [`Role::Error`] and changed words
+	/// [`Role::Highlight`].
+	#[inline(always)]
+	pub const fn from_roles(roles: &StyleRoles) -> Self {
+		Self {
+			file_header: roles.get(Role::Heading),
+			hunk_header: roles.get(Role::Info),
+			addition: roles.get(Role::Success),
+			deletion: roles.get(Role::Error),
+			changed: roles.get(Role::Highlight),
+		}
+	}
+
+	/// Return the style of lines of `kind`.
+	#[inline(always)]
+	pub fn style(&self, kind: DiffKind) -> Style {
+		let mut style = Style::new();
+		match kind {
+			DiffKind::FileHeader => style.apply(&self.file_header),
+			DiffKind::HunkHeader => style.apply(&self.hunk_header),
+			DiffKind::Addition => style.apply(&self.addition),
+			DiffKind::Deletion => style.apply(&self.deletion),
+			DiffKind::Context => {}
+		}
+		style
+	}
+}
+
+/// Line of a unified diff, displayed in the style of its [`DiffKind`].
+/// 
+/// If the line is an addition or a deletion and the line that it replaces is
+/// known, then the words that differ between the two lines can be highlighted
+/// with [`Self::with_counterpart`].
+/// 
+/// Control characters other than tabs are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffLine<'a> {
+	/// Text of the line, without the line terminator.
+	pub line: &'a str,
+	pub kind: DiffKind,
+	/// Line that this one replaces or is replaced by, if any.
+	pub counterpart: Option<&'a str>,
+	pub theme: DiffTheme,
+}
+
+impl<'a> DiffLine<'a> {
+	/// Create a [`DiffLine`] for `line`, which is classified with
+	/// [`DiffKind::classify`].
+	#[inline(always)]
+	pub fn new(line: &'a str) -> Self {
+		Self {
+			line,
+			kind: DiffKind::classify(line),
+			counterpart: None,
+			theme: DiffTheme::new(),
+		}
+	}
+
+	/// Return `self` with the words that differ from `counterpart`
+	/// highlighted.
+	/// 
+	/// `counterpart` is the removed line for an addition, or the added line
+	/// for a deletion, including its `+` or `-` prefix.
+	#[inline(always)]
+	pub const fn with_counterpart(mut self, counterpart: &'a str) -> Self {
+		self.counterpart = Some(counterpart);
+		self
+	}
+
+	/// Return `self` with the specified styles.
+	#[inline(always)]
+	pub const fn with_theme(mut self, theme: DiffTheme) -> Self {
+		self.theme = theme;
+		self
+	}
+
+	/// Return the range of bytes of [`Self::line`] that differ from
+	/// [`Self::counterpart`], widened to whole words.
+	pub fn changed_range(&self) -> Option<(usize, usize)> {
+		if !matches!(self.kind, DiffKind::Addition | DiffKind::Deletion) {
+			return None
+		}
+		let line = self.line.get(1..)?;
+		let other = self.counterpart?.get(1..)?;
+
+		// Compare characters, so that the range can't split one.
+		let prefix: usize = line.chars().zip(other.chars())
+			.take_while(move |(a, b)| a == b)
+			.map(move |(c, _)| c.len_utf8())
+			.sum();
+		let suffix: usize = line[prefix..].chars().rev()
+			.zip(other[prefix..].chars().rev())
+			.take_while(move |(a, b)| a == b)
+			.map(move |(c, _)| c.len_utf8())
+			.sum();
+
+		if prefix + suffix == line.len() {
+			// Nothing in this line differs, though text may have been removed.
+			return None
+		}
+
+		let start = line[..prefix].rfind(is_word_break).map_or(0, move |i| i + 1);
+		let end = line.len() - suffix;
+		let end = line[end..].find(is_word_break).map_or(line.len(), move |i| end + i);
+		(start < end).then_some((1 + start, 1 + end))
+	}
+}
+
+/// Return `true` if words are broken at `c`.
+#[inline(always)]
+fn is_word_break(c: char) -> bool {
+	!(c.is_alphanumeric() || c == '_')
+}
+
+/// Write `s`, dropping control characters other than tabs.
+fn write_text(f: &mut Formatter<'_>, s: &str) -> FmtResult {
+	s.split(move |c: char| c.is_control() && c != '\t')
+		.try_for_each(move |part| f.write_str(part))
+}
+
+impl<'a> Display for DiffLine<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let style = self.theme.style(self.kind);
+		let default = Style::new();
+		write!(f, "{}", default.diff_to(&style))?;
+		match self.changed_range() {
+			Some((start, end)) => {
+				let mut changed = style;
+				changed.apply(&self.theme.changed);
+				write_text(f, &self.line[..start])?;
+				write!(f, "{}", style.diff_to(&changed))?;
+				write_text(f, &self.line[start..end])?;
+				write!(f, "{}", changed.diff_to(&style))?;
+				write_text(f, &self.line[end..])?;
+			}
+			None => write_text(f, self.line)?,
+		}
+		style.diff_to(&default).fmt(f)
+	}
+}
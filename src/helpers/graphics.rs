@@ -0,0 +1,147 @@
+use crate::{
+	base64::Base64Encoder,
+	fe_seq::{
+		PayloadLimit, PayloadOverflow, Terminator, ESC_STR,
+	},
+};
+
+use core::fmt::{
+	Result as FmtResult, Write,
+};
+
+/// Maximum number of encoded bytes in one chunk of a kitty graphics payload.
+const CHUNK_LEN: usize = 4096;
+
+/// Writer of a command of the kitty graphics protocol, which sends its payload
+/// in APC sequences of at most [`Self::CHUNK_LEN`] encoded bytes each.
+/// 
+/// The payload is base64-encoded as it is written, and only one chunk is
+/// buffered at a time. The total size of the payload is unlimited unless
+/// [`Self::with_limit`] is used. The command is finished on drop if
+/// [`Self::finish`] wasn't called, ignoring errors.
+#[derive(Debug)]
+pub struct KittyGraphicsWriter<'w, 'c, W: Write + ?Sized> {
+	chunks: Chunks<'w, 'c, W>,
+	encoder: Base64Encoder,
+	limit: PayloadLimit,
+	is_finished: bool,
+}
+
+impl<'w, 'c, W: Write + ?Sized> KittyGraphicsWriter<'w, 'c, W> {
+	/// Maximum number of encoded bytes in one chunk of the payload.
+	pub const CHUNK_LEN: usize = CHUNK_LEN;
+
+	/// Start writing a command with the control data `control`, such as
+	/// `"a=T,f=100"`.
+	/// 
+	/// The `m` key, which marks chunks that are followed by more chunks, is
+	/// added to `control`, so it shouldn't be included.
+	#[inline(always)]
+	pub const fn new(inner: &'w mut W, control: &'c str) -> Self {
+		Self {
+			chunks: Chunks {
+				inner,
+				control,
+				chunk: [0; CHUNK_LEN],
+				len: 0,
+				is_first: true,
+			},
+			encoder: Base64Encoder::new(),
+			limit: PayloadLimit::NONE,
+			is_finished: false,
+		}
+	}
+
+	/// Return `self` with at most `limit` bytes of encoded payload written in
+	/// all chunks, handling the rest as specified by `overflow`.
+	/// 
+	/// The limit is applied to the payload before it's encoded, so that the
+	/// encoded payload stays valid: at most
+	/// [`Base64Encoder::max_input_len`] of `limit` bytes of payload are
+	/// written.
+	#[inline(always)]
+	pub const fn with_limit(mut self, limit: usize, overflow: PayloadOverflow) -> Self {
+		let limit = Base64Encoder::max_input_len(limit);
+		self.limit = PayloadLimit::new(limit, overflow);
+		self
+	}
+
+	/// Write `bytes` of the payload.
+	/// 
+	/// Chunks are written as they are filled, except for the last one.
+	pub fn write_bytes(&mut self, bytes: &[u8]) -> FmtResult {
+		self.limit.check(bytes.len())?;
+		let bytes = self.limit.take_bytes(bytes);
+		self.encoder.encode(bytes, &mut self.chunks)
+	}
+
+	/// Write the rest of the payload as the last chunk.
+	#[inline(always)]
+	pub fn finish(mut self) -> FmtResult {
+		self.terminate()
+	}
+
+	fn terminate(&mut self) -> FmtResult {
+		if self.is_finished {
+			return Ok(())
+		}
+		self.is_finished = true;
+		self.encoder.finish(&mut self.chunks)?;
+		self.chunks.flush(false)
+	}
+}
+
+impl<W: Write + ?Sized> Drop for KittyGraphicsWriter<'_, '_, W> {
+	#[inline(always)]
+	fn drop(&mut self) {
+		let _ = self.terminate();
+	}
+}
+
+/// Buffer of one chunk of a [`KittyGraphicsWriter`] payload.
+#[derive(Debug)]
+struct Chunks<'w, 'c, W: Write + ?Sized> {
+	inner: &'w mut W,
+	control: &'c str,
+	chunk: [u8; CHUNK_LEN],
+	len: usize,
+	is_first: bool,
+}
+
+impl<W: Write + ?Sized> Chunks<'_, '_, W> {
+	/// Write the buffered chunk, with `m=1` if `has_more` is `true`.
+	fn flush(&mut self, has_more: bool) -> FmtResult {
+		self.inner.write_str(ESC_STR)?;
+		self.inner.write_str("_G")?;
+		if self.is_first && !self.control.is_empty() {
+			self.inner.write_str(self.control)?;
+			self.inner.write_char(',')?;
+		}
+		self.inner.write_str(if has_more { "m=1;" } else { "m=0;" })?;
+		// SAFETY: Only `str`s are written to `chunk`, and it's flushed at
+		// multiples of 4 bytes of base64, which is ASCII.
+		self.inner.write_str(unsafe {
+			core::str::from_utf8_unchecked(&self.chunk[..self.len])
+		})?;
+		self.inner.write_str(Terminator::St.as_str())?;
+		self.len = 0;
+		self.is_first = false;
+		Ok(())
+	}
+}
+
+impl<W: Write + ?Sized> Write for Chunks<'_, '_, W> {
+	fn write_str(&mut self, s: &str) -> FmtResult {
+		let mut rest = s.as_bytes();
+		while !rest.is_empty() {
+			if self.len == self.chunk.len() {
+				self.flush(true)?;
+			}
+			let n = rest.len().min(self.chunk.len() - self.len);
+			self.chunk[self.len..self.len + n].copy_from_slice(&rest[..n]);
+			self.len += n;
+			rest = &rest[n..];
+		}
+		Ok(())
+	}
+}
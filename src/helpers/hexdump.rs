@@ -0,0 +1,120 @@
+use super::{
+	HighlightSpan, Style,
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Hex dump of bytes, with the offset, the bytes in hexadecimal and the
+/// printable ASCII characters of each row, like `hexdump -C` shows them.
+/// 
+/// Characters other than printable ASCII are shown as `.` in the gutter, so
+/// the dump can show escape sequences without the terminal interpreting them.
+/// Bytes in [`Self::highlights`] are shown in the style of the first span
+/// that contains them, both in hexadecimal and in the gutter. Rows are
+/// separated by line feeds, without one after the last row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexDump<'a> {
+	pub bytes: &'a [u8],
+	/// Number of bytes in each row.
+	pub row_len: usize,
+	/// Offset shown for the first byte.
+	pub offset: usize,
+	/// Ranges of [`Self::bytes`] to show in a style.
+	pub highlights: &'a [HighlightSpan],
+}
+
+impl<'a> HexDump<'a> {
+	/// Number of bytes in each row by default.
+	pub const DEFAULT_ROW_LEN: usize = 16;
+
+	/// Create a [`HexDump`] of `bytes` with [`Self::DEFAULT_ROW_LEN`] bytes in
+	/// each row, starting at offset `0`.
+	#[inline(always)]
+	pub const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			bytes,
+			row_len: Self::DEFAULT_ROW_LEN,
+			offset: 0,
+			highlights: &[],
+		}
+	}
+
+	/// Return `self` with `row_len` bytes in each row.
+	#[inline(always)]
+	pub const fn with_row_len(mut self, row_len: usize) -> Self {
+		self.row_len = row_len;
+		self
+	}
+
+	/// Return `self` with `offset` shown for the first byte.
+	#[inline(always)]
+	pub const fn with_offset(mut self, offset: usize) -> Self {
+		self.offset = offset;
+		self
+	}
+
+	/// Return `self` with the bytes in `highlights` shown in their styles.
+	#[inline(always)]
+	pub const fn with_highlights(mut self, highlights: &'a [HighlightSpan]) -> Self {
+		self.highlights = highlights;
+		self
+	}
+
+	/// Return the style of the byte at `idx`.
+	fn style_at(&self, idx: usize) -> Style {
+		self.highlights.iter()
+			.find(move |span| span.range().contains(&idx))
+			.map_or(Style::new(), move |span| span.style)
+	}
+}
+
+impl Display for HexDump<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let row_len = self.row_len.max(1);
+		let mut style = Style::new();
+		let mut set_style = move |f: &mut Formatter<'_>, target: Style| {
+			if style != target {
+				style.diff_to(&target).fmt(f)?;
+				style = target;
+			}
+			Ok(())
+		};
+
+		for (row_idx, row) in self.bytes.chunks(row_len).enumerate() {
+			let start = row_idx * row_len;
+			if row_idx > 0 {
+				f.write_char('\n')?;
+			}
+			write!(f, "{:08x} ", self.offset.wrapping_add(start))?;
+
+			for col in 0..row_len {
+				f.write_char(' ')?;
+				if col > 0 && col % 8 == 0 {
+					f.write_char(' ')?;
+				}
+				match row.get(col) {
+					Some(byte) => {
+						set_style(f, self.style_at(start + col))?;
+						write!(f, "{byte:02x}")?;
+						set_style(f, Style::new())?;
+					}
+					None => f.write_str("  ")?,
+				}
+			}
+
+			f.write_str("  |")?;
+			for (col, &byte) in row.iter().enumerate() {
+				set_style(f, self.style_at(start + col))?;
+				f.write_char(match byte {
+					0x20..=0x7e => byte as char,
+					_ => '.',
+				})?;
+			}
+			set_style(f, Style::new())?;
+			f.write_char('|')?;
+		}
+		Ok(())
+	}
+}
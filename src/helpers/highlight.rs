@@ -0,0 +1,148 @@
+use super::Style;
+
+use core::{
+	fmt::{
+		Display, Formatter, Result as FmtResult, Write,
+	},
+	ops::Range,
+};
+
+/// Range of bytes of source text with the style to display it with, as
+/// produced by syntax highlighters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightSpan {
+	/// Byte offset of the start of the span.
+	pub start: usize,
+	/// Byte offset of the end of the span, exclusive.
+	pub end: usize,
+	/// Style of the text in the span.
+	pub style: Style,
+}
+
+impl HighlightSpan {
+	/// Create a [`HighlightSpan`] for the bytes in `range`.
+	#[inline(always)]
+	pub const fn new(range: Range<usize>, style: Style) -> Self {
+		Self {
+			start: range.start,
+			end: range.end,
+			style,
+		}
+	}
+
+	/// Return the range of bytes of the span.
+	#[inline(always)]
+	pub const fn range(&self) -> Range<usize> {
+		self.start..self.end
+	}
+}
+
+/// Writer of source text in the styles of [`HighlightSpan`]s, which only
+/// writes the settings that change between consecutive spans.
+/// 
+/// Spans must be written in the order of their positions. Text before a span
+/// that isn't covered by another span is written in the default style, and
+/// the part of a span that overlaps text that was already written is skipped.
+/// Offsets that aren't on a character boundary are moved forward to the next
+/// one, so that a character is never split between styles.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Highlighter {
+	style: Style,
+	pos: usize,
+}
+
+impl Highlighter {
+	/// Create a [`Highlighter`] at the start of the source text.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			style: Style::new(),
+			pos: 0,
+		}
+	}
+
+	/// Return the byte offset up to which the source text was written.
+	#[inline(always)]
+	pub const fn position(&self) -> usize {
+		self.pos
+	}
+
+	/// Write the text of `span` in `source`, preceded by any text since the
+	/// last span.
+	pub fn write_span(
+		&mut self, w: &mut impl Write, source: &str, span: &HighlightSpan,
+	) -> FmtResult {
+		let start = ceil_char_boundary(source, span.start);
+		let end = ceil_char_boundary(source, span.end);
+		self.write_text(w, source, start, Style::new())?;
+		self.write_text(w, source, end, span.style)
+	}
+
+	/// Write the rest of `source` in the default style and reset the style.
+	pub fn finish(&mut self, w: &mut impl Write, source: &str) -> FmtResult {
+		self.write_text(w, source, source.len(), Style::new())?;
+		self.set_style(w, Style::new())
+	}
+
+	/// Write `source` up to `end` in `style`.
+	fn write_text(
+		&mut self, w: &mut impl Write, source: &str, end: usize, style: Style,
+	) -> FmtResult {
+		// The style only changes before text, so that empty spans and gaps
+		// don't write anything.
+		let Some(text) = source.get(self.pos..end).filter(move |s| !s.is_empty())
+		else {
+			return Ok(())
+		};
+		self.set_style(w, style)?;
+		w.write_str(text)?;
+		self.pos = end;
+		Ok(())
+	}
+
+	fn set_style(&mut self, w: &mut dyn Write, style: Style) -> FmtResult {
+		if style != self.style {
+			write!(w, "{}", self.style.diff_to(&style))?;
+			self.style = style;
+		}
+		Ok(())
+	}
+}
+
+/// Return `idx` moved forward to the next character boundary of `s`, clamped
+/// to the length of `s`.
+fn ceil_char_boundary(s: &str, idx: usize) -> usize {
+	(idx..s.len()).find(move |&i| s.is_char_boundary(i)).unwrap_or(s.len())
+}
+
+/// Source text that is displayed in the styles of [`HighlightSpan`]s.
+/// 
+/// See [`Highlighter`] for how spans are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Highlighted<'a> {
+	/// Source text.
+	pub source: &'a str,
+	/// Spans of `source`, in the order of their positions.
+	pub spans: &'a [HighlightSpan],
+}
+
+impl<'a> Highlighted<'a> {
+	/// Create a [`Highlighted`] from source text and its spans.
+	#[inline(always)]
+	pub const fn new(source: &'a str, spans: &'a [HighlightSpan]) -> Self {
+		Self {
+			source,
+			spans,
+		}
+	}
+}
+
+impl<'a> Display for Highlighted<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let mut highlighter = Highlighter::new();
+		for span in self.spans {
+			highlighter.write_span(f, self.source, span)?;
+		}
+		highlighter.finish(f, self.source)
+	}
+}
@@ -0,0 +1,220 @@
+use super::{
+	Role, StateChange, StyleRoles,
+};
+
+use core::{
+	fmt::{
+		Display, Formatter, Result as FmtResult,
+	},
+	time::Duration,
+};
+
+/// Write `value` in `style`, resetting the style after it.
+#[inline(always)]
+fn write_styled(
+	f: &mut Formatter<'_>, style: &StateChange,
+	value: impl FnOnce(&mut Formatter<'_>) -> FmtResult,
+) -> FmtResult {
+	style.fmt(f)?;
+	value(f)?;
+	style.resetter().fmt(f)
+}
+
+/// Duration displayed with the two most significant units, such as `1h 05m`
+/// or `12.5s`, for elapsed times and estimates.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HumanDuration {
+	pub duration: Duration,
+	/// Style of the text.
+	pub style: StateChange,
+}
+
+impl HumanDuration {
+	/// Create a [`HumanDuration`] for `duration`.
+	#[inline(always)]
+	pub const fn new(duration: Duration) -> Self {
+		Self {
+			duration,
+			style: StateChange::new(),
+		}
+	}
+
+	/// Return `self` displayed in `style`.
+	#[inline(always)]
+	pub const fn with_style(mut self, style: StateChange) -> Self {
+		self.style = style;
+		self
+	}
+
+	/// Create a [`HumanDuration`] for `duration`, displayed in the settings of
+	/// [`Role::Info`] in `roles`.
+	#[inline(always)]
+	pub const fn from_roles(duration: Duration, roles: &StyleRoles) -> Self {
+		Self::new(duration).with_style(roles.get(Role::Info))
+	}
+}
+
+impl Display for HumanDuration {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let secs = self.duration.as_secs();
+		write_styled(f, &self.style, move |f| match secs {
+			0 => write!(f, "{}ms", self.duration.subsec_millis()),
+			1..60 => {
+				let tenths = self.duration.subsec_millis() / 100;
+				write!(f, "{secs}.{tenths}s")
+			}
+			60..3600 => write!(f, "{}m {:02}s", secs / 60, secs % 60),
+			3600..86400 => write!(f, "{}h {:02}m", secs / 3600, secs / 60 % 60),
+			_ => write!(f, "{}d {:02}h", secs / 86400, secs / 3600 % 24),
+		})
+	}
+}
+
+/// Number of bytes displayed with a unit prefix, such as `1.5 KiB`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HumanBytes {
+	pub bytes: u64,
+	/// Whether to use powers of 1000 (`kB`, `MB`) instead of powers of 1024
+	/// (`KiB`, `MiB`).
+	pub decimal: bool,
+	/// Style of the text.
+	pub style: StateChange,
+}
+
+impl HumanBytes {
+	/// Create a [`HumanBytes`] for `bytes`, using binary prefixes.
+	#[inline(always)]
+	pub const fn new(bytes: u64) -> Self {
+		Self {
+			bytes,
+			decimal: false,
+			style: StateChange::new(),
+		}
+	}
+
+	/// Return `self` with decimal or binary prefixes.
+	#[inline(always)]
+	pub const fn with_decimal(mut self, decimal: bool) -> Self {
+		self.decimal = decimal;
+		self
+	}
+
+	/// Return `self` displayed in `style`.
+	#[inline(always)]
+	pub const fn with_style(mut self, style: StateChange) -> Self {
+		self.style = style;
+		self
+	}
+
+	/// Create a [`HumanBytes`] for `bytes` like [`Self::new`], displayed in the
+	/// settings of [`Role::Info`] in `roles`.
+	#[inline(always)]
+	pub const fn from_roles(bytes: u64, roles: &StyleRoles) -> Self {
+		Self::new(bytes).with_style(roles.get(Role::Info))
+	}
+}
+
+impl Display for HumanBytes {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		const BINARY: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+		const DECIMAL: [&str; 6] = ["kB", "MB", "GB", "TB", "PB", "EB"];
+		let (base, units) = if self.decimal { (1000, DECIMAL) } else { (1024, BINARY) };
+
+		write_styled(f, &self.style, move |f| {
+			if self.bytes < base {
+				return write!(f, "{} B", self.bytes)
+			}
+			// Find the largest unit that the number is at least one of, in
+			// tenths to round to one decimal place.
+			let mut unit = 0;
+			let mut scale = base as u128;
+			while unit + 1 < units.len() && self.bytes as u128 >= scale * base as u128 {
+				scale *= base as u128;
+				unit += 1;
+			}
+			let mut tenths = (self.bytes as u128 * 10 + scale / 2) / scale;
+			if tenths >= base as u128 * 10 && unit + 1 < units.len() {
+				// Rounding made the number reach the next unit.
+				tenths = (tenths + base as u128 / 2) / base as u128;
+				unit += 1;
+			}
+			write!(f, "{}.{} {}", tenths / 10, tenths % 10, units[unit])
+		})
+	}
+}
+
+/// Ratio of done work to total work, displayed as a percentage such as `42%`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percent {
+	pub done: u64,
+	pub total: u64,
+	/// Number of digits after the decimal point, up to 9.
+	pub decimals: u8,
+	/// Style of the text.
+	pub style: StateChange,
+}
+
+impl Percent {
+	/// Create a [`Percent`] of `done` out of `total`, without decimals.
+	/// 
+	/// The percentage is rounded down, so that it's only `100%` when all work
+	/// is done. No work is `100%` done, and more work than the total is
+	/// clamped to `100%`.
+	#[inline(always)]
+	pub const fn new(done: u64, total: u64) -> Self {
+		Self {
+			done,
+			total,
+			decimals: 0,
+			style: StateChange::new(),
+		}
+	}
+
+	/// Return `self` with `decimals` digits after the decimal point.
+	#[inline(always)]
+	pub const fn with_decimals(mut self, decimals: u8) -> Self {
+		self.decimals = decimals;
+		self
+	}
+
+	/// Return `self` displayed in `style`.
+	#[inline(always)]
+	pub const fn with_style(mut self, style: StateChange) -> Self {
+		self.style = style;
+		self
+	}
+
+	/// Create a [`Percent`] of `done` out of `total` like [`Self::new`],
+	/// displayed in the settings of [`Role::Info`] in `roles`.
+	#[inline(always)]
+	pub const fn from_roles(done: u64, total: u64, roles: &StyleRoles) -> Self {
+		Self::new(done, total).with_style(roles.get(Role::Info))
+	}
+
+	/// Return the ratio of done work, from `0.0` to `1.0`.
+	#[inline(always)]
+	pub fn ratio(&self) -> f64 {
+		match self.total {
+			0 => 1.0,
+			total => (self.done as f64 / total as f64).min(1.0),
+		}
+	}
+}
+
+impl Display for Percent {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let decimals = self.decimals.min(9) as u32;
+		let unit = 10u128.pow(decimals);
+		let scaled = match self.total {
+			0 => 100 * unit,
+			total => self.done.min(total) as u128 * 100 * unit / total as u128,
+		};
+		write_styled(f, &self.style, move |f| {
+			write!(f, "{}", scaled / unit)?;
+			if decimals > 0 {
+				write!(f, ".{:01$}", scaled % unit, decimals as usize)?;
+			}
+			f.write_str("%")
+		})
+	}
+}
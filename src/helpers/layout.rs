@@ -0,0 +1,163 @@
+/// Rectangular area of the screen or of a
+#[cfg_attr(feature = "alloc", doc = "[`Buffer`](super::Buffer).")]
+#[cfg_attr(not(feature = "alloc"), doc = "`Buffer`.")]
+/// 
+/// Coordinates are 0-based, with `x` being the column and `y` the row.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+	pub x: u16,
+	pub y: u16,
+	pub width: u16,
+	pub height: u16,
+}
+
+impl Rect {
+	/// Create a [`Rect`] with its top left corner at `x`, `y`.
+	#[inline(always)]
+	pub const fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+		Self {
+			x,
+			y,
+			width,
+			height,
+		}
+	}
+
+	/// Return the number of cells in the rectangle.
+	#[inline(always)]
+	pub const fn area(&self) -> u32 {
+		self.width as u32 * self.height as u32
+	}
+
+	/// Return `true` if the rectangle has no cells.
+	#[inline(always)]
+	pub const fn is_empty(&self) -> bool {
+		self.width == 0 || self.height == 0
+	}
+
+	/// Return the column after the right edge.
+	#[inline(always)]
+	pub const fn right(&self) -> u16 {
+		self.x.saturating_add(self.width)
+	}
+
+	/// Return the row after the bottom edge.
+	#[inline(always)]
+	pub const fn bottom(&self) -> u16 {
+		self.y.saturating_add(self.height)
+	}
+
+	/// Return `true` if the cell at `x`, `y` is in the rectangle.
+	#[inline(always)]
+	pub const fn contains(&self, x: u16, y: u16) -> bool {
+		self.x <= x && x < self.right() && self.y <= y && y < self.bottom()
+	}
+
+	/// Return the rectangle shrunk by `margin` cells on every side.
+	#[inline(always)]
+	pub const fn inner(&self, margin: u16) -> Self {
+		Self {
+			x: self.x.saturating_add(margin),
+			y: self.y.saturating_add(margin),
+			width: self.width.saturating_sub(margin.saturating_mul(2)),
+			height: self.height.saturating_sub(margin.saturating_mul(2)),
+		}
+	}
+
+	/// Split the rectangle into `N` parts along `direction`, sized by
+	/// `constraints`.
+	/// 
+	/// [`Constraint::Length`] and [`Constraint::Percentage`] parts get their
+	/// size first, and the rest of the space is shared evenly between
+	/// [`Constraint::Min`] and [`Constraint::Max`] parts. If the parts don't
+	/// fit, the ones at the end are shrunk.
+	pub fn split<const N: usize>(
+		&self, direction: Direction, constraints: [Constraint; N],
+	) -> [Self; N] {
+		let total = match direction {
+			Direction::Horizontal => self.width,
+			Direction::Vertical => self.height,
+		};
+		let mut sizes = constraints.map(move |c| c.base_size(total));
+		let used = sizes.iter().map(move |&n| n as u32).sum::<u32>();
+		let mut remaining = (total as u32).saturating_sub(used);
+		while remaining > 0 {
+			let is_growable = |i: usize| sizes[i] < constraints[i].max_size();
+			let count = (0..N).filter(|&i| is_growable(i)).count() as u32;
+			if count == 0 {
+				break
+			}
+			let share = (remaining / count).max(1);
+			for i in 0..N {
+				let room = constraints[i].max_size().saturating_sub(sizes[i]) as u32;
+				let add = share.min(remaining).min(room);
+				sizes[i] += add as u16;
+				remaining -= add;
+			}
+		}
+
+		let mut pos = 0;
+		sizes.map(move |size| {
+			let size = size.min(total - pos);
+			let start = pos;
+			pos += size;
+			match direction {
+				Direction::Horizontal => Self {
+					x: self.x + start,
+					width: size,
+					..*self
+				},
+				Direction::Vertical => Self {
+					y: self.y + start,
+					height: size,
+					..*self
+				},
+			}
+		})
+	}
+}
+
+/// Direction along which a [`Rect`] is split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+	/// Split into columns, side by side.
+	Horizontal,
+	/// Split into rows, one under another.
+	Vertical,
+}
+
+/// Size of one part of a split [`Rect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Constraint {
+	/// Exactly the specified number of cells.
+	Length(u16),
+	/// The specified percentage of the cells, up to 100.
+	Percentage(u16),
+	/// At least the specified number of cells, growing into free space.
+	Min(u16),
+	/// At most the specified number of cells, growing into free space.
+	Max(u16),
+}
+
+impl Constraint {
+	/// Return the size of the part before free space is shared out.
+	const fn base_size(self, total: u16) -> u16 {
+		match self {
+			Self::Length(n) | Self::Min(n) => n,
+			Self::Percentage(p) => {
+				let p = if p < 100 { p } else { 100 };
+				(total as u32 * p as u32 / 100) as u16
+			}
+			Self::Max(_) => 0,
+		}
+	}
+
+	/// Return the size that the part can grow to.
+	const fn max_size(self) -> u16 {
+		match self {
+			Self::Length(_) | Self::Percentage(_) => 0,
+			Self::Min(_) => u16::MAX,
+			Self::Max(n) => n,
+		}
+	}
+}
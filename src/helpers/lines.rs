@@ -0,0 +1,110 @@
+use super::{
+	SgrState, Style,
+};
+use crate::{
+	scan::Token,
+	sgr::Sgr,
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult,
+};
+
+/// Line of styled output that can be displayed independently of the lines
+/// around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyledLine<'a> {
+	/// Style in effect at the start of the line.
+	pub style: Style,
+	/// Bytes of the line, without the line terminator.
+	pub content: &'a [u8],
+	/// Style in effect at the end of the line.
+	pub end_style: Style,
+}
+
+impl<'a> Display for StyledLine<'a> {
+	/// Display the line, preceded by the sequence that sets the style at its
+	/// start and followed by a reset if a style is in effect at its end.
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Style::new().diff_to(&self.style).fmt(f)?;
+		for chunk in self.content.utf8_chunks() {
+			f.write_str(chunk.valid())?;
+			if !chunk.invalid().is_empty() {
+				f.write_str(char::REPLACEMENT_CHARACTER.encode_utf8(&mut [0; 4]))?;
+			}
+		}
+		if !self.end_style.is_default() {
+			Sgr::Reset.fmt(f)?;
+		}
+		Ok(())
+	}
+}
+
+/// Iterator that splits styled output into [`StyledLine`]s.
+/// 
+/// Lines are terminated by `\n` or `\r\n`. SGR sequences are tracked across
+/// lines, so that every line carries the style that is in effect at its
+/// start.
+#[derive(Debug, Clone)]
+pub struct StyledLines<'a> {
+	rest: &'a [u8],
+	state: SgrState,
+}
+
+impl<'a> StyledLines<'a> {
+	/// Create an iterator over the lines in `bytes`.
+	#[inline(always)]
+	pub const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			rest: bytes,
+			state: SgrState::new(),
+		}
+	}
+
+	/// Create an iterator over the lines in `bytes`, with `style` in effect at
+	/// the start.
+	#[inline(always)]
+	pub fn with_style(bytes: &'a [u8], style: Style) -> Self {
+		let mut state = SgrState::new();
+		state.set_style(style);
+		Self {
+			rest: bytes,
+			state,
+		}
+	}
+}
+
+impl<'a> Iterator for StyledLines<'a> {
+	type Item = StyledLine<'a>;
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.rest.is_empty() {
+			return None
+		}
+
+		let style = self.state.current_style();
+		let bytes = self.rest;
+		let mut pos = 0;
+		let (content, next) = loop {
+			match Token::parse(&bytes[pos..]) {
+				Ok((Token::Control(b'\n'), ..)) => {
+					let content = &bytes[..pos];
+					break (content.strip_suffix(b"\r").unwrap_or(content), pos + 1)
+				}
+				Ok((token, len)) => {
+					if let Token::Csi(csi) = token {
+						self.state.apply_csi(&csi);
+					}
+					pos += len;
+				}
+				Err(..) => break (bytes, bytes.len()),
+			}
+		};
+		self.rest = &bytes[next..];
+
+		Some(StyledLine {
+			style,
+			content,
+			end_style: self.state.current_style(),
+		})
+	}
+}
@@ -0,0 +1,251 @@
+use super::{
+	CursorMove, EraseDisplay, EraseLine, Truncated,
+};
+use crate::{
+	profile::Profile,
+	sgr::Sgr,
+	width::WidthPolicy,
+};
+
+use std::io::{
+	self, Write,
+};
+
+/// Region of status lines at the bottom of the output, which can be updated
+/// in place while normal output scrolls above them, like the progress lines
+/// of build tools and test runners.
+/// 
+/// Output written through the region is buffered until it ends a line, and
+/// then written above the status lines, which are drawn again below it. The
+/// status lines are erased when the region is dropped, unless it's finished
+/// with [`Self::finish`].
+/// 
+/// The cursor is moved relative to the last status line, so each line should
+/// fit on one row, which [`Self::set_width`] can ensure. Nothing else should
+/// be written to the terminal while the region exists.
+#[derive(Debug)]
+pub struct MultiLine<W: Write> {
+	inner: W,
+	lines: Vec<String>,
+	/// Number of status lines on the screen, the last of which has the cursor.
+	drawn: usize,
+	width: Option<u16>,
+	policy: WidthPolicy,
+	/// Output that doesn't end a line yet.
+	pending: Vec<u8>,
+	is_finished: bool,
+}
+
+impl<W: Write> MultiLine<W> {
+	/// Draw `count` empty status lines.
+	#[inline(always)]
+	pub fn new(inner: W, count: usize) -> io::Result<Self> {
+		Self::with_profile(inner, count, &Profile::new())
+	}
+
+	/// Draw `count` empty status lines, measuring them as appropriate for
+	/// `profile`.
+	pub fn with_profile(inner: W, count: usize, profile: &Profile) -> io::Result<Self> {
+		let mut region = Self {
+			inner,
+			lines: std::vec![String::new(); count],
+			drawn: 0,
+			width: None,
+			policy: profile.width,
+			pending: Vec::new(),
+			is_finished: false,
+		};
+		region.draw()?;
+		region.inner.flush()?;
+		Ok(region)
+	}
+
+	/// Return the number of status lines.
+	#[inline(always)]
+	pub fn len(&self) -> usize {
+		self.lines.len()
+	}
+
+	/// Return `true` if there are no status lines.
+	#[inline(always)]
+	pub fn is_empty(&self) -> bool {
+		self.lines.is_empty()
+	}
+
+	/// Return the status line at `idx`, if there is one.
+	#[inline(always)]
+	pub fn line(&self, idx: usize) -> Option<&str> {
+		self.lines.get(idx).map(String::as_str)
+	}
+
+	/// Set the number of cells that status lines are truncated to, such as
+	/// the width of the terminal, or `None` to not truncate them.
+	/// 
+	/// This takes effect when the lines are next drawn.
+	#[inline(always)]
+	pub fn set_width(&mut self, width: Option<u16>) {
+		self.width = width;
+	}
+
+	/// Replace the status line at `idx` with `text`, and draw it.
+	/// 
+	/// Empty status lines are added if there are fewer than `idx + 1` of them.
+	/// Line breaks in `text` are replaced with spaces. `text` may contain SGR
+	/// sequences, whose style is reset at the end of the line.
+	pub fn set_line(&mut self, idx: usize, text: &str) -> io::Result<()> {
+		if idx >= self.lines.len() {
+			self.lines.resize(idx + 1, String::new());
+		}
+		let line = &mut self.lines[idx];
+		line.clear();
+		line.extend(text.chars().map(move |c| match c {
+			'\n' | '\r' => ' ',
+			c => c,
+		}));
+
+		if self.drawn != self.lines.len() {
+			self.erase()?;
+			self.draw()?;
+			return self.inner.flush()
+		}
+		let up = self.drawn - 1 - idx;
+		self.inner.write_all(b"\r")?;
+		move_rows(&mut self.inner, -(up as isize))?;
+		write_line(&mut self.inner, &self.lines[idx], self.width, &self.policy)?;
+		move_rows(&mut self.inner, up as isize)?;
+		self.inner.flush()
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	/// 
+	/// Writing to it directly breaks the region, unless the status lines are
+	/// drawn again afterwards.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	/// Leave the status lines on the screen as normal output, after any
+	/// buffered output that doesn't end a line.
+	pub fn finish(mut self) -> io::Result<()> {
+		self.is_finished = true;
+		if !self.pending.is_empty() {
+			self.erase()?;
+			self.inner.write_all(&self.pending)?;
+			self.inner.write_all(b"\r\n")?;
+			self.draw()?;
+		}
+		if self.drawn > 0 {
+			self.inner.write_all(b"\r\n")?;
+		}
+		self.inner.flush()
+	}
+
+	/// Erase the status lines, leaving the cursor at the start of the first.
+	fn erase(&mut self) -> io::Result<()> {
+		if self.drawn == 0 {
+			return Ok(())
+		}
+		self.inner.write_all(b"\r")?;
+		move_rows(&mut self.inner, 1 - self.drawn as isize)?;
+		write!(self.inner, "{}", EraseDisplay::CurToEnd)?;
+		self.drawn = 0;
+		Ok(())
+	}
+
+	/// Draw every status line, starting at the cursor.
+	fn draw(&mut self) -> io::Result<()> {
+		for (i, line) in self.lines.iter().enumerate() {
+			if i > 0 {
+				self.inner.write_all(b"\r\n")?;
+			}
+			write_line(&mut self.inner, line, self.width, &self.policy)?;
+		}
+		self.drawn = self.lines.len();
+		Ok(())
+	}
+}
+
+impl<W: Write> Write for MultiLine<W> {
+	/// Write output above the status lines once it ends a line.
+	/// 
+	/// `buf` is only consumed if the output is written, so that it can be
+	/// written again after an error. If the status lines can't be drawn again
+	/// after it, they are drawn when the region is flushed.
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let Some(end) = buf.iter().rposition(|&b| b == b'\n') else {
+			self.pending.extend_from_slice(buf);
+			return Ok(buf.len())
+		};
+		self.erase()?;
+		let pending_len = self.pending.len();
+		self.pending.extend_from_slice(&buf[..=end]);
+		if let Err(error) = self.inner.write_all(&self.pending) {
+			self.pending.truncate(pending_len);
+			return Err(error)
+		}
+		self.pending.clear();
+		self.pending.extend_from_slice(&buf[end + 1..]);
+		// The output was written, so `buf` is consumed even if this fails.
+		let _ = self.draw();
+		Ok(buf.len())
+	}
+
+	/// Draw the status lines if they aren't drawn, and flush the inner
+	/// writer.
+	fn flush(&mut self) -> io::Result<()> {
+		if self.drawn != self.lines.len() {
+			self.erase()?;
+			self.draw()?;
+		}
+		self.inner.flush()
+	}
+}
+
+impl<W: Write> Drop for MultiLine<W> {
+	fn drop(&mut self) {
+		if self.is_finished {
+			return
+		}
+		// Errors can't be reported from `drop`.
+		let _ = self.erase();
+		let _ = self.inner.write_all(&self.pending);
+		let _ = self.inner.flush();
+	}
+}
+
+/// Move the cursor `rows` rows down, or up if `rows` is negative.
+fn move_rows(w: &mut impl Write, mut rows: isize) -> io::Result<()> {
+	// Each sequence moves by as many rows as fit in an `i8`.
+	while rows != 0 {
+		let step = rows.clamp(i8::MIN as isize, i8::MAX as isize) as i8;
+		write!(w, "{}", CursorMove::Rows(step))?;
+		rows -= step as isize;
+	}
+	Ok(())
+}
+
+/// Write a status line, truncated to `width` cells, and erase the rest of the
+/// row.
+fn write_line(
+	w: &mut impl Write, line: &str, width: Option<u16>, policy: &WidthPolicy,
+) -> io::Result<()> {
+	match width {
+		Some(width) => {
+			let truncated = Truncated::new(line, width as usize)
+				.with_width_policy(policy);
+			write!(w, "{truncated}")?;
+		}
+		None => w.write_all(line.as_bytes())?,
+	}
+	if line.contains('\x1b') {
+		write!(w, "{}", Sgr::Reset)?;
+	}
+	write!(w, "{}", EraseLine::CurToEnd)
+}
@@ -0,0 +1,571 @@
+use super::{
+	Color, Italic, Role, StateChange, Style, StyleRoles, StyledLines, Underline,
+	Weight,
+};
+use crate::{
+	fe_seq::ESC_STR,
+	profile::Profile,
+	scan::Token,
+};
+
+use alloc::string::String;
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Styles used by [`Markdown`] for each kind of markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownTheme {
+	/// Style of level 1 headings.
+	pub title: StateChange,
+	/// Style of headings other than level 1.
+	pub heading: StateChange,
+	/// Style of `*emphasis*`.
+	pub emphasis: StateChange,
+	/// Style of `**strong emphasis**`.
+	pub strong: StateChange,
+	/// Style of `` `code` `` spans and fenced code blocks.
+	pub code: StateChange,
+	/// Style of the text of links.
+	pub link: StateChange,
+	/// Style of list bullets and numbers.
+	pub bullet: StateChange,
+}
+
+impl Default for MarkdownTheme {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl MarkdownTheme {
+	/// Create the default [`MarkdownTheme`], which only uses the 16 table
+	/// colors.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			title: StateChange::new()
+				.with_weight(Weight::Bold)
+				.with_underline(Underline::Single),
+			heading: StateChange::new().with_weight(Weight::Bold),
+			emphasis: StateChange::new().with_italic(Italic::On),
+			strong: StateChange::new().with_weight(Weight::Bold),
+			code: StateChange::new().with_foreground(Color::Table(6)),
+			link: StateChange::new()
+				.with_foreground(Color::Table(4))
+				.with_underline(Underline::Single),
+			bullet: StateChange::new().with_foreground(Color::Table(3)),
+		}
+	}
+
+	/// Create a [`MarkdownTheme`] with the settings of the roles in `roles`.
+	/// 
+	/// Each kind of markup uses the role of the same name, and list bullets
+	/// use [`Role::Accent`].
+	#[inline(always)]
+	pub const fn from_roles(roles: &StyleRoles) -> Self {
+		Self {
+			title: roles.get(Role::Title),
+			heading: roles.get(Role::Heading),
+			emphasis: roles.get(Role::Emphasis),
+			strong: roles.get(Role::Strong),
+			code: roles.get(Role::Code),
+			link: roles.get(Role::Link),
+			bullet: roles.get(Role::Accent),
+		}
+	}
+}
+
+/// Renderer of a safe subset of Markdown, such as help text or a changelog.
+/// 
+/// Supported markup is ATX headings (`# Title`), bullet and numbered lists,
+/// fenced code blocks, `*emphasis*`, `**strong emphasis**`, `` `code` ``
+/// spans, backslash escapes and `[links](https://example.com)`, which are
+/// written as OSC 8 hyperlinks. Anything else is displayed as text.
+/// Paragraphs and list items are wrapped to [`Self::width`] columns.
+/// 
+/// Control characters in the source are dropped, so the source can't write
+/// escape sequences of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Markdown<'a> {
+	/// Markdown source.
+	pub source: &'a str,
+	/// Number of columns to wrap text to.
+	pub width: usize,
+	/// Styles of the markup.
+	pub theme: MarkdownTheme,
+	/// Whether to write links as OSC 8 hyperlinks. If this is `false`, then
+	/// the target of a link is written in parentheses after its text.
+	pub hyperlinks: bool,
+	/// Terminal that output is intended for.
+	pub profile: Profile,
+}
+
+impl<'a> Markdown<'a> {
+	/// Create a [`Markdown`] renderer for `source`, wrapped to `width` columns.
+	#[inline(always)]
+	pub const fn new(source: &'a str, width: usize) -> Self {
+		Self {
+			source,
+			width,
+			theme: MarkdownTheme::new(),
+			hyperlinks: true,
+			profile: Profile::new(),
+		}
+	}
+
+	/// Return `self` with the specified styles.
+	#[inline(always)]
+	pub const fn with_theme(mut self, theme: MarkdownTheme) -> Self {
+		self.theme = theme;
+		self
+	}
+
+	/// Return `self` with links written as hyperlinks or not.
+	#[inline(always)]
+	pub const fn with_hyperlinks(mut self, hyperlinks: bool) -> Self {
+		self.hyperlinks = hyperlinks;
+		self
+	}
+
+	/// Return `self` with output written as appropriate for `profile`.
+	#[inline(always)]
+	pub const fn with_profile(mut self, profile: &Profile) -> Self {
+		self.profile = *profile;
+		self
+	}
+}
+
+/// Kind of block of Markdown that is being collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block<'a> {
+	Paragraph,
+	Heading(usize),
+	/// List item with its indentation and marker.
+	Item(usize, &'a str),
+}
+
+impl<'a> Display for Markdown<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		let mut renderer = Renderer {
+			md: self,
+			text: String::new(),
+			styled: String::new(),
+			block: None,
+			needs_gap: false,
+			in_list: false,
+		};
+
+		let mut fence: Option<&str> = None;
+		for line in self.source.lines() {
+			let trimmed = line.trim_start();
+			if let Some(marker) = fence {
+				if trimmed.starts_with(marker) {
+					fence = None;
+				} else {
+					renderer.write_code_line(f, line)?;
+				}
+				continue
+			}
+
+			if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+				renderer.finish_block(f)?;
+				renderer.gap(f)?;
+				fence = Some(&trimmed[..3]);
+			} else if trimmed.is_empty() {
+				renderer.finish_block(f)?;
+			} else if let Some((level, title)) = heading(trimmed) {
+				renderer.finish_block(f)?;
+				renderer.block = Some(Block::Heading(level));
+				renderer.text.push_str(title);
+				renderer.finish_block(f)?;
+			} else if let Some((marker, item)) = list_marker(trimmed) {
+				renderer.finish_block(f)?;
+				let indent = line.len() - trimmed.len();
+				renderer.block = Some(Block::Item(indent, marker));
+				renderer.text.push_str(item);
+			} else {
+				if renderer.block.is_none() {
+					renderer.block = Some(Block::Paragraph);
+				} else {
+					renderer.text.push(' ');
+				}
+				renderer.text.push_str(trimmed);
+			}
+		}
+		renderer.finish_block(f)
+	}
+}
+
+/// Return the level and text of an ATX heading.
+fn heading(line: &str) -> Option<(usize, &str)> {
+	let level = line.bytes().take_while(move |&b| b == b'#').count();
+	let rest = &line[level..];
+	if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+		return None
+	}
+	Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Return the marker and the text of a list item.
+fn list_marker(line: &str) -> Option<(&str, &str)> {
+	let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+	let marker_len = match line.as_bytes().get(digits) {
+		Some(b'-' | b'*' | b'+') if digits == 0 => 1,
+		Some(b'.' | b')') if (1..=9).contains(&digits) => digits + 1,
+		_ => return None,
+	};
+	let rest = line[marker_len..].strip_prefix(' ')?;
+	Some((&line[..marker_len], rest.trim_start()))
+}
+
+/// State of rendering a [`Markdown`] document.
+struct Renderer<'m, 'a> {
+	md: &'m Markdown<'a>,
+	/// Source text of the current block.
+	text: String,
+	/// Styled text of the current block.
+	styled: String,
+	block: Option<Block<'a>>,
+	/// Whether an empty line must be written before the next block.
+	needs_gap: bool,
+	/// Whether the last block was a list item.
+	in_list: bool,
+}
+
+impl<'m, 'a> Renderer<'m, 'a> {
+	/// Write an empty line if a block was written before.
+	fn gap(&mut self, f: &mut Formatter<'_>) -> FmtResult {
+		if self.needs_gap {
+			f.write_char('\n')?;
+			self.needs_gap = false;
+		}
+		Ok(())
+	}
+
+	/// Write one line of a fenced code block.
+	fn write_code_line(&mut self, f: &mut Formatter<'_>, line: &str) -> FmtResult {
+		let line = line.replace('\t', "    ");
+		let width = self.md.width.saturating_sub(2);
+		let code = &self.md.theme.code;
+		write!(f, "  {code}")?;
+		write_text(f, self.md.profile.width.truncate(&line, width))?;
+		writeln!(f, "{}", code.resetter())?;
+		self.needs_gap = true;
+		self.in_list = false;
+		Ok(())
+	}
+
+	/// Write the block that was collected, if any.
+	fn finish_block(&mut self, f: &mut Formatter<'_>) -> FmtResult {
+		let Some(block) = self.block.take() else {
+			return Ok(())
+		};
+
+		let mut base = Style::new();
+		let (first_prefix, indent) = match block {
+			Block::Paragraph => (0, 0),
+			Block::Heading(1) => {
+				base.apply(&self.md.theme.title);
+				(0, 0)
+			}
+			Block::Heading(..) => {
+				base.apply(&self.md.theme.heading);
+				(0, 0)
+			}
+			Block::Item(indent, marker) => (indent, indent + marker.len() + 1),
+		};
+		// Keep the items of a list together.
+		let is_item = matches!(block, Block::Item(..));
+		if !(is_item && self.in_list) {
+			self.gap(f)?;
+		}
+
+		self.styled.clear();
+		Inline::new(self.md, &mut self.styled, base).render(&self.text)?;
+		self.text.clear();
+
+		let width = self.md.width.saturating_sub(indent).max(1);
+		let mut style = Style::new();
+		let mut link: Option<&str> = None;
+		for (i, line) in self.md.profile.width.wrap(&self.styled, width).enumerate() {
+			if i == 0 {
+				write_spaces(f, first_prefix)?;
+				if let Block::Item(.., marker) = block {
+					let bullet = &self.md.theme.bullet;
+					let marker = if marker.len() == 1 { "•" } else { marker };
+					write!(f, "{bullet}{marker}{} ", bullet.resetter())?;
+				}
+			} else {
+				write_spaces(f, indent)?;
+			}
+			if let Some(open) = link {
+				f.write_str(open)?;
+			}
+
+			let styled = StyledLines::with_style(line.as_bytes(), style).next();
+			if let Some(styled) = styled {
+				styled.fmt(f)?;
+				style = styled.end_style;
+			}
+			link = last_link(line, link);
+			if link.is_some() {
+				self.write_link_end(f)?;
+			}
+			f.write_char('\n')?;
+		}
+		self.needs_gap = true;
+		self.in_list = is_item;
+		Ok(())
+	}
+
+	fn write_link_end(&self, f: &mut dyn Write) -> FmtResult {
+		write!(f, "{ESC_STR}]8;;{}", self.md.profile.terminator)
+	}
+}
+
+/// Return the OSC 8 sequence that starts the hyperlink in effect at the end of
+/// `line`, given the one in effect at its start.
+fn last_link<'s>(line: &'s str, mut link: Option<&'s str>) -> Option<&'s str> {
+	let bytes = line.as_bytes();
+	let mut idx = 0;
+	while let Ok((token, len)) = Token::parse(&bytes[idx..]) {
+		if let Token::Escape(seq) = token {
+			if let Some(params) = seq.strip_prefix(b"\x1b]8;") {
+				// The target of the link is empty in the sequence that ends it.
+				let uri = params.splitn(2, move |&b| b == b';').nth(1);
+				let is_end = uri.is_none_or(move |uri| {
+					matches!(uri.first(), None | Some(0x07 | 0x1b))
+				});
+				link = (!is_end).then(|| &line[idx..idx + len]);
+			}
+		}
+		idx += len;
+	}
+	link
+}
+
+#[inline(always)]
+fn write_spaces(f: &mut dyn Write, n: usize) -> FmtResult {
+	(0..n).try_for_each(move |_| f.write_char(' '))
+}
+
+/// Write `s`, dropping control characters.
+#[inline(always)]
+fn write_text(f: &mut dyn Write, s: &str) -> FmtResult {
+	s.split(char::is_control).try_for_each(move |part| f.write_str(part))
+}
+
+/// Renderer of the inline markup of a block.
+struct Inline<'m, 'a, 'w> {
+	md: &'m Markdown<'a>,
+	out: &'w mut String,
+	base: Style,
+	style: Style,
+	strong: bool,
+	emphasis: bool,
+	code: bool,
+	link: bool,
+}
+
+impl<'m, 'a, 'w> Inline<'m, 'a, 'w> {
+	fn new(md: &'m Markdown<'a>, out: &'w mut String, base: Style) -> Self {
+		Self {
+			md,
+			out,
+			base,
+			style: Style::new(),
+			strong: false,
+			emphasis: false,
+			code: false,
+			link: false,
+		}
+	}
+
+	/// Change the style of the output to the one for the current markup.
+	fn update_style(&mut self) -> FmtResult {
+		let theme = &self.md.theme;
+		let mut style = self.base;
+		let changes = [
+			(self.link, &theme.link),
+			(self.emphasis, &theme.emphasis),
+			(self.strong, &theme.strong),
+			(self.code, &theme.code),
+		];
+		for (is_on, change) in changes {
+			if is_on {
+				style.apply(change);
+			}
+		}
+		write!(self.out, "{}", self.style.diff_to(&style))?;
+		self.style = style;
+		Ok(())
+	}
+
+	fn render(mut self, s: &str) -> FmtResult {
+		self.update_style()?;
+		self.render_span(s)?;
+		self.strong = false;
+		self.emphasis = false;
+		self.base = Style::new();
+		self.update_style()
+	}
+
+	fn render_span(&mut self, s: &str) -> FmtResult {
+		let bytes = s.as_bytes();
+		let mut start = 0;
+		let mut idx = 0;
+		while idx < bytes.len() {
+			let rest = &s[idx..];
+			let consumed = match bytes[idx] {
+				b'\\' => match rest[1..].chars().next() {
+					Some(c) if c.is_ascii_punctuation() => {
+						write_text(self.out, &s[start..idx])?;
+						start = idx + 1;
+						2
+					}
+					_ => 1,
+				},
+				b'`' => {
+					let ticks = rest.bytes().take_while(move |&b| b == b'`').count();
+					let fence = &rest[..ticks];
+					match rest[ticks..].find(fence) {
+						Some(len) => {
+							write_text(self.out, &s[start..idx])?;
+							let code = &rest[ticks..ticks + len];
+							let code = match code.strip_prefix(' ') {
+								Some(inner) if !inner.trim().is_empty() => {
+									inner.strip_suffix(' ').unwrap_or(inner)
+								}
+								_ => code,
+							};
+							self.code = true;
+							self.update_style()?;
+							write_text(self.out, code)?;
+							self.code = false;
+							self.update_style()?;
+							start = idx + 2 * ticks + len;
+							start - idx
+						}
+						None => ticks,
+					}
+				}
+				delim @ (b'*' | b'_') => {
+					let run = rest.bytes().take_while(move |&b| b == delim).count();
+					let before = s[..idx].chars().next_back();
+					let after = rest[run..].chars().next();
+					let toggled = self.delimiter(delim, run, before, after, &rest[run..]);
+					if toggled > 0 {
+						write_text(self.out, &s[start..idx])?;
+						self.update_style()?;
+						start = idx + toggled;
+						toggled
+					} else {
+						run
+					}
+				}
+				b'[' if !self.link => match link(rest) {
+					Some((text, uri, len)) => {
+						write_text(self.out, &s[start..idx])?;
+						self.write_link(text, uri)?;
+						start = idx + len;
+						len
+					}
+					None => 1,
+				},
+				_ => rest.chars().next().map_or(1, char::len_utf8),
+			};
+			idx += consumed;
+		}
+		write_text(self.out, &s[start..])
+	}
+
+	/// Toggle emphasis for a run of `run` delimiters, returning the number of
+	/// delimiters that were used.
+	fn delimiter(
+		&mut self,
+		delim: u8, run: usize,
+		before: Option<char>, after: Option<char>,
+		rest: &str,
+	) -> usize {
+		let is_space = move |c: Option<char>| c.is_none_or(char::is_whitespace);
+		let is_word = move |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+		// `snake_case` words aren't emphasized.
+		if delim == b'_' && is_word(before) && is_word(after) {
+			return 0
+		}
+		let can_open = !is_space(after);
+		let can_close = !is_space(before);
+
+		let pair = if delim == b'*' { "**" } else { "__" };
+
+		let mut used = 0;
+		let mut toggle = |is_on: &mut bool, len: usize| {
+			if used + len > run {
+				return
+			}
+			let closer_exists = rest.contains(&pair[..len]);
+			if (*is_on && can_close) || (!*is_on && can_open && closer_exists) {
+				*is_on = !*is_on;
+				used += len;
+			}
+		};
+		if can_close && (self.strong || self.emphasis) {
+			// Close the innermost emphasis first.
+			toggle(&mut self.emphasis, 1);
+			toggle(&mut self.strong, 2);
+		} else {
+			toggle(&mut self.strong, 2);
+			toggle(&mut self.emphasis, 1);
+		}
+		used
+	}
+
+	fn write_link(&mut self, text: &str, uri: &str) -> FmtResult {
+		let terminator = self.md.profile.terminator;
+		if self.md.hyperlinks {
+			write!(self.out, "{ESC_STR}]8;;")?;
+			write_text(self.out, uri)?;
+			write!(self.out, "{terminator}")?;
+		}
+		self.link = true;
+		self.update_style()?;
+		self.render_span(text)?;
+		self.link = false;
+		self.update_style()?;
+		if self.md.hyperlinks {
+			write!(self.out, "{ESC_STR}]8;;{terminator}")
+		} else {
+			self.out.push_str(" (");
+			write_text(self.out, uri)?;
+			self.out.push(')');
+			Ok(())
+		}
+	}
+}
+
+/// Return the text and target of a link at the start of `s`, along with the
+/// length of the link.
+fn link(s: &str) -> Option<(&str, &str, usize)> {
+	let mut depth = 0usize;
+	let text_end = s.bytes().enumerate().skip(1).find_map(|(i, b)| match b {
+		b'[' => {
+			depth += 1;
+			None
+		}
+		b']' if depth == 0 => Some(i),
+		b']' => {
+			depth -= 1;
+			None
+		}
+		_ => None,
+	})?;
+	let rest = s[text_end + 1..].strip_prefix('(')?;
+	let uri_len = rest.find(')')?;
+	let uri = rest[..uri_len].trim();
+	if uri.is_empty() || uri.contains(char::is_whitespace) {
+		return None
+	}
+	Some((&s[1..text_end], uri, text_end + 2 + uri_len + 1))
+}
@@ -0,0 +1,431 @@
+use crate::{
+	base64::Base64Encoder,
+	capabilities::{
+		Capabilities, Feature
+	},
+	fe_seq::{
+		OscWriter, PayloadLimit, PayloadOverflow, Terminator, ESC_STR
+	},
+	parse::{
+		parse_string, FromSequence, ParseError, BEL
+	},
+	profile::{
+		NotifyStyle, Profile
+	},
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Write `s`, skipping control characters so that they can't end an OSC
+/// sequence early.
+fn write_osc_text(f: &mut Formatter<'_>, s: &str) -> FmtResult {
+	s.split(char::is_control).try_for_each(move |part| f.write_str(part))
+}
+
+/// Return the [`Terminator`] that `sequence`, which was parsed by
+/// [`parse_string`], ends with.
+fn terminator_of(sequence: &[u8]) -> Terminator {
+	match sequence.last() {
+		Some(&BEL) => Terminator::Bel,
+		_ => Terminator::St,
+	}
+}
+
+/// Desktop notification.
+/// 
+/// Terminals that support it show a notification outside of the terminal
+/// window, which is useful to tell the user that a long-running job is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Notify<'a> {
+	/// Title of the notification.
+	pub title: Option<&'a str>,
+	/// Text of the notification.
+	pub body: &'a str,
+	/// Form of the sequence.
+	pub style: NotifyStyle,
+	/// Terminator of the sequence.
+	pub terminator: Terminator,
+}
+
+impl<'a> Notify<'a> {
+	/// Create a notification with the specified title and body, which uses the
+	/// default [`Profile`].
+	#[inline(always)]
+	pub const fn new(title: Option<&'a str>, body: &'a str) -> Self {
+		Self {
+			title,
+			body,
+			style: NotifyStyle::Osc9,
+			terminator: Terminator::St,
+		}
+	}
+
+	/// Return `self` with the sequence written as appropriate for `profile`.
+	#[inline(always)]
+	pub const fn with_profile(mut self, profile: &Profile) -> Self {
+		self.style = profile.notify;
+		self.terminator = profile.terminator;
+		self
+	}
+}
+
+impl<'a> Display for Notify<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_str(ESC_STR)?;
+		match self.style {
+			NotifyStyle::Osc9 => {
+				// This form has no title, so it's prepended to the body.
+				f.write_str("]9;")?;
+				if let Some(title) = self.title {
+					write_osc_text(f, title)?;
+					f.write_str(": ")?;
+				}
+			}
+			NotifyStyle::Osc777 => {
+				f.write_str("]777;notify;")?;
+				// The title ends at the first `;`.
+				for part in self.title.unwrap_or_default().split(';') {
+					write_osc_text(f, part)?;
+				}
+				f.write_str(";")?;
+			}
+		}
+		write_osc_text(f, self.body)?;
+		self.terminator.fmt(f)
+	}
+}
+
+impl<'a> FromSequence<'a> for Notify<'a> {
+	/// Parse a notification in either form.
+	/// 
+	/// The title of an OSC 9 notification is written as part of its body, so
+	/// it's parsed as the body, and an empty title of an OSC 777 notification
+	/// is parsed as no title.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b']')?;
+		let payload = core::str::from_utf8(payload).map_err(|_| ParseError::Invalid)?;
+		let (title, body, style) = if let Some(body) = payload.strip_prefix("9;") {
+			(None, body, NotifyStyle::Osc9)
+		} else if let Some(rest) = payload.strip_prefix("777;notify;") {
+			let (title, body) = rest.split_once(';').ok_or(ParseError::Invalid)?;
+			(Some(title).filter(|title| !title.is_empty()), body, NotifyStyle::Osc777)
+		} else {
+			return Err(ParseError::Invalid)
+		};
+		let terminator = terminator_of(&bytes[..len]);
+		Ok((Self { title, body, style, terminator }, len))
+	}
+}
+
+/// Report of the current working directory to the terminal, or **OSC 7**.
+/// 
+/// Terminals use this to open new tabs and windows in the same directory. The
+/// directory is written as a `file://` URL, with the path percent-encoded, and
+/// Windows paths such as `C:\Users` are written as `file:///C:/Users`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkingDirectory<'a> {
+	/// Name of the host that the directory is on.
+	/// 
+	/// Terminals may ignore the report if this doesn't match their own host
+	/// name.
+	pub host: &'a str,
+	/// Absolute path of the directory.
+	pub path: &'a [u8],
+	/// Terminator of the sequence.
+	pub terminator: Terminator,
+}
+
+impl<'a> WorkingDirectory<'a> {
+	/// Create a report of the directory at `path` on `host`.
+	#[inline(always)]
+	pub const fn new(host: &'a str, path: &'a str) -> Self {
+		Self {
+			host,
+			path: path.as_bytes(),
+			terminator: Terminator::St,
+		}
+	}
+
+	/// Return `self` with the sequence written as appropriate for `profile`.
+	#[inline(always)]
+	pub const fn with_profile(mut self, profile: &Profile) -> Self {
+		self.terminator = profile.terminator;
+		self
+	}
+
+	/// Write a report of the current working directory of the process to
+	/// `w`.
+	#[cfg(feature = "std")]
+	pub fn write_current(
+		w: &mut impl std::io::Write, host: &str, profile: &Profile,
+	) -> std::io::Result<()> {
+		let dir = std::env::current_dir()?;
+		#[cfg(unix)]
+		let path = std::os::unix::ffi::OsStrExt::as_bytes(dir.as_os_str());
+		#[cfg(not(unix))]
+		let path = dir.to_string_lossy();
+		#[cfg(not(unix))]
+		let path = path.as_bytes();
+
+		let report = WorkingDirectory {
+			host,
+			path,
+			terminator: profile.terminator,
+		};
+		write!(w, "{report}")?;
+		w.flush()
+	}
+}
+
+/// Write a `file://` URL for `path` on `host`, with the path
+/// percent-encoded.
+/// 
+/// Paths that start with a drive letter, and every path on Windows, have
+/// their backslashes written as slashes, so that `C:\Users` becomes
+/// `file:///C:/Users`.
+fn write_file_url(f: &mut Formatter<'_>, host: &str, path: &[u8]) -> FmtResult {
+	const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+	let has_drive = matches!(path, [letter, b':', ..] if letter.is_ascii_alphabetic());
+	let is_windows = cfg!(windows) || has_drive;
+
+	f.write_str("file://")?;
+	write_osc_text(f, host)?;
+	let is_rooted = path.starts_with(b"/") || is_windows && path.starts_with(b"\\");
+	if !is_rooted {
+		f.write_str("/")?;
+	}
+	for (i, &byte) in path.iter().enumerate() {
+		if is_windows && byte == b'\\' {
+			f.write_char('/')?;
+		} else if has_drive && i == 1 {
+			f.write_char(':')?;
+		} else if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+			f.write_char(byte as char)?;
+		} else {
+			f.write_char('%')?;
+			f.write_char(HEX[(byte >> 4) as usize] as char)?;
+			f.write_char(HEX[(byte & 0xf) as usize] as char)?;
+		}
+	}
+	Ok(())
+}
+
+impl<'a> Display for WorkingDirectory<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_str(ESC_STR)?;
+		f.write_str("]7;")?;
+		write_file_url(f, self.host, self.path)?;
+		self.terminator.fmt(f)
+	}
+}
+
+impl<'a> FromSequence<'a> for WorkingDirectory<'a> {
+	/// Parse a report of a `file://` URL.
+	/// 
+	/// The path is borrowed from `bytes` as it's written in the URL, so a
+	/// path with percent-encoded bytes is invalid, and a Windows path is
+	/// parsed with slashes, such as `/C:/Users`.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b']')?;
+		let url = payload.strip_prefix(b"7;").ok_or(ParseError::Invalid)?;
+		let (host, path) = parse_file_url(url)?;
+		let terminator = terminator_of(&bytes[..len]);
+		Ok((Self { host, path, terminator }, len))
+	}
+}
+
+/// Split a `file://` URL written by [`write_file_url`] into its host and its
+/// path, which must not have percent-encoded bytes.
+fn parse_file_url(url: &[u8]) -> Result<(&str, &[u8]), ParseError> {
+	let url = url.strip_prefix(b"file://").ok_or(ParseError::Invalid)?;
+	let host_len = url.iter().position(|&b| b == b'/');
+	let (host, path) = url.split_at(host_len.ok_or(ParseError::Invalid)?);
+	if path.contains(&b'%') {
+		return Err(ParseError::Invalid)
+	}
+	let host = core::str::from_utf8(host).map_err(|_| ParseError::Invalid)?;
+	Ok((host, path))
+}
+
+/// Path to a file that is displayed as a hyperlink to it, using **OSC 8**.
+/// 
+/// Terminals that support hyperlinks open the file when the path is clicked.
+/// The target is written as a `file://` URL, with the path percent-encoded.
+/// If hyperlinks are disabled, then only the path is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkedPath<'a> {
+	/// Name of the host that the file is on.
+	pub host: &'a str,
+	/// Absolute path of the file.
+	pub path: &'a [u8],
+	/// Whether to write the hyperlink.
+	pub enabled: bool,
+	/// Terminator of the sequences.
+	pub terminator: Terminator,
+}
+
+impl<'a> LinkedPath<'a> {
+	/// Create a hyperlink to the file at `path` on `host`.
+	#[inline(always)]
+	pub const fn new(host: &'a str, path: &'a str) -> Self {
+		Self {
+			host,
+			path: path.as_bytes(),
+			enabled: true,
+			terminator: Terminator::St,
+		}
+	}
+
+	/// Return `self` with the sequences written as appropriate for `profile`.
+	#[inline(always)]
+	pub const fn with_profile(mut self, profile: &Profile) -> Self {
+		self.terminator = profile.terminator;
+		self
+	}
+
+	/// Return `self` with the hyperlink enabled or disabled.
+	#[inline(always)]
+	pub const fn with_enabled(mut self, enabled: bool) -> Self {
+		self.enabled = enabled;
+		self
+	}
+
+	/// Return `self` with the hyperlink enabled only if `capabilities` says
+	/// that the terminal supports [`Feature::Hyperlinks`].
+	#[inline(always)]
+	pub fn with_capabilities(self, capabilities: &mut Capabilities) -> Self {
+		self.with_enabled(capabilities.supports(Feature::Hyperlinks))
+	}
+}
+
+impl<'a> Display for LinkedPath<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		if self.enabled {
+			f.write_str(ESC_STR)?;
+			f.write_str("]8;;")?;
+			write_file_url(f, self.host, self.path)?;
+			self.terminator.fmt(f)?;
+		}
+		for chunk in self.path.utf8_chunks() {
+			write_osc_text(f, chunk.valid())?;
+			if !chunk.invalid().is_empty() {
+				f.write_char(char::REPLACEMENT_CHARACTER)?;
+			}
+		}
+		if self.enabled {
+			f.write_str(ESC_STR)?;
+			f.write_str("]8;;")?;
+			self.terminator.fmt(f)?;
+		}
+		Ok(())
+	}
+}
+
+impl<'a> FromSequence<'a> for LinkedPath<'a> {
+	/// Parse a hyperlink to a `file://` URL, along with the text that it's
+	/// displayed as and the sequence that ends it.
+	/// 
+	/// The path is parsed from the URL like that of [`WorkingDirectory`], and
+	/// the displayed text is skipped. A disabled [`LinkedPath`] is displayed
+	/// as plain text, so it isn't parsed.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b']')?;
+		let (host, path) = parse_file_url(link_uri(payload)?)?;
+		let terminator = terminator_of(&bytes[..len]);
+
+		let text_len = bytes[len..].iter()
+			.position(|&b| b == 0x1b)
+			.ok_or(ParseError::Incomplete)?;
+		let end = len + text_len;
+		let (payload, end_len) = parse_string(&bytes[end..], b']')?;
+		if !link_uri(payload)?.is_empty() {
+			return Err(ParseError::Invalid)
+		}
+		let link = Self {
+			host,
+			path,
+			enabled: true,
+			terminator,
+		};
+		Ok((link, end + end_len))
+	}
+}
+
+/// Return the URI of the payload of an OSC 8 sequence, after its parameters.
+fn link_uri(payload: &[u8]) -> Result<&[u8], ParseError> {
+	let rest = payload.strip_prefix(b"8;").ok_or(ParseError::Invalid)?;
+	let params_len = rest.iter().position(|&b| b == b';');
+	Ok(&rest[params_len.ok_or(ParseError::Invalid)? + 1..])
+}
+
+/// Writer of data to copy to the clipboard with an OSC 52 sequence.
+/// 
+/// Data is base64-encoded as it is written, so it doesn't have to be buffered.
+/// Terminals and multiplexers limit how much data they accept, which
+/// [`Self::with_limit`] can enforce. The sequence is finished on drop if
+/// [`Self::finish`] wasn't called, ignoring errors.
+#[derive(Debug)]
+pub struct ClipboardWriter<'w, W: Write + ?Sized> {
+	osc: OscWriter<'w, W>,
+	encoder: Base64Encoder,
+	limit: PayloadLimit,
+}
+
+impl<'w, W: Write + ?Sized> ClipboardWriter<'w, W> {
+	/// Start writing data to copy to the selections named by `selection`, such
+	/// as `"c"` for the clipboard, using the default [`Profile`].
+	#[inline(always)]
+	pub fn new(inner: &'w mut W, selection: &str) -> Result<Self, core::fmt::Error> {
+		Self::with_profile(inner, selection, &Profile::new())
+	}
+
+	/// Start writing data to copy to the selections named by `selection`,
+	/// terminating the sequence as appropriate for `profile`.
+	pub fn with_profile(
+		inner: &'w mut W, selection: &str, profile: &Profile,
+	) -> Result<Self, core::fmt::Error> {
+		let mut osc = OscWriter::with_terminator(inner, profile.terminator)?;
+		write!(osc, "52;{selection};")?;
+		Ok(Self {
+			osc,
+			encoder: Base64Encoder::new(),
+			limit: PayloadLimit::NONE,
+		})
+	}
+
+	/// Return `self` with at most `limit` bytes of encoded data written,
+	/// handling the rest as specified by `overflow`.
+	/// 
+	/// The limit is applied to the data before it's encoded, so that the
+	/// encoded data stays valid: at most
+	/// [`Base64Encoder::max_input_len`] of `limit` bytes of data are written.
+	#[inline(always)]
+	pub const fn with_limit(mut self, limit: usize, overflow: PayloadOverflow) -> Self {
+		let limit = Base64Encoder::max_input_len(limit);
+		self.limit = PayloadLimit::new(limit, overflow);
+		self
+	}
+
+	/// Write `bytes` of the data.
+	pub fn write_bytes(&mut self, bytes: &[u8]) -> FmtResult {
+		self.limit.check(bytes.len())?;
+		let bytes = self.limit.take_bytes(bytes);
+		self.encoder.encode(bytes, &mut self.osc)
+	}
+
+	/// Write the rest of the data and the terminator of the sequence.
+	pub fn finish(mut self) -> FmtResult {
+		self.encoder.finish(&mut self.osc)?;
+		self.osc.terminate()
+	}
+}
+
+impl<W: Write + ?Sized> Drop for ClipboardWriter<'_, W> {
+	#[inline(always)]
+	fn drop(&mut self) {
+		let _ = self.encoder.finish(&mut self.osc);
+	}
+}
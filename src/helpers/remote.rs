@@ -0,0 +1,469 @@
+use super::{
+	render::is_same_cell, Buffer, LineSize, LinkId, Protection, SgrState,
+	Style,
+};
+use crate::{
+	csi::Csi,
+	parse::ParseError,
+};
+
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+use core::fmt::Write;
+
+/// Operations in a frame, each of which is a byte followed by its operands.
+/// 
+/// Numbers are little-endian. The cursor, style, hyperlink and protection
+/// start at nothing, the default style, no hyperlink and
+/// [`Protection::Unprotected`] in each frame.
+mod op {
+	/// Size of the buffer, as a `u16` width and height.
+	pub const SIZE: u8 = 1;
+	/// Size of the characters on a row, as a `u16` row and a `u8` size.
+	pub const LINE_SIZE: u8 = 2;
+	/// Move the cursor to a `u16` column and row.
+	pub const MOVE: u8 = 3;
+	/// Change the style, as a `u8` length followed by an SGR sequence that
+	/// changes the current style to the new one.
+	pub const STYLE: u8 = 4;
+	/// Change the hyperlink, as a `u16` length followed by the target, which
+	/// is empty for no hyperlink.
+	pub const LINK: u8 = 5;
+	/// Change the protection, as a `u8` that is `1` for protected cells.
+	pub const PROTECTION: u8 = 6;
+	/// Set the cell at the cursor and move the cursor to the next column, as a
+	/// `u8` length followed by the symbol, which is empty for a continuation.
+	pub const CELL: u8 = 7;
+}
+
+/// Encoder of the changes between [`Buffer`]s into frames of a compact binary
+/// stream, which a [`FrameDecoder`] replays into its own buffer on the other
+/// side of a connection.
+/// 
+/// Each frame is a `u32` little-endian length, followed by that many bytes
+/// of operations. Like a [`Renderer`](super::Renderer), the encoder only
+/// includes the cells that changed since the last frame, and the cells marked
+/// with [`Buffer::mark_dirty`].
+#[derive(Debug, Clone, Default)]
+pub struct FrameEncoder {
+	previous: Option<Buffer>,
+	scratch: String,
+}
+
+impl FrameEncoder {
+	/// Create an encoder that includes every cell in the first frame.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			previous: None,
+			scratch: String::new(),
+		}
+	}
+
+	/// Make the next frame include every cell.
+	#[inline(always)]
+	pub fn invalidate(&mut self) {
+		self.previous = None;
+	}
+
+	/// Append a frame with the changes in `buffer` to `out`.
+	/// 
+	/// Cells marked with [`Buffer::mark_dirty`] are included until
+	/// [`Buffer::clear_dirty`] is called.
+	pub fn encode(&mut self, buffer: &Buffer, out: &mut Vec<u8>) {
+		let start = out.len();
+		out.extend_from_slice(&[0; 4]);
+		self.encode_ops(buffer, out);
+		let len = (out.len() - start - 4) as u32;
+		out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+	}
+
+	fn encode_ops(&mut self, buffer: &Buffer, out: &mut Vec<u8>) {
+		let previous = self.previous.as_ref()
+			.filter(move |previous| {
+				(previous.width(), previous.height())
+					== (buffer.width(), buffer.height())
+			});
+
+		out.push(op::SIZE);
+		out.extend_from_slice(&buffer.width().to_le_bytes());
+		out.extend_from_slice(&buffer.height().to_le_bytes());
+
+		let mut cursor = None;
+		let mut style = Style::new();
+		let mut link = None;
+		let mut protection = Protection::Unprotected;
+		for y in 0..buffer.height() {
+			let row = buffer.row(y).unwrap_or_default();
+			let line_size = buffer.line_size(y);
+			let old_row = previous
+				.filter(move |previous| previous.line_size(y) == line_size)
+				.and_then(move |previous| Some((previous, previous.row(y)?)));
+			if old_row.is_none() {
+				out.push(op::LINE_SIZE);
+				out.extend_from_slice(&y.to_le_bytes());
+				out.push(line_size_code(line_size));
+			}
+			for (x, cell) in row.iter().enumerate() {
+				let idx = y as usize * buffer.width() as usize + x;
+				let is_changed = old_row.is_none_or(move |(previous, old_row)| {
+					!is_same_cell(buffer, cell, previous, &old_row[x])
+				});
+				if !buffer.dirty(idx) && !is_changed {
+					continue
+				}
+
+				let x = x as u16;
+				if cursor != Some((x, y)) {
+					out.push(op::MOVE);
+					out.extend_from_slice(&x.to_le_bytes());
+					out.extend_from_slice(&y.to_le_bytes());
+				}
+				if cell.style != style {
+					self.scratch.clear();
+					let diff = style.diff_to(&cell.style);
+					let _ = write!(self.scratch, "{diff}");
+					out.push(op::STYLE);
+					out.push(self.scratch.len() as u8);
+					out.extend_from_slice(self.scratch.as_bytes());
+					style = cell.style;
+				}
+				if cell.link != link {
+					let uri = cell.link.and_then(move |id| buffer.link_uri(id))
+						.unwrap_or_default();
+					let len = uri.len().min(u16::MAX as usize);
+					let uri = &uri.as_bytes()[..len];
+					out.push(op::LINK);
+					out.extend_from_slice(&(uri.len() as u16).to_le_bytes());
+					out.extend_from_slice(uri);
+					link = cell.link;
+				}
+				if cell.protection != protection {
+					out.push(op::PROTECTION);
+					out.push((cell.protection == Protection::Protected) as u8);
+					protection = cell.protection;
+				}
+				out.push(op::CELL);
+				out.push(cell.symbol().len() as u8);
+				out.extend_from_slice(cell.symbol().as_bytes());
+				cursor = Some((x + 1, y));
+			}
+		}
+
+		match &mut self.previous {
+			Some(previous) => previous.clone_from(buffer),
+			None => self.previous = Some(buffer.clone()),
+		}
+	}
+}
+
+/// Decoder of the frames written by a [`FrameEncoder`], which replays them
+/// into a [`Buffer`].
+/// 
+/// The buffer can be drawn to the terminal with a
+/// [`Renderer`](super::Renderer) after each frame, which only draws the cells
+/// that the frame changed.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+	buffer: Buffer,
+}
+
+impl Default for FrameDecoder {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl FrameDecoder {
+	/// Maximum number of cells that a frame can resize the buffer to.
+	pub const MAX_CELLS: usize = 1 << 20;
+	/// Maximum number of different hyperlink targets that frames can store in
+	/// the buffer.
+	pub const MAX_LINKS: usize = 1024;
+
+	/// Create a decoder with an empty buffer, which is resized by the first
+	/// frame.
+	#[inline(always)]
+	pub fn new() -> Self {
+		Self {
+			buffer: Buffer::new(0, 0),
+		}
+	}
+
+	/// Return the buffer that frames are replayed into.
+	#[inline(always)]
+	pub const fn buffer(&self) -> &Buffer {
+		&self.buffer
+	}
+
+	/// Return a mutable reference to the buffer that frames are replayed
+	/// into, such as for drawing it.
+	#[inline(always)]
+	pub fn buffer_mut(&mut self) -> &mut Buffer {
+		&mut self.buffer
+	}
+
+	/// Replay the frame at the start of `bytes`, returning the number of bytes
+	/// that it occupies.
+	/// 
+	/// Returns [`ParseError::Incomplete`] if the frame isn't complete yet, in
+	/// which case nothing is replayed. If the frame is invalid, the operations
+	/// before the invalid one are replayed. Frames that resize the buffer to
+	/// an empty size or to more than [`Self::MAX_CELLS`] cells, or that store
+	/// more than [`Self::MAX_LINKS`] hyperlink targets, are invalid.
+	pub fn decode(&mut self, bytes: &[u8]) -> Result<usize, ParseError> {
+		let header = bytes.first_chunk::<4>().ok_or(ParseError::Incomplete)?;
+		let len = u32::from_le_bytes(*header) as usize;
+		let frame = bytes.get(4..4 + len).ok_or(ParseError::Incomplete)?;
+		self.replay(&mut Reader(frame)).ok_or(ParseError::Invalid)?;
+		Ok(4 + len)
+	}
+
+	fn replay(&mut self, r: &mut Reader<'_>) -> Option<()> {
+		let mut cursor = None;
+		let mut style = SgrState::new();
+		let mut link: Option<LinkId> = None;
+		let mut protection = Protection::Unprotected;
+		while let Some(code) = r.u8() {
+			match code {
+				op::SIZE => {
+					let (width, height) = (r.u16()?, r.u16()?);
+					let cells = width as usize * height as usize;
+					if cells == 0 || cells > Self::MAX_CELLS {
+						return None
+					}
+					let size = (self.buffer.width(), self.buffer.height());
+					if (width, height) != size {
+						self.buffer.resize(width, height);
+					}
+				}
+				op::LINE_SIZE => {
+					let y = r.u16()?;
+					self.buffer.set_line_size(y, line_size_from_code(r.u8()?)?);
+				}
+				op::MOVE => cursor = Some((r.u16()?, r.u16()?)),
+				op::STYLE => {
+					let len = r.u8()?;
+					let (csi, ..) = Csi::parse(r.bytes(len as usize)?).ok()?;
+					style.apply_csi(&csi).then_some(())?;
+				}
+				op::LINK => {
+					let len = r.u16()?;
+					let uri = r.bytes(len as usize)?;
+					let uri = core::str::from_utf8(uri).ok()?;
+					link = match uri {
+						"" => None,
+						uri if self.buffer.link_count() < Self::MAX_LINKS => {
+							self.buffer.add_link(uri)
+						}
+						uri => Some(self.buffer.find_link(uri)?),
+					};
+				}
+				op::PROTECTION => {
+					protection = match r.u8()? {
+						0 => Protection::Unprotected,
+						1 => Protection::Protected,
+						_ => return None,
+					};
+				}
+				op::CELL => {
+					let len = r.u8()?;
+					let symbol = r.bytes(len as usize)?;
+					let symbol = core::str::from_utf8(symbol).ok()?;
+					let (x, y) = cursor?;
+					let cell = self.buffer.get_mut(x, y)?;
+					if symbol.is_empty() {
+						cell.set_continuation(style.current_style());
+					} else {
+						cell.set_symbol(symbol);
+						cell.style = style.current_style();
+						cell.link = link;
+						cell.protection = protection;
+					}
+					cursor = Some((x + 1, y));
+				}
+				_ => return None,
+			}
+		}
+		Some(())
+	}
+}
+
+/// Reader of the operands of operations in a frame.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+	fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+		let (bytes, rest) = self.0.split_at_checked(len)?;
+		self.0 = rest;
+		Some(bytes)
+	}
+
+	fn u8(&mut self) -> Option<u8> {
+		self.bytes(1).map(move |bytes| bytes[0])
+	}
+
+	fn u16(&mut self) -> Option<u16> {
+		self.bytes(2).map(move |bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+	}
+}
+
+/// Return the code of `size` in frames.
+const fn line_size_code(size: LineSize) -> u8 {
+	match size {
+		LineSize::SingleWidth => 0,
+		LineSize::DoubleWidth => 1,
+		LineSize::DoubleHeightTop => 2,
+		LineSize::DoubleHeightBottom => 3,
+	}
+}
+
+/// Return the [`LineSize`] with the code `code` in frames.
+const fn line_size_from_code(code: u8) -> Option<LineSize> {
+	match code {
+		0 => Some(LineSize::SingleWidth),
+		1 => Some(LineSize::DoubleWidth),
+		2 => Some(LineSize::DoubleHeightTop),
+		3 => Some(LineSize::DoubleHeightBottom),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::helpers::{
+		Color, Weight,
+	};
+
+	/// Assert that `a` and `b` have the same size and the same cells.
+	#[track_caller]
+	fn assert_same(a: &Buffer, b: &Buffer) {
+		assert_eq!((a.width(), a.height()), (b.width(), b.height()));
+		for y in 0..a.height() {
+			assert_eq!(a.line_size(y), b.line_size(y), "row {y}");
+			for x in 0..a.width() {
+				let cell = a.get(x, y).unwrap();
+				let other = b.get(x, y).unwrap();
+				assert_eq!(
+					(cell.symbol(), cell.style, cell.protection),
+					(other.symbol(), other.style, other.protection),
+					"cell at {x}, {y}",
+				);
+				assert_eq!(
+					cell.link.and_then(|id| a.link_uri(id)),
+					other.link.and_then(|id| b.link_uri(id)),
+					"link of the cell at {x}, {y}",
+				);
+			}
+		}
+	}
+
+	/// Return a frame with the operations in `ops`.
+	fn frame(ops: &[u8]) -> Vec<u8> {
+		let mut frame = (ops.len() as u32).to_le_bytes().to_vec();
+		frame.extend_from_slice(ops);
+		frame
+	}
+
+	#[test]
+	fn replays_changes() {
+		let mut buffer = Buffer::new(20, 4);
+		let bold = Style::new()
+			.with_weight(Weight::Bold)
+			.with_foreground(Color::Rgb(1, 2, 3));
+		buffer.set_str(0, 0, "title", bold);
+		buffer.set_str(2, 1, "wide 漢字", Style::new());
+		let link = buffer.add_link("https://example.com");
+		buffer.set_link(2, 1, 4, link);
+		buffer.set_protection(0, 2, 3, Protection::Protected);
+		buffer.set_line_size(3, LineSize::DoubleWidth);
+
+		let mut encoder = FrameEncoder::new();
+		let mut decoder = FrameDecoder::new();
+		let mut first = Vec::new();
+		encoder.encode(&buffer, &mut first);
+		assert_eq!(decoder.decode(&first), Ok(first.len()));
+		assert_same(decoder.buffer(), &buffer);
+
+		// Only the changed cells are in the next frame.
+		buffer.set_str(1, 0, "I", bold.with_background(Color::Table(4)));
+		let mut second = Vec::new();
+		encoder.encode(&buffer, &mut second);
+		assert!(second.len() < first.len() / 4);
+		assert_eq!(decoder.decode(&second), Ok(second.len()));
+		assert_same(decoder.buffer(), &buffer);
+
+		buffer.resize(10, 2);
+		let mut third = Vec::new();
+		encoder.encode(&buffer, &mut third);
+		assert_eq!(decoder.decode(&third), Ok(third.len()));
+		assert_same(decoder.buffer(), &buffer);
+	}
+
+	#[test]
+	fn waits_for_complete_frames() {
+		let mut buffer = Buffer::new(8, 2);
+		buffer.set_str(0, 0, "partial", Style::new());
+		let mut bytes = Vec::new();
+		FrameEncoder::new().encode(&buffer, &mut bytes);
+
+		let mut decoder = FrameDecoder::new();
+		for len in 0..bytes.len() {
+			let result = decoder.decode(&bytes[..len]);
+			assert_eq!(result, Err(ParseError::Incomplete));
+			assert_eq!(decoder.buffer().width(), 0);
+		}
+		assert_eq!(decoder.decode(&bytes), Ok(bytes.len()));
+		assert_same(decoder.buffer(), &buffer);
+	}
+
+	#[test]
+	fn rejects_invalid_frames() {
+		let mut decoder = FrameDecoder::new();
+		let sizes: [(u16, u16); 3] = [(0, 5), (5, 0), (2048, 1024)];
+		for (width, height) in sizes {
+			let [w0, w1] = width.to_le_bytes();
+			let [h0, h1] = height.to_le_bytes();
+			let bytes = frame(&[op::SIZE, w0, w1, h0, h1]);
+			assert_eq!(decoder.decode(&bytes), Err(ParseError::Invalid));
+			assert_eq!(decoder.buffer().width(), 0);
+		}
+
+		assert_eq!(decoder.decode(&frame(&[0xff])), Err(ParseError::Invalid));
+		// A cell before the cursor is moved.
+		let bytes = frame(&[op::SIZE, 1, 0, 1, 0, op::CELL, 1, b'x']);
+		assert_eq!(decoder.decode(&bytes), Err(ParseError::Invalid));
+		// A cell beyond the buffer.
+		let bytes = frame(&[
+			op::SIZE, 1, 0, 1, 0, op::MOVE, 1, 0, 0, 0, op::CELL, 1, b'x',
+		]);
+		assert_eq!(decoder.decode(&bytes), Err(ParseError::Invalid));
+	}
+
+	#[test]
+	fn limits_hyperlink_targets() {
+		let mut ops = alloc::vec![op::SIZE, 1, 0, 1, 0];
+		for i in 0..FrameDecoder::MAX_LINKS {
+			let uri = alloc::format!("/{i}");
+			ops.push(op::LINK);
+			ops.extend_from_slice(&(uri.len() as u16).to_le_bytes());
+			ops.extend_from_slice(uri.as_bytes());
+		}
+		let mut decoder = FrameDecoder::new();
+		let bytes = frame(&ops);
+		assert_eq!(decoder.decode(&bytes), Ok(bytes.len()));
+
+		// Targets that are already stored can still be used.
+		ops.extend_from_slice(&[op::LINK, 2, 0, b'/', b'0']);
+		let bytes = frame(&ops);
+		assert_eq!(decoder.decode(&bytes), Ok(bytes.len()));
+
+		ops.extend_from_slice(&[op::LINK, 2, 0, b'/', b'x']);
+		let bytes = frame(&ops);
+		assert_eq!(decoder.decode(&bytes), Err(ParseError::Invalid));
+	}
+}
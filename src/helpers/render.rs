@@ -0,0 +1,300 @@
+use super::{
+	Buffer, Cell, LineSize, LinkId, Protection, SelectiveEraseDisplay,
+	Style,
+};
+use crate::{
+	csi::Csi,
+	fe_seq::{
+		Terminator, ESC_STR,
+	},
+	pos::Pos,
+	sgr::Sgr,
+};
+
+use core::fmt::{
+	Result as FmtResult, Write,
+};
+
+/// Statistics about one frame drawn by a [`Renderer`].
+#[cfg(feature = "stats")]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+	/// Number of bytes written.
+	pub bytes: usize,
+	/// Number of cells that were drawn.
+	pub cells: usize,
+	/// Number of escape sequences written, for moving the cursor and changing
+	/// the style.
+	pub sequences: usize,
+	/// Time spent rendering the frame, including writing it.
+	pub duration: std::time::Duration,
+}
+
+/// Writer that counts the bytes written to another writer.
+struct Counter<'a, W: ?Sized> {
+	inner: &'a mut W,
+	bytes: usize,
+}
+
+impl<'a, W: Write + ?Sized> Write for Counter<'a, W> {
+	#[inline(always)]
+	fn write_str(&mut self, s: &str) -> FmtResult {
+		self.bytes += s.len();
+		self.inner.write_str(s)
+	}
+}
+
+/// Renderer that draws [`Buffer`]s to the terminal, only writing the cells that
+/// changed since the last frame.
+/// 
+/// The renderer assumes that the screen isn't changed by anything else between
+/// frames. If it is, mark the cells that were changed with
+/// [`Buffer::mark_dirty`], or call [`Self::invalidate`] to redraw everything.
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+	previous: Option<Buffer>,
+	#[cfg(feature = "stats")]
+	stats: RenderStats,
+}
+
+impl Renderer {
+	/// Create a renderer that draws every cell in the first frame.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			previous: None,
+			#[cfg(feature = "stats")]
+			stats: RenderStats {
+				bytes: 0,
+				cells: 0,
+				sequences: 0,
+				duration: std::time::Duration::ZERO,
+			},
+		}
+	}
+
+	/// Make the next frame draw every cell.
+	#[inline(always)]
+	pub fn invalidate(&mut self) {
+		self.previous = None;
+	}
+
+	/// Return statistics about the last frame.
+	#[cfg(feature = "stats")]
+	#[inline(always)]
+	pub const fn last_stats(&self) -> &RenderStats {
+		&self.stats
+	}
+
+	/// Scroll the rows of the last frame up by `rows`, or down if `rows` is
+	/// negative, so that the next frame only draws the rows that are
+	/// uncovered.
+	/// 
+	/// This writes SU or SD within a scrolling region that covers the rows
+	/// of the last frame, then resets the scrolling region. Nothing is
+	/// written if there's no last frame.
+	pub fn scroll(&mut self, rows: i32, w: &mut impl Write) -> FmtResult {
+		let Some(previous) = &mut self.previous else {
+			return Ok(())
+		};
+		if rows == 0 {
+			return Ok(())
+		}
+		Csi::write_begin(w)?;
+		write!(w, "1;{}r", previous.height())?;
+		Csi::write_begin(w)?;
+		let final_byte = if rows > 0 { 'S' } else { 'T' };
+		write!(w, "{}{final_byte}", rows.unsigned_abs())?;
+		Csi::write_begin(w)?;
+		w.write_char('r')?;
+		previous.scroll(rows);
+		Ok(())
+	}
+
+	/// Erase every unprotected cell of the last frame with
+	/// [`SelectiveEraseDisplay::All`].
+	/// 
+	/// This is cheaper than drawing blank cells over the cells that are no
+	/// longer used, and leaves protected cells, such as borders and titles,
+	/// as they are. Call [`Buffer::erase_unprotected`] on the next frame to
+	/// keep it in sync.
+	pub fn erase_unprotected(&mut self, w: &mut impl Write) -> FmtResult {
+		let Some(previous) = &mut self.previous else {
+			return Ok(())
+		};
+		write!(w, "{}", SelectiveEraseDisplay::All)?;
+		previous.erase_unprotected();
+		Ok(())
+	}
+
+	/// Draw `buffer` to `w`, which should be at the top left of the screen.
+	/// 
+	/// The size of a row is set with [`LineSize`] when it changes, which
+	/// redraws the whole row. Protected cells are written with
+	/// [`Protection::Protected`] in effect. Cells that are part of a hyperlink
+	/// are written in an OSC 8 region with the [`LinkId`] as its `id`, so that
+	/// a link that is partly redrawn is still treated as one link.
+	/// 
+	/// Cells marked with [`Buffer::mark_dirty`] are drawn even if they didn't
+	/// change, until [`Buffer::clear_dirty`] is called.
+	/// 
+	/// The style, protection and hyperlink are reset at the end of the frame,
+	/// and the cursor is left after the last cell that was drawn.
+	pub fn render(&mut self, buffer: &Buffer, w: &mut impl Write) -> FmtResult {
+		#[cfg(feature = "stats")]
+		let start = std::time::Instant::now();
+
+		let mut w = Counter {
+			inner: w,
+			bytes: 0,
+		};
+		let previous = self.previous.as_ref()
+			.filter(move |previous| {
+				(previous.width(), previous.height())
+					== (buffer.width(), buffer.height())
+			});
+
+		let mut cells = 0;
+		let mut sequences = 0;
+		let mut cursor = None;
+		let mut style = Style::new();
+		let mut link = None;
+		let mut protection = Protection::Unprotected;
+		for y in 0..buffer.height() {
+			let row = buffer.row(y).unwrap_or_default();
+			let line_size = buffer.line_size(y);
+			let columns = match line_size.is_double_width() {
+				true => buffer.width() / 2,
+				false => buffer.width(),
+			};
+			let old_row = previous
+				.filter(move |previous| previous.line_size(y) == line_size)
+				.and_then(move |previous| Some((previous, previous.row(y)?)));
+			let is_resized = previous.is_some()
+				|| line_size != LineSize::SingleWidth;
+			if old_row.is_none() && is_resized {
+				Csi::write_begin(&mut w)?;
+				write!(w, "{};1H{line_size}", y + 1)?;
+				cursor = Some(Pos::new(y, 0));
+				sequences += 2;
+			}
+			for (x, cell) in row[..columns as usize].iter().enumerate() {
+				if cell.is_continuation() {
+					continue
+				}
+				let width = 1 + row[x + 1..columns as usize].iter()
+					.take_while(move |cell| cell.is_continuation())
+					.count();
+				let row_start = y as usize * buffer.width() as usize;
+				let is_damaged = (x..x + width)
+					.any(move |x| buffer.dirty(row_start + x));
+				let is_changed = old_row.is_none_or(move |(previous, old_row)| {
+					let new_cells = row[x..x + width].iter();
+					let old_cells = &old_row[x..x + width];
+					new_cells.zip(old_cells).any(move |(new, old)| {
+						!is_same_cell(buffer, new, previous, old)
+					})
+				});
+				if !is_damaged && !is_changed {
+					continue
+				}
+
+				let pos = Pos::new(y, x as u16);
+				if cursor != Some(pos) {
+					let (row, col) = pos.to_one_based();
+					Csi::write_begin(&mut w)?;
+					write!(w, "{row};{col}H")?;
+					sequences += 1;
+				}
+				if cell.style != style {
+					write!(w, "{}", style.diff_to(&cell.style))?;
+					style = cell.style;
+					sequences += 1;
+				}
+				if cell.link != link {
+					let uri = cell.link.and_then(move |id| buffer.link_uri(id));
+					write_link(&mut w, cell.link.zip(uri))?;
+					link = cell.link;
+					sequences += 1;
+				}
+				if cell.protection != protection {
+					write!(w, "{}", cell.protection)?;
+					protection = cell.protection;
+					sequences += 1;
+				}
+				w.write_str(symbol_or_blank(cell))?;
+				cells += 1;
+
+				// The cursor stays on the last column instead of wrapping.
+				let next = pos.col + width as u16;
+				cursor = (next < columns).then_some(Pos::new(y, next));
+			}
+		}
+		if !style.is_default() {
+			write!(w, "{}", Sgr::Reset)?;
+			sequences += 1;
+		}
+		if link.is_some() {
+			write_link(&mut w, None)?;
+			sequences += 1;
+		}
+		if protection != Protection::Unprotected {
+			write!(w, "{}", Protection::Unprotected)?;
+			sequences += 1;
+		}
+
+		match &mut self.previous {
+			Some(previous) => previous.clone_from(buffer),
+			None => self.previous = Some(buffer.clone()),
+		}
+
+		#[cfg(feature = "stats")]
+		{
+			self.stats = RenderStats {
+				bytes: w.bytes,
+				cells,
+				sequences,
+				duration: start.elapsed(),
+			};
+		}
+		#[cfg(not(feature = "stats"))]
+		let _ = (cells, sequences);
+		Ok(())
+	}
+}
+
+/// Return `true` if the cells `a` of `a_buffer` and `b` of `b_buffer` look
+/// the same, including the targets of their hyperlinks.
+pub(super) fn is_same_cell(
+	a_buffer: &Buffer, a: &Cell, b_buffer: &Buffer, b: &Cell,
+) -> bool {
+	a.symbol() == b.symbol()
+		&& a.style == b.style
+		&& a.protection == b.protection
+		&& a.link.and_then(move |id| a_buffer.link_uri(id))
+			== b.link.and_then(move |id| b_buffer.link_uri(id))
+}
+
+/// Write the OSC 8 sequence that starts the hyperlink `link`, or ends the
+/// current one if `link` is [`None`].
+pub(super) fn write_link(
+	w: &mut dyn Write, link: Option<(LinkId, &str)>,
+) -> FmtResult {
+	write!(w, "{ESC_STR}]8;")?;
+	if let Some((id, uri)) = link {
+		write!(w, "id={id};")?;
+		uri.split(char::is_control).try_for_each(|part| w.write_str(part))?;
+	} else {
+		w.write_char(';')?;
+	}
+	write!(w, "{}", Terminator::St)
+}
+
+/// Return the symbol of `cell`, or a space if it's empty.
+#[inline(always)]
+fn symbol_or_blank(cell: &Cell) -> &str {
+	match cell.symbol() {
+		"" => " ",
+		symbol => symbol,
+	}
+}
@@ -0,0 +1,118 @@
+use super::{
+	Color, Invert, Italic, StateChange, Underline, Weight,
+};
+
+/// Purpose of a piece of text, which the helpers that style text look up in
+/// [`StyleRoles`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+	/// Most important headings, such as level 1 headings in Markdown.
+	Title,
+	/// Other headings, such as file headers in diffs.
+	Heading,
+	/// Emphasized text.
+	Emphasis,
+	/// Strongly emphasized text.
+	Strong,
+	/// Code and other literal text.
+	Code,
+	/// Text of hyperlinks.
+	Link,
+	/// Markers that draw the eye, such as list bullets.
+	Accent,
+	/// Informational text, such as hunk headers in diffs.
+	Info,
+	/// Additions and successful outcomes.
+	Success,
+	/// Warnings.
+	Warning,
+	/// Deletions and errors.
+	Error,
+	/// Parts of other text that are pointed out, such as the words that
+	/// changed between two lines.
+	Highlight,
+	/// Selected text and search matches.
+	Selection,
+}
+
+impl Role {
+	/// Number of roles.
+	pub const COUNT: usize = 13;
+}
+
+/// Settings that the helpers apply to text of each [`Role`], so that all of
+/// them can be themed in one place.
+/// 
+/// Helpers that are created from a [`StyleRoles`], such as with
+/// [`DiffTheme::from_roles`](super::DiffTheme::from_roles), use the settings
+/// of the roles of their parts. A pair of them for dark and light color
+/// schemes can be kept in a [`Theme`](super::Theme).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyleRoles {
+	styles: [StateChange; Role::COUNT],
+}
+
+impl Default for StyleRoles {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl StyleRoles {
+	/// Create the default [`StyleRoles`], which only use the 16 table colors.
+	pub const fn new() -> Self {
+		let underline = StateChange::new().with_underline(Underline::Single);
+		let bold = StateChange::new().with_weight(Weight::Bold);
+		Self {
+			styles: [
+				bold.with_underline(Underline::Single),
+				bold,
+				StateChange::new().with_italic(Italic::On),
+				bold,
+				StateChange::new().with_foreground(Color::Table(6)),
+				underline.with_foreground(Color::Table(4)),
+				StateChange::new().with_foreground(Color::Table(3)),
+				StateChange::new().with_foreground(Color::Table(6)),
+				StateChange::new().with_foreground(Color::Table(2)),
+				bold.with_foreground(Color::Table(3)),
+				StateChange::new().with_foreground(Color::Table(1)),
+				bold.with_underline(Underline::Single),
+				StateChange::new().with_invert(Invert::On),
+			],
+		}
+	}
+
+	/// Return the settings of `role`.
+	#[inline(always)]
+	pub const fn get(&self, role: Role) -> StateChange {
+		self.styles[role as usize]
+	}
+
+	/// Set the settings of `role`.
+	#[inline(always)]
+	pub fn set(&mut self, role: Role, change: StateChange) {
+		self.styles[role as usize] = change;
+	}
+
+	/// Return `self` with `change` as the settings of `role`.
+	#[inline(always)]
+	pub const fn with(mut self, role: Role, change: StateChange) -> Self {
+		self.styles[role as usize] = change;
+		self
+	}
+
+	/// Return `overriding` if it's specified, or the settings of `role`
+	/// otherwise.
+	#[inline(always)]
+	pub const fn resolve(
+		&self, role: Role, overriding: Option<StateChange>,
+	) -> StateChange {
+		match overriding {
+			Some(change) => change,
+			None => self.get(role),
+		}
+	}
+}
+
+const _: () = assert!(Role::Selection as usize + 1 == Role::COUNT);
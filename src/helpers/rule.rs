@@ -0,0 +1,133 @@
+use super::{
+	Repeated, Role, Style, StyleRoles,
+};
+use crate::{
+	profile::Profile,
+	width::WidthPolicy,
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult,
+};
+
+/// Horizontal separator that fills a line with a glyph, such as `─`.
+/// 
+/// The glyph is repeated as many times as fit in [`Self::width`] cells, which
+/// takes wide glyphs into account, with REP if the [`Profile`] allows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+	/// Number of cells to fill, or `None` for [`Self::DEFAULT_WIDTH`].
+	/// 
+	/// The width of the terminal can be used with
+	#[cfg_attr(feature = "std", doc = "[`Self::with_terminal_width`].")]
+	#[cfg_attr(not(feature = "std"), doc = "`Self::with_terminal_width`.")]
+	pub width: Option<u16>,
+	/// Character that the rule is made of.
+	pub glyph: char,
+	/// Style of the rule.
+	pub style: Style,
+	/// Rules for the number of cells that the glyph occupies.
+	pub policy: WidthPolicy,
+	/// Whether to repeat the glyph with REP.
+	pub use_rep: bool,
+}
+
+impl Default for Rule {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new('─')
+	}
+}
+
+impl Rule {
+	/// Number of cells that a rule without a width fills.
+	pub const DEFAULT_WIDTH: u16 = 80;
+
+	/// Create a [`Rule`] made of `glyph`, which fills
+	/// [`Self::DEFAULT_WIDTH`] cells in the default style.
+	#[inline(always)]
+	pub const fn new(glyph: char) -> Self {
+		Self {
+			width: None,
+			glyph,
+			style: Style::new(),
+			policy: WidthPolicy::new(),
+			use_rep: false,
+		}
+	}
+
+	/// Create a [`Rule`] made of `glyph` like [`Self::new`], in the settings of
+	/// [`Role::Accent`] in `roles`.
+	pub fn from_roles(glyph: char, roles: &StyleRoles) -> Self {
+		let mut style = Style::new();
+		style.apply(&roles.get(Role::Accent));
+		Self::new(glyph).with_style(style)
+	}
+
+	/// Return `self` filling `width` cells.
+	#[inline(always)]
+	pub const fn with_width(mut self, width: u16) -> Self {
+		self.width = Some(width);
+		self
+	}
+
+	/// Return `self` with the specified style.
+	#[inline(always)]
+	pub const fn with_style(mut self, style: Style) -> Self {
+		self.style = style;
+		self
+	}
+
+	/// Return `self` with the glyph measured and repeated as appropriate for
+	/// `profile`.
+	#[inline(always)]
+	pub const fn with_profile(mut self, profile: &Profile) -> Self {
+		self.policy = profile.width;
+		self.use_rep = profile.repeat;
+		self
+	}
+
+	/// Return `self` filling the width of the terminal, which is found by
+	/// moving the cursor to the last column and querying its position, then
+	/// moving it back.
+	#[cfg(feature = "std")]
+	pub fn with_terminal_width(
+		self, output: &mut impl std::io::Write, input: &mut impl std::io::Read,
+	) -> Result<Self, crate::Error> {
+		use super::CursorMove;
+		use crate::fe_seq::ESC_STR;
+		use core::num::NonZeroU16;
+
+		// Save the cursor (DECSC) and move it as far right as it goes.
+		let last_column = CursorMove::Column(NonZeroU16::MAX);
+		write!(output, "{ESC_STR}7{last_column}")?;
+		let pos = crate::query::round_trip(
+			&crate::query::CursorPositionQuery, output, input,
+		);
+		// Restore the cursor (DECRC).
+		write!(output, "{ESC_STR}8")?;
+		output.flush()?;
+		Ok(self.with_width(pos?.col.saturating_add(1)))
+	}
+
+	/// Return the number of times that the glyph is written.
+	pub fn count(&self) -> u16 {
+		let width = self.width.unwrap_or(Self::DEFAULT_WIDTH);
+		let glyph_width = self.policy.char_width(self.glyph).max(1);
+		(width as usize / glyph_width) as u16
+	}
+}
+
+impl Display for Rule {
+	/// Display the glyphs in the style of the rule, followed by the sequence
+	/// that resets the style if it isn't the default.
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Style::new().diff_to(&self.style).fmt(f)?;
+		Repeated {
+			ch: self.glyph,
+			count: self.count(),
+			use_rep: self.use_rep,
+		}.fmt(f)?;
+		self.style.diff_to(&Style::new()).fmt(f)
+	}
+}
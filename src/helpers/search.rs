@@ -0,0 +1,107 @@
+use super::{
+	Buffer, Invert, Line, Role, StateChange, StyleRoles,
+};
+use crate::{
+	scan::Token,
+	width::graphemes,
+};
+
+use core::ops::Range;
+
+/// Overlay of a style on the cells of search matches in a [`Buffer`].
+/// 
+/// Matches are ranges of bytes of a [`Line`], counted over the contents of
+/// all of its spans, one after another. The overlay is applied to the cells
+/// that a line was already rendered to, so the line itself isn't changed, and
+/// the settings that the overlay doesn't change are kept from the style of
+/// each cell. When the buffer is drawn with a [`Renderer`](super::Renderer),
+/// only the cells whose matches changed are written again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchHighlighter {
+	overlay: StateChange,
+}
+
+impl Default for MatchHighlighter {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl MatchHighlighter {
+	/// Create a [`MatchHighlighter`] that shows matches in inverse video.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self::with_overlay(StateChange::new().with_invert(Invert::On))
+	}
+
+	/// Create a [`MatchHighlighter`] that applies `overlay` to the styles of
+	/// matches.
+	#[inline(always)]
+	pub const fn with_overlay(overlay: StateChange) -> Self {
+		Self {
+			overlay,
+		}
+	}
+
+	/// Create a [`MatchHighlighter`] that applies the settings of
+	/// [`Role::Selection`] in `roles` to the styles of matches.
+	#[inline(always)]
+	pub const fn from_roles(roles: &StyleRoles) -> Self {
+		Self::with_overlay(roles.get(Role::Selection))
+	}
+
+	/// Return the settings that are changed in the styles of matches.
+	#[inline(always)]
+	pub const fn overlay(&self) -> &StateChange {
+		&self.overlay
+	}
+
+	/// Apply the overlay to the cells of `buffer` that show the bytes of
+	/// `line` in `matches`, where `line` was rendered at column `x` of row
+	/// `y`.
+	/// 
+	/// Clusters that are only partly in a match are highlighted whole.
+	pub fn apply(
+		&self, buffer: &mut Buffer, line: &Line<'_>, mut x: u16, y: u16,
+		matches: &[Range<usize>],
+	) {
+		if matches.is_empty() {
+			return
+		}
+		let policy = buffer.width_policy();
+		let mut offset = 0;
+		for span in line.spans {
+			let mut rest = span.content.as_bytes();
+			while let Ok((token, len)) = Token::parse(rest) {
+				if let Token::Text(text) = token {
+					let mut start = offset + span.content.len() - rest.len();
+					for g in graphemes(text) {
+						let range = start..start + g.len();
+						start = range.end;
+						let width = policy.grapheme_width(g) as u16;
+						if width == 0 {
+							continue
+						}
+						if x as usize + width as usize > buffer.width() as usize {
+							return
+						}
+						let is_match = matches.iter().any(move |m| {
+							m.start < range.end && range.start < m.end
+						});
+						if is_match {
+							for cell_x in x..x + width {
+								if let Some(cell) = buffer.get_mut(cell_x, y) {
+									cell.style.apply(&self.overlay);
+								}
+							}
+						}
+						x += width;
+					}
+				}
+				rest = &rest[len..];
+			}
+			offset += span.content.len();
+		}
+	}
+}
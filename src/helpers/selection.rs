@@ -0,0 +1,199 @@
+use super::{
+	Buffer, ClipboardWriter,
+};
+use crate::{
+	pos::Pos,
+	profile::Profile,
+};
+
+use alloc::string::String;
+use core::{
+	fmt::{
+		Result as FmtResult, Write,
+	},
+	ops::RangeInclusive,
+};
+
+/// Shape of a [`Selection`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionMode {
+	/// Cells from the start to the end in reading order, wrapping at the ends
+	/// of rows, like a selection of text.
+	#[default]
+	Linear,
+	/// Rectangle of cells with the anchor and extent at opposite corners.
+	Rectangular,
+}
+
+/// Selection of cells in a [`Buffer`], such as one made by dragging the mouse.
+/// 
+/// The selection goes from the anchor, where it was started, to the extent,
+/// which follows the pointer. Both are included in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Selection {
+	pub anchor: Pos,
+	pub extent: Pos,
+	pub mode: SelectionMode,
+}
+
+impl Selection {
+	/// Create a selection of the cell at `anchor`.
+	#[inline(always)]
+	pub const fn new(anchor: Pos, mode: SelectionMode) -> Self {
+		Self {
+			anchor,
+			extent: anchor,
+			mode,
+		}
+	}
+
+	/// Return `self` with the extent at `extent`.
+	#[inline(always)]
+	pub const fn with_extent(mut self, extent: Pos) -> Self {
+		self.extent = extent;
+		self
+	}
+
+	/// Return the first selected cell, which is the top left corner for a
+	/// rectangular selection.
+	pub fn start(&self) -> Pos {
+		match self.mode {
+			SelectionMode::Linear => self.anchor.min(self.extent),
+			SelectionMode::Rectangular => Pos::new(
+				self.anchor.row.min(self.extent.row),
+				self.anchor.col.min(self.extent.col),
+			),
+		}
+	}
+
+	/// Return the last selected cell, which is the bottom right corner for a
+	/// rectangular selection.
+	pub fn end(&self) -> Pos {
+		match self.mode {
+			SelectionMode::Linear => self.anchor.max(self.extent),
+			SelectionMode::Rectangular => Pos::new(
+				self.anchor.row.max(self.extent.row),
+				self.anchor.col.max(self.extent.col),
+			),
+		}
+	}
+
+	/// Return the columns that are selected in `row` of a screen that is
+	/// `width` columns wide, if any.
+	pub fn columns(&self, row: u16, width: u16) -> Option<RangeInclusive<u16>> {
+		let (start, end) = (self.start(), self.end());
+		if width == 0 || row < start.row || row > end.row {
+			return None
+		}
+		let (first, last) = match self.mode {
+			SelectionMode::Linear => (
+				if row == start.row { start.col } else { 0 },
+				if row == end.row { end.col } else { u16::MAX },
+			),
+			SelectionMode::Rectangular => (start.col, end.col),
+		};
+		(first < width).then(move || first..=last.min(width - 1))
+	}
+
+	/// Return `true` if the cell at `pos` is selected.
+	pub fn contains(&self, pos: Pos) -> bool {
+		let (start, end) = (self.start(), self.end());
+		match self.mode {
+			SelectionMode::Linear => start <= pos && pos <= end,
+			SelectionMode::Rectangular => {
+				(start.row..=end.row).contains(&pos.row)
+					&& (start.col..=end.col).contains(&pos.col)
+			}
+		}
+	}
+
+	/// Write the text of the selected cells of `buffer` without their styles.
+	/// 
+	/// Rows are separated by line feeds, and spaces at the ends of rows are
+	/// left out. Wide clusters that are partly selected are included.
+	pub fn write_text(&self, buffer: &Buffer, w: &mut dyn Write) -> FmtResult {
+		let end = self.end().row.min(buffer.height().saturating_sub(1));
+		for y in self.start().row..=end {
+			let Some(columns) = self.columns(y, buffer.width()) else {
+				continue
+			};
+			let Some(row) = buffer.row(y) else {
+				continue
+			};
+			let mut first = *columns.start() as usize;
+			while first > 0 && row[first].is_continuation() {
+				first -= 1;
+			}
+			let cells = &row[first..=*columns.end() as usize];
+			let len = cells.iter()
+				.rposition(move |cell| !matches!(cell.symbol(), " " | ""))
+				.map_or(0, move |idx| idx + 1);
+			if y > self.start().row {
+				w.write_char('\n')?;
+			}
+			for cell in &cells[..len] {
+				w.write_str(cell.symbol())?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Return the text of the selected cells of `buffer`, as written by
+	/// [`Self::write_text`].
+	pub fn text(&self, buffer: &Buffer) -> String {
+		let mut text = String::new();
+		let _ = self.write_text(buffer, &mut text);
+		text
+	}
+
+	/// Copy the text of the selected cells of `buffer` to the clipboard with
+	/// [`ClipboardWriter`].
+	pub fn copy<W: Write + ?Sized>(
+		&self, buffer: &Buffer, w: &mut W, profile: &Profile,
+	) -> FmtResult {
+		let mut clipboard = ClipboardWriter::with_profile(w, "c", profile)?;
+		clipboard.write_bytes(self.text(buffer).as_bytes())?;
+		clipboard.finish()
+	}
+
+	/// Update `selection` with a [`Gesture`](crate::Gesture) from
+	/// [`MouseGestures`](crate::MouseGestures), returning `true` if it
+	/// changed.
+	/// 
+	/// Dragging with the left button selects cells with `mode`, and clicking
+	/// it clears the selection. Other gestures are ignored.
+	#[cfg(feature = "std")]
+	pub fn handle_gesture(
+		selection: &mut Option<Self>, gesture: &crate::Gesture,
+		mode: SelectionMode,
+	) -> bool {
+		use crate::{
+			Gesture, MouseButton,
+		};
+		let new = match *gesture {
+			Gesture::Click { button: MouseButton::Left, .. } => None,
+			Gesture::DragStart { button: MouseButton::Left, pos, .. } => {
+				Some(Self::new(pos, mode))
+			}
+			Gesture::Selection { button: MouseButton::Left, start, end } => {
+				let Some(current) = *selection else {
+					return false
+				};
+				// The anchor is at one of the corners, and the extent is at the
+				// opposite one.
+				let anchor = current.anchor;
+				Some(current.with_extent(Pos::new(
+					if anchor.row == start.row { end.row } else { start.row },
+					if anchor.col == start.col { end.col } else { start.col },
+				)))
+			}
+			Gesture::DragEnd { button: MouseButton::Left, start, end, .. } => {
+				Some(Self::new(start, mode).with_extent(end))
+			}
+			_ => return false,
+		};
+		let changed = *selection != new;
+		*selection = new;
+		changed
+	}
+}
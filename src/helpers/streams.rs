@@ -0,0 +1,191 @@
+use crate::scan::{
+	Scanner, Token,
+};
+
+use std::io::{
+	self, IsTerminal, Write,
+};
+use std::sync::Mutex;
+
+/// Coordinator of output to the standard output and standard error, which
+/// keeps escape sequences away from streams that aren't terminals and stops
+/// output to one stream from being written in the middle of a sequence or
+/// line written to the other.
+/// 
+/// Output is written through [`StreamWriter`]s, which buffer it until it ends
+/// a line or they are flushed, and then write it while holding a lock shared
+/// by both streams. Output to a stream that isn't styled has its escape
+/// sequences removed, so the same styled output can be written to either
+/// stream.
+#[derive(Debug)]
+pub struct Streams {
+	stdout_styled: bool,
+	stderr_styled: bool,
+	lock: Mutex<()>,
+}
+
+impl Default for Streams {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Streams {
+	/// Create a [`Streams`] that styles the output to the streams that are
+	/// terminals.
+	pub fn new() -> Self {
+		Self::with_styled(io::stdout().is_terminal(), io::stderr().is_terminal())
+	}
+
+	/// Create a [`Streams`] that styles the output to the standard output if
+	/// `stdout` is `true`, and to the standard error if `stderr` is `true`,
+	/// such as when colors are forced on or off by a command line option.
+	#[inline(always)]
+	pub const fn with_styled(stdout: bool, stderr: bool) -> Self {
+		Self {
+			stdout_styled: stdout,
+			stderr_styled: stderr,
+			lock: Mutex::new(()),
+		}
+	}
+
+	/// Return `true` if output to the standard output keeps its escape
+	/// sequences.
+	#[inline(always)]
+	pub const fn is_stdout_styled(&self) -> bool {
+		self.stdout_styled
+	}
+
+	/// Return `true` if output to the standard error keeps its escape
+	/// sequences.
+	#[inline(always)]
+	pub const fn is_stderr_styled(&self) -> bool {
+		self.stderr_styled
+	}
+
+	/// Return a writer to the standard output.
+	#[inline(always)]
+	pub const fn stdout(&self) -> StreamWriter<'_> {
+		StreamWriter::new(self, false)
+	}
+
+	/// Return a writer to the standard error.
+	#[inline(always)]
+	pub const fn stderr(&self) -> StreamWriter<'_> {
+		StreamWriter::new(self, true)
+	}
+
+	/// Write `bytes` to the standard error if `is_stderr` is `true`, or else to
+	/// the standard output, while no other output is written.
+	fn write_locked(&self, is_stderr: bool, bytes: &[u8]) -> io::Result<()> {
+		let _lock = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+		match is_stderr {
+			true => write_stream(&mut io::stderr().lock(), bytes, self.stderr_styled),
+			false => write_stream(&mut io::stdout().lock(), bytes, self.stdout_styled),
+		}
+	}
+}
+
+/// Write `bytes` to `w`, without escape sequences unless `styled` is `true`,
+/// and flush it.
+fn write_stream(w: &mut impl Write, bytes: &[u8], styled: bool) -> io::Result<()> {
+	if styled {
+		w.write_all(bytes)?;
+		return w.flush()
+	}
+	let mut result = Ok(());
+	let mut write_plain = |token: Token<'_>| {
+		if result.is_err() {
+			return
+		}
+		result = match token {
+			Token::Text(..) | Token::Control(..) => token.write_to(w),
+			Token::Unrecognized(bytes) if !bytes.starts_with(b"\x1b") => {
+				token.write_to(w)
+			}
+			_ => Ok(()),
+		};
+	};
+	let mut scanner = Scanner::new();
+	scanner.feed(bytes, &mut write_plain);
+	scanner.flush(&mut write_plain);
+	result?;
+	w.flush()
+}
+
+/// Writer to one of the streams of [`Streams`].
+/// 
+/// Output is buffered until it ends a line, or until the writer is flushed or
+/// dropped, so that a sequence or a line isn't split by output to the other
+/// stream. Any number of writers can be used at once, by any thread.
+#[derive(Debug)]
+pub struct StreamWriter<'a> {
+	streams: &'a Streams,
+	is_stderr: bool,
+	/// Output that doesn't end a line yet.
+	pending: Vec<u8>,
+}
+
+impl<'a> StreamWriter<'a> {
+	#[inline(always)]
+	const fn new(streams: &'a Streams, is_stderr: bool) -> Self {
+		Self {
+			streams,
+			is_stderr,
+			pending: Vec::new(),
+		}
+	}
+
+	/// Return `true` if output written to this writer keeps its escape
+	/// sequences.
+	#[inline(always)]
+	pub const fn is_styled(&self) -> bool {
+		match self.is_stderr {
+			true => self.streams.stderr_styled,
+			false => self.streams.stdout_styled,
+		}
+	}
+}
+
+impl<'a> Write for StreamWriter<'a> {
+	/// Write the output up to the end of its last line.
+	/// 
+	/// `buf` is only consumed if the output is written, so that it can be
+	/// written again after an error.
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let Some(end) = buf.iter().rposition(|&b| b == b'\n') else {
+			self.pending.extend_from_slice(buf);
+			return Ok(buf.len())
+		};
+		let pending_len = self.pending.len();
+		self.pending.extend_from_slice(&buf[..=end]);
+		let result = self.streams.write_locked(self.is_stderr, &self.pending);
+		if let Err(error) = result {
+			self.pending.truncate(pending_len);
+			return Err(error)
+		}
+		self.pending.clear();
+		self.pending.extend_from_slice(&buf[end + 1..]);
+		Ok(buf.len())
+	}
+
+	/// Write the buffered output, even if it doesn't end a line.
+	/// 
+	/// The output is kept if it can't be written.
+	fn flush(&mut self) -> io::Result<()> {
+		if self.pending.is_empty() {
+			return Ok(())
+		}
+		self.streams.write_locked(self.is_stderr, &self.pending)?;
+		self.pending.clear();
+		Ok(())
+	}
+}
+
+impl<'a> Drop for StreamWriter<'a> {
+	fn drop(&mut self) {
+		// Errors can't be reported from `drop`.
+		let _ = self.flush();
+	}
+}
@@ -0,0 +1,388 @@
+use super::{
+	Color, Invert, Italic, StateChange, Strikethrough, Underline, Weight,
+};
+use crate::{
+	csi::Csi,
+	parse::{
+		FromSequence, ParseError,
+	},
+	sgr::Sgr,
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult,
+};
+
+/// Complete set of graphics settings.
+/// 
+/// Unlike [`StateChange`], which only describes the settings that change,
+/// this describes the state of every setting.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Style {
+	pub weight: Weight,
+	pub italic: Italic,
+	pub underline: Underline,
+	pub strikethrough: Strikethrough,
+	pub invert: Invert,
+	pub foreground: Color,
+	pub background: Color,
+}
+
+impl Style {
+	/// Create a [`Style`] with every setting at its default.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			weight: Weight::Regular,
+			italic: Italic::Off,
+			underline: Underline::None,
+			strikethrough: Strikethrough::Off,
+			invert: Invert::Off,
+			foreground: Color::Reset,
+			background: Color::Reset,
+		}
+	}
+
+	/// Set [`Self::weight`].
+	#[inline(always)]
+	pub const fn with_weight(self, x: Weight) -> Self {
+		Self {
+			weight: x,
+			..self
+		}
+	}
+
+	/// Set [`Self::italic`].
+	#[inline(always)]
+	pub const fn with_italic(self, x: Italic) -> Self {
+		Self {
+			italic: x,
+			..self
+		}
+	}
+
+	/// Set [`Self::underline`].
+	#[inline(always)]
+	pub const fn with_underline(self, x: Underline) -> Self {
+		Self {
+			underline: x,
+			..self
+		}
+	}
+
+	/// Set [`Self::strikethrough`].
+	#[inline(always)]
+	pub const fn with_strikethrough(self, x: Strikethrough) -> Self {
+		Self {
+			strikethrough: x,
+			..self
+		}
+	}
+
+	/// Set [`Self::invert`].
+	#[inline(always)]
+	pub const fn with_invert(self, x: Invert) -> Self {
+		Self {
+			invert: x,
+			..self
+		}
+	}
+
+	/// Set [`Self::foreground`].
+	#[inline(always)]
+	pub const fn with_foreground(self, x: Color) -> Self {
+		Self {
+			foreground: x,
+			..self
+		}
+	}
+
+	/// Set [`Self::background`].
+	#[inline(always)]
+	pub const fn with_background(self, x: Color) -> Self {
+		Self {
+			background: x,
+			..self
+		}
+	}
+
+	/// Return `true` if every setting is at its default.
+	#[inline(always)]
+	pub fn is_default(&self) -> bool {
+		*self == Self::new()
+	}
+
+	/// Return a [`StateChange`] that sets every setting to the one in `self`.
+	#[inline(always)]
+	pub const fn to_state_change(&self) -> StateChange {
+		StateChange {
+			weight: Some(self.weight),
+			italic: Some(self.italic),
+			underline: Some(self.underline),
+			strikethrough: Some(self.strikethrough),
+			invert: Some(self.invert),
+			foreground: Some(self.foreground),
+			background: Some(self.background),
+		}
+	}
+
+	/// Return the [`StateChange`] that changes only the settings that differ
+	/// between `self` and `target`.
+	pub fn diff_to(&self, target: &Self) -> StateChange {
+		#[inline(always)]
+		fn diff<T: PartialEq + Copy>(from: T, to: T) -> Option<T> {
+			(from != to).then_some(to)
+		}
+		StateChange {
+			weight: diff(self.weight, target.weight),
+			italic: diff(self.italic, target.italic),
+			underline: diff(self.underline, target.underline),
+			strikethrough: diff(self.strikethrough, target.strikethrough),
+			invert: diff(self.invert, target.invert),
+			foreground: diff(self.foreground, target.foreground),
+			background: diff(self.background, target.background),
+		}
+	}
+
+	/// Apply the settings changed by `change`.
+	pub fn apply(&mut self, change: &StateChange) {
+		if let Some(x) = change.weight { self.weight = x; }
+		if let Some(x) = change.italic { self.italic = x; }
+		if let Some(x) = change.underline { self.underline = x; }
+		if let Some(x) = change.strikethrough { self.strikethrough = x; }
+		if let Some(x) = change.invert { self.invert = x; }
+		if let Some(x) = change.foreground { self.foreground = x; }
+		if let Some(x) = change.background { self.background = x; }
+	}
+
+	/// Apply the settings changed by `sgr`.
+	/// 
+	/// SGRs that change settings not described by [`Style`] are ignored.
+	pub fn apply_sgr(&mut self, sgr: Sgr) {
+		match sgr {
+			Sgr::Reset => *self = Self::new(),
+			Sgr::WeightBoldOn => self.weight = Weight::Bold,
+			Sgr::WeightThinOn => self.weight = Weight::Thin,
+			Sgr::WeightAllOff => self.weight = Weight::Regular,
+			Sgr::ItalicOn => self.italic = Italic::On,
+			Sgr::ItalicOff => self.italic = Italic::Off,
+			Sgr::UnderlineSingle => self.underline = Underline::Single,
+			Sgr::UnderlineDouble => self.underline = Underline::Double,
+			Sgr::UnderlineNone => self.underline = Underline::None,
+			Sgr::StrikethroughOn => self.strikethrough = Strikethrough::On,
+			Sgr::StrikethroughOff => self.strikethrough = Strikethrough::Off,
+			Sgr::Invert => self.invert = Invert::On,
+			Sgr::ReversedOff => self.invert = Invert::Off,
+			Sgr::Foreground1 => self.foreground = Color::Table(0),
+			Sgr::Foreground2 => self.foreground = Color::Table(1),
+			Sgr::Foreground3 => self.foreground = Color::Table(2),
+			Sgr::Foreground4 => self.foreground = Color::Table(3),
+			Sgr::Foreground5 => self.foreground = Color::Table(4),
+			Sgr::Foreground6 => self.foreground = Color::Table(5),
+			Sgr::Foreground7 => self.foreground = Color::Table(6),
+			Sgr::Foreground8 => self.foreground = Color::Table(7),
+			Sgr::Foreground(color) => self.foreground = color.into(),
+			Sgr::ForegroundDefault => self.foreground = Color::Reset,
+			Sgr::Background1 => self.background = Color::Table(0),
+			Sgr::Background2 => self.background = Color::Table(1),
+			Sgr::Background3 => self.background = Color::Table(2),
+			Sgr::Background4 => self.background = Color::Table(3),
+			Sgr::Background5 => self.background = Color::Table(4),
+			Sgr::Background6 => self.background = Color::Table(5),
+			Sgr::Background7 => self.background = Color::Table(6),
+			Sgr::Background8 => self.background = Color::Table(7),
+			Sgr::Background(color) => self.background = color.into(),
+			Sgr::BackgroundDefault => self.background = Color::Reset,
+			_ => {}
+		}
+	}
+}
+
+impl Display for Style {
+	/// Display the sequence that sets every setting to the one in `self`.
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		self.to_state_change().fmt(f)
+	}
+}
+
+impl<'a> FromSequence<'a> for Style {
+	/// Parse an SGR sequence that sets every setting of a [`Style`], in any
+	/// order.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let (change, len) = StateChange::parse_sequence(bytes)?;
+		let StateChange {
+			weight: Some(weight),
+			italic: Some(italic),
+			underline: Some(underline),
+			strikethrough: Some(strikethrough),
+			invert: Some(invert),
+			foreground: Some(foreground),
+			background: Some(background),
+		} = change else {
+			return Err(ParseError::Invalid)
+		};
+		let style = Self {
+			weight,
+			italic,
+			underline,
+			strikethrough,
+			invert,
+			foreground,
+			background,
+		};
+		Ok((style, len))
+	}
+}
+
+/// Tracker of the graphics settings in effect in a stream of output.
+/// 
+/// Feeding it every SGR written to a terminal keeps track of the current
+/// [`Style`], so it can be re-asserted after other output is injected.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SgrState {
+	style: Style,
+}
+
+impl SgrState {
+	/// Create an [`SgrState`] with every setting at its default.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			style: Style::new(),
+		}
+	}
+
+	/// Return the style currently in effect.
+	#[inline(always)]
+	pub const fn current_style(&self) -> Style {
+		self.style
+	}
+
+	/// Replace the current style.
+	#[inline(always)]
+	pub fn set_style(&mut self, style: Style) {
+		self.style = style;
+	}
+
+	/// Apply `sgr` to the current style.
+	#[inline(always)]
+	pub fn apply(&mut self, sgr: Sgr) {
+		self.style.apply_sgr(sgr);
+	}
+
+	/// Apply every SGR in `csi` to the current style.
+	/// 
+	/// Returns `false` if `csi` isn't an SGR sequence.
+	pub fn apply_csi(&mut self, csi: &Csi<'_>) -> bool {
+		match Sgr::parse_csi(csi) {
+			Some(params) => {
+				params.for_each(|sgr| self.apply(sgr));
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Return the [`StateChange`] that changes the current style to
+	/// `target`.
+	#[inline(always)]
+	pub fn diff_to(&self, target: &Style) -> StateChange {
+		self.style.diff_to(target)
+	}
+
+	/// Return the [`StateChange`] that re-asserts the current style after the
+	/// terminal was reset to the default style.
+	#[inline(always)]
+	pub fn restorer(&self) -> StateChange {
+		Style::new().diff_to(&self.style)
+	}
+}
+
+/// Stack of [`Style`]s for nested scopes, which returns the [`StateChange`]
+/// to write for each transition.
+/// 
+/// This allows markup such as bold text inside colored text inside
+/// underlined text to be rendered without xterm's SGR stack. At most `N`
+/// styles can be pushed. The style of an empty stack is the default style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StyleStack<const N: usize> {
+	styles: [Style; N],
+	len: usize,
+}
+
+impl<const N: usize> Default for StyleStack<N> {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const N: usize> StyleStack<N> {
+	/// Create an empty [`StyleStack`].
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			styles: [Style::new(); N],
+			len: 0,
+		}
+	}
+
+	/// Return the number of styles that were pushed.
+	#[inline(always)]
+	pub const fn depth(&self) -> usize {
+		self.len
+	}
+
+	/// Return the style of the innermost scope.
+	#[inline(always)]
+	pub const fn current(&self) -> Style {
+		match self.len {
+			0 => Style::new(),
+			len => self.styles[len - 1],
+		}
+	}
+
+	/// Enter a scope with `style`, returning the change from the current
+	/// style to it.
+	/// 
+	/// Returns [`None`] if the stack is full, in which case nothing changes.
+	pub fn push(&mut self, style: Style) -> Option<StateChange> {
+		if self.len == N {
+			return None
+		}
+		let change = self.current().diff_to(&style);
+		self.styles[self.len] = style;
+		self.len += 1;
+		Some(change)
+	}
+
+	/// Enter a scope with the current style changed by `change`, returning
+	/// the change that has an effect.
+	/// 
+	/// Returns [`None`] if the stack is full, in which case nothing changes.
+	#[inline(always)]
+	pub fn push_change(&mut self, change: &StateChange) -> Option<StateChange> {
+		let mut style = self.current();
+		style.apply(change);
+		self.push(style)
+	}
+
+	/// Leave the innermost scope, returning the change from its style back to
+	/// the style of the scope around it.
+	/// 
+	/// Returns [`None`] if the stack is empty.
+	pub fn pop(&mut self) -> Option<StateChange> {
+		let style = self.current();
+		self.len = self.len.checked_sub(1)?;
+		Some(style.diff_to(&self.current()))
+	}
+
+	/// Leave every scope, returning the change back to the default style.
+	pub fn clear(&mut self) -> StateChange {
+		let style = self.current();
+		self.len = 0;
+		style.diff_to(&Style::new())
+	}
+}
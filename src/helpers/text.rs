@@ -0,0 +1,389 @@
+use super::{
+	EraseLine, Style,
+};
+#[cfg(feature = "alloc")]
+use super::Buffer;
+use crate::{
+	csi::Csi,
+	scan::Token,
+	sgr::Sgr,
+	width::{
+		graphemes, WidthPolicy,
+	},
+};
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use core::fmt::Arguments;
+use core::fmt::{
+	Result as FmtResult, Write,
+};
+
+/// Borrowed string that is displayed with one [`Style`].
+/// 
+/// The content may contain escape sequences, which are ignored when measuring
+/// it and when writing it to a
+#[cfg_attr(feature = "alloc", doc = "[`Buffer`].")]
+#[cfg_attr(not(feature = "alloc"), doc = "`Buffer`.")]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span<'a> {
+	pub content: &'a str,
+	pub style: Style,
+}
+
+impl<'a> From<&'a str> for Span<'a> {
+	#[inline(always)]
+	fn from(value: &'a str) -> Self {
+		Self::raw(value)
+	}
+}
+
+impl<'a> Span<'a> {
+	/// Create a [`Span`] with the given content and style.
+	#[inline(always)]
+	pub const fn new(content: &'a str, style: Style) -> Self {
+		Self {
+			content,
+			style,
+		}
+	}
+
+	/// Create a [`Span`] with the default style.
+	#[inline(always)]
+	pub const fn raw(content: &'a str) -> Self {
+		Self::new(content, Style::new())
+	}
+
+	/// Format `args` into `buf`, replacing its contents, and return a span of
+	/// the result with `style`.
+	/// 
+	/// Reusing `buf` between calls avoids allocating a new string for each
+	/// span. See [`format_spans`] and [`spans!`](crate::spans!) for formatting
+	/// several spans into one buffer.
+	#[cfg(feature = "alloc")]
+	pub fn from_fmt(buf: &'a mut String, args: Arguments<'_>, style: Style) -> Self {
+		buf.clear();
+		let _ = buf.write_fmt(args);
+		Self::new(buf, style)
+	}
+
+	/// Set [`Self::style`].
+	#[inline(always)]
+	pub const fn with_style(self, x: Style) -> Self {
+		Self {
+			style: x,
+			..self
+		}
+	}
+
+	/// Return the number of cells that the content occupies, ignoring escape
+	/// sequences.
+	#[inline(always)]
+	pub fn width(&self, policy: &WidthPolicy) -> usize {
+		policy.visible_width(self.content.as_bytes())
+	}
+
+	/// Return the longest start of this span that occupies at most `width`
+	/// cells.
+	/// 
+	/// See [`WidthPolicy::truncate`].
+	#[inline(always)]
+	pub fn truncate(self, policy: &WidthPolicy, width: usize) -> Self {
+		Self {
+			content: policy.truncate(self.content, width),
+			..self
+		}
+	}
+
+	/// Write this span to `buffer` at `x`, `y`, stopping at the end of the
+	/// row.
+	/// 
+	/// Returns the column after the last cell written.
+	#[cfg(feature = "alloc")]
+	pub fn render(&self, buffer: &mut Buffer, mut x: u16, y: u16) -> u16 {
+		let policy = buffer.width_policy();
+		let mut rest = self.content.as_bytes();
+		while let Ok((token, len)) = Token::parse(rest) {
+			if let Token::Text(text) = token {
+				let end = x as usize + policy.str_width(text);
+				x = buffer.set_str(x, y, text, self.style);
+				if (x as usize) < end {
+					break
+				}
+			}
+			rest = &rest[len..];
+		}
+		x
+	}
+}
+
+/// Format every item of `items` into `buf`, replacing its contents, and return
+/// a span of each result with its style.
+/// 
+/// This is what [`spans!`](crate::spans!) expands to.
+#[cfg(feature = "alloc")]
+pub fn format_spans<'a, const N: usize>(
+	buf: &'a mut String, items: [(Arguments<'_>, Style); N],
+) -> [Span<'a>; N] {
+	buf.clear();
+	let mut ends = [0; N];
+	let mut styles = [Style::new(); N];
+	for (i, (args, style)) in items.into_iter().enumerate() {
+		let _ = buf.write_fmt(args);
+		ends[i] = buf.len();
+		styles[i] = style;
+	}
+
+	let buf: &'a String = buf;
+	core::array::from_fn(move |i| {
+		let start = if i == 0 { 0 } else { ends[i - 1] };
+		Span::new(&buf[start..ends[i]], styles[i])
+	})
+}
+
+/// Format several [`Span`]s into one string buffer, returning an array of
+/// them.
+/// 
+/// The first argument is a `&mut String` whose contents are replaced. It is
+/// followed by a `;` and a list of `(style, format string, arguments...)`
+/// tuples, such as
+/// `spans![&mut buf; (bold, "{n}"), (Style::new(), " items")]`.
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! spans {
+	($buf:expr; $(($style:expr, $($fmt:tt)+)),* $(,)?) => {
+		$crate::format_spans($buf, [$((::core::format_args!($($fmt)+), $style)),*])
+	};
+}
+
+/// Sequence of [`Span`]s that are displayed on one line.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+	pub spans: &'a [Span<'a>],
+}
+
+impl<'a> From<&'a [Span<'a>]> for Line<'a> {
+	#[inline(always)]
+	fn from(value: &'a [Span<'a>]) -> Self {
+		Self::new(value)
+	}
+}
+
+impl<'a> Line<'a> {
+	/// Create a [`Line`] from its spans.
+	#[inline(always)]
+	pub const fn new(spans: &'a [Span<'a>]) -> Self {
+		Self {
+			spans,
+		}
+	}
+
+	/// Return the number of cells that the line occupies, ignoring escape
+	/// sequences.
+	pub fn width(&self, policy: &WidthPolicy) -> usize {
+		self.spans.iter().map(move |span| span.width(policy)).sum()
+	}
+
+	/// Return an iterator over the spans of the line, with the span at
+	/// `width` cells truncated and the spans after it dropped.
+	#[inline(always)]
+	pub const fn truncate(&self, policy: &WidthPolicy, width: usize) -> TruncatedLine<'a> {
+		TruncatedLine {
+			spans: self.spans,
+			remaining: width,
+			policy: *policy,
+		}
+	}
+
+	/// Write the cells that differ between `old` and `new` to `w`, assuming
+	/// that `old` is displayed on the row of the cursor, starting at the first
+	/// column.
+	/// 
+	/// Only the range from the first to the last differing cell is rewritten.
+	/// The style is reset before the range and after it, and the rest of the
+	/// row is erased if `new` is narrower than `old`.
+	pub fn render_diff(
+		old: &Line<'_>, new: &Line<'_>, policy: &WidthPolicy, w: &mut impl Write,
+	) -> FmtResult {
+		let old_width = old.width(policy);
+		let new_width = new.width(policy);
+		let mut old_cells = CellCursor::new(cells(old, *policy));
+		let mut new_cells = CellCursor::new(cells(new, *policy));
+		let mut range = None;
+		for col in 0..old_width.max(new_width) {
+			if old_cells.at(col) != new_cells.at(col) {
+				range = Some((range.map_or(col, |(first, _)| first), col + 1));
+			}
+		}
+		let Some((first, last)) = range else {
+			return Ok(())
+		};
+
+		Csi::write_begin(w)?;
+		write!(w, "{}G{}", first + 1, Sgr::Reset)?;
+		let mut style = Style::new();
+		let mut col = 0;
+		for (g, cell_style, width) in cells(new, *policy) {
+			if col >= last {
+				break
+			}
+			if col >= first {
+				if cell_style != style {
+					write!(w, "{}", style.diff_to(&cell_style))?;
+					style = cell_style;
+				}
+				w.write_str(g)?;
+			}
+			col += width;
+		}
+		if !style.is_default() {
+			write!(w, "{}", Sgr::Reset)?;
+		}
+		if old_width > new_width {
+			write!(w, "{}", EraseLine::CurToEnd)?;
+		}
+		Ok(())
+	}
+
+	/// Write this line to `buffer` at `x`, `y`, stopping at the end of the
+	/// row.
+	/// 
+	/// Returns the column after the last cell written.
+	#[cfg(feature = "alloc")]
+	pub fn render(&self, buffer: &mut Buffer, mut x: u16, y: u16) -> u16 {
+		let policy = buffer.width_policy();
+		for span in self.spans {
+			let end = x as usize + span.width(&policy);
+			x = span.render(buffer, x, y);
+			if (x as usize) < end {
+				break
+			}
+		}
+		x
+	}
+}
+
+/// Iterator over the spans of a truncated [`Line`], returned by
+/// [`Line::truncate`].
+#[derive(Debug, Clone)]
+pub struct TruncatedLine<'a> {
+	spans: &'a [Span<'a>],
+	remaining: usize,
+	policy: WidthPolicy,
+}
+
+impl<'a> Iterator for TruncatedLine<'a> {
+	type Item = Span<'a>;
+	fn next(&mut self) -> Option<Self::Item> {
+		let (span, rest) = self.spans.split_first()?;
+		let width = span.width(&self.policy);
+		if width <= self.remaining {
+			self.spans = rest;
+			self.remaining -= width;
+			Some(*span)
+		} else {
+			self.spans = &[];
+			Some(span.truncate(&self.policy, self.remaining))
+		}
+	}
+}
+
+/// Return an iterator over the grapheme clusters of `line` that occupy cells,
+/// with their styles and widths, ignoring escape sequences.
+fn cells<'a>(
+	line: &Line<'a>, policy: WidthPolicy,
+) -> impl Iterator<Item = (&'a str, Style, usize)> + 'a {
+	line.spans.iter().flat_map(move |span| {
+		let mut rest = span.content.as_bytes();
+		core::iter::from_fn(move || loop {
+			let (token, len) = Token::parse(rest).ok()?;
+			rest = &rest[len..];
+			if let Token::Text(text) = token {
+				return Some(text)
+			}
+		})
+			.flat_map(graphemes)
+			.map(move |g| (g, span.style, policy.grapheme_width(g)))
+			.filter(move |&(_, _, width)| width > 0)
+	})
+}
+
+/// Walker over the cells of a line, returned by [`cells`], by column.
+struct CellCursor<'a, I> {
+	iter: I,
+	cell: Option<(usize, &'a str, Style, usize)>,
+	next_col: usize,
+}
+
+impl<'a, I: Iterator<Item = (&'a str, Style, usize)>> CellCursor<'a, I> {
+	#[inline(always)]
+	const fn new(iter: I) -> Self {
+		Self {
+			iter,
+			cell: None,
+			next_col: 0,
+		}
+	}
+
+	/// Return the start column, cluster and style of the cell that covers
+	/// `col`, which must not be less than in the previous call.
+	fn at(&mut self, col: usize) -> Option<(usize, &'a str, Style)> {
+		while self.cell.is_none_or(move |(start, _, _, width)| start + width <= col) {
+			let (g, style, width) = self.iter.next()?;
+			self.cell = Some((self.next_col, g, style, width));
+			self.next_col += width;
+		}
+		self.cell.map(|(start, g, style, _)| (start, g, style))
+	}
+}
+
+/// Sequence of [`Line`]s that are displayed one under another.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Text<'a> {
+	pub lines: &'a [Line<'a>],
+}
+
+impl<'a> From<&'a [Line<'a>]> for Text<'a> {
+	#[inline(always)]
+	fn from(value: &'a [Line<'a>]) -> Self {
+		Self::new(value)
+	}
+}
+
+impl<'a> Text<'a> {
+	/// Create a [`Text`] from its lines.
+	#[inline(always)]
+	pub const fn new(lines: &'a [Line<'a>]) -> Self {
+		Self {
+			lines,
+		}
+	}
+
+	/// Return the number of cells that the widest line occupies, ignoring
+	/// escape sequences.
+	pub fn width(&self, policy: &WidthPolicy) -> usize {
+		self.lines.iter().map(move |line| line.width(policy)).max().unwrap_or(0)
+	}
+
+	/// Return the number of lines.
+	#[inline(always)]
+	pub const fn height(&self) -> usize {
+		self.lines.len()
+	}
+
+	/// Write this text to `buffer` with its first line at `x`, `y`, stopping
+	/// at the end of each row and at the bottom of the buffer.
+	/// 
+	/// Returns the number of lines written.
+	#[cfg(feature = "alloc")]
+	pub fn render(&self, buffer: &mut Buffer, x: u16, y: u16) -> u16 {
+		let rows = buffer.height().saturating_sub(y);
+		let mut written = 0;
+		for line in self.lines.iter().take(rows as usize) {
+			line.render(buffer, x, y + written);
+			written += 1;
+		}
+		written
+	}
+}
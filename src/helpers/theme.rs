@@ -0,0 +1,87 @@
+use crate::{
+	event::Event,
+	query::ColorScheme,
+};
+
+/// Pair of variants of something, such as a set of styles, for dark and light
+/// color schemes, of which the one for the current scheme is used.
+/// 
+/// The current scheme can be detected once with
+#[cfg_attr(feature = "std", doc = "[`Self::auto`],")]
+#[cfg_attr(not(feature = "std"), doc = "`Self::auto`,")]
+/// and kept up to date with [`Self::watch`] while
+/// [`ColorSchemeUpdates::On`](super::ColorSchemeUpdates::On) is in effect.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme<T> {
+	pub dark: T,
+	pub light: T,
+	scheme: ColorScheme,
+}
+
+impl<T> Theme<T> {
+	/// Create a [`Theme`] with the variants `dark` and `light`, which uses the
+	/// dark one until the scheme is known.
+	#[inline(always)]
+	pub const fn new(dark: T, light: T) -> Self {
+		Self {
+			dark,
+			light,
+			scheme: ColorScheme::Dark,
+		}
+	}
+
+	/// Return `self` with `scheme` as the current scheme.
+	#[inline(always)]
+	pub fn with_scheme(mut self, scheme: ColorScheme) -> Self {
+		self.scheme = scheme;
+		self
+	}
+
+	/// Create a [`Theme`] with the variants `dark` and `light`, and detect the
+	/// current scheme with [`ColorScheme::detect`].
+	/// 
+	/// The dark variant is used if the terminal doesn't report its scheme or
+	/// background color.
+	#[cfg(feature = "std")]
+	pub fn auto(
+		dark: T, light: T,
+		output: &mut impl std::io::Write, input: &mut impl std::io::Read,
+	) -> Result<Self, crate::Error> {
+		let scheme = ColorScheme::detect(output, input)?.unwrap_or_default();
+		Ok(Self::new(dark, light).with_scheme(scheme))
+	}
+
+	/// Return the current scheme.
+	#[inline(always)]
+	pub const fn scheme(&self) -> ColorScheme {
+		self.scheme
+	}
+
+	/// Set the current scheme, returning `true` if it changed.
+	#[inline(always)]
+	pub fn set_scheme(&mut self, scheme: ColorScheme) -> bool {
+		let changed = self.scheme != scheme;
+		self.scheme = scheme;
+		changed
+	}
+
+	/// Return the variant for the current scheme.
+	#[inline(always)]
+	pub const fn current(&self) -> &T {
+		match self.scheme {
+			ColorScheme::Dark => &self.dark,
+			ColorScheme::Light => &self.light,
+		}
+	}
+
+	/// Update the current scheme if `event` reports it, returning the variant
+	/// for the new scheme if it changed.
+	pub fn watch(&mut self, event: &Event<'_>) -> Option<&T> {
+		match *event {
+			Event::ColorSchemeChanged(scheme) if self.set_scheme(scheme) => {
+				Some(self.current())
+			}
+			_ => None,
+		}
+	}
+}
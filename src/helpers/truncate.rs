@@ -0,0 +1,134 @@
+use super::{
+	SgrState, Style,
+};
+use crate::{
+	scan::Token,
+	width::WidthPolicy,
+};
+
+use core::fmt::{
+	Display, Formatter, Result as FmtResult,
+};
+
+/// Part of text that is replaced with an ellipsis by [`Truncated`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EllipsisPlacement {
+	/// Keep the start of the text.
+	#[default]
+	End,
+	/// Keep the start and the end of the text, which suits paths.
+	Middle,
+	/// Keep the end of the text.
+	Start,
+}
+
+/// Text that is cut to at most a number of cells, with an ellipsis where
+/// text was removed.
+/// 
+/// Escape sequences don't count towards the width. If a cut lands where a
+/// style is in effect, then the style is reset before the ellipsis, and set
+/// again after it for the text that follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncated<'a> {
+	/// Text to truncate.
+	pub text: &'a str,
+	/// Maximum number of cells to display.
+	pub width: usize,
+	/// Text that replaces the removed text.
+	pub ellipsis: &'a str,
+	pub placement: EllipsisPlacement,
+	pub policy: WidthPolicy,
+}
+
+impl<'a> Truncated<'a> {
+	/// Ellipsis that occupies one cell.
+	pub const ELLIPSIS: &'static str = "…";
+	/// Ellipsis made of ASCII characters.
+	pub const ASCII_ELLIPSIS: &'static str = "...";
+
+	/// Create a [`Truncated`] that cuts `text` to `width` cells, ending it
+	/// with [`Self::ELLIPSIS`] if it's cut.
+	#[inline(always)]
+	pub const fn new(text: &'a str, width: usize) -> Self {
+		Self {
+			text,
+			width,
+			ellipsis: Self::ELLIPSIS,
+			placement: EllipsisPlacement::End,
+			policy: WidthPolicy::new(),
+		}
+	}
+
+	/// Return `self` with the specified ellipsis.
+	#[inline(always)]
+	pub const fn with_ellipsis(mut self, ellipsis: &'a str) -> Self {
+		self.ellipsis = ellipsis;
+		self
+	}
+
+	/// Return `self` with the ellipsis placed as specified.
+	#[inline(always)]
+	pub const fn with_placement(mut self, placement: EllipsisPlacement) -> Self {
+		self.placement = placement;
+		self
+	}
+
+	/// Return `self` with text measured according to `policy`.
+	#[inline(always)]
+	pub const fn with_width_policy(mut self, policy: &WidthPolicy) -> Self {
+		self.policy = *policy;
+		self
+	}
+
+	/// Return `true` if the text is too wide and will be cut.
+	#[inline(always)]
+	pub fn is_cut(&self) -> bool {
+		self.policy.visible_width(self.text.as_bytes()) > self.width
+	}
+}
+
+/// Return the style in effect at the end of `s`.
+fn style_after(s: &str, mut state: SgrState) -> SgrState {
+	let mut bytes = s.as_bytes();
+	while let Ok((token, len)) = Token::parse(bytes) {
+		if let Token::Csi(csi) = token {
+			state.apply_csi(&csi);
+		}
+		bytes = &bytes[len..];
+	}
+	state
+}
+
+impl<'a> Display for Truncated<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		if !self.is_cut() {
+			return f.write_str(self.text)
+		}
+		let ellipsis_width = self.policy.str_width(self.ellipsis);
+		let Some(available) = self.width.checked_sub(ellipsis_width) else {
+			return f.write_str(self.policy.truncate(self.ellipsis, self.width))
+		};
+
+		let (head, tail) = match self.placement {
+			EllipsisPlacement::End => (available, 0),
+			EllipsisPlacement::Middle => (available.div_ceil(2), available / 2),
+			EllipsisPlacement::Start => (0, available),
+		};
+		let head = self.policy.truncate(self.text, head);
+		let tail = match tail {
+			0 => "",
+			tail => self.policy.truncate_start(self.text, tail),
+		};
+
+		f.write_str(head)?;
+		let state = style_after(head, SgrState::new());
+		state.diff_to(&Style::new()).fmt(f)?;
+		f.write_str(self.ellipsis)?;
+		if !tail.is_empty() {
+			let removed = &self.text[head.len()..self.text.len() - tail.len()];
+			style_after(removed, state).restorer().fmt(f)?;
+			f.write_str(tail)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,155 @@
+use super::{
+	Buffer, Line, MatchHighlighter, Renderer, Text,
+};
+use crate::width::WidthPolicy;
+
+use core::{
+	fmt::{
+		Result as FmtResult, Write,
+	},
+	ops::Range,
+};
+
+/// Scrollable view of a [`Text`] that has more lines than fit on the screen.
+/// 
+/// The visible lines are drawn at the top left of the screen with a
+/// [`Renderer`], so only the cells that changed are written. Scrolling by less
+/// than the height of the view moves the lines that stay visible with the
+/// terminal's scrolling instead of drawing them again.
+#[derive(Debug, Clone)]
+pub struct Viewport {
+	offset: usize,
+	rendered_offset: Option<usize>,
+	screen: Buffer,
+	renderer: Renderer,
+}
+
+impl Viewport {
+	/// Create a viewport with the specified size, scrolled to the top.
+	#[inline(always)]
+	pub fn new(width: u16, height: u16) -> Self {
+		Self::with_policy(width, height, WidthPolicy::new())
+	}
+
+	/// Create a viewport with the specified size, scrolled to the top, which
+	/// measures text with `policy`.
+	pub fn with_policy(width: u16, height: u16, policy: WidthPolicy) -> Self {
+		Self {
+			offset: 0,
+			rendered_offset: None,
+			screen: Buffer::with_policy(width, height, policy),
+			renderer: Renderer::new(),
+		}
+	}
+
+	/// Return the width of the view, in columns.
+	#[inline(always)]
+	pub const fn width(&self) -> u16 {
+		self.screen.width()
+	}
+
+	/// Return the height of the view, in rows.
+	#[inline(always)]
+	pub const fn height(&self) -> u16 {
+		self.screen.height()
+	}
+
+	/// Return the index of the first visible line.
+	#[inline(always)]
+	pub const fn offset(&self) -> usize {
+		self.offset
+	}
+
+	/// Set the index of the first visible line.
+	/// 
+	/// The offset is limited when rendering so that the view isn't scrolled
+	/// past the last line.
+	#[inline(always)]
+	pub fn set_offset(&mut self, offset: usize) {
+		self.offset = offset;
+	}
+
+	/// Scroll the view down by `lines`, or up if `lines` is negative.
+	#[inline(always)]
+	pub fn scroll_by(&mut self, lines: isize) {
+		self.offset = self.offset.saturating_add_signed(lines);
+	}
+
+	/// Scroll the view so that the last line of `text` is at the bottom.
+	#[inline(always)]
+	pub fn scroll_to_end(&mut self, text: &Text<'_>) {
+		self.offset = self.max_offset(text);
+	}
+
+	/// Change the size of the view, which redraws it completely.
+	pub fn resize(&mut self, width: u16, height: u16) {
+		self.screen.resize(width, height);
+		self.invalidate();
+	}
+
+	/// Make the next call to [`Self::render`] draw every cell.
+	#[inline(always)]
+	pub fn invalidate(&mut self) {
+		self.rendered_offset = None;
+		self.renderer.invalidate();
+	}
+
+	/// Return the renderer that draws the view.
+	#[inline(always)]
+	pub const fn renderer(&self) -> &Renderer {
+		&self.renderer
+	}
+
+	/// Draw the visible lines of `text` to `w`.
+	#[inline(always)]
+	pub fn render(&mut self, text: &Text<'_>, w: &mut impl Write) -> FmtResult {
+		self.render_with(text, w, move |_, _, _, _| {})
+	}
+
+	/// Draw the visible lines of `text` to `w`, highlighting the search
+	/// matches that `matches` returns for the line at each index with
+	/// `highlighter`.
+	/// 
+	/// Only the cells whose highlighting changed since the last frame are
+	/// written again.
+	pub fn render_highlighted<'m>(
+		&mut self, text: &Text<'_>, highlighter: &MatchHighlighter,
+		mut matches: impl FnMut(usize) -> &'m [Range<usize>],
+		w: &mut impl Write,
+	) -> FmtResult {
+		self.render_with(text, w, move |screen, idx, line, y| {
+			highlighter.apply(screen, line, 0, y, matches(idx));
+		})
+	}
+
+	/// Draw the visible lines of `text` to `w`, calling `overlay` with the
+	/// screen, the index of each line and the row it was drawn to.
+	fn render_with(
+		&mut self, text: &Text<'_>, w: &mut impl Write,
+		mut overlay: impl FnMut(&mut Buffer, usize, &Line<'_>, u16),
+	) -> FmtResult {
+		self.offset = self.offset.min(self.max_offset(text));
+		let height = self.height() as usize;
+		if let Some(rendered) = self.rendered_offset {
+			let delta = self.offset as isize - rendered as isize;
+			if delta != 0 && delta.unsigned_abs() < height {
+				self.renderer.scroll(delta as i32, w)?;
+			}
+		}
+
+		self.screen.clear();
+		let lines = text.lines.iter().enumerate().skip(self.offset).take(height);
+		for (y, (idx, line)) in lines.enumerate() {
+			line.render(&mut self.screen, 0, y as u16);
+			overlay(&mut self.screen, idx, line, y as u16);
+		}
+		self.renderer.render(&self.screen, w)?;
+		self.rendered_offset = Some(self.offset);
+		Ok(())
+	}
+
+	#[inline(always)]
+	fn max_offset(&self, text: &Text<'_>) -> usize {
+		text.height().saturating_sub(self.height() as usize)
+	}
+}
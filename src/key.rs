@@ -0,0 +1,662 @@
+use crate::csi::Csi;
+
+use core::{
+	fmt::{
+		self, Write,
+	},
+	ops::{
+		BitAnd, BitOr, BitOrAssign, Not,
+	},
+};
+
+/// Set of modifier keys held during a key press or mouse event.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+	pub const NONE: Self = Self(0);
+	pub const SHIFT: Self = Self(1 << 0);
+	pub const ALT: Self = Self(1 << 1);
+	pub const CTRL: Self = Self(1 << 2);
+	pub const SUPER: Self = Self(1 << 3);
+	pub const HYPER: Self = Self(1 << 4);
+	pub const META: Self = Self(1 << 5);
+	/// Caps Lock is on, as reported by kitty's keyboard protocol.
+	pub const CAPS_LOCK: Self = Self(1 << 6);
+	/// Num Lock is on, as reported by kitty's keyboard protocol.
+	pub const NUM_LOCK: Self = Self(1 << 7);
+
+	/// Modifiers that are written by [`Display`](fmt::Display), along with
+	/// their names.
+	const NAMES: [(Self, &'static str); 6] = [
+		(Self::CTRL, "Ctrl"),
+		(Self::ALT, "Alt"),
+		(Self::SHIFT, "Shift"),
+		(Self::SUPER, "Super"),
+		(Self::HYPER, "Hyper"),
+		(Self::META, "Meta"),
+	];
+
+	/// Create a set of modifiers from its bits.
+	#[inline(always)]
+	pub const fn from_bits(bits: u8) -> Self {
+		Self(bits)
+	}
+
+	/// Return the bits of this set of modifiers.
+	#[inline(always)]
+	pub const fn bits(&self) -> u8 {
+		self.0
+	}
+
+	/// Decode the modifier parameter used by xterm-style key sequences, which
+	/// is one more than the bits of the modifiers.
+	#[inline(always)]
+	pub const fn from_param(param: u16) -> Self {
+		Self(param.saturating_sub(1) as u8)
+	}
+
+	/// Encode `self` as the modifier parameter used by xterm-style key
+	/// sequences.
+	#[inline(always)]
+	pub const fn to_param(&self) -> u16 {
+		self.0 as u16 + 1
+	}
+
+	/// Return `true` if no modifiers are set.
+	#[inline(always)]
+	pub const fn is_empty(&self) -> bool {
+		self.0 == 0
+	}
+
+	/// Return `true` if all modifiers in `other` are set in `self`.
+	#[inline(always)]
+	pub const fn contains(&self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// Return the union of `self` and `other`.
+	#[inline(always)]
+	pub const fn union(self, other: Self) -> Self {
+		Self(self.0 | other.0)
+	}
+}
+
+impl fmt::Display for Modifiers {
+	/// Write the names of the modifiers separated by `+`, such as
+	/// `Ctrl+Shift`.
+	/// 
+	/// The state of Caps Lock and Num Lock isn't written.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut is_first = true;
+		for (modifier, name) in Self::NAMES {
+			if self.contains(modifier) {
+				if !is_first {
+					f.write_char('+')?;
+				}
+				is_first = false;
+				f.write_str(name)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl BitOr for Modifiers {
+	type Output = Self;
+	#[inline(always)]
+	fn bitor(self, rhs: Self) -> Self {
+		self.union(rhs)
+	}
+}
+
+impl BitOrAssign for Modifiers {
+	#[inline(always)]
+	fn bitor_assign(&mut self, rhs: Self) {
+		*self = self.union(rhs);
+	}
+}
+
+impl BitAnd for Modifiers {
+	type Output = Self;
+	#[inline(always)]
+	fn bitand(self, rhs: Self) -> Self {
+		Self(self.0 & rhs.0)
+	}
+}
+
+impl Not for Modifiers {
+	type Output = Self;
+	#[inline(always)]
+	fn not(self) -> Self {
+		Self(!self.0)
+	}
+}
+
+/// Key that can be pressed on a keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+	Char(char),
+	Enter,
+	Tab,
+	Backspace,
+	Escape,
+	Up,
+	Down,
+	Left,
+	Right,
+	Home,
+	End,
+	PageUp,
+	PageDown,
+	Insert,
+	Delete,
+	/// Function key, starting from `F(1)`.
+	F(u8),
+	CapsLock,
+	ScrollLock,
+	NumLock,
+	PrintScreen,
+	Pause,
+	Menu,
+	Keypad(KeypadKey),
+	Media(MediaKey),
+}
+
+/// First code of the keys that are only reported by kitty's keyboard protocol,
+/// starting with [`KeyCode::CapsLock`].
+const KITTY_LOCK_START: u32 = 57358;
+/// Code of `F(13)` in kitty's keyboard protocol.
+const KITTY_F13: u32 = 57376;
+/// Code of `Keypad(KeypadKey::Digit(0))` in kitty's keyboard protocol.
+const KITTY_KEYPAD_START: u32 = 57399;
+/// Code of `Media(MediaKey::Play)` in kitty's keyboard protocol.
+const KITTY_MEDIA_START: u32 = 57428;
+/// Code after the last media key in kitty's keyboard protocol.
+const KITTY_MEDIA_END: u32 = KITTY_MEDIA_START + MediaKey::ALL.len() as u32;
+
+const LOCK_KEYS: [KeyCode; 6] = [
+	KeyCode::CapsLock,
+	KeyCode::ScrollLock,
+	KeyCode::NumLock,
+	KeyCode::PrintScreen,
+	KeyCode::Pause,
+	KeyCode::Menu,
+];
+
+impl KeyCode {
+	/// Return the key with the specified code in kitty's keyboard protocol,
+	/// which is a Unicode code point for keys that produce text.
+	pub fn from_kitty_code(code: u32) -> Option<Self> {
+		Some(match code {
+			9 => Self::Tab,
+			13 => Self::Enter,
+			27 => Self::Escape,
+			127 => Self::Backspace,
+			KITTY_LOCK_START..KITTY_F13 => {
+				*LOCK_KEYS.get((code - KITTY_LOCK_START) as usize)?
+			}
+			KITTY_F13..KITTY_KEYPAD_START => {
+				Self::F((code - KITTY_F13 + 13) as u8)
+			}
+			KITTY_KEYPAD_START..KITTY_MEDIA_START => {
+				Self::Keypad(KeypadKey::from_index(code - KITTY_KEYPAD_START)?)
+			}
+			KITTY_MEDIA_START..KITTY_MEDIA_END => {
+				Self::Media(MediaKey::from_index(code - KITTY_MEDIA_START)?)
+			}
+			// Modifier keys, which are only reported when pressed on their own.
+			57441..=57452 => return None,
+			_ => Self::Char(char::from_u32(code)?),
+		})
+	}
+
+	/// Return the code of `self` in kitty's keyboard protocol, if it's
+	/// reported with a `CSI u` sequence.
+	pub fn kitty_code(&self) -> Option<u32> {
+		Some(match *self {
+			Self::Char(c) => c as u32,
+			Self::Tab => 9,
+			Self::Enter => 13,
+			Self::Escape => 27,
+			Self::Backspace => 127,
+			Self::F(n @ 13..=35) => KITTY_F13 + (n - 13) as u32,
+			Self::Keypad(key) => KITTY_KEYPAD_START + key.index(),
+			Self::Media(key) => KITTY_MEDIA_START + key as u32,
+			_ => {
+				let idx = LOCK_KEYS.iter().position(move |key| key == self)?;
+				KITTY_LOCK_START + idx as u32
+			}
+		})
+	}
+}
+
+impl fmt::Display for KeyCode {
+	/// Write the name of the key, such as `F5`, `PageUp` or `a`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Char(' ') => "Space",
+			Self::Char(c) => return f.write_char(*c),
+			Self::Enter => "Enter",
+			Self::Tab => "Tab",
+			Self::Backspace => "Backspace",
+			Self::Escape => "Esc",
+			Self::Up => "Up",
+			Self::Down => "Down",
+			Self::Left => "Left",
+			Self::Right => "Right",
+			Self::Home => "Home",
+			Self::End => "End",
+			Self::PageUp => "PageUp",
+			Self::PageDown => "PageDown",
+			Self::Insert => "Insert",
+			Self::Delete => "Delete",
+			Self::F(n) => return write!(f, "F{n}"),
+			Self::CapsLock => "CapsLock",
+			Self::ScrollLock => "ScrollLock",
+			Self::NumLock => "NumLock",
+			Self::PrintScreen => "PrintScreen",
+			Self::Pause => "Pause",
+			Self::Menu => "Menu",
+			Self::Keypad(KeypadKey::Digit(n)) => return write!(f, "Keypad{n}"),
+			Self::Keypad(key) => return write!(f, "Keypad{}", key.name()),
+			Self::Media(key) => return write!(f, "Media{}", key.name()),
+		})
+	}
+}
+
+/// Key on the numeric keypad, as reported by kitty's keyboard protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeypadKey {
+	/// Digit key, from `0` to `9`.
+	Digit(u8),
+	Decimal,
+	Divide,
+	Multiply,
+	Subtract,
+	Add,
+	Enter,
+	Equal,
+	Separator,
+	Left,
+	Right,
+	Up,
+	Down,
+	PageUp,
+	PageDown,
+	Home,
+	End,
+	Insert,
+	Delete,
+	Begin,
+}
+
+impl KeypadKey {
+	/// Keys other than digits, in the order of their codes.
+	pub(crate) const OTHERS: [Self; 19] = [
+		Self::Decimal,
+		Self::Divide,
+		Self::Multiply,
+		Self::Subtract,
+		Self::Add,
+		Self::Enter,
+		Self::Equal,
+		Self::Separator,
+		Self::Left,
+		Self::Right,
+		Self::Up,
+		Self::Down,
+		Self::PageUp,
+		Self::PageDown,
+		Self::Home,
+		Self::End,
+		Self::Insert,
+		Self::Delete,
+		Self::Begin,
+	];
+
+	fn from_index(idx: u32) -> Option<Self> {
+		match idx {
+			0..=9 => Some(Self::Digit(idx as u8)),
+			_ => Self::OTHERS.get(idx as usize - 10).copied(),
+		}
+	}
+
+	fn index(&self) -> u32 {
+		match *self {
+			Self::Digit(n) => n as u32,
+			_ => 10 + Self::OTHERS.iter()
+				.position(move |key| key == self)
+				.unwrap_or_default() as u32,
+		}
+	}
+
+	/// Return the name of the key, without the `Keypad` prefix.
+	/// 
+	/// Digits are named `Digit`.
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Digit(..) => "Digit",
+			Self::Decimal => "Decimal",
+			Self::Divide => "Divide",
+			Self::Multiply => "Multiply",
+			Self::Subtract => "Subtract",
+			Self::Add => "Add",
+			Self::Enter => "Enter",
+			Self::Equal => "Equal",
+			Self::Separator => "Separator",
+			Self::Left => "Left",
+			Self::Right => "Right",
+			Self::Up => "Up",
+			Self::Down => "Down",
+			Self::PageUp => "PageUp",
+			Self::PageDown => "PageDown",
+			Self::Home => "Home",
+			Self::End => "End",
+			Self::Insert => "Insert",
+			Self::Delete => "Delete",
+			Self::Begin => "Begin",
+		}
+	}
+}
+
+/// Media key, as reported by kitty's keyboard protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MediaKey {
+	Play,
+	Pause,
+	PlayPause,
+	Reverse,
+	Stop,
+	FastForward,
+	Rewind,
+	TrackNext,
+	TrackPrevious,
+	Record,
+	LowerVolume,
+	RaiseVolume,
+	MuteVolume,
+}
+
+impl MediaKey {
+	pub(crate) const ALL: [Self; 13] = [
+		Self::Play,
+		Self::Pause,
+		Self::PlayPause,
+		Self::Reverse,
+		Self::Stop,
+		Self::FastForward,
+		Self::Rewind,
+		Self::TrackNext,
+		Self::TrackPrevious,
+		Self::Record,
+		Self::LowerVolume,
+		Self::RaiseVolume,
+		Self::MuteVolume,
+	];
+
+	fn from_index(idx: u32) -> Option<Self> {
+		Self::ALL.get(idx as usize).copied()
+	}
+
+	/// Return the name of the key, without the `Media` prefix.
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Play => "Play",
+			Self::Pause => "Pause",
+			Self::PlayPause => "PlayPause",
+			Self::Reverse => "Reverse",
+			Self::Stop => "Stop",
+			Self::FastForward => "FastForward",
+			Self::Rewind => "Rewind",
+			Self::TrackNext => "TrackNext",
+			Self::TrackPrevious => "TrackPrevious",
+			Self::Record => "Record",
+			Self::LowerVolume => "LowerVolume",
+			Self::RaiseVolume => "RaiseVolume",
+			Self::MuteVolume => "MuteVolume",
+		}
+	}
+}
+
+/// Key press, along with the modifiers that were held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+	pub code: KeyCode,
+	pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+	/// Modifiers that are written as part of a chord.
+	const CHORD_MODIFIERS: Modifiers = Modifiers::from_bits(
+		!(Modifiers::CAPS_LOCK.bits() | Modifiers::NUM_LOCK.bits()),
+	);
+
+	/// Create a [`KeyEvent`] for a key with no modifiers.
+	#[inline(always)]
+	pub const fn new(code: KeyCode) -> Self {
+		Self {
+			code,
+			modifiers: Modifiers::NONE,
+		}
+	}
+
+	/// Set [`Self::modifiers`].
+	#[inline(always)]
+	pub const fn with_modifiers(self, modifiers: Modifiers) -> Self {
+		Self {
+			modifiers,
+			..self
+		}
+	}
+
+	/// Return a [`Display`](fmt::Display)able item that writes the sequence
+	/// that a terminal sends for `self` with the specified encoding.
+	#[inline(always)]
+	pub const fn encode(self, encoding: KeyEncoding) -> EncodedKey {
+		EncodedKey {
+			key: self,
+			encoding,
+		}
+	}
+}
+
+impl fmt::Display for KeyEvent {
+	/// Write the key as a chord, such as `Ctrl+Shift+F5`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if !(self.modifiers & Self::CHORD_MODIFIERS).is_empty() {
+			write!(f, "{}+", self.modifiers)?;
+		}
+		self.code.fmt(f)
+	}
+}
+
+impl From<KeyCode> for KeyEvent {
+	#[inline(always)]
+	fn from(value: KeyCode) -> Self {
+		Self::new(value)
+	}
+}
+
+/// Encoding of key presses sent by a terminal.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyEncoding {
+	/// Encoding used by xterm by default, which can't represent some
+	/// combinations of keys and modifiers.
+	#[default]
+	Legacy,
+	/// kitty's keyboard protocol, with only the "disambiguate escape codes"
+	/// flag enabled.
+	Kitty,
+	/// Encoding used by xterm while `modifyOtherKeys` is set to `2`.
+	ModifyOtherKeys,
+}
+
+/// Sequence that a terminal sends for a key press.
+/// 
+/// Arrow keys are encoded as in normal cursor key mode. Keys that can't be
+/// represented with the encoding are written without the modifiers that can't
+/// be represented, or not at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodedKey {
+	pub key: KeyEvent,
+	pub encoding: KeyEncoding,
+}
+
+/// Return the control character that is sent when `c` is pressed with Ctrl.
+const fn ctrl_byte(c: char) -> Option<u8> {
+	Some(match c {
+		' ' | '@' | '2' => 0x00,
+		'a'..='z' => c as u8 - b'a' + 1,
+		'A'..='Z' => c as u8 - b'A' + 1,
+		'[' | '3' => 0x1b,
+		'\\' | '4' => 0x1c,
+		']' | '5' => 0x1d,
+		'^' | '6' => 0x1e,
+		'_' | '7' | '/' => 0x1f,
+		'?' | '8' => 0x7f,
+		_ => return None,
+	})
+}
+
+/// Write a CSI key sequence, omitting the number if it's `1` and there are no
+/// modifiers.
+fn write_csi_key(
+	f: &mut fmt::Formatter<'_>,
+	number: u16, modifiers: Modifiers, final_char: char,
+) -> fmt::Result {
+	Csi::write_begin(f)?;
+	if !modifiers.is_empty() {
+		write!(f, "{number};{}", modifiers.to_param())?;
+	} else if number != 1 || final_char == '~' {
+		write!(f, "{number}")?;
+	}
+	f.write_char(final_char)
+}
+
+impl fmt::Display for EncodedKey {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let modifiers = self.key.modifiers;
+		let (number, final_char) = match self.key.code {
+			KeyCode::Up => (1, 'A'),
+			KeyCode::Down => (1, 'B'),
+			KeyCode::Right => (1, 'C'),
+			KeyCode::Left => (1, 'D'),
+			KeyCode::Home => (1, 'H'),
+			KeyCode::End => (1, 'F'),
+			KeyCode::Insert => (2, '~'),
+			KeyCode::Delete => (3, '~'),
+			KeyCode::PageUp => (5, '~'),
+			KeyCode::PageDown => (6, '~'),
+			KeyCode::F(n @ 1..=4) => {
+				let final_char = (b'P' + n - 1) as char;
+				if modifiers.is_empty() {
+					f.write_str(crate::fe_seq::ESC_STR)?;
+					f.write_char('O')?;
+					return f.write_char(final_char)
+				}
+				(1, final_char)
+			}
+			KeyCode::F(n @ 13..=35) if self.encoding == KeyEncoding::Kitty => {
+				(KITTY_F13 as u16 + (n - 13) as u16, 'u')
+			}
+			KeyCode::F(n @ 5..=20) => {
+				let number = match n {
+					5 => 15,
+					6..=10 => n as u16 + 11,
+					11..=14 => n as u16 + 12,
+					15 | 16 => n as u16 + 13,
+					_ => n as u16 + 14,
+				};
+				(number, '~')
+			}
+			KeyCode::Keypad(key) if self.encoding != KeyEncoding::Kitty => {
+				let code = match key {
+					KeypadKey::Digit(n) => KeyCode::Char((b'0' + n) as char),
+					KeypadKey::Decimal => KeyCode::Char('.'),
+					KeypadKey::Divide => KeyCode::Char('/'),
+					KeypadKey::Multiply => KeyCode::Char('*'),
+					KeypadKey::Subtract => KeyCode::Char('-'),
+					KeypadKey::Add => KeyCode::Char('+'),
+					KeypadKey::Enter => KeyCode::Enter,
+					KeypadKey::Equal => KeyCode::Char('='),
+					KeypadKey::Separator => KeyCode::Char(','),
+					KeypadKey::Left => KeyCode::Left,
+					KeypadKey::Right => KeyCode::Right,
+					KeypadKey::Up => KeyCode::Up,
+					KeypadKey::Down => KeyCode::Down,
+					KeypadKey::PageUp => KeyCode::PageUp,
+					KeypadKey::PageDown => KeyCode::PageDown,
+					KeypadKey::Home => KeyCode::Home,
+					KeypadKey::End => KeyCode::End,
+					KeypadKey::Insert => KeyCode::Insert,
+					KeypadKey::Delete => KeyCode::Delete,
+					KeypadKey::Begin => return write_csi_key(f, 1, modifiers, 'E'),
+				};
+				return KeyEvent { code, ..self.key }.encode(self.encoding).fmt(f)
+			}
+			code @ (
+				KeyCode::F(..) | KeyCode::Keypad(..) | KeyCode::Media(..)
+				| KeyCode::CapsLock | KeyCode::ScrollLock | KeyCode::NumLock
+				| KeyCode::PrintScreen | KeyCode::Pause | KeyCode::Menu
+			) => match (self.encoding, code.kitty_code()) {
+				(KeyEncoding::Kitty, Some(code)) => (code as u16, 'u'),
+				_ => return Ok(()),
+			},
+			KeyCode::Char(c) => return self.fmt_text(f, c as u32),
+			KeyCode::Enter => return self.fmt_text(f, 13),
+			KeyCode::Tab => return self.fmt_text(f, 9),
+			KeyCode::Backspace => return self.fmt_text(f, 127),
+			KeyCode::Escape => return self.fmt_text(f, 27),
+		};
+		write_csi_key(f, number, modifiers, final_char)
+	}
+}
+
+impl EncodedKey {
+	/// Write a key that produces text or a C0 control character, which has the
+	/// Unicode code point `code`.
+	fn fmt_text(&self, f: &mut fmt::Formatter<'_>, code: u32) -> fmt::Result {
+		let modifiers = self.key.modifiers;
+		let c = char::from_u32(code).unwrap_or_default();
+		let is_text = matches!(self.key.code, KeyCode::Char(..))
+			&& Modifiers::SHIFT.contains(modifiers);
+
+		match self.encoding {
+			KeyEncoding::Kitty if !is_text && (
+				!modifiers.is_empty() || self.key.code == KeyCode::Escape
+			) => {
+				Csi::write_begin(f)?;
+				write!(f, "{code}")?;
+				if !modifiers.is_empty() {
+					write!(f, ";{}", modifiers.to_param())?;
+				}
+				f.write_char('u')
+			}
+			KeyEncoding::ModifyOtherKeys if !(is_text || modifiers.is_empty()) => {
+				Csi::write_begin(f)?;
+				write!(f, "27;{};{code}~", modifiers.to_param())
+			}
+			_ => {
+				let is_shift = modifiers.contains(Modifiers::SHIFT);
+				if self.key.code == KeyCode::Tab && is_shift {
+					Csi::write_begin(f)?;
+					return f.write_char('Z')
+				}
+				if modifiers.contains(Modifiers::ALT) {
+					f.write_str(crate::fe_seq::ESC_STR)?;
+				}
+				let is_ctrl = modifiers.contains(Modifiers::CTRL);
+				let c = match (is_ctrl, self.key.code) {
+					(true, KeyCode::Char(c)) => ctrl_byte(c).map_or(c, char::from),
+					(true, KeyCode::Backspace) => '\x08',
+					(_, KeyCode::Enter) => '\r',
+					_ => c,
+				};
+				f.write_char(c)
+			}
+		}
+	}
+}
@@ -9,12 +9,115 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(any(test, feature = "alloc"))]
+extern crate alloc;
+
+#[cfg(feature = "macros")]
+pub use tyrminell_macros::ansi_template;
+
+mod ansi;
+pub use ansi::*;
+mod base64;
+pub use base64::*;
+mod binding;
+pub use binding::*;
+mod capabilities;
+pub use capabilities::*;
+#[cfg(all(windows, feature = "std"))]
+mod console;
+#[cfg(all(windows, feature = "std"))]
+pub use console::*;
+pub mod consts;
 mod csi;
 pub use csi::*;
+mod demux;
+pub use demux::*;
+mod error;
+pub use error::*;
+mod event;
+pub use event::*;
+#[cfg(feature = "alloc")]
+mod fake;
+#[cfg(feature = "alloc")]
+pub use fake::*;
 mod fe_seq;
 pub use fe_seq::*;
+#[cfg(feature = "std")]
+mod frame;
+#[cfg(feature = "std")]
+pub use frame::*;
+#[cfg(feature = "std")]
+mod gesture;
+#[cfg(feature = "std")]
+pub use gesture::*;
+#[cfg(feature = "std")]
+mod guard;
+#[cfg(feature = "std")]
+pub use guard::*;
+mod key;
+pub use key::*;
+mod mouse;
+pub use mouse::*;
+#[cfg(feature = "std")]
+mod paced;
+#[cfg(feature = "std")]
+pub use paced::*;
+mod palette;
+pub use palette::*;
+mod parse;
+pub use parse::*;
+mod pos;
+pub use pos::*;
+mod profile;
+pub use profile::*;
+#[cfg(all(feature = "std", any(unix, windows)))]
+mod pty;
+#[cfg(all(feature = "std", any(unix, windows)))]
+pub use pty::*;
+mod query;
+pub use query::*;
+#[cfg(feature = "alloc")]
+mod record;
+#[cfg(feature = "alloc")]
+pub use record::*;
+#[cfg(feature = "std")]
+mod rewrite;
+#[cfg(feature = "std")]
+pub use rewrite::*;
+mod scan;
+pub use scan::*;
+mod seq;
+pub use seq::*;
 mod sgr;
 pub use sgr::*;
+#[cfg(any(feature = "std", feature = "wasm"))]
+mod terminal;
+#[cfg(any(feature = "std", feature = "wasm"))]
+pub use terminal::*;
+#[cfg(feature = "terminfo")]
+mod terminfo;
+#[cfg(feature = "terminfo")]
+pub use terminfo::*;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+#[cfg(feature = "std")]
+mod timed;
+#[cfg(feature = "std")]
+pub use timed::*;
+mod visible;
+pub use visible::*;
+mod width;
+pub use width::*;
+
+/// Return the number of decimal digits needed to display `n`.
+pub(crate) const fn dec_len(mut n: u32) -> usize {
+	let mut len = 1;
+	while n >= 10 {
+		n /= 10;
+		len += 1;
+	}
+	len
+}
 
 macro_rules! byte_wrapper {
 	{
@@ -85,18 +188,123 @@ macro_rules! byte_wrapper {
 			/// If one `u8` is not valid as per [`Self::is_byte_valid`], then
 			/// this function will return [`Err`] with the position of the
 			/// first invalid byte encountered.
-			pub fn slice_from_bytes(bytes: &[u8]) -> Result<&[Self], usize> {
-				if let Some(idx) = bytes.iter().copied().enumerate()
+			pub fn slice_from_bytes(
+				bytes: &[u8]
+			) -> Result<&[Self], $crate::InvalidByte> {
+				if let Some(position) = bytes.iter().copied().enumerate()
 					.find_map(move |(idx, byte)| {
 						(!Self::is_byte_valid(byte)).then_some(idx)
 					})
 				{
-					Err(idx)
+					Err($crate::InvalidByte { position })
 				} else {
 					Ok(unsafe { Self::slice_from_bytes_unchecked(bytes) })
 				}
 			}
 
+			/// Return an iterator that converts every [`u8`] in `bytes` to
+			/// [`Self`], yielding [`Err`] with the position of each invalid
+			/// byte.
+			pub fn iter_from_bytes(
+				bytes: &[u8]
+			) -> impl Iterator<Item = Result<Self, $crate::InvalidByte>> + '_ {
+				bytes.iter().enumerate().map(move |(position, &byte)| {
+					Self::new(byte).ok_or($crate::InvalidByte { position })
+				})
+			}
+
+			/// Convert a mutable slice of [`Self`] to a mutable slice of [`u8`].
+			/// 
+			/// # Safety
+			/// Only bytes that are valid for [`Self::new_unchecked`] may be
+			/// written to the returned slice.
+			#[inline(always)]
+			pub unsafe fn slice_as_bytes_mut(slice: &mut [Self]) -> &mut [u8] {
+				// SAFETY: `Self` wraps a `u8` using `repr(transparent)`.
+				unsafe {
+					core::slice::from_raw_parts_mut(
+						slice.as_mut_ptr() as *mut _,
+						slice.len()
+					)
+				}
+			}
+
+			/// Try to convert a mutable slice of [`u8`] to a mutable slice of
+			/// [`Self`].
+			/// 
+			/// See also [`Self::slice_from_bytes`].
+			pub fn slice_from_bytes_mut(
+				bytes: &mut [u8]
+			) -> Result<&mut [Self], $crate::InvalidByte> {
+				match bytes.iter().position(move |&byte| !Self::is_byte_valid(byte)) {
+					Some(position) => Err($crate::InvalidByte { position }),
+					None => Ok(unsafe { Self::slice_from_bytes_mut_unchecked(bytes) }),
+				}
+			}
+
+			/// Convert a mutable slice of [`u8`] to a mutable slice of [`Self`].
+			/// 
+			/// # Safety
+			/// `bytes` must only contain bytes that are valid for
+			/// [`Self::new_unchecked`].
+			#[inline(always)]
+			pub unsafe fn slice_from_bytes_mut_unchecked(
+				bytes: &mut [u8]
+			) -> &mut [Self] {
+				// SAFETY: `Self` wraps a `u8` using `repr(transparent)`.
+				unsafe {
+					core::slice::from_raw_parts_mut(
+						bytes.as_mut_ptr() as *mut _,
+						bytes.len()
+					)
+				}
+			}
+
+			/// Convert a [`Vec`](alloc::vec::Vec) of [`Self`] to a `Vec` of
+			/// [`u8`] without reallocating.
+			#[cfg(feature = "alloc")]
+			pub fn vec_into_bytes(vec: alloc::vec::Vec<Self>) -> alloc::vec::Vec<u8> {
+				let mut vec = core::mem::ManuallyDrop::new(vec);
+				// SAFETY: `Self` wraps a `u8` using `repr(transparent)`, so the
+				// layout of the allocation is the same.
+				unsafe {
+					alloc::vec::Vec::from_raw_parts(
+						vec.as_mut_ptr() as *mut u8,
+						vec.len(),
+						vec.capacity()
+					)
+				}
+			}
+
+			/// Try to convert a [`Vec`](alloc::vec::Vec) of [`u8`] to a `Vec`
+			/// of [`Self`] without reallocating.
+			/// 
+			/// If one `u8` is not valid as per [`Self::is_byte_valid`], then
+			/// this function will return [`Err`] with the original bytes and
+			/// the position of the first invalid byte encountered.
+			#[cfg(feature = "alloc")]
+			pub fn vec_from_bytes(
+				bytes: alloc::vec::Vec<u8>
+			) -> Result<
+				alloc::vec::Vec<Self>, (alloc::vec::Vec<u8>, $crate::InvalidByte)
+			> {
+				if let Some(position) = bytes.iter()
+					.position(move |&byte| !Self::is_byte_valid(byte))
+				{
+					return Err((bytes, $crate::InvalidByte { position }))
+				}
+				let mut bytes = core::mem::ManuallyDrop::new(bytes);
+				// SAFETY: Every byte was validated, and `Self` wraps a `u8`
+				// using `repr(transparent)`.
+				Ok(unsafe {
+					alloc::vec::Vec::from_raw_parts(
+						bytes.as_mut_ptr() as *mut Self,
+						bytes.len(),
+						bytes.capacity()
+					)
+				})
+			}
+
 			/// Convert a slice of [`u8`] to a slice of [`Self`].
 			/// 
 			/// See also [`Self::slice_from_bytes`].
@@ -169,6 +377,99 @@ macro_rules! byte_utf8able {
 }
 pub(crate) use byte_utf8able;
 
+/// Error returned when a byte isn't valid for a byte wrapper type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidByte {
+	/// Position of the first invalid byte.
+	pub position: usize,
+}
+
+impl core::fmt::Display for InvalidByte {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "invalid byte at position {}", self.position)
+	}
+}
+
+macro_rules! byte_from_str {
+	($name:ident, lossy = $replacement:literal) => {
+		$crate::byte_from_str!($name);
+
+		impl $name {
+			/// Byte that is substituted for invalid bytes by lossy conversions.
+			pub const REPLACEMENT: Self = Self($replacement);
+
+			/// Return an iterator that converts every [`u8`] in `bytes` to
+			/// [`Self`], substituting [`Self::REPLACEMENT`] for invalid bytes.
+			pub fn iter_from_bytes_lossy(
+				bytes: &[u8]
+			) -> impl Iterator<Item = Self> + '_ {
+				bytes.iter().map(move |&byte| {
+					Self::new(byte).unwrap_or(Self::REPLACEMENT)
+				})
+			}
+
+			/// Return an iterator that converts every [`char`] in `s` to
+			/// [`Self`], substituting one [`Self::REPLACEMENT`] for each invalid
+			/// character.
+			pub fn iter_from_str_lossy(s: &str) -> impl Iterator<Item = Self> + '_ {
+				s.chars().map(move |c| {
+					Self::try_from(c).unwrap_or(Self::REPLACEMENT)
+				})
+			}
+		}
+	};
+	($name:ident) => {
+		impl $name {
+			/// Return an iterator that converts every byte in `s` to [`Self`],
+			/// yielding [`Err`] with the position of each invalid byte.
+			#[inline(always)]
+			pub fn iter_from_str(
+				s: &str
+			) -> impl Iterator<Item = Result<Self, $crate::InvalidByte>> + '_ {
+				Self::iter_from_bytes(s.as_bytes())
+			}
+
+			/// Try to convert a [`str`] to a slice of [`Self`].
+			/// 
+			/// If one byte is not valid as per [`Self::is_byte_valid`], then
+			/// this function will return [`Err`] with the position of the
+			/// first invalid byte encountered.
+			#[inline(always)]
+			pub fn slice_from_str(s: &str) -> Result<&[Self], $crate::InvalidByte> {
+				Self::slice_from_bytes(s.as_bytes())
+			}
+		}
+
+		impl TryFrom<char> for $name {
+			type Error = $crate::InvalidByte;
+			#[inline(always)]
+			fn try_from(value: char) -> Result<Self, Self::Error> {
+				u8::try_from(value).ok()
+					.filter(move |&byte| byte.is_ascii())
+					.and_then(Self::new)
+					.ok_or($crate::InvalidByte { position: 0 })
+			}
+		}
+
+		impl TryFrom<&str> for $name {
+			type Error = $crate::InvalidByte;
+			/// Convert a string that consists of exactly one valid byte.
+			fn try_from(value: &str) -> Result<Self, Self::Error> {
+				let bytes = value.as_bytes();
+				match bytes {
+					&[byte] if Self::is_byte_valid(byte) => Ok(Self(byte)),
+					_ => Err($crate::InvalidByte {
+						position: bytes.iter()
+							.position(|&b| !Self::is_byte_valid(b))
+							.unwrap_or(bytes.len().min(1)),
+					}),
+				}
+			}
+		}
+	};
+}
+pub(crate) use byte_from_str;
+
 #[cfg(feature = "helpers")]
 mod helpers;
 #[cfg(feature = "helpers")]
@@ -0,0 +1,130 @@
+use crate::key::{
+	KeyCode, KeyEvent, Modifiers
+};
+
+/// Mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+	Left,
+	Middle,
+	Right,
+}
+
+/// Encoding of mouse reports, which is selected by enabling one of the xterm
+/// private modes.
+/// 
+/// Without any of these enabled, reports use the X10 encoding, which can't
+/// represent positions beyond column or row 223.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseEncoding {
+	/// Mode `1006`, which reports positions as decimal parameters.
+	Sgr,
+	/// Mode `1016`, which is like [`Self::Sgr`] but reports positions in
+	/// pixels.
+	SgrPixels,
+	/// Mode `1015`, which reports positions as decimal parameters but can't
+	/// report which button was released.
+	Urxvt,
+}
+
+impl MouseEncoding {
+	/// Return the number of the private mode that enables this encoding.
+	#[inline(always)]
+	pub const fn mode(self) -> u16 {
+		match self {
+			Self::Sgr => 1006,
+			Self::SgrPixels => 1016,
+			Self::Urxvt => 1015,
+		}
+	}
+}
+
+/// Kind of a [`MouseEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseEventKind {
+	Down(MouseButton),
+	/// Release of a button, which is `None` if the terminal doesn't report
+	/// which button was released.
+	Up(Option<MouseButton>),
+	Drag(MouseButton),
+	Moved,
+	ScrollUp,
+	ScrollDown,
+	ScrollLeft,
+	ScrollRight,
+}
+
+/// Mouse action reported by the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseEvent {
+	pub kind: MouseEventKind,
+	/// 1-based row of the mouse pointer, or its vertical position in pixels
+	/// if [`Self::pixels`] is `true`.
+	pub row: u16,
+	/// 1-based column of the mouse pointer, or its horizontal position in
+	/// pixels if [`Self::pixels`] is `true`.
+	pub column: u16,
+	pub modifiers: Modifiers,
+	/// Whether the position is in pixels rather than cells, as reported with
+	/// [`MouseEncoding::SgrPixels`].
+	pub pixels: bool,
+}
+
+impl MouseEvent {
+	/// Decode the button code used by xterm mouse reports, along with the
+	/// position of the pointer.
+	/// 
+	/// `released` is `true` if the encoding reports the release separately
+	/// from the button code, as is the case with SGR (1006) reports.
+	pub const fn from_code(
+		code: u16, row: u16, column: u16, released: bool,
+	) -> Option<Self> {
+		let mut modifiers = Modifiers::NONE;
+		if code & 4 != 0 { modifiers = modifiers.union(Modifiers::SHIFT); }
+		if code & 8 != 0 { modifiers = modifiers.union(Modifiers::ALT); }
+		if code & 16 != 0 { modifiers = modifiers.union(Modifiers::CTRL); }
+
+		let button = match code & 0b11 {
+			0 => Some(MouseButton::Left),
+			1 => Some(MouseButton::Middle),
+			2 => Some(MouseButton::Right),
+			_ => None,
+		};
+
+		let kind = match (code & 0b1100_0000, code & 32 != 0, button) {
+			(64, _, _) => match code & 0b11 {
+				0 => MouseEventKind::ScrollUp,
+				1 => MouseEventKind::ScrollDown,
+				2 => MouseEventKind::ScrollLeft,
+				_ => MouseEventKind::ScrollRight,
+			},
+			(0, true, Some(button)) => MouseEventKind::Drag(button),
+			(0, true, None) => MouseEventKind::Moved,
+			(0, false, button) if released => MouseEventKind::Up(button),
+			(0, false, Some(button)) => MouseEventKind::Down(button),
+			(0, false, None) => MouseEventKind::Up(None),
+			_ => return None,
+		};
+
+		Some(Self {
+			kind,
+			row,
+			column,
+			modifiers,
+			pixels: false,
+		})
+	}
+
+	/// Return the arrow key that corresponds to this event if it's a turn of
+	/// the mouse wheel, with the same modifiers.
+	pub const fn wheel_key(&self) -> Option<KeyEvent> {
+		let code = match self.kind {
+			MouseEventKind::ScrollUp => KeyCode::Up,
+			MouseEventKind::ScrollDown => KeyCode::Down,
+			MouseEventKind::ScrollLeft => KeyCode::Left,
+			MouseEventKind::ScrollRight => KeyCode::Right,
+			_ => return None,
+		};
+		Some(KeyEvent::new(code).with_modifiers(self.modifiers))
+	}
+}
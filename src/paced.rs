@@ -0,0 +1,184 @@
+use std::{
+	io::{
+		self, Write,
+	},
+	time::{
+		Duration, Instant,
+	},
+};
+
+/// XON character, which a terminal sends to resume output.
+pub const XON: u8 = 0x11;
+/// XOFF character, which a terminal sends to pause output.
+pub const XOFF: u8 = 0x13;
+
+/// Writer that limits how fast output is written to the inner writer, for
+/// hardware terminals connected over a serial line, which drop characters if
+/// they arrive faster than they can be processed.
+/// 
+/// Output is written in small chunks, sleeping between them so that no more
+/// than the configured number of bytes is written per second. With flow
+/// control, the terminal can also pause output by sending [`XOFF`] and resume
+/// it by sending [`XON`], which have to be passed to [`Self::observe_input`].
+/// Output that is written while paused is kept until output is resumed, and
+/// is written before any other output.
+#[derive(Debug)]
+pub struct PacedWriter<W: Write> {
+	inner: W,
+	bytes_per_sec: u32,
+	flow_control: bool,
+	is_paused: bool,
+	/// Output written while paused.
+	pending: Vec<u8>,
+	/// Time at which the next chunk may be written.
+	next: Option<Instant>,
+}
+
+impl<W: Write> PacedWriter<W> {
+	/// Create a [`PacedWriter`] that writes at most `bytes_per_sec` bytes per
+	/// second to `inner`, without flow control.
+	/// 
+	/// A rate of `0` doesn't limit the rate.
+	#[inline(always)]
+	pub const fn new(inner: W, bytes_per_sec: u32) -> Self {
+		Self {
+			inner,
+			bytes_per_sec,
+			flow_control: false,
+			is_paused: false,
+			pending: Vec::new(),
+			next: None,
+		}
+	}
+
+	/// Create a [`PacedWriter`] for a serial line with `baud` bits per second,
+	/// with 8 data bits, 1 start bit and 1 stop bit per byte.
+	#[inline(always)]
+	pub const fn with_baud(inner: W, baud: u32) -> Self {
+		Self::new(inner, baud / 10)
+	}
+
+	/// Return `self` with [`XON`]/[`XOFF`] flow control enabled or disabled.
+	#[inline(always)]
+	pub fn with_flow_control(mut self, enabled: bool) -> Self {
+		self.flow_control = enabled;
+		self
+	}
+
+	/// Return the maximum number of bytes written per second.
+	#[inline(always)]
+	pub const fn bytes_per_sec(&self) -> u32 {
+		self.bytes_per_sec
+	}
+
+	/// Change the maximum number of bytes written per second.
+	#[inline(always)]
+	pub fn set_bytes_per_sec(&mut self, bytes_per_sec: u32) {
+		self.bytes_per_sec = bytes_per_sec;
+	}
+
+	/// Return `true` if the terminal paused output with [`XOFF`].
+	#[inline(always)]
+	pub const fn is_paused(&self) -> bool {
+		self.is_paused
+	}
+
+	/// Look for [`XON`] and [`XOFF`] in `input` received from the terminal,
+	/// pausing or resuming output if flow control is enabled.
+	/// 
+	/// If output is resumed, the output that was kept while it was paused is
+	/// written.
+	pub fn observe_input(&mut self, input: &[u8]) -> io::Result<()> {
+		if !self.flow_control {
+			return Ok(())
+		}
+		let last = input.iter().rev().find(move |&&b| b == XON || b == XOFF);
+		match last {
+			Some(&XOFF) => self.is_paused = true,
+			Some(..) => {
+				self.is_paused = false;
+				self.write_pending()?;
+			}
+			None => {}
+		}
+		Ok(())
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	/// 
+	/// Writing to it directly bypasses pacing.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	/// Return the number of bytes written in one chunk, which takes about
+	/// 10 milliseconds to send.
+	#[inline(always)]
+	const fn chunk_len(&self) -> usize {
+		let len = self.bytes_per_sec as usize / 100;
+		if len == 0 { 1 } else { len }
+	}
+
+	/// Write the output that was kept while paused, sleeping between chunks.
+	/// 
+	/// If this fails, the output that wasn't written is kept.
+	fn write_pending(&mut self) -> io::Result<()> {
+		while !self.pending.is_empty() {
+			let pending = core::mem::take(&mut self.pending);
+			let result = self.write_chunk(&pending);
+			self.pending = pending;
+			match result? {
+				0 => return Err(io::ErrorKind::WriteZero.into()),
+				len => drop(self.pending.drain(..len)),
+			}
+		}
+		Ok(())
+	}
+
+	/// Write one chunk from the start of `bytes` after sleeping until it may
+	/// be written, returning its length.
+	fn write_chunk(&mut self, bytes: &[u8]) -> io::Result<usize> {
+		if self.bytes_per_sec == 0 {
+			return self.inner.write(bytes)
+		}
+		let now = Instant::now();
+		let delay = self.next.and_then(move |next| next.checked_duration_since(now));
+		if let Some(delay) = delay {
+			std::thread::sleep(delay);
+		}
+		let chunk = &bytes[..bytes.len().min(self.chunk_len())];
+		let len = self.inner.write(chunk)?;
+		self.inner.flush()?;
+		let start = self.next.map_or(now, move |next| next.max(now));
+		let time = Duration::from_secs_f64(len as f64 / self.bytes_per_sec as f64);
+		self.next = Some(start + time);
+		Ok(len)
+	}
+}
+
+impl<W: Write> Write for PacedWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if self.is_paused {
+			self.pending.extend_from_slice(buf);
+			return Ok(buf.len())
+		}
+		// Output that was kept while paused goes first, even if writing it
+		// failed when output was resumed.
+		self.write_pending()?;
+		self.write_chunk(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		if !self.is_paused {
+			self.write_pending()?;
+		}
+		self.inner.flush()
+	}
+}
@@ -0,0 +1,216 @@
+use crate::{
+	fe_seq::{
+		Terminator, ESC_STR,
+	},
+	parse::{
+		parse_string, ParseError,
+	},
+	query::Rgb16,
+};
+#[cfg(feature = "std")]
+use crate::error::Error;
+
+use core::fmt;
+
+/// Copy of the 256-color palette of the terminal, which can be queried with
+/// OSC 4 and restored later.
+/// 
+/// This is useful for applications that change the palette while they run,
+/// which should put the original colors back before they exit. Entries that
+/// the terminal didn't report are unknown, and aren't restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+	colors: [Option<Rgb16>; 256],
+}
+
+impl Default for Palette {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Palette {
+	/// Number of entries that are queried or set by each OSC 4 sequence.
+	const BATCH_LEN: usize = 16;
+
+	/// Create a [`Palette`] in which every entry is unknown.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			colors: [None; 256],
+		}
+	}
+
+	/// Return the color of entry `index`, if it's known.
+	#[inline(always)]
+	pub const fn get(&self, index: u8) -> Option<Rgb16> {
+		self.colors[index as usize]
+	}
+
+	/// Set the color of entry `index`, or make it unknown.
+	#[inline(always)]
+	pub fn set(&mut self, index: u8, color: Option<Rgb16>) {
+		self.colors[index as usize] = color;
+	}
+
+	/// Return the number of entries that are known.
+	pub fn known_len(&self) -> usize {
+		self.colors.iter().filter(move |color| color.is_some()).count()
+	}
+
+	/// Write the queries for every entry, terminated with `terminator`.
+	/// 
+	/// Several entries are queried with each sequence, and the terminal
+	/// replies with one report for each entry, which can be recorded with
+	/// [`Self::apply_report`]. Terminals that don't support OSC 4 don't reply,
+	/// so a query that every terminal replies to, such as
+	/// [`PrimaryAttributesQuery`](crate::PrimaryAttributesQuery), should be
+	/// sent after them to know when all reports have arrived.
+	pub fn write_query(
+		w: &mut impl fmt::Write, terminator: Terminator,
+	) -> fmt::Result {
+		for batch_start in (0..256).step_by(Self::BATCH_LEN) {
+			w.write_str(ESC_STR)?;
+			w.write_str("]4")?;
+			for index in batch_start..batch_start + Self::BATCH_LEN {
+				write!(w, ";{index};?")?;
+			}
+			w.write_str(terminator.as_str())?;
+		}
+		Ok(())
+	}
+
+	/// Write the sequences that set every known entry back to its color,
+	/// terminated with `terminator`.
+	pub fn write_restore(
+		&self, w: &mut impl fmt::Write, terminator: Terminator,
+	) -> fmt::Result {
+		let known = self.colors.iter().enumerate()
+			.filter_map(move |(index, color)| Some((index, (*color)?)));
+		let mut batch_len = 0;
+		for (index, color) in known {
+			if batch_len == 0 {
+				w.write_str(ESC_STR)?;
+				w.write_str("]4")?;
+			}
+			write!(
+				w, ";{index};rgb:{:04x}/{:04x}/{:04x}",
+				color.r, color.g, color.b,
+			)?;
+			batch_len += 1;
+			if batch_len == Self::BATCH_LEN {
+				w.write_str(terminator.as_str())?;
+				batch_len = 0;
+			}
+		}
+		if batch_len > 0 {
+			w.write_str(terminator.as_str())?;
+		}
+		Ok(())
+	}
+
+	/// Parse an OSC 4 report from the start of `bytes`, recording the colors
+	/// of the entries in it, and return the number of bytes it occupies.
+	pub fn apply_report(&mut self, bytes: &[u8]) -> Result<usize, ParseError> {
+		let (payload, len) = parse_string(bytes, b']')?;
+		let mut parts = payload.strip_prefix(b"4;")
+			.ok_or(ParseError::Invalid)?
+			.split(move |&b| b == b';');
+		let mut colors = [(0, Rgb16::default()); Self::BATCH_LEN];
+		let mut count = 0;
+		while let Some(index) = parts.next() {
+			let index = core::str::from_utf8(index).ok()
+				.and_then(move |index| index.parse::<u8>().ok());
+			let color = parts.next().and_then(Rgb16::parse_spec);
+			let (Some(index), Some(color)) = (index, color) else {
+				return Err(ParseError::Invalid)
+			};
+			*colors.get_mut(count).ok_or(ParseError::Invalid)? = (index, color);
+			count += 1;
+		}
+		for &(index, color) in &colors[..count] {
+			self.colors[index as usize] = Some(color);
+		}
+		Ok(len)
+	}
+
+	/// Query every entry of the palette from the terminal, by writing the
+	/// queries and a [`PrimaryAttributesQuery`](crate::PrimaryAttributesQuery)
+	/// to `output` and reading reports from `input` until its response
+	/// arrives.
+	/// 
+	/// Other input that arrives before then (such as keys pressed by the user)
+	/// is discarded. The terminal should be in raw mode, and this function
+	/// blocks until the response arrives.
+	#[cfg(feature = "std")]
+	pub fn query(
+		output: &mut impl std::io::Write, input: &mut impl std::io::Read,
+	) -> Result<Self, Error> {
+		use crate::{
+			Demux, Event, PrimaryAttributesQuery,
+		};
+
+		let mut demux = Demux::new();
+		let mut query = std::string::String::new();
+		Self::write_query(&mut query, Terminator::St)
+			.and_then(|_| demux.send(&PrimaryAttributesQuery, &mut query))?;
+		// Every entry is reported on its own.
+		for _ in 0..256 {
+			demux.expect();
+		}
+		output.write_all(query.as_bytes())?;
+		output.flush()?;
+
+		let mut palette = Self::new();
+		let mut buf = [0; 256];
+		loop {
+			let read = input.read(&mut buf)?;
+			if read == 0 {
+				return Err(Error::Incomplete)
+			}
+			// Reports that don't fit in the slots of `demux` are passed on.
+			let mut is_answered = false;
+			demux.advance(&buf[..read], |event| match event {
+				Event::Unrecognized(bytes) => {
+					let _ = palette.apply_report(bytes);
+				}
+				Event::DeviceAttributes(..) => is_answered = true,
+				_ => {}
+			});
+			while let Some(report) = demux.take(&ReportQuery) {
+				let known = report.colors.iter().enumerate()
+					.filter_map(move |(index, color)| Some((index, (*color)?)));
+				for (index, color) in known {
+					palette.colors[index] = Some(color);
+				}
+			}
+			if is_answered || demux.take(&PrimaryAttributesQuery).is_some() {
+				return Ok(palette)
+			}
+		}
+	}
+}
+
+/// Query for the entries of the palette, which takes OSC 4 reports from a
+/// [`Demux`](crate::Demux).
+#[cfg(feature = "std")]
+struct ReportQuery;
+
+#[cfg(feature = "std")]
+impl crate::TerminalQuery for ReportQuery {
+	type Response = Palette;
+
+	#[inline(always)]
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Palette::write_query(w, Terminator::St)
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let mut palette = Palette::new();
+		let len = palette.apply_report(bytes)?;
+		Ok((palette, len))
+	}
+}
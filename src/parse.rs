@@ -0,0 +1,311 @@
+use crate::csi::{
+	Csi, CsiParam, CsiInter, CsiFinal
+};
+
+use core::fmt;
+
+/// ASCII bell character, which some terminals use to terminate string
+/// sequences instead of [`FeSeq::StringTerminator`](crate::FeSeq).
+pub const BEL: u8 = 0x07;
+
+/// Error that can occur when parsing bytes received from a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+	/// The bytes end before the sequence is complete.
+	Incomplete,
+	/// The bytes do not form the expected sequence.
+	Invalid,
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Incomplete => "incomplete sequence",
+			Self::Invalid => "invalid sequence",
+		})
+	}
+}
+
+/// Limits on the numeric parameters of a [`Csi`] sequence.
+/// 
+/// The default limits are [`Self::NONE`], which [`CsiParams`] and the
+/// [`Parser`](crate::Parser) use unless other limits are set.
+/// [`Self::XTERM`] are the limits that DEC terminals and xterm use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamLimits {
+	/// Largest value of a parameter, which larger values are clamped to.
+	pub max_value: u16,
+	/// Number of parameters after which the rest are ignored.
+	pub max_count: usize,
+}
+
+impl Default for ParamLimits {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ParamLimits {
+	/// Limits under which values only saturate at [`u16::MAX`], and no
+	/// parameters are ignored.
+	/// 
+	/// Some sequences need these, such as mouse reports in pixels, whose
+	/// coordinates can be larger than `9999` on large screens.
+	pub const NONE: Self = Self {
+		max_value: u16::MAX,
+		max_count: usize::MAX,
+	};
+
+	/// Limits that DEC terminals and xterm use for control sequences: values
+	/// above `9999` are clamped to it, and parameters after the first 16 are
+	/// ignored.
+	pub const XTERM: Self = Self {
+		max_value: 9999,
+		max_count: 16,
+	};
+
+	/// Create the default [`ParamLimits`], which are [`Self::NONE`].
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self::NONE
+	}
+
+	/// Return `self` with `max_value` as the largest value of a parameter.
+	#[inline(always)]
+	pub const fn with_max_value(mut self, max_value: u16) -> Self {
+		self.max_value = max_value;
+		self
+	}
+
+	/// Return `self` with `max_count` as the number of parameters after which
+	/// the rest are ignored.
+	#[inline(always)]
+	pub const fn with_max_count(mut self, max_count: usize) -> Self {
+		self.max_count = max_count;
+		self
+	}
+}
+
+/// Iterator over the numeric parameters of a [`Csi`] sequence.
+/// 
+/// Each item is `None` if the parameter is empty, which means that the
+/// default value should be used, as in ECMA-48. Sub-parameters separated by
+/// `:` are ignored, and values are limited by [`ParamLimits`], which are
+/// [`ParamLimits::NONE`] unless [`Self::with_limits`] is used.
+#[derive(Debug, Clone)]
+pub struct CsiParams<'a> {
+	bytes: &'a [u8],
+	done: bool,
+	limits: ParamLimits,
+	count: usize,
+}
+
+impl<'a> CsiParams<'a> {
+	/// Create an iterator over the parameters in `bytes`, which shouldn't
+	/// include the private marker.
+	#[inline(always)]
+	pub const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			bytes,
+			done: false,
+			limits: ParamLimits::NONE,
+			count: 0,
+		}
+	}
+
+	/// Return `self` with the parameters limited by `limits`.
+	#[inline(always)]
+	pub const fn with_limits(mut self, limits: ParamLimits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Return the next parameter, or `default` if it's empty or there are no
+	/// more parameters, which ECMA-48 treats the same way.
+	#[inline(always)]
+	pub fn next_or(&mut self, default: u16) -> u16 {
+		self.next().flatten().unwrap_or(default)
+	}
+
+	/// Return the bytes of the parameters that haven't been iterated over.
+	#[inline(always)]
+	pub const fn remaining(&self) -> &'a [u8] {
+		if self.done { &[] } else { self.bytes }
+	}
+}
+
+impl<'a> Iterator for CsiParams<'a> {
+	type Item = Option<u16>;
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done || self.count == self.limits.max_count {
+			return None
+		}
+		self.count += 1;
+
+		let (param, rest) = match self.bytes.iter().position(|&b| b == b';') {
+			Some(idx) => (&self.bytes[..idx], &self.bytes[idx + 1..]),
+			None => {
+				self.done = true;
+				(self.bytes, &[][..])
+			}
+		};
+		self.bytes = rest;
+
+		let mut value: Option<u16> = None;
+		for &byte in param.iter().take_while(move |&&b| b != b':') {
+			if byte.is_ascii_digit() {
+				let digit = (byte - b'0') as u16;
+				value = Some(
+					value.unwrap_or(0).saturating_mul(10).saturating_add(digit)
+				);
+			}
+		}
+		Some(value.map(|value| value.min(self.limits.max_value)))
+	}
+}
+
+/// Sequence that can be parsed back from the bytes that it's displayed as.
+pub trait FromSequence<'a>: Sized {
+	/// Parse the sequence from the start of `bytes`, returning it along with
+	/// the number of bytes it occupies.
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError>;
+}
+
+impl<'a> FromSequence<'a> for Csi<'a> {
+	#[inline(always)]
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		Self::parse(bytes)
+	}
+}
+
+impl<'a> Csi<'a> {
+	/// Parse a CSI sequence from the start of `bytes`, returning it along with
+	/// the number of bytes it occupies.
+	pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let rest = expect_intro(bytes, Self::INTRO_BYTE)?;
+		let begin = bytes.len() - rest.len();
+
+		let params_len = rest.iter()
+			.position(move |&b| !CsiParam::is_byte_valid(b))
+			.ok_or(ParseError::Incomplete)?;
+		let (params, rest) = rest.split_at(params_len);
+		let inter_len = rest.iter()
+			.position(move |&b| !CsiInter::is_byte_valid(b))
+			.ok_or(ParseError::Incomplete)?;
+		let (inter, rest) = rest.split_at(inter_len);
+		let final_byte = CsiFinal::new(rest[0])
+			.ok_or(ParseError::Invalid)?;
+
+		// SAFETY: The lengths above were found by validating every byte.
+		let csi = unsafe {
+			Self {
+				parameter_bytes: CsiParam::slice_from_bytes_unchecked(params),
+				intermediate_bytes: CsiInter::slice_from_bytes_unchecked(inter),
+				final_byte,
+			}
+		};
+		Ok((csi, begin + params_len + inter_len + 1))
+	}
+
+	/// Return the private marker (one of `<`, `=`, `>` or `?`) that starts
+	/// the parameters of this sequence, if there is one.
+	pub fn private_marker(&self) -> Option<u8> {
+		match self.parameter_bytes.first().map(CsiParam::byte) {
+			Some(marker @ 0x3c..=0x3f) => Some(marker),
+			_ => None,
+		}
+	}
+
+	/// Return an iterator over the numeric parameters of this sequence,
+	/// skipping the private marker, limited by `limits`.
+	#[inline(always)]
+	pub fn params_with(&self, limits: ParamLimits) -> CsiParams<'a> {
+		self.params().with_limits(limits)
+	}
+
+	/// Return an iterator over the numeric parameters of this sequence,
+	/// skipping the private marker.
+	pub fn params(&self) -> CsiParams<'a> {
+		let bytes = CsiParam::slice_as_bytes(self.parameter_bytes);
+		match self.private_marker() {
+			Some(..) => CsiParams::new(&bytes[1..]),
+			None => CsiParams::new(bytes),
+		}
+	}
+
+	/// Return the only numeric parameter of this sequence, which is `0` if
+	/// it's empty, if the sequence has the private marker `marker` and ends
+	/// with `final_str`, which is the intermediate bytes followed by the final
+	/// byte.
+	#[cfg(feature = "helpers")]
+	pub(crate) fn single_param(
+		&self, marker: Option<u8>, final_str: &str,
+	) -> Option<u16> {
+		let inter = CsiInter::slice_as_bytes(self.intermediate_bytes);
+		let is_match = self.private_marker() == marker
+			&& final_str.as_bytes().split_last().is_some_and(|(&last, rest)| {
+				last == self.final_byte.byte() && rest == inter
+			});
+		if !is_match {
+			return None
+		}
+		let mut params = self.params();
+		match (params.next(), params.next()) {
+			(Some(param), None) => Some(param.unwrap_or(0)),
+			_ => None,
+		}
+	}
+}
+
+/// Check that `bytes` starts with an escape character followed by `intro`,
+/// returning the rest of the bytes.
+pub(crate) fn expect_intro(bytes: &[u8], intro: u8) -> Result<&[u8], ParseError> {
+	match bytes {
+		[] | [0x1b] => Err(ParseError::Incomplete),
+		[0x1b, byte, rest @ ..] if *byte == intro => Ok(rest),
+		_ => Err(ParseError::Invalid),
+	}
+}
+
+/// Parse a string sequence (such as OSC or DCS) introduced by an escape
+/// character followed by `intro` from the start of `bytes`.
+/// 
+/// The sequence may be terminated by either the String Terminator or [`BEL`].
+/// On success, this returns the payload and the length of the whole sequence.
+pub(crate) fn parse_string(
+	bytes: &[u8], intro: u8,
+) -> Result<(&[u8], usize), ParseError> {
+	let rest = expect_intro(bytes, intro)?;
+	let begin = bytes.len() - rest.len();
+	for (idx, &byte) in rest.iter().enumerate() {
+		match byte {
+			BEL => return Ok((&rest[..idx], begin + idx + 1)),
+			0x1b => return match rest.get(idx + 1) {
+				Some(b'\\') => Ok((&rest[..idx], begin + idx + 2)),
+				Some(..) => Err(ParseError::Invalid),
+				None => Err(ParseError::Incomplete),
+			},
+			_ => {}
+		}
+	}
+	Err(ParseError::Incomplete)
+}
+
+/// Return the value of an ASCII hexadecimal digit.
+pub(crate) const fn hex_digit(byte: u8) -> Option<u8> {
+	match byte {
+		b'0'..=b'9' => Some(byte - b'0'),
+		b'a'..=b'f' => Some(byte - b'a' + 10),
+		b'A'..=b'F' => Some(byte - b'A' + 10),
+		_ => None,
+	}
+}
+
+/// Write `bytes` as uppercase hexadecimal digits.
+pub(crate) fn write_hex(w: &mut dyn fmt::Write, bytes: &[u8]) -> fmt::Result {
+	for byte in bytes {
+		write!(w, "{byte:02X}")?;
+	}
+	Ok(())
+}
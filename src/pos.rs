@@ -0,0 +1,134 @@
+/// Position of a cell on the screen, with `0` being the top row and the left
+/// column.
+/// 
+/// Positions are ordered by row first, which is the order in which cells are
+/// written.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pos {
+	pub row: u16,
+	pub col: u16,
+}
+
+impl Pos {
+	/// Top left cell of the screen.
+	pub const ORIGIN: Self = Self::new(0, 0);
+
+	/// Create a [`Pos`] from its 0-based row and column.
+	#[inline(always)]
+	pub const fn new(row: u16, col: u16) -> Self {
+		Self {
+			row,
+			col,
+		}
+	}
+
+	/// Create a [`Pos`] from the 1-based row and column used by escape
+	/// sequences, treating `0` as `1`.
+	#[inline(always)]
+	pub const fn from_one_based(row: u16, col: u16) -> Self {
+		Self::new(row.saturating_sub(1), col.saturating_sub(1))
+	}
+
+	/// Return `(row, col)` counted from `1`, as used by escape sequences.
+	#[inline(always)]
+	pub const fn to_one_based(self) -> (u32, u32) {
+		(self.row as u32 + 1, self.col as u32 + 1)
+	}
+
+	/// Return `self` moved by `rows` and `cols`, or [`None`] if that is
+	/// outside of the range of [`Pos`].
+	#[inline(always)]
+	pub const fn checked_offset(self, rows: i32, cols: i32) -> Option<Self> {
+		match (offset(self.row, rows), offset(self.col, cols)) {
+			(Some(row), Some(col)) => Some(Self::new(row, col)),
+			_ => None,
+		}
+	}
+
+	/// Return `self` moved by `rows` and `cols`, stopping at the edges of the
+	/// range of [`Pos`].
+	#[inline(always)]
+	pub const fn saturating_offset(self, rows: i32, cols: i32) -> Self {
+		Self::new(
+			saturating_offset(self.row, rows),
+			saturating_offset(self.col, cols),
+		)
+	}
+
+	/// Return the sum of the rows and columns of `self` and `other`, or
+	/// [`None`] if it overflows.
+	#[inline(always)]
+	pub const fn checked_add(self, other: Self) -> Option<Self> {
+		match (self.row.checked_add(other.row), self.col.checked_add(other.col)) {
+			(Some(row), Some(col)) => Some(Self::new(row, col)),
+			_ => None,
+		}
+	}
+
+	/// Return the difference of the rows and columns of `self` and `other`, or
+	/// [`None`] if it underflows.
+	#[inline(always)]
+	pub const fn checked_sub(self, other: Self) -> Option<Self> {
+		match (self.row.checked_sub(other.row), self.col.checked_sub(other.col)) {
+			(Some(row), Some(col)) => Some(Self::new(row, col)),
+			_ => None,
+		}
+	}
+
+	/// Return the sum of the rows and columns of `self` and `other`, stopping
+	/// at [`u16::MAX`].
+	#[inline(always)]
+	pub const fn saturating_add(self, other: Self) -> Self {
+		Self::new(
+			self.row.saturating_add(other.row),
+			self.col.saturating_add(other.col),
+		)
+	}
+
+	/// Return the difference of the rows and columns of `self` and `other`,
+	/// stopping at `0`.
+	#[inline(always)]
+	pub const fn saturating_sub(self, other: Self) -> Self {
+		Self::new(
+			self.row.saturating_sub(other.row),
+			self.col.saturating_sub(other.col),
+		)
+	}
+
+	/// Return `true` if `self` is inside a screen of `width` columns and
+	/// `height` rows.
+	#[inline(always)]
+	pub const fn is_within(self, width: u16, height: u16) -> bool {
+		self.row < height && self.col < width
+	}
+}
+
+impl From<Pos> for (u16, u16) {
+	/// Return `(row, col)`.
+	#[inline(always)]
+	fn from(pos: Pos) -> Self {
+		(pos.row, pos.col)
+	}
+}
+
+#[inline(always)]
+const fn offset(x: u16, delta: i32) -> Option<u16> {
+	let x = x as i32 + delta;
+	if x >= 0 && x <= u16::MAX as i32 {
+		Some(x as u16)
+	} else {
+		None
+	}
+}
+
+#[inline(always)]
+const fn saturating_offset(x: u16, delta: i32) -> u16 {
+	let x = (x as i32).saturating_add(delta);
+	if x < 0 {
+		0
+	} else if x > u16::MAX as i32 {
+		u16::MAX
+	} else {
+		x as u16
+	}
+}
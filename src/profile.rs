@@ -0,0 +1,100 @@
+use crate::{
+	fe_seq::Terminator,
+	width::WidthPolicy,
+};
+
+/// Description of the quirks of the terminal that output is intended for.
+/// 
+/// Emitters that have several ways to write the same thing can consult a
+/// [`Profile`] to pick the one that the terminal understands.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Profile {
+	/// Terminator of string sequences, such as OSC and DCS sequences.
+	pub terminator: Terminator,
+	/// Form of desktop notification sequences.
+	pub notify: NotifyStyle,
+	/// Rules for the number of cells that characters occupy.
+	pub width: WidthPolicy,
+	/// Sequences that switch to and from the alternate screen.
+	pub alt_screen: AltScreenMode,
+	/// Whether the terminal supports Repeat, or **REP**, which repeats the
+	/// last character that was written.
+	/// 
+	/// This is part of ECMA-48, but some common terminals ignore it, so it's
+	/// `false` by default.
+	pub repeat: bool,
+}
+
+impl Profile {
+	/// Create a [`Profile`] for a terminal that conforms to ECMA-48.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			terminator: Terminator::St,
+			notify: NotifyStyle::Osc9,
+			width: WidthPolicy::new(),
+			alt_screen: AltScreenMode::Combined,
+			repeat: false,
+		}
+	}
+
+	/// Return `self` with the specified string sequence terminator.
+	#[inline(always)]
+	pub const fn with_terminator(mut self, terminator: Terminator) -> Self {
+		self.terminator = terminator;
+		self
+	}
+
+	/// Return `self` with the specified rules for character widths.
+	#[inline(always)]
+	pub const fn with_width_policy(mut self, width: WidthPolicy) -> Self {
+		self.width = width;
+		self
+	}
+
+	/// Return `self` with the specified form of desktop notifications.
+	#[inline(always)]
+	pub const fn with_notify(mut self, notify: NotifyStyle) -> Self {
+		self.notify = notify;
+		self
+	}
+
+	/// Return `self` with the specified sequences for the alternate screen.
+	#[inline(always)]
+	pub const fn with_alt_screen(mut self, alt_screen: AltScreenMode) -> Self {
+		self.alt_screen = alt_screen;
+		self
+	}
+
+	/// Return `self` with support for REP enabled or disabled.
+	#[inline(always)]
+	pub const fn with_repeat(mut self, repeat: bool) -> Self {
+		self.repeat = repeat;
+		self
+	}
+}
+
+/// Form of the sequence that shows a desktop notification.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotifyStyle {
+	/// `OSC 9`, as supported by iTerm2, ConEmu and others.
+	#[default]
+	Osc9,
+	/// `OSC 777;notify`, as supported by urxvt and others.
+	Osc777,
+}
+
+/// Sequences that switch to and from the alternate screen.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AltScreenMode {
+	/// Private mode `1049`, which saves the cursor and clears the alternate
+	/// screen when switching to it, and restores the cursor when switching
+	/// back.
+	#[default]
+	Combined,
+	/// Save Cursor, or **DECSC**, private mode `47` and Erase in Display when
+	/// switching to the alternate screen, and the reverse when switching back,
+	/// for terminals that mishandle mode `1049`.
+	Decomposed,
+}
@@ -0,0 +1,120 @@
+//! Pseudo-terminals for running programs as if they were in a terminal.
+
+#[cfg(unix)]
+#[path = "pty/unix.rs"]
+mod sys;
+#[cfg(windows)]
+#[path = "pty/windows.rs"]
+mod sys;
+
+use std::{
+	fs::File,
+	io::{
+		self, Read, Write,
+	},
+	process::{
+		Command, ExitStatus,
+	},
+};
+
+/// Child process that runs in a pseudo-terminal.
+/// 
+/// Reading from the [`Pty`] returns what the child writes to its terminal, and
+/// writing to it is seen by the child as input from the terminal, so the
+/// output is usually fed to a [`Scanner`](crate::Scanner) or
+/// [`FakeTerminal`](crate::FakeTerminal), and the input is usually encoded key
+/// events.
+/// 
+/// Reads block until the child writes something, so they're usually done on
+/// another thread with a reader from [`Self::try_clone_reader`]. Once the
+/// child exits, reads fail or return end of file, depending on the platform.
+/// 
+/// On Unix, the child runs in a new session with the pseudo-terminal as its
+/// controlling terminal. On Windows, a ConPTY pseudo console is used, which
+/// requires Windows 10 version 1809 or later.
+#[derive(Debug)]
+pub struct Pty {
+	reader: File,
+	writer: File,
+	process: sys::Process,
+}
+
+impl Pty {
+	/// Spawn `command` in a new pseudo-terminal that is `width` columns wide
+	/// and `height` rows high.
+	/// 
+	/// The standard streams of `command` are replaced with the
+	/// pseudo-terminal. On Unix, every other setting of `command` is used. On
+	/// Windows, only its program, arguments, environment variables and
+	/// working directory are used, and [`Command::env_clear`] is ignored.
+	pub fn spawn(command: Command, width: u16, height: u16) -> io::Result<Self> {
+		let (reader, writer, process) = sys::spawn(command, width, height)?;
+		Ok(Self {
+			reader,
+			writer,
+			process,
+		})
+	}
+
+	/// Return the OS-assigned identifier of the child process.
+	#[inline(always)]
+	pub fn id(&self) -> u32 {
+		self.process.id()
+	}
+
+	/// Create another reader of the output of the child.
+	#[inline(always)]
+	pub fn try_clone_reader(&self) -> io::Result<File> {
+		self.reader.try_clone()
+	}
+
+	/// Create another writer of the input of the child.
+	#[inline(always)]
+	pub fn try_clone_writer(&self) -> io::Result<File> {
+		self.writer.try_clone()
+	}
+
+	/// Change the size of the pseudo-terminal, which the child is notified of.
+	#[inline(always)]
+	pub fn resize(&self, width: u16, height: u16) -> io::Result<()> {
+		self.process.resize(width, height)
+	}
+
+	/// Wait for the child to exit.
+	#[inline(always)]
+	pub fn wait(&mut self) -> io::Result<ExitStatus> {
+		self.process.wait()
+	}
+
+	/// Return the exit status of the child if it has exited, without
+	/// blocking.
+	#[inline(always)]
+	pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+		self.process.try_wait()
+	}
+
+	/// Forcibly end the child.
+	#[inline(always)]
+	pub fn kill(&mut self) -> io::Result<()> {
+		self.process.kill()
+	}
+}
+
+impl Read for Pty {
+	#[inline(always)]
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.reader.read(buf)
+	}
+}
+
+impl Write for Pty {
+	#[inline(always)]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.writer.write(buf)
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		self.writer.flush()
+	}
+}
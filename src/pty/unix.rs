@@ -0,0 +1,187 @@
+use core::ffi::{
+	c_char, c_int, c_ulong, CStr,
+};
+use std::{
+	fs::{
+		File, OpenOptions,
+	},
+	io,
+	os::unix::{
+		ffi::OsStrExt,
+		fs::OpenOptionsExt,
+		io::{
+			AsRawFd, FromRawFd,
+		},
+		process::CommandExt,
+	},
+	process::{
+		Child, Command, ExitStatus, Stdio,
+	},
+	sync::Mutex,
+};
+
+const O_RDWR: c_int = 0o2;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const O_NOCTTY: c_int = 0o400;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const O_NOCTTY: c_int = 0x20000;
+#[cfg(any(
+	target_os = "freebsd", target_os = "dragonfly", target_os = "openbsd",
+	target_os = "netbsd",
+))]
+const O_NOCTTY: c_int = 0x8000;
+#[cfg(target_os = "solaris")]
+const O_NOCTTY: c_int = 0x800;
+const F_SETFD: c_int = 2;
+const FD_CLOEXEC: c_int = 1;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const TIOCSCTTY: c_ulong = 0x540e;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const TIOCSCTTY: c_ulong = 0x2000_7461;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const TIOCSWINSZ: c_ulong = 0x5414;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const TIOCSWINSZ: c_ulong = 0x8008_7467;
+
+#[repr(C)]
+struct WinSize {
+	rows: u16,
+	columns: u16,
+	x_pixels: u16,
+	y_pixels: u16,
+}
+
+extern "C" {
+	fn posix_openpt(flags: c_int) -> c_int;
+	fn grantpt(fd: c_int) -> c_int;
+	fn unlockpt(fd: c_int) -> c_int;
+	fn ptsname(fd: c_int) -> *mut c_char;
+	fn fcntl(fd: c_int, command: c_int, ...) -> c_int;
+	fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+	fn setsid() -> c_int;
+}
+
+/// Lock that is held while calling [`ptsname`], which isn't thread-safe.
+static PTSNAME_LOCK: Mutex<()> = Mutex::new(());
+
+/// Turn the result of a libc function into an [`io::Result`].
+#[inline(always)]
+fn check(result: c_int) -> io::Result<c_int> {
+	match result {
+		-1 => Err(io::Error::last_os_error()),
+		result => Ok(result),
+	}
+}
+
+/// Open the master side of a new pseudo-terminal, returning it along with the
+/// slave side.
+fn open() -> io::Result<(File, File)> {
+	// SAFETY: `posix_openpt` has no preconditions, and the descriptor that it
+	// returns is owned by nothing else.
+	let master = unsafe {
+		File::from_raw_fd(check(posix_openpt(O_RDWR | O_NOCTTY))?)
+	};
+	let fd = master.as_raw_fd();
+	// SAFETY: `fd` is a valid descriptor of a pseudo-terminal master.
+	unsafe {
+		check(fcntl(fd, F_SETFD, FD_CLOEXEC))?;
+		check(grantpt(fd))?;
+		check(unlockpt(fd))?;
+	}
+
+	let path = {
+		let _lock = PTSNAME_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+		// SAFETY: `fd` is valid, and the name is copied before the lock is
+		// released.
+		let name = unsafe { ptsname(fd) };
+		if name.is_null() {
+			return Err(io::Error::last_os_error())
+		}
+		// SAFETY: `ptsname` returns a NUL-terminated string on success.
+		std::ffi::OsStr::from_bytes(unsafe { CStr::from_ptr(name) }.to_bytes())
+			.to_owned()
+	};
+	let slave = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.custom_flags(O_NOCTTY)
+		.open(path)?;
+	Ok((master, slave))
+}
+
+/// Set the size of the pseudo-terminal whose master is `master`.
+fn set_size(master: &File, width: u16, height: u16) -> io::Result<()> {
+	let size = WinSize {
+		rows: height,
+		columns: width,
+		x_pixels: 0,
+		y_pixels: 0,
+	};
+	// SAFETY: The descriptor is valid, and `size` is a valid `winsize`.
+	check(unsafe { ioctl(master.as_raw_fd(), TIOCSWINSZ, &size as *const WinSize) })?;
+	Ok(())
+}
+
+pub fn spawn(
+	mut command: Command, width: u16, height: u16,
+) -> io::Result<(File, File, Process)> {
+	let (master, slave) = open()?;
+	set_size(&master, width, height)?;
+
+	command
+		.stdin(Stdio::from(slave.try_clone()?))
+		.stdout(Stdio::from(slave.try_clone()?))
+		.stderr(Stdio::from(slave));
+	// SAFETY: Only async-signal-safe functions are called in the child.
+	unsafe {
+		command.pre_exec(|| {
+			// The standard input is already the slave, and becomes the
+			// controlling terminal of the new session.
+			check(setsid())?;
+			check(ioctl(0, TIOCSCTTY, 0 as c_int))?;
+			Ok(())
+		});
+	}
+	let child = command.spawn()?;
+
+	let reader = master.try_clone()?;
+	let writer = master.try_clone()?;
+	Ok((reader, writer, Process {
+		master,
+		child,
+	}))
+}
+
+#[derive(Debug)]
+pub struct Process {
+	master: File,
+	child: Child,
+}
+
+impl Process {
+	#[inline(always)]
+	pub fn id(&self) -> u32 {
+		self.child.id()
+	}
+
+	#[inline(always)]
+	pub fn resize(&self, width: u16, height: u16) -> io::Result<()> {
+		set_size(&self.master, width, height)
+	}
+
+	#[inline(always)]
+	pub fn wait(&mut self) -> io::Result<ExitStatus> {
+		self.child.wait()
+	}
+
+	#[inline(always)]
+	pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+		self.child.try_wait()
+	}
+
+	#[inline(always)]
+	pub fn kill(&mut self) -> io::Result<()> {
+		self.child.kill()
+	}
+}
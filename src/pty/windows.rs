@@ -0,0 +1,400 @@
+use core::{
+	ffi::c_void,
+	ptr,
+};
+use std::{
+	ffi::{
+		OsStr, OsString,
+	},
+	fs::File,
+	io,
+	os::windows::{
+		ffi::OsStrExt,
+		io::FromRawHandle,
+		process::ExitStatusExt,
+	},
+	process::{
+		Command, ExitStatus,
+	},
+};
+
+type Handle = *mut c_void;
+
+const EXTENDED_STARTUPINFO_PRESENT: u32 = 0x0008_0000;
+const CREATE_UNICODE_ENVIRONMENT: u32 = 0x0000_0400;
+const PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE: usize = 0x0002_0016;
+const STARTF_USESTDHANDLES: u32 = 0x0000_0100;
+const INFINITE: u32 = u32::MAX;
+const WAIT_OBJECT_0: u32 = 0;
+const WAIT_TIMEOUT: u32 = 0x102;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Coord {
+	x: i16,
+	y: i16,
+}
+
+impl Coord {
+	#[inline(always)]
+	const fn size(width: u16, height: u16) -> Self {
+		Self {
+			x: if width > i16::MAX as u16 { i16::MAX } else { width as i16 },
+			y: if height > i16::MAX as u16 { i16::MAX } else { height as i16 },
+		}
+	}
+}
+
+#[repr(C)]
+struct StartupInfo {
+	cb: u32,
+	reserved: *mut u16,
+	desktop: *mut u16,
+	title: *mut u16,
+	x: u32,
+	y: u32,
+	x_size: u32,
+	y_size: u32,
+	x_count_chars: u32,
+	y_count_chars: u32,
+	fill_attribute: u32,
+	flags: u32,
+	show_window: u16,
+	reserved2_len: u16,
+	reserved2: *mut u8,
+	std_input: Handle,
+	std_output: Handle,
+	std_error: Handle,
+}
+
+#[repr(C)]
+struct StartupInfoEx {
+	startup_info: StartupInfo,
+	attribute_list: *mut c_void,
+}
+
+#[repr(C)]
+struct ProcessInformation {
+	process: Handle,
+	thread: Handle,
+	process_id: u32,
+	thread_id: u32,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+	fn CreatePipe(
+		read: *mut Handle, write: *mut Handle, attributes: *const c_void,
+		size: u32,
+	) -> i32;
+	fn CloseHandle(handle: Handle) -> i32;
+	fn CreatePseudoConsole(
+		size: Coord, input: Handle, output: Handle, flags: u32,
+		console: *mut Handle,
+	) -> i32;
+	fn ResizePseudoConsole(console: Handle, size: Coord) -> i32;
+	fn ClosePseudoConsole(console: Handle);
+	fn InitializeProcThreadAttributeList(
+		list: *mut c_void, count: u32, flags: u32, size: *mut usize,
+	) -> i32;
+	fn UpdateProcThreadAttribute(
+		list: *mut c_void, flags: u32, attribute: usize, value: *const c_void,
+		size: usize, previous: *mut c_void, return_size: *mut usize,
+	) -> i32;
+	fn DeleteProcThreadAttributeList(list: *mut c_void);
+	fn CreateProcessW(
+		application: *const u16, command_line: *mut u16,
+		process_attributes: *const c_void, thread_attributes: *const c_void,
+		inherit_handles: i32, flags: u32, environment: *const c_void,
+		current_directory: *const u16, startup_info: *const StartupInfo,
+		information: *mut ProcessInformation,
+	) -> i32;
+	fn WaitForSingleObject(handle: Handle, milliseconds: u32) -> u32;
+	fn GetExitCodeProcess(process: Handle, code: *mut u32) -> i32;
+	fn TerminateProcess(process: Handle, code: u32) -> i32;
+}
+
+/// Turn the result of a Win32 function into an [`io::Result`].
+#[inline(always)]
+fn check(result: i32) -> io::Result<()> {
+	match result {
+		0 => Err(io::Error::last_os_error()),
+		_ => Ok(()),
+	}
+}
+
+/// Turn an `HRESULT` into an [`io::Result`].
+#[inline(always)]
+fn check_hresult(result: i32) -> io::Result<()> {
+	match result {
+		0.. => Ok(()),
+		_ => Err(io::Error::other(format!("pseudo console error {result:#010x}"))),
+	}
+}
+
+/// Create an anonymous pipe, returning its read and write ends.
+fn pipe() -> io::Result<(File, File)> {
+	let mut read = ptr::null_mut();
+	let mut write = ptr::null_mut();
+	// SAFETY: The pointers are valid, and the handles that are created are
+	// owned by nothing else.
+	unsafe {
+		check(CreatePipe(&mut read, &mut write, ptr::null(), 0))?;
+		Ok((File::from_raw_handle(read), File::from_raw_handle(write)))
+	}
+}
+
+/// Append `arg` to `command_line`, quoted so that it is parsed back the same
+/// way by the C runtime.
+fn push_arg(command_line: &mut Vec<u16>, arg: &OsStr) {
+	if !command_line.is_empty() {
+		command_line.push(b' ' as u16);
+	}
+	let needs_quotes = arg.is_empty() || arg.encode_wide()
+		.any(|c| c == b' ' as u16 || c == b'\t' as u16 || c == b'"' as u16);
+	if !needs_quotes {
+		command_line.extend(arg.encode_wide());
+		return
+	}
+
+	command_line.push(b'"' as u16);
+	let mut backslashes = 0;
+	for c in arg.encode_wide() {
+		if c == b'\\' as u16 {
+			backslashes += 1;
+		} else {
+			// Backslashes are only special before a quote.
+			if c == b'"' as u16 {
+				command_line.extend(core::iter::repeat_n(b'\\' as u16, backslashes + 1));
+			}
+			backslashes = 0;
+		}
+		command_line.push(c);
+	}
+	command_line.extend(core::iter::repeat_n(b'\\' as u16, backslashes));
+	command_line.push(b'"' as u16);
+}
+
+/// Build the environment block for `command`, or return [`None`] if it
+/// inherits the environment unchanged.
+fn environment_block(command: &Command) -> Option<Vec<u16>> {
+	if command.get_envs().len() == 0 {
+		return None
+	}
+
+	let mut vars: Vec<(OsString, OsString)> = std::env::vars_os().collect();
+	for (key, value) in command.get_envs() {
+		let upper = key.to_ascii_uppercase();
+		vars.retain(|(k, _)| k.to_ascii_uppercase() != upper);
+		if let Some(value) = value {
+			vars.push((key.to_owned(), value.to_owned()));
+		}
+	}
+	vars.sort_by_cached_key(|(key, _)| key.to_ascii_uppercase());
+
+	let mut block = Vec::new();
+	for (key, value) in vars {
+		block.extend(key.encode_wide());
+		block.push(b'=' as u16);
+		block.extend(value.encode_wide());
+		block.push(0);
+	}
+	block.push(0);
+	Some(block)
+}
+
+pub fn spawn(
+	command: Command, width: u16, height: u16,
+) -> io::Result<(File, File, Process)> {
+	use std::os::windows::io::AsRawHandle;
+
+	let (input_read, input_write) = pipe()?;
+	let (output_read, output_write) = pipe()?;
+	let mut console = ptr::null_mut();
+	// SAFETY: The handles are valid, and the pseudo console duplicates them.
+	check_hresult(unsafe {
+		CreatePseudoConsole(
+			Coord::size(width, height), input_read.as_raw_handle(),
+			output_write.as_raw_handle(), 0, &mut console,
+		)
+	})?;
+	drop((input_read, output_write));
+	let console = PseudoConsole(console);
+
+	let mut list_size = 0;
+	// SAFETY: Passing a null list returns the size that it needs.
+	unsafe {
+		InitializeProcThreadAttributeList(ptr::null_mut(), 1, 0, &mut list_size);
+	}
+	let mut list = vec![0usize; list_size.div_ceil(size_of::<usize>())];
+	let list_ptr = list.as_mut_ptr().cast::<c_void>();
+	// SAFETY: `list` is at least `list_size` bytes long, and the value of the
+	// attribute outlives the list.
+	unsafe {
+		check(InitializeProcThreadAttributeList(list_ptr, 1, 0, &mut list_size))?;
+	}
+	let attribute_list = AttributeList(list_ptr);
+	// SAFETY: The pseudo console attribute is passed by value.
+	check(unsafe {
+		UpdateProcThreadAttribute(
+			attribute_list.0, 0, PROC_THREAD_ATTRIBUTE_PSEUDOCONSOLE, console.0,
+			size_of::<Handle>(), ptr::null_mut(), ptr::null_mut(),
+		)
+	})?;
+
+	let mut command_line = Vec::new();
+	push_arg(&mut command_line, command.get_program());
+	for arg in command.get_args() {
+		push_arg(&mut command_line, arg);
+	}
+	command_line.push(0);
+	let environment = environment_block(&command);
+	let directory = command.get_current_dir().map(|dir| {
+		dir.as_os_str().encode_wide().chain([0]).collect::<Vec<u16>>()
+	});
+
+	let startup_info = StartupInfoEx {
+		startup_info: StartupInfo {
+			cb: size_of::<StartupInfoEx>() as u32,
+			reserved: ptr::null_mut(),
+			desktop: ptr::null_mut(),
+			title: ptr::null_mut(),
+			x: 0,
+			y: 0,
+			x_size: 0,
+			y_size: 0,
+			x_count_chars: 0,
+			y_count_chars: 0,
+			fill_attribute: 0,
+			// Without this, the child would inherit the standard handles of
+			// this process instead of using the pseudo console.
+			flags: STARTF_USESTDHANDLES,
+			show_window: 0,
+			reserved2_len: 0,
+			reserved2: ptr::null_mut(),
+			std_input: ptr::null_mut(),
+			std_output: ptr::null_mut(),
+			std_error: ptr::null_mut(),
+		},
+		attribute_list: attribute_list.0,
+	};
+	let mut information = ProcessInformation {
+		process: ptr::null_mut(),
+		thread: ptr::null_mut(),
+		process_id: 0,
+		thread_id: 0,
+	};
+	// SAFETY: Every pointer is either null or valid for the duration of the
+	// call, and the strings are NUL-terminated.
+	check(unsafe {
+		CreateProcessW(
+			ptr::null(), command_line.as_mut_ptr(), ptr::null(), ptr::null(), 0,
+			EXTENDED_STARTUPINFO_PRESENT | CREATE_UNICODE_ENVIRONMENT,
+			environment.as_ref().map_or(ptr::null(), |block| block.as_ptr().cast()),
+			directory.as_ref().map_or(ptr::null(), |dir| dir.as_ptr()),
+			&startup_info.startup_info, &mut information,
+		)
+	})?;
+	drop(attribute_list);
+	// SAFETY: The thread handle is owned by nothing else.
+	unsafe {
+		CloseHandle(information.thread);
+	}
+
+	Ok((output_read, input_write, Process {
+		console,
+		process: information.process,
+		id: information.process_id,
+	}))
+}
+
+/// Owner of a pseudo console, which is closed on drop.
+#[derive(Debug)]
+struct PseudoConsole(Handle);
+
+impl Drop for PseudoConsole {
+	fn drop(&mut self) {
+		// SAFETY: The pseudo console is owned by `self`.
+		unsafe {
+			ClosePseudoConsole(self.0);
+		}
+	}
+}
+
+/// Owner of an initialized attribute list, which is deleted on drop.
+struct AttributeList(*mut c_void);
+
+impl Drop for AttributeList {
+	fn drop(&mut self) {
+		// SAFETY: The list was initialized, and is owned by `self`.
+		unsafe {
+			DeleteProcThreadAttributeList(self.0);
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct Process {
+	console: PseudoConsole,
+	process: Handle,
+	id: u32,
+}
+
+// SAFETY: Process and pseudo console handles can be used and closed by any
+// thread.
+unsafe impl Send for Process {}
+unsafe impl Sync for Process {}
+
+impl Process {
+	#[inline(always)]
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	#[inline(always)]
+	pub fn resize(&self, width: u16, height: u16) -> io::Result<()> {
+		// SAFETY: The pseudo console is valid.
+		check_hresult(unsafe {
+			ResizePseudoConsole(self.console.0, Coord::size(width, height))
+		})
+	}
+
+	pub fn wait(&mut self) -> io::Result<ExitStatus> {
+		// SAFETY: The process handle is valid.
+		match unsafe { WaitForSingleObject(self.process, INFINITE) } {
+			WAIT_OBJECT_0 => self.exit_status(),
+			_ => Err(io::Error::last_os_error()),
+		}
+	}
+
+	pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+		// SAFETY: The process handle is valid.
+		match unsafe { WaitForSingleObject(self.process, 0) } {
+			WAIT_OBJECT_0 => self.exit_status().map(Some),
+			WAIT_TIMEOUT => Ok(None),
+			_ => Err(io::Error::last_os_error()),
+		}
+	}
+
+	#[inline(always)]
+	pub fn kill(&mut self) -> io::Result<()> {
+		// SAFETY: The process handle is valid.
+		check(unsafe { TerminateProcess(self.process, 1) })
+	}
+
+	fn exit_status(&self) -> io::Result<ExitStatus> {
+		let mut code = 0;
+		// SAFETY: The process handle is valid, and `code` is a valid pointer.
+		check(unsafe { GetExitCodeProcess(self.process, &mut code) })?;
+		Ok(ExitStatus::from_raw(code))
+	}
+}
+
+impl Drop for Process {
+	fn drop(&mut self) {
+		// SAFETY: The process handle is owned by `self`.
+		unsafe {
+			CloseHandle(self.process);
+		}
+	}
+}
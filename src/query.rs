@@ -0,0 +1,876 @@
+use crate::{
+	csi::{
+		Csi, CsiInter
+	},
+	fe_seq::Terminator,
+	parse::{
+		hex_digit, parse_string, write_hex, ParseError,
+	},
+	pos::Pos,
+};
+#[cfg(feature = "std")]
+use crate::error::Error;
+
+use core::fmt;
+
+/// Query that can be sent to a terminal, which replies with a response.
+/// 
+/// Implementing this trait allows custom queries to be used with
+#[cfg_attr(feature = "std", doc = "[`round_trip`].")]
+#[cfg_attr(not(feature = "std"), doc = "`round_trip`.")]
+pub trait TerminalQuery {
+	/// Parsed response to this query.
+	type Response;
+
+	/// Write the sequence that asks the terminal for a response.
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result;
+
+	/// Parse the response to this query from the start of `bytes`, returning
+	/// it along with the number of bytes it occupies.
+	/// 
+	/// If `bytes` may be the start of a valid response, this function must
+	/// return [`ParseError::Incomplete`].
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError>;
+
+	/// Return `true` if the response to this query is a Cursor Position Report,
+	/// which looks the same as some keys pressed with modifiers.
+	#[inline(always)]
+	fn is_position_query(&self) -> bool {
+		false
+	}
+}
+
+/// Write `query` to `output`, then read from `input` until its response is
+/// received.
+/// 
+/// Bytes that precede the response and don't belong to it (such as keys
+/// pressed by the user) are discarded. The terminal should be in raw mode, and
+/// this function blocks until the response arrives.
+#[cfg(feature = "std")]
+#[inline(always)]
+pub fn round_trip<Q: TerminalQuery + ?Sized>(
+	query: &Q,
+	output: &mut impl std::io::Write,
+	input: &mut impl std::io::Read,
+) -> Result<Q::Response, Error> {
+	round_trip_with(query, output, input, |_| Ok(()))
+}
+
+/// Write `query` to `output`, then read from `input` until its response is
+/// received or `deadline` passes.
+/// 
+/// This is like [`round_trip`], but returns [`Error::Timeout`] if the
+/// response doesn't arrive by `deadline`, such as for queries that some
+/// terminals don't respond to.
+#[cfg(all(unix, feature = "std"))]
+pub fn round_trip_until<Q, R>(
+	query: &Q,
+	output: &mut impl std::io::Write,
+	input: &mut R,
+	deadline: std::time::Instant,
+) -> Result<Q::Response, Error>
+where
+	Q: TerminalQuery + ?Sized,
+	R: std::io::Read + std::os::fd::AsFd,
+{
+	round_trip_with(query, output, input, move |input| {
+		let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+		match crate::timed::poll_readable(input.as_fd(), timeout)? {
+			true => Ok(()),
+			false => Err(Error::Timeout),
+		}
+	})
+}
+
+/// Write `query` to `output`, then read from `input` until its response is
+/// received, calling `wait` before each read.
+#[cfg(feature = "std")]
+fn round_trip_with<Q: TerminalQuery + ?Sized, R: std::io::Read>(
+	query: &Q,
+	output: &mut impl std::io::Write,
+	input: &mut R,
+	mut wait: impl FnMut(&mut R) -> Result<(), Error>,
+) -> Result<Q::Response, Error> {
+	struct Adapter<'a, W: ?Sized> {
+		inner: &'a mut W,
+		error: Option<std::io::Error>,
+	}
+	impl<'a, W: std::io::Write + ?Sized> fmt::Write for Adapter<'a, W> {
+		fn write_str(&mut self, s: &str) -> fmt::Result {
+			self.inner.write_all(s.as_bytes()).map_err(|error| {
+				self.error = Some(error);
+				fmt::Error
+			})
+		}
+	}
+
+	let mut adapter = Adapter {
+		inner: output,
+		error: None,
+	};
+	if let Err(error) = query.write_query(&mut adapter) {
+		// Report the error of the output rather than the formatting error.
+		return Err(adapter.error.map_or(Error::from(error), Error::Io))
+	}
+	output.flush()?;
+
+	let mut buf = [0u8; 1024];
+	let mut len = 0;
+	loop {
+		if len == buf.len() {
+			return Err(Error::TooLong)
+		}
+		wait(input)?;
+		let read = input.read(&mut buf[len..])?;
+		if read == 0 {
+			return Err(Error::Incomplete)
+		}
+		len += read;
+
+		let mut start = 0;
+		while start < len {
+			let escape = buf[start..len].iter().position(|&b| b == 0x1b);
+			let Some(offset) = escape else {
+				start = len;
+				break
+			};
+			start += offset;
+			match query.parse_response(&buf[start..len]) {
+				Ok((response, ..)) => return Ok(response),
+				Err(ParseError::Incomplete) => break,
+				Err(ParseError::Invalid) => start += 1,
+			}
+		}
+		// Only keep the response that may still be incomplete.
+		buf.copy_within(start..len, 0);
+		len -= start;
+	}
+}
+
+/// Parse a CSI sequence with the specified private marker and final byte.
+fn expect_csi(
+	bytes: &[u8], marker: Option<u8>, final_byte: u8,
+) -> Result<(Csi<'_>, usize), ParseError> {
+	let (csi, len) = Csi::parse(bytes)?;
+	if csi.private_marker() == marker && csi.final_byte.byte() == final_byte {
+		Ok((csi, len))
+	} else {
+		Err(ParseError::Invalid)
+	}
+}
+
+/// Device Status Report, or **DSR**, query for the operating status of the
+/// terminal.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusQuery;
+
+/// Response to [`StatusQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceStatus {
+	Ok,
+	Malfunction,
+}
+
+impl TerminalQuery for StatusQuery {
+	type Response = DeviceStatus;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		w.write_str("5n")
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (csi, len) = expect_csi(bytes, None, b'n')?;
+		match csi.params().next() {
+			Some(Some(0)) => Ok((DeviceStatus::Ok, len)),
+			Some(Some(3)) => Ok((DeviceStatus::Malfunction, len)),
+			_ => Err(ParseError::Invalid),
+		}
+	}
+}
+
+/// Device Status Report, or **DSR**, query for the position of the cursor.
+/// 
+/// The response is the position of the cursor.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorPositionQuery;
+
+impl CursorPositionQuery {
+	/// Parse a Cursor Position Report from the start of `bytes`.
+	pub fn parse_report(bytes: &[u8]) -> Result<(Pos, usize), ParseError> {
+		let (csi, len) = expect_csi(bytes, None, b'R')?;
+		let mut params = csi.params();
+		match (params.next(), params.next(), params.next()) {
+			(Some(row), Some(column), None) => {
+				let pos = Pos::from_one_based(row.unwrap_or(1), column.unwrap_or(1));
+				Ok((pos, len))
+			}
+			_ => Err(ParseError::Invalid),
+		}
+	}
+}
+
+impl TerminalQuery for CursorPositionQuery {
+	type Response = Pos;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		w.write_str("6n")
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		Self::parse_report(bytes)
+	}
+
+	#[inline(always)]
+	fn is_position_query(&self) -> bool {
+		true
+	}
+}
+
+/// Primary Device Attributes, or **DA1**, query.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimaryAttributesQuery;
+
+/// Attributes reported in response to [`PrimaryAttributesQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceAttributes {
+	attributes: [u16; Self::CAPACITY],
+	len: u8,
+}
+
+impl DeviceAttributes {
+	/// Maximum number of attributes that are kept.
+	pub const CAPACITY: usize = 16;
+
+	/// Parse a DA1 response from the start of `bytes`.
+	/// 
+	/// Attributes past [`Self::CAPACITY`] are ignored.
+	pub fn parse(bytes: &[u8]) -> Result<(Self, usize), ParseError> {
+		let (csi, len) = expect_csi(bytes, Some(b'?'), b'c')?;
+		let mut result = Self {
+			attributes: [0; Self::CAPACITY],
+			len: 0,
+		};
+		for param in csi.params().take(Self::CAPACITY) {
+			result.attributes[result.len as usize] = param.unwrap_or(0);
+			result.len += 1;
+		}
+		Ok((result, len))
+	}
+
+	/// Return the class of the terminal, which is the first attribute.
+	#[inline(always)]
+	pub fn class(&self) -> Option<u16> {
+		self.attributes().first().copied()
+	}
+
+	/// Return the reported attributes, including the class.
+	#[inline(always)]
+	pub fn attributes(&self) -> &[u16] {
+		&self.attributes[..self.len as usize]
+	}
+
+	/// Return `true` if the specified attribute was reported.
+	#[inline(always)]
+	pub fn has(&self, attribute: u16) -> bool {
+		self.attributes().get(1..).unwrap_or_default().contains(&attribute)
+	}
+}
+
+impl TerminalQuery for PrimaryAttributesQuery {
+	type Response = DeviceAttributes;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		w.write_str("c")
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		DeviceAttributes::parse(bytes)
+	}
+}
+
+/// Secondary Device Attributes, or **DA2**, query.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecondaryAttributesQuery;
+
+/// Response to [`SecondaryAttributesQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecondaryAttributes {
+	/// Identifier of the terminal type.
+	pub terminal_type: u16,
+	/// Firmware version of the terminal.
+	pub version: u16,
+	/// ROM cartridge registration number, which is usually `0`.
+	pub rom: u16,
+}
+
+impl TerminalQuery for SecondaryAttributesQuery {
+	type Response = SecondaryAttributes;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		w.write_str(">c")
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (csi, len) = expect_csi(bytes, Some(b'>'), b'c')?;
+		let mut params = csi.params().map(move |p| p.unwrap_or(0));
+		let response = SecondaryAttributes {
+			terminal_type: params.next().ok_or(ParseError::Invalid)?,
+			version: params.next().unwrap_or(0),
+			rom: params.next().unwrap_or(0),
+		};
+		Ok((response, len))
+	}
+}
+
+/// Request Mode, or **DECRQM**, query for the state of a terminal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModeQuery {
+	/// Number of the mode.
+	pub mode: u16,
+	/// Whether the mode is a private (DEC) mode, as opposed to an ANSI mode.
+	pub private: bool,
+}
+
+impl ModeQuery {
+	/// Create a query for a private (DEC) mode.
+	#[inline(always)]
+	pub const fn private(mode: u16) -> Self {
+		Self {
+			mode,
+			private: true,
+		}
+	}
+
+	/// Create a query for an ANSI mode.
+	#[inline(always)]
+	pub const fn ansi(mode: u16) -> Self {
+		Self {
+			mode,
+			private: false,
+		}
+	}
+}
+
+/// State of a mode reported in response to [`ModeQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeStatus {
+	NotRecognized,
+	Set,
+	Reset,
+	PermanentlySet,
+	PermanentlyReset,
+}
+
+impl ModeStatus {
+	/// Return `true` if the mode is known to the terminal.
+	#[inline(always)]
+	pub const fn is_recognized(&self) -> bool {
+		!matches!(self, Self::NotRecognized)
+	}
+
+	/// Return `true` if the mode is set, permanently or not.
+	#[inline(always)]
+	pub const fn is_set(&self) -> bool {
+		matches!(self, Self::Set | Self::PermanentlySet)
+	}
+}
+
+impl TerminalQuery for ModeQuery {
+	type Response = ModeStatus;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		if self.private {
+			w.write_str("?")?;
+		}
+		write!(w, "{}$p", self.mode)
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let marker = self.private.then_some(b'?');
+		let (csi, len) = expect_csi(bytes, marker, b'y')?;
+		if csi.intermediate_bytes.iter().map(CsiInter::byte).ne([b'$']) {
+			return Err(ParseError::Invalid)
+		}
+
+		let mut params = csi.params();
+		if params.next() != Some(Some(self.mode)) {
+			return Err(ParseError::Invalid)
+		}
+		let status = match params.next() {
+			Some(Some(1)) => ModeStatus::Set,
+			Some(Some(2)) => ModeStatus::Reset,
+			Some(Some(3)) => ModeStatus::PermanentlySet,
+			Some(Some(4)) => ModeStatus::PermanentlyReset,
+			Some(Some(0) | None) => ModeStatus::NotRecognized,
+			_ => return Err(ParseError::Invalid),
+		};
+		Ok((status, len))
+	}
+}
+
+/// Dynamic color that can be queried with [`ColorQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTarget {
+	/// Entry in the 256-color palette.
+	Palette(u8),
+	Foreground,
+	Background,
+	Cursor,
+}
+
+/// Operating System Command, or **OSC**, query for the value of a color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorQuery(pub ColorTarget);
+
+/// Color with 16 bits per channel, as reported by terminals.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb16 {
+	pub r: u16,
+	pub g: u16,
+	pub b: u16,
+}
+
+impl Rgb16 {
+	/// Convert `self` into a color with 8 bits per channel.
+	#[inline(always)]
+	pub const fn to_rgb8(self) -> (u8, u8, u8) {
+		((self.r >> 8) as u8, (self.g >> 8) as u8, (self.b >> 8) as u8)
+	}
+
+	/// Parse a color specification in the form `rgb:R/G/B`, where each
+	/// channel has 1 to 4 hexadecimal digits.
+	pub fn parse_spec(spec: &[u8]) -> Option<Self> {
+		fn channel(digits: &[u8]) -> Option<u16> {
+			if digits.is_empty() || digits.len() > 4 {
+				return None
+			}
+			let mut value: u32 = 0;
+			for &digit in digits {
+				value = (value << 4) | hex_digit(digit)? as u32;
+			}
+			// Scale the value so that the maximum maps to `u16::MAX`.
+			let max = (1u32 << (4 * digits.len())) - 1;
+			Some((value * u16::MAX as u32 / max) as u16)
+		}
+
+		let spec = spec.strip_prefix(b"rgb:")?;
+		let mut channels = spec.split(move |&b| b == b'/');
+		let color = Self {
+			r: channel(channels.next()?)?,
+			g: channel(channels.next()?)?,
+			b: channel(channels.next()?)?,
+		};
+		channels.next().is_none().then_some(color)
+	}
+}
+
+impl ColorQuery {
+	/// Write the query, terminated with `terminator`.
+	/// 
+	/// [`TerminalQuery::write_query`] uses [`Terminator::St`].
+	pub fn write_query_terminated(
+		&self, w: &mut impl fmt::Write, terminator: Terminator,
+	) -> fmt::Result {
+		w.write_str(crate::fe_seq::ESC_STR)?;
+		w.write_str("]")?;
+		self.write_target(w)?;
+		w.write_str(";?")?;
+		w.write_str(terminator.as_str())
+	}
+
+	fn write_target(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		match self.0 {
+			ColorTarget::Palette(n) => write!(w, "4;{n}"),
+			ColorTarget::Foreground => w.write_str("10"),
+			ColorTarget::Background => w.write_str("11"),
+			ColorTarget::Cursor => w.write_str("12"),
+		}
+	}
+}
+
+impl TerminalQuery for ColorQuery {
+	type Response = Rgb16;
+
+	#[inline(always)]
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		self.write_query_terminated(w, Terminator::St)
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b']')?;
+
+		let mut target = TargetBuf([0; 8], 0);
+		self.write_target(&mut target).map_err(|_| ParseError::Invalid)?;
+		let spec = payload.strip_prefix(&target.0[..target.1])
+			.and_then(move |rest| rest.strip_prefix(b";"))
+			.ok_or(ParseError::Invalid)?;
+
+		let color = Rgb16::parse_spec(spec).ok_or(ParseError::Invalid)?;
+		Ok((color, len))
+	}
+}
+
+/// Small buffer for formatting [`ColorTarget`]s.
+struct TargetBuf([u8; 8], usize);
+impl fmt::Write for TargetBuf {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let dest = self.0.get_mut(self.1..self.1 + s.len()).ok_or(fmt::Error)?;
+		dest.copy_from_slice(s.as_bytes());
+		self.1 += s.len();
+		Ok(())
+	}
+}
+
+/// Color scheme that the user prefers, which is either dark or light.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorScheme {
+	#[default]
+	Dark,
+	Light,
+}
+
+impl ColorScheme {
+	/// Return the color scheme that matches the background color `background`,
+	/// which is dark if its luminance is less than half of the maximum.
+	pub const fn from_background(background: Rgb16) -> Self {
+		let luminance = (
+			2126 * background.r as u64
+			+ 7152 * background.g as u64
+			+ 722 * background.b as u64
+		) / 10000;
+		if luminance < (u16::MAX / 2) as u64 { Self::Dark } else { Self::Light }
+	}
+
+	/// Return the [`ColorScheme`] with the parameter `param` of a report.
+	#[inline(always)]
+	pub const fn from_param(param: u16) -> Option<Self> {
+		match param {
+			1 => Some(Self::Dark),
+			2 => Some(Self::Light),
+			_ => None,
+		}
+	}
+
+	/// Parse a color scheme report, which is sent in response to
+	/// [`ColorSchemeQuery`] and when the color scheme changes while the
+	/// terminal's private mode `2031` is set, from the start of `bytes`.
+	pub fn parse_report(bytes: &[u8]) -> Result<(Self, usize), ParseError> {
+		let (csi, len) = expect_csi(bytes, Some(b'?'), b'n')?;
+		let mut params = csi.params();
+		if params.next() != Some(Some(997)) {
+			return Err(ParseError::Invalid)
+		}
+		let scheme = params.next().flatten()
+			.and_then(Self::from_param)
+			.ok_or(ParseError::Invalid)?;
+		Ok((scheme, len))
+	}
+
+	/// Detect the color scheme of the terminal, by writing a
+	/// [`ColorSchemeQuery`], a [`ColorQuery`] for the background and a
+	/// [`PrimaryAttributesQuery`] to `output`, and reading from `input` until
+	/// the response to the last one arrives.
+	/// 
+	/// The reported color scheme is preferred, and otherwise the scheme is
+	/// guessed from the background color with [`Self::from_background`]. This
+	/// returns `None` if the terminal reports neither. Other bytes that arrive
+	/// before then are discarded.
+	#[cfg(feature = "std")]
+	pub fn detect(
+		output: &mut impl std::io::Write, input: &mut impl std::io::Read,
+	) -> Result<Option<Self>, Error> {
+		let background = ColorQuery(ColorTarget::Background);
+		let mut demux = crate::Demux::new();
+		let mut query = std::string::String::new();
+		demux.send(&ColorSchemeQuery, &mut query)
+			.and_then(|_| demux.send(&background, &mut query))
+			.and_then(|_| demux.send(&PrimaryAttributesQuery, &mut query))?;
+		output.write_all(query.as_bytes())?;
+		output.flush()?;
+
+		let mut buf = [0; 256];
+		loop {
+			let read = input.read(&mut buf)?;
+			if read == 0 {
+				return Err(Error::Incomplete)
+			}
+			demux.advance(&buf[..read], |_| {});
+			if demux.take(&PrimaryAttributesQuery).is_some() {
+				break
+			}
+		}
+		let reported = demux.take(&ColorSchemeQuery);
+		let guessed = demux.take(&background).map(Self::from_background);
+		Ok(reported.or(guessed))
+	}
+}
+
+/// Query for the color scheme that the user prefers, which is supported by
+/// terminals that implement the private mode `2031` of contour.
+/// 
+/// Terminals that don't support it don't respond.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorSchemeQuery;
+
+impl TerminalQuery for ColorSchemeQuery {
+	type Response = ColorScheme;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		w.write_str("?996n")
+	}
+
+	#[inline(always)]
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		ColorScheme::parse_report(bytes)
+	}
+}
+
+/// xterm query for a termcap/terminfo capability, or **XTGETTCAP**.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcapQuery<'a> {
+	/// Name of the capability, such as `"colors"` or `"Tc"`.
+	pub name: &'a str,
+}
+
+/// Value of a capability reported in response to [`TcapQuery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcapValue {
+	bytes: [u8; Self::CAPACITY],
+	len: u8,
+}
+
+impl TcapValue {
+	/// Maximum length of a value that can be received.
+	pub const CAPACITY: usize = 128;
+
+	/// Return the decoded value.
+	#[inline(always)]
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes[..self.len as usize]
+	}
+
+	/// Return the decoded value as a string, if it's valid UTF-8.
+	#[inline(always)]
+	pub fn as_str(&self) -> Option<&str> {
+		core::str::from_utf8(self.as_bytes()).ok()
+	}
+}
+
+impl<'a> TcapQuery<'a> {
+	/// Write the query, terminated with `terminator`.
+	/// 
+	/// [`TerminalQuery::write_query`] uses [`Terminator::St`].
+	pub fn write_query_terminated(
+		&self, w: &mut impl fmt::Write, terminator: Terminator,
+	) -> fmt::Result {
+		w.write_str(crate::fe_seq::ESC_STR)?;
+		w.write_str("P+q")?;
+		write_hex(w, self.name.as_bytes())?;
+		w.write_str(terminator.as_str())
+	}
+}
+
+/// Iterate over the bytes encoded as hexadecimal digits in `hex`.
+fn decode_hex(hex: &[u8]) -> impl Iterator<Item = Option<u8>> + '_ {
+	hex.chunks(2).map(move |pair| match pair {
+		&[hi, lo] => Some(hex_digit(hi)? << 4 | hex_digit(lo)?),
+		_ => None,
+	})
+}
+
+impl<'a> TerminalQuery for TcapQuery<'a> {
+	/// The value of the capability, or `None` if the terminal doesn't know it.
+	/// 
+	/// Boolean capabilities that are set have an empty value.
+	type Response = Option<TcapValue>;
+
+	#[inline(always)]
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		self.write_query_terminated(w, Terminator::St)
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b'P')?;
+		let reply = match payload {
+			[b'1', b'+', b'r', reply @ ..] => reply,
+			[b'0', b'+', b'r', ..] => return Ok((None, len)),
+			_ => return Err(ParseError::Invalid),
+		};
+
+		let (name, value) = match reply.iter().position(|&b| b == b'=') {
+			Some(idx) => (&reply[..idx], &reply[idx + 1..]),
+			None => (reply, &[][..]),
+		};
+		let is_name = name.len() == 2 * self.name.len()
+			&& decode_hex(name).zip(self.name.bytes())
+				.all(move |(byte, expected)| byte == Some(expected));
+		if !is_name || value.len() > 2 * TcapValue::CAPACITY {
+			return Err(ParseError::Invalid)
+		}
+
+		let mut result = TcapValue {
+			bytes: [0; TcapValue::CAPACITY],
+			len: 0,
+		};
+		for byte in decode_hex(value) {
+			result.bytes[result.len as usize] = byte.ok_or(ParseError::Invalid)?;
+			result.len += 1;
+		}
+		Ok((Some(result), len))
+	}
+}
+
+/// Request Checksum of Rectangular Area, or **DECRQCRA**, query for a checksum
+/// of the characters in an area of the screen.
+/// 
+/// Coordinates are 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumQuery {
+	/// Identifier that the terminal includes in its response.
+	pub id: u16,
+	/// Number of the page, which is `1` for terminals with one page.
+	pub page: u16,
+	pub top: u16,
+	pub left: u16,
+	pub bottom: u16,
+	pub right: u16,
+}
+
+impl ChecksumQuery {
+	/// Create a query for the area with the specified corners on the first
+	/// page.
+	#[inline(always)]
+	pub const fn new(top: u16, left: u16, bottom: u16, right: u16) -> Self {
+		Self {
+			id: 1,
+			page: 1,
+			top,
+			left,
+			bottom,
+			right,
+		}
+	}
+}
+
+impl TerminalQuery for ChecksumQuery {
+	/// The checksum of the area.
+	type Response = u16;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		write!(
+			w, "{};{};{};{};{};{}*y",
+			self.id, self.page, self.top, self.left, self.bottom, self.right,
+		)
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b'P')?;
+		let idx = payload.iter()
+			.position(|&b| b == b'!')
+			.ok_or(ParseError::Invalid)?;
+		let (id, rest) = payload.split_at(idx);
+		let id = core::str::from_utf8(id).ok()
+			.and_then(|id| id.parse::<u16>().ok());
+		let digits = match rest {
+			[b'!', b'~', digits @ ..] if id == Some(self.id) => digits,
+			_ => return Err(ParseError::Invalid),
+		};
+		if digits.is_empty() || digits.len() > 4 {
+			return Err(ParseError::Invalid)
+		}
+
+		let mut checksum = 0u16;
+		for &digit in digits {
+			let digit = hex_digit(digit).ok_or(ParseError::Invalid)?;
+			checksum = (checksum << 4) | digit as u16;
+		}
+		Ok((checksum, len))
+	}
+}
+
+/// Query for the flags of kitty's keyboard protocol that are enabled.
+/// 
+/// Terminals that don't support the protocol don't respond.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardFlagsQuery;
+
+impl TerminalQuery for KeyboardFlagsQuery {
+	type Response = u16;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		Csi::write_begin(w)?;
+		w.write_str("?u")
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (csi, len) = expect_csi(bytes, Some(b'?'), b'u')?;
+		Ok((csi.params().next().flatten().unwrap_or(0), len))
+	}
+}
+
+/// Query for support of kitty's graphics protocol, which asks the terminal to
+/// check a 1x1 image without displaying it.
+/// 
+/// The response is `true` if the image was accepted. Terminals that don't
+/// support the protocol don't respond.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphicsQuery;
+
+impl GraphicsQuery {
+	/// Identifier of the image that is checked.
+	const ID: &'static [u8] = b"31";
+}
+
+impl TerminalQuery for GraphicsQuery {
+	type Response = bool;
+
+	fn write_query(&self, w: &mut impl fmt::Write) -> fmt::Result {
+		w.write_str(crate::fe_seq::ESC_STR)?;
+		w.write_str("_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA")?;
+		w.write_str(Terminator::St.as_str())
+	}
+
+	fn parse_response(
+		&self, bytes: &[u8],
+	) -> Result<(Self::Response, usize), ParseError> {
+		let (payload, len) = parse_string(bytes, b'_')?;
+		let [b'G', b'i', b'=', rest @ ..] = payload else {
+			return Err(ParseError::Invalid)
+		};
+		let Some(message) = rest.strip_prefix(Self::ID)
+			.and_then(move |rest| rest.strip_prefix(b";"))
+		else {
+			return Err(ParseError::Invalid)
+		};
+		Ok((message == b"OK", len))
+	}
+}
@@ -0,0 +1,418 @@
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use std::{
+	io::{
+		self, BufRead, Error, ErrorKind, Write,
+	},
+	time::{
+		Instant, SystemTime,
+	},
+};
+
+/// Output written at some point during a [`Recording`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CastEvent {
+	/// Time since the start of the recording.
+	pub time: Duration,
+	/// Data that was written.
+	pub data: String,
+}
+
+/// Terminal session that can be saved and loaded in the
+/// [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+	/// Width of the terminal, in columns.
+	pub width: u16,
+	/// Height of the terminal, in rows.
+	pub height: u16,
+	/// Unix timestamp of the start of the recording.
+	pub timestamp: Option<u64>,
+	/// Output, in the order in which it was written.
+	pub events: Vec<CastEvent>,
+}
+
+impl Recording {
+	/// Create an empty [`Recording`] of a terminal with the specified size.
+	#[inline(always)]
+	pub const fn new(width: u16, height: u16) -> Self {
+		Self {
+			width,
+			height,
+			timestamp: None,
+			events: Vec::new(),
+		}
+	}
+
+	/// Return the time at which the last event happened.
+	pub fn duration(&self) -> Duration {
+		self.events.last().map_or(Duration::ZERO, move |event| event.time)
+	}
+
+	/// Write `self` in the asciicast v2 format.
+	#[cfg(feature = "std")]
+	pub fn write_asciicast(&self, w: &mut impl Write) -> io::Result<()> {
+		write!(
+			w, "{{\"version\": 2, \"width\": {}, \"height\": {}",
+			self.width, self.height,
+		)?;
+		if let Some(timestamp) = self.timestamp {
+			write!(w, ", \"timestamp\": {timestamp}")?;
+		}
+		w.write_all(b"}\n")?;
+
+		for event in self.events.iter() {
+			write!(w, "[{:.6}, \"o\", ", event.time.as_secs_f64())?;
+			write_json_str(w, &event.data)?;
+			w.write_all(b"]\n")?;
+		}
+		Ok(())
+	}
+
+	/// Read a recording in the asciicast v2 format.
+	/// 
+	/// Events other than output are ignored.
+	#[cfg(feature = "std")]
+	pub fn read_asciicast(r: impl BufRead) -> io::Result<Self> {
+		let invalid = move || {
+			Error::new(ErrorKind::InvalidData, "invalid asciicast")
+		};
+		let mut lines = r.lines();
+
+		let header = lines.next().ok_or_else(invalid)??;
+		let mut recording = Self::new(0, 0);
+		let mut json = Json::new(&header);
+		let mut version = None;
+		json.object(|json, key| {
+			match key.as_str() {
+				"version" => version = Some(json.number()?),
+				"width" => recording.width = json.number()? as u16,
+				"height" => recording.height = json.number()? as u16,
+				"timestamp" => recording.timestamp = Some(json.number()? as u64),
+				_ => json.skip_value()?,
+			}
+			Some(())
+		}).ok_or_else(invalid)?;
+		if version != Some(2.0) {
+			return Err(Error::new(ErrorKind::InvalidData, "unsupported version"))
+		}
+
+		for line in lines {
+			let line = line?;
+			if line.trim().is_empty() {
+				continue
+			}
+
+			let mut json = Json::new(&line);
+			let event = (move || {
+				json.expect(b'[')?;
+				let time = json.number()?;
+				json.expect(b',')?;
+				let kind = json.string()?;
+				json.expect(b',')?;
+				let data = json.string()?;
+				json.expect(b']')?;
+				let time = Duration::try_from_secs_f64(time).ok()?;
+				Some((kind == "o").then_some(CastEvent { time, data }))
+			})().ok_or_else(invalid)?;
+			recording.events.extend(event);
+		}
+
+		Ok(recording)
+	}
+
+	/// Write the recorded output to `w`, waiting between events as long as
+	/// they were apart when they were recorded.
+	/// 
+	/// `speed` is a multiplier for the playback speed, which must be greater
+	/// than zero, or else this returns an error of kind
+	/// [`ErrorKind::InvalidInput`]. The same error is returned when an event
+	/// is reached that would be too late to wait for at that speed.
+	#[cfg(feature = "std")]
+	pub fn replay(&self, w: &mut impl Write, speed: f64) -> io::Result<()> {
+		if speed.is_nan() || speed <= 0.0 {
+			return Err(Error::new(ErrorKind::InvalidInput, "speed must be positive"))
+		}
+		let start = Instant::now();
+		for event in self.events.iter() {
+			let at = Duration::try_from_secs_f64(event.time.as_secs_f64() / speed)
+				.map_err(|_| Error::new(ErrorKind::InvalidInput, "event too late"))?;
+			if let Some(delay) = at.checked_sub(start.elapsed()) {
+				std::thread::sleep(delay);
+			}
+			w.write_all(event.data.as_bytes())?;
+			w.flush()?;
+		}
+		Ok(())
+	}
+}
+
+/// Writer that passes everything through to another writer, recording the
+/// time at which it was written.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Recorder<W> {
+	inner: W,
+	start: Instant,
+	recording: Recording,
+	/// Bytes at the end of the last write that are an incomplete UTF-8
+	/// sequence.
+	pending: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Recorder<W> {
+	/// Start recording output written to `inner`, which is a terminal with the
+	/// specified size.
+	pub fn new(inner: W, width: u16, height: u16) -> Self {
+		let timestamp = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH).ok()
+			.map(move |time| time.as_secs());
+		Self {
+			inner,
+			start: Instant::now(),
+			recording: Recording {
+				timestamp,
+				..Recording::new(width, height)
+			},
+			pending: Vec::new(),
+		}
+	}
+
+	/// Return the output recorded so far.
+	#[inline(always)]
+	pub fn recording(&self) -> &Recording {
+		&self.recording
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Stop recording, returning the inner writer and the recording.
+	pub fn finish(mut self) -> (W, Recording) {
+		if !self.pending.is_empty() {
+			let data = String::from_utf8_lossy(&self.pending).into_owned();
+			self.push(data);
+		}
+		(self.inner, self.recording)
+	}
+
+	fn push(&mut self, data: String) {
+		self.recording.events.push(CastEvent {
+			time: self.start.elapsed(),
+			data,
+		});
+	}
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for Recorder<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let len = self.inner.write(buf)?;
+		self.pending.extend_from_slice(&buf[..len]);
+
+		// Keep an incomplete UTF-8 sequence at the end for the next write.
+		let valid_len = match core::str::from_utf8(&self.pending) {
+			Ok(..) => self.pending.len(),
+			Err(e) if e.error_len().is_none() => e.valid_up_to(),
+			Err(..) => self.pending.len(),
+		};
+		if valid_len > 0 {
+			let rest = self.pending.split_off(valid_len);
+			let data = String::from_utf8_lossy(&self.pending).into_owned();
+			self.pending = rest;
+			self.push(data);
+		}
+		Ok(len)
+	}
+
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+/// Write `s` as a JSON string.
+#[cfg(feature = "std")]
+fn write_json_str(w: &mut impl Write, s: &str) -> io::Result<()> {
+	w.write_all(b"\"")?;
+	let mut start = 0;
+	for (idx, c) in s.char_indices() {
+		let escaped = match c {
+			'"' => "\\\"",
+			'\\' => "\\\\",
+			'\n' => "\\n",
+			'\r' => "\\r",
+			'\t' => "\\t",
+			'\0'..='\x1f' | '\x7f' => "",
+			_ => continue,
+		};
+		w.write_all(&s.as_bytes()[start..idx])?;
+		if escaped.is_empty() {
+			write!(w, "\\u{:04x}", c as u32)?;
+		} else {
+			w.write_all(escaped.as_bytes())?;
+		}
+		start = idx + c.len_utf8();
+	}
+	w.write_all(&s.as_bytes()[start..])?;
+	w.write_all(b"\"")
+}
+
+/// Minimal reader of JSON values on a single line.
+#[cfg(feature = "std")]
+struct Json<'a> {
+	s: &'a [u8],
+}
+
+#[cfg(feature = "std")]
+impl<'a> Json<'a> {
+	fn new(s: &'a str) -> Self {
+		Self {
+			s: s.as_bytes(),
+		}
+	}
+
+	fn skip_ws(&mut self) {
+		while let [b' ' | b'\t' | b'\r' | b'\n', rest @ ..] = self.s {
+			self.s = rest;
+		}
+	}
+
+	fn peek(&mut self) -> Option<u8> {
+		self.skip_ws();
+		self.s.first().copied()
+	}
+
+	fn expect(&mut self, byte: u8) -> Option<()> {
+		(self.peek()? == byte).then(|| self.s = &self.s[1..])
+	}
+
+	fn number(&mut self) -> Option<f64> {
+		self.skip_ws();
+		let len = self.s.iter()
+			.position(|b| {
+				!matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')
+			})
+			.unwrap_or(self.s.len());
+		let (number, rest) = self.s.split_at(len);
+		self.s = rest;
+		core::str::from_utf8(number).ok()?.parse().ok()
+	}
+
+	fn string(&mut self) -> Option<String> {
+		self.expect(b'"')?;
+		let mut result = Vec::new();
+		loop {
+			let (&byte, rest) = self.s.split_first()?;
+			self.s = rest;
+			match byte {
+				b'"' => return String::from_utf8(result).ok(),
+				b'\\' => {
+					let (&escape, rest) = self.s.split_first()?;
+					self.s = rest;
+					let c = match escape {
+						b'"' => '"',
+						b'\\' => '\\',
+						b'/' => '/',
+						b'b' => '\x08',
+						b'f' => '\x0c',
+						b'n' => '\n',
+						b'r' => '\r',
+						b't' => '\t',
+						b'u' => self.unicode_escape()?,
+						_ => return None,
+					};
+					let mut buf = [0; 4];
+					result.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+				}
+				_ => result.push(byte),
+			}
+		}
+	}
+
+	/// Parse the digits of a `\u` escape, including a following low surrogate.
+	fn unicode_escape(&mut self) -> Option<char> {
+		let hex4 = move |json: &mut Self| {
+			let digits = json.s.get(..4)?;
+			json.s = &json.s[4..];
+			u32::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()
+		};
+		let high = hex4(self)?;
+		if (0xd800..0xdc00).contains(&high) {
+			self.s = self.s.strip_prefix(b"\\u")?;
+			let low = hex4(self)?;
+			if !(0xdc00..0xe000).contains(&low) {
+				return None
+			}
+			let c = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+			char::from_u32(c)
+		} else {
+			char::from_u32(high)
+		}
+	}
+
+	/// Parse an object, calling `f` to parse the value for each key.
+	fn object(
+		&mut self, mut f: impl FnMut(&mut Self, String) -> Option<()>,
+	) -> Option<()> {
+		self.expect(b'{')?;
+		if self.expect(b'}').is_some() {
+			return Some(())
+		}
+		loop {
+			let key = self.string()?;
+			self.expect(b':')?;
+			f(self, key)?;
+			match self.peek()? {
+				b',' => self.s = &self.s[1..],
+				b'}' => {
+					self.s = &self.s[1..];
+					return Some(())
+				}
+				_ => return None,
+			}
+		}
+	}
+
+	fn skip_value(&mut self) -> Option<()> {
+		match self.peek()? {
+			b'"' => self.string().map(drop),
+			b'{' => self.object(move |json, _| json.skip_value()),
+			b'[' => {
+				self.expect(b'[')?;
+				if self.expect(b']').is_some() {
+					return Some(())
+				}
+				loop {
+					self.skip_value()?;
+					match self.peek()? {
+						b',' => self.s = &self.s[1..],
+						b']' => {
+							self.s = &self.s[1..];
+							return Some(())
+						}
+						_ => return None,
+					}
+				}
+			}
+			b't' | b'f' | b'n' => {
+				let len = self.s.iter()
+					.position(|b| !b.is_ascii_alphabetic())
+					.unwrap_or(self.s.len());
+				self.s = &self.s[len..];
+				Some(())
+			}
+			_ => self.number().map(drop),
+		}
+	}
+}
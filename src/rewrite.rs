@@ -0,0 +1,117 @@
+use crate::scan::{
+	Scanner, Token
+};
+
+use std::io::{
+	self, Write,
+};
+
+/// What a [`Rewriter`] does with a [`Token`] after it was passed to the
+/// callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rewrite {
+	/// Write the token unchanged.
+	Keep,
+	/// Don't write the token.
+	/// 
+	/// To replace the token, the callback writes the replacement to the
+	/// writer that it was passed before returning this.
+	Drop,
+}
+
+/// Writer that splits the bytes written to it into [`Token`]s and passes them
+/// to a callback, which decides what is written to the inner writer instead.
+/// 
+/// Tokens that are kept are written exactly as they were received, so a
+/// callback that keeps every token doesn't change the stream. This can be
+/// used to strip OSC 52 sequences from untrusted output, or to downgrade
+/// colors before recording it.
+/// 
+/// Sequences that are split between writes are held until the rest of them
+/// arrive, and are passed to the callback whole. Call [`Self::finish`] at the
+/// end of the stream to pass the bytes of an incomplete sequence on.
+#[derive(Debug)]
+pub struct Rewriter<W: Write, F> {
+	inner: W,
+	rewrite: F,
+	scanner: Scanner,
+}
+
+impl<W, F> Rewriter<W, F>
+where
+	W: Write,
+	F: FnMut(Token<'_>, &mut W) -> io::Result<Rewrite>,
+{
+	/// Create a [`Rewriter`] that writes to `inner` what `rewrite` decides.
+	#[inline(always)]
+	pub const fn new(inner: W, rewrite: F) -> Self {
+		Self {
+			inner,
+			rewrite,
+			scanner: Scanner::new(),
+		}
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	/// Pass any buffered bytes to the callback as [`Token::Unrecognized`],
+	/// flush the inner writer and return it.
+	pub fn finish(mut self) -> io::Result<W> {
+		let mut result = Ok(());
+		self.scanner.flush(|token| {
+			if result.is_ok() {
+				result = apply(&mut self.inner, &mut self.rewrite, token);
+			}
+		});
+		result?;
+		self.inner.flush()?;
+		Ok(self.inner)
+	}
+}
+
+/// Pass `token` to `rewrite`, writing it to `inner` if it's kept.
+fn apply<W: Write>(
+	inner: &mut W,
+	rewrite: &mut impl FnMut(Token<'_>, &mut W) -> io::Result<Rewrite>,
+	token: Token<'_>,
+) -> io::Result<()> {
+	match rewrite(token, inner)? {
+		Rewrite::Keep => token.write_to(inner)?,
+		Rewrite::Drop => {}
+	}
+	Ok(())
+}
+
+impl<W, F> Write for Rewriter<W, F>
+where
+	W: Write,
+	F: FnMut(Token<'_>, &mut W) -> io::Result<Rewrite>,
+{
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut result = Ok(());
+		self.scanner.feed(buf, |token| {
+			if result.is_ok() {
+				result = apply(&mut self.inner, &mut self.rewrite, token);
+			}
+		});
+		result.map(move |_| buf.len())
+	}
+
+	/// Flush the inner writer.
+	/// 
+	/// Bytes of an incomplete sequence stay buffered.
+	#[inline(always)]
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
@@ -0,0 +1,253 @@
+use crate::{
+	csi::Csi,
+	parse::{
+		parse_string, ParseError, BEL
+	},
+};
+
+/// Piece of a stream of bytes written to a terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+	/// Printable text.
+	Text(&'a str),
+	/// C0 control character other than the escape character.
+	Control(u8),
+	/// Complete CSI sequence.
+	Csi(Csi<'a>),
+	/// Complete escape sequence other than a CSI sequence, including the
+	/// escape character.
+	Escape(&'a [u8]),
+	/// Bytes that don't form a valid sequence, or part of a sequence that
+	/// is too long to be buffered.
+	Unrecognized(&'a [u8]),
+}
+
+impl<'a> Token<'a> {
+	/// Parse one token from the start of `bytes`, returning it along with the
+	/// number of bytes it occupies.
+	/// 
+	/// This only fails with [`ParseError::Incomplete`]. Text is split at the
+	/// end of `bytes`, unless it ends in the middle of a UTF-8 sequence.
+	pub fn parse(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		match Self::parse_valid(bytes) {
+			Err(ParseError::Invalid) => {
+				let len = invalid_len(bytes);
+				Ok((Self::Unrecognized(&bytes[..len]), len))
+			}
+			result => result,
+		}
+	}
+
+	/// Write the bytes that this token was parsed from.
+	#[cfg(feature = "std")]
+	pub fn write_to(
+		&self, w: &mut impl std::io::Write,
+	) -> Result<(), crate::Error> {
+		match self {
+			Self::Text(text) => w.write_all(text.as_bytes())?,
+			Self::Control(byte) => w.write_all(&[*byte])?,
+			Self::Csi(csi) => crate::FeSeq::Csi(*csi).write_to(w)?,
+			Self::Escape(bytes) | Self::Unrecognized(bytes) => {
+				w.write_all(bytes)?
+			}
+		}
+		Ok(())
+	}
+
+	fn parse_valid(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		match bytes {
+			[] => Err(ParseError::Incomplete),
+			[0x1b, rest @ ..] => match rest {
+				[] => Err(ParseError::Incomplete),
+				[b'[', ..] => {
+					Csi::parse(bytes).map(move |(csi, len)| (Self::Csi(csi), len))
+				}
+				[intro @ (b']' | b'P' | b'X' | b'^' | b'_'), ..] => {
+					let (.., len) = parse_string(bytes, *intro)?;
+					Ok((Self::Escape(&bytes[..len]), len))
+				}
+				_ => {
+					// `nF` sequences have intermediate bytes before the final byte.
+					let inter = rest.iter()
+						.take_while(move |&&b| matches!(b, 0x20..=0x2f))
+						.count();
+					match rest.get(inter) {
+						Some(0x30..=0x7e) => {
+							Ok((Self::Escape(&bytes[..inter + 2]), inter + 2))
+						}
+						Some(..) => Err(ParseError::Invalid),
+						None => Err(ParseError::Incomplete),
+					}
+				}
+			},
+			[byte @ (0x00..=0x1f | 0x7f), ..] => Ok((Self::Control(*byte), 1)),
+			_ => {
+				let len = bytes.iter()
+					.position(move |&b| matches!(b, 0x00..=0x1f | 0x7f))
+					.unwrap_or(bytes.len());
+				match core::str::from_utf8(&bytes[..len]) {
+					Ok(text) => Ok((Self::Text(text), len)),
+					Err(e) if e.valid_up_to() > 0 => {
+						let len = e.valid_up_to();
+						// SAFETY: `from_utf8` validated these bytes.
+						let text = unsafe {
+							core::str::from_utf8_unchecked(&bytes[..len])
+						};
+						Ok((Self::Text(text), len))
+					}
+					Err(e) if e.error_len().is_none() && len == bytes.len() => {
+						Err(ParseError::Incomplete)
+					}
+					Err(..) => Err(ParseError::Invalid),
+				}
+			}
+		}
+	}
+}
+
+/// Return the length of the invalid bytes at the start of `bytes`.
+fn invalid_len(bytes: &[u8]) -> usize {
+	match bytes {
+		[0x1b, b'[', rest @ ..] => {
+			2 + rest.iter().take_while(|&&b| matches!(b, 0x20..=0x3f)).count()
+		}
+		[0x1b, ..] => 1,
+		_ => match core::str::from_utf8(bytes) {
+			Err(e) => e.error_len().unwrap_or(1),
+			Ok(..) => 1,
+		},
+	}
+}
+
+/// Kind of sequence that is being passed through after it overflowed the
+/// scanner's buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overflow {
+	Csi,
+	String {
+		after_esc: bool,
+	},
+}
+
+/// Splitter of a stream of bytes written to a terminal into [`Token`]s.
+/// 
+/// Bytes can be fed in chunks of any size. Sequences that are split between
+/// chunks are kept until the rest of them arrive.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+	buf: [u8; Self::CAPACITY],
+	len: usize,
+	overflow: Option<Overflow>,
+}
+
+impl Default for Scanner {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Scanner {
+	/// Maximum length of a sequence that can be buffered.
+	pub const CAPACITY: usize = 256;
+
+	/// Create a new [`Scanner`] with no buffered bytes.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			buf: [0; Self::CAPACITY],
+			len: 0,
+			overflow: None,
+		}
+	}
+
+	/// Return `true` if bytes of an incomplete token are buffered.
+	#[inline(always)]
+	pub const fn has_pending(&self) -> bool {
+		self.len > 0 || self.overflow.is_some()
+	}
+
+	/// Feed `bytes` to the scanner, calling `f` with every complete token.
+	pub fn feed(&mut self, mut bytes: &[u8], mut f: impl FnMut(Token<'_>)) {
+		// Complete the buffered token first.
+		while self.has_pending() {
+			let Some((&byte, rest)) = bytes.split_first() else {
+				return
+			};
+			bytes = rest;
+			self.push(byte, &mut f);
+		}
+
+		while !bytes.is_empty() {
+			match Token::parse(bytes) {
+				Ok((token, len)) => {
+					f(token);
+					bytes = &bytes[len..];
+				}
+				Err(..) => {
+					for &byte in bytes {
+						self.push(byte, &mut f);
+					}
+					return
+				}
+			}
+		}
+	}
+
+	/// Deliver any buffered bytes as [`Token::Unrecognized`].
+	pub fn flush(&mut self, mut f: impl FnMut(Token<'_>)) {
+		if self.len > 0 {
+			f(Token::Unrecognized(&self.buf[..self.len]));
+		}
+		self.len = 0;
+		self.overflow = None;
+	}
+
+	fn push(&mut self, byte: u8, f: &mut impl FnMut(Token<'_>)) {
+		if self.len == Self::CAPACITY {
+			let bytes = &self.buf[..self.len];
+			self.overflow = match (self.overflow, bytes) {
+				(Some(Overflow::Csi), ..) | (None, [0x1b, b'[', ..]) => {
+					Some(Overflow::Csi)
+				}
+				(.., [.., last]) => Some(Overflow::String {
+					after_esc: *last == 0x1b,
+				}),
+				(overflow, []) => overflow,
+			};
+			f(Token::Unrecognized(bytes));
+			self.len = 0;
+		}
+		self.buf[self.len] = byte;
+		self.len += 1;
+
+		if let Some(overflow) = self.overflow {
+			let is_end = match overflow {
+				Overflow::Csi => matches!(byte, 0x40..=0x7e),
+				Overflow::String { after_esc } => {
+					self.overflow = Some(Overflow::String {
+						after_esc: byte == 0x1b,
+					});
+					byte == BEL || (after_esc && byte == b'\\')
+				}
+			};
+			if is_end {
+				f(Token::Unrecognized(&self.buf[..self.len]));
+				self.len = 0;
+				self.overflow = None;
+			}
+			return
+		}
+
+		while self.len > 0 {
+			match Token::parse(&self.buf[..self.len]) {
+				Ok((token, len)) => {
+					f(token);
+					self.buf.copy_within(len..self.len, 0);
+					self.len -= len;
+				}
+				Err(..) => break,
+			}
+		}
+	}
+}
@@ -0,0 +1,100 @@
+use core::fmt::{
+	Display, Formatter, Result as FmtResult,
+};
+
+/// Sequences that are displayed one after another, such as hiding the cursor,
+/// clearing the screen and moving the cursor home, so that they can be passed
+/// around and written as one value.
+/// 
+/// The sequences are a tuple of up to 12 [`Display`] values of any types, or
+/// an array or slice of values of one type, such as
+/// `Seq((consts::HIDE_CURSOR, EraseDisplay::All, consts::HOME))`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Seq<T>(pub T);
+
+impl<T: Display, const N: usize> Display for Seq<[T; N]> {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		Seq(&self.0[..]).fmt(f)
+	}
+}
+
+impl<T: Display> Display for Seq<&[T]> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		self.0.iter().try_for_each(move |item| item.fmt(f))
+	}
+}
+
+macro_rules! seq_tuple {
+	($($name:ident),+) => {
+		impl<$($name: Display),+> Display for Seq<($($name,)+)> {
+			#[allow(non_snake_case)]
+			fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+				let ($($name,)+) = &self.0;
+				$($name.fmt(f)?;)+
+				Ok(())
+			}
+		}
+	};
+}
+
+seq_tuple!(A);
+seq_tuple!(A, B);
+seq_tuple!(A, B, C);
+seq_tuple!(A, B, C, D);
+seq_tuple!(A, B, C, D, E);
+seq_tuple!(A, B, C, D, E, F);
+seq_tuple!(A, B, C, D, E, F, G);
+seq_tuple!(A, B, C, D, E, F, G, H);
+seq_tuple!(A, B, C, D, E, F, G, H, I);
+seq_tuple!(A, B, C, D, E, F, G, H, I, J);
+seq_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+seq_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+/// Sequence that is only displayed if a condition is `true`, such as
+/// `If(colors_enabled, style)`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct If<T>(pub bool, pub T);
+
+impl<T: Display> Display for If<T> {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		match self {
+			Self(true, item) => item.fmt(f),
+			Self(false, ..) => Ok(()),
+		}
+	}
+}
+
+/// One of two sequences of different types, which is displayed as the one
+/// that it contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<A, B> {
+	/// Sequence of the first type.
+	Left(A),
+	/// Sequence of the second type.
+	Right(B),
+}
+
+impl<A, B> Either<A, B> {
+	/// Create [`Either::Left`] with `left` if `condition` is `true`, or
+	/// [`Either::Right`] with `right` otherwise.
+	#[inline(always)]
+	pub fn new(condition: bool, left: A, right: B) -> Self {
+		match condition {
+			true => Self::Left(left),
+			false => Self::Right(right),
+		}
+	}
+}
+
+impl<A: Display, B: Display> Display for Either<A, B> {
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		match self {
+			Self::Left(left) => left.fmt(f),
+			Self::Right(right) => right.fmt(f),
+		}
+	}
+}
@@ -1,4 +1,12 @@
-use crate::csi::Csi;
+use crate::{
+	csi::{
+		Csi, CsiParam, CsiSequence
+	},
+	dec_len,
+	parse::{
+		CsiParams, FromSequence, ParseError
+	},
+};
 
 use core::fmt;
 
@@ -55,79 +63,130 @@ pub enum Sgr {
 }
 
 impl Sgr {
-	pub fn write_params_to(&self, w: &mut impl fmt::Write) -> fmt::Result {
+	/// Maximum length of the parameters written by [`Self::write_params_to`].
+	pub const MAX_PARAMS_LEN: usize = 3 + SgrColor::MAX_LEN;
+	/// Maximum length of the complete sequence displayed by [`Sgr`].
+	pub const MAX_LEN: usize =
+		Csi::BEGIN_LEN + Self::MAX_PARAMS_LEN + Csi::FINAL_STR.len();
+
+	/// Return the numeric code of this SGR, which is the first of its
+	/// parameters.
+	pub const fn code(&self) -> SgrCode {
+		SgrCode(match self {
+			Self::Reset => 0,
+			Self::WeightBoldOn => 1,
+			Self::WeightThinOn => 2,
+			Self::ItalicOn => 3,
+			Self::UnderlineSingle => 4,
+			Self::BlinkSlow => 5,
+			Self::BlinkRapid => 6,
+			Self::Invert => 7,
+			Self::ConcealOn => 8,
+			Self::StrikethroughOn => 9,
+			Self::FontPrimary => 10,
+			Self::Font1 => 11,
+			Self::Font2 => 12,
+			Self::Font3 => 13,
+			Self::Font4 => 14,
+			Self::Font5 => 15,
+			Self::Font6 => 16,
+			Self::Font7 => 17,
+			Self::Font8 => 18,
+			Self::Font9 => 19,
+			Self::Fraktur => 20,
+			Self::UnderlineDouble => 21,
+			Self::WeightAllOff => 22,
+			Self::ItalicOff => 23,
+			Self::UnderlineNone => 24,
+			Self::BlinkNone => 25,
+			Self::ProportionalSpacingOn => 26,
+			Self::ReversedOff => 27,
+			Self::ConcealOff => 28,
+			Self::StrikethroughOff => 29,
+			Self::Foreground1 => 30,
+			Self::Foreground2 => 31,
+			Self::Foreground3 => 32,
+			Self::Foreground4 => 33,
+			Self::Foreground5 => 34,
+			Self::Foreground6 => 35,
+			Self::Foreground7 => 36,
+			Self::Foreground8 => 37,
+			Self::ForegroundDefault => 39,
+			Self::Background1 => 40,
+			Self::Background2 => 41,
+			Self::Background3 => 42,
+			Self::Background4 => 43,
+			Self::Background5 => 44,
+			Self::Background6 => 45,
+			Self::Background7 => 46,
+			Self::Background8 => 47,
+			Self::BackgroundDefault => 49,
+			Self::ProportionalSpacingOff => 50,
+			Self::FrameFramed => 51,
+			Self::FrameEncircled => 52,
+			Self::OverlinedOn => 53,
+			Self::FrameNone => 54,
+			Self::OverlinedOff => 55,
+			Self::UnderlineColorDefault => 59,
+			Self::Foreground(..) => 38,
+			Self::Background(..) => 48,
+			Self::UnderlineColor(..) => 58,
+		})
+	}
+
+	/// Return the length of the parameters written by
+	/// [`Self::write_params_to`].
+	pub const fn params_len(&self) -> usize {
+		match self {
+			Self::Foreground(color) | Self::Background(color)
+			| Self::UnderlineColor(color) => 3 + color.encoded_len(),
+			_ => dec_len(self.code().get() as _),
+		}
+	}
+
+	/// Return the length of the complete sequence displayed by `self`.
+	pub const fn encoded_len(&self) -> usize {
+		Csi::BEGIN_LEN + self.params_len() + Csi::FINAL_STR.len()
+	}
+
+	/// Write the parameters of this SGR, without the introducer or the final
+	/// byte.
+	pub fn write_params_to(&self, w: &mut (impl fmt::Write + ?Sized)) -> fmt::Result {
 		match self {
-			Self::Reset => w.write_str("0"),
-			Self::WeightBoldOn => w.write_str("1"),
-			Self::WeightThinOn => w.write_str("2"),
-			Self::ItalicOn => w.write_str("3"),
-			Self::UnderlineSingle => w.write_str("4"),
-			Self::BlinkSlow => w.write_str("5"),
-			Self::BlinkRapid => w.write_str("6"),
-			Self::Invert => w.write_str("7"),
-			Self::ConcealOn => w.write_str("8"),
-			Self::StrikethroughOn => w.write_str("9"),
-			Self::FontPrimary => w.write_str("10"),
-			Self::Font1 => w.write_str("11"),
-			Self::Font2 => w.write_str("12"),
-			Self::Font3 => w.write_str("13"),
-			Self::Font4 => w.write_str("14"),
-			Self::Font5 => w.write_str("15"),
-			Self::Font6 => w.write_str("16"),
-			Self::Font7 => w.write_str("17"),
-			Self::Font8 => w.write_str("18"),
-			Self::Font9 => w.write_str("19"),
-			Self::Fraktur => w.write_str("20"),
-			Self::UnderlineDouble => w.write_str("21"),
-			Self::WeightAllOff => w.write_str("22"),
-			Self::ItalicOff => w.write_str("23"),
-			Self::UnderlineNone => w.write_str("24"),
-			Self::BlinkNone => w.write_str("25"),
-			Self::ProportionalSpacingOn => w.write_str("26"),
-			Self::ReversedOff => w.write_str("27"),
-			Self::ConcealOff => w.write_str("28"),
-			Self::StrikethroughOff => w.write_str("29"),
-			Self::Foreground1 => w.write_str("30"),
-			Self::Foreground2 => w.write_str("31"),
-			Self::Foreground3 => w.write_str("32"),
-			Self::Foreground4 => w.write_str("33"),
-			Self::Foreground5 => w.write_str("34"),
-			Self::Foreground6 => w.write_str("35"),
-			Self::Foreground7 => w.write_str("36"),
-			Self::Foreground8 => w.write_str("37"),
 			Self::Foreground(color) => {
 				w.write_str("38;")?;
 				write!(w, "{color}")
 			}
-			Self::ForegroundDefault => w.write_str("39"),
-			Self::Background1 => w.write_str("40"),
-			Self::Background2 => w.write_str("41"),
-			Self::Background3 => w.write_str("42"),
-			Self::Background4 => w.write_str("43"),
-			Self::Background5 => w.write_str("44"),
-			Self::Background6 => w.write_str("45"),
-			Self::Background7 => w.write_str("46"),
-			Self::Background8 => w.write_str("47"),
 			Self::Background(color) => {
 				w.write_str("48;")?;
 				write!(w, "{color}")
 			}
-			Self::BackgroundDefault => w.write_str("49"),
-			Self::ProportionalSpacingOff => w.write_str("50"),
-			Self::FrameFramed => w.write_str("51"),
-			Self::FrameEncircled => w.write_str("52"),
-			Self::OverlinedOn => w.write_str("53"),
-			Self::FrameNone => w.write_str("54"),
-			Self::OverlinedOff => w.write_str("55"),
 			Self::UnderlineColor(color) => {
 				w.write_str("58;")?;
 				write!(w, "{color}")
 			}
-			Self::UnderlineColorDefault => w.write_str("59"),
+			_ => write!(w, "{}", self.code()),
 		}
 	}
 }
 
+impl CsiSequence for Sgr {
+	#[inline(always)]
+	fn write_params_to(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+		Self::write_params_to(self, w)
+	}
+
+	#[inline(always)]
+	fn final_str(&self) -> &'static str {
+		Csi::FINAL_STR
+	}
+
+	#[inline(always)]
+	fn is_chainable(&self) -> bool {
+		true
+	}
+}
+
 impl fmt::Display for Sgr {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		Csi::write_begin(f)?;
@@ -136,6 +195,18 @@ impl fmt::Display for Sgr {
 	}
 }
 
+impl<'a> FromSequence<'a> for Sgr {
+	/// Parse an SGR sequence with exactly one [`Sgr`].
+	fn parse_sequence(bytes: &'a [u8]) -> Result<(Self, usize), ParseError> {
+		let (csi, len) = Csi::parse(bytes)?;
+		let mut params = Self::parse_csi(&csi).ok_or(ParseError::Invalid)?;
+		match (params.next(), params.next()) {
+			(Some(sgr), None) => Ok((sgr, len)),
+			_ => Err(ParseError::Invalid),
+		}
+	}
+}
+
 /// [`Sgr::Foreground`] and [`Sgr::Background`] parameters.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SgrColor {
@@ -143,6 +214,21 @@ pub enum SgrColor {
 	Rgb(u8, u8, u8),
 }
 
+impl SgrColor {
+	/// Maximum length of the parameters displayed by [`SgrColor`].
+	pub const MAX_LEN: usize = Self::Rgb(u8::MAX, u8::MAX, u8::MAX).encoded_len();
+
+	/// Return the length of the parameters displayed by `self`.
+	pub const fn encoded_len(&self) -> usize {
+		match *self {
+			Self::Table(n) => 2 + dec_len(n as _),
+			Self::Rgb(r, g, b) => {
+				4 + dec_len(r as _) + dec_len(g as _) + dec_len(b as _)
+			}
+		}
+	}
+}
+
 impl fmt::Display for SgrColor {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
@@ -151,3 +237,309 @@ impl fmt::Display for SgrColor {
 		}
 	}
 }
+
+/// Levels of each channel in the 6×6×6 color cube of the 256-color table.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Return the color `n` of xterm's default 256-color table, as red, green and
+/// blue.
+/// 
+/// Colors `0` to `15` are xterm's defaults, which many terminals and themes
+/// change. Colors `16` to `231` are a 6×6×6 color cube, and colors `232` to
+/// `255` are a ramp of grays.
+pub const fn xterm_256_to_rgb(n: u8) -> (u8, u8, u8) {
+	const BASIC: [(u8, u8, u8); 16] = [
+		(0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+		(0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+		(127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+		(92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+	];
+	match n {
+		0..=15 => BASIC[n as usize],
+		16..=231 => {
+			let n = n - 16;
+			(
+				CUBE_LEVELS[(n / 36) as usize],
+				CUBE_LEVELS[(n / 6 % 6) as usize],
+				CUBE_LEVELS[(n % 6) as usize],
+			)
+		}
+		232.. => {
+			let level = 8 + (n - 232) * 10;
+			(level, level, level)
+		}
+	}
+}
+
+/// Return the color of the 256-color table that is nearest to `r`, `g`, `b`,
+/// as [`xterm_256_to_rgb`] defines them.
+/// 
+/// Only the color cube and the grays are considered, because the first 16
+/// colors differ between terminals.
+pub const fn rgb_to_xterm_256(r: u8, g: u8, b: u8) -> u8 {
+	const fn cube_index(c: u8) -> u8 {
+		// Halfway between the levels.
+		match c {
+			0..48 => 0,
+			48..115 => 1,
+			_ => (c - 35) / 40,
+		}
+	}
+	const fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+		let dr = a.0.abs_diff(b.0) as u32;
+		let dg = a.1.abs_diff(b.1) as u32;
+		let db = a.2.abs_diff(b.2) as u32;
+		dr * dr + dg * dg + db * db
+	}
+
+	let cube = 16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b);
+	let average = (r as u16 + g as u16 + b as u16) / 3;
+	let gray = match average {
+		0..=8 => 232,
+		238.. => 255,
+		_ => 232 + ((average - 3) / 10) as u8,
+	};
+	let target = (r, g, b);
+	let gray_distance = distance(xterm_256_to_rgb(gray), target);
+	if gray_distance < distance(xterm_256_to_rgb(cube), target) {
+		gray
+	} else {
+		cube
+	}
+}
+
+const _: () = {
+	assert!(SgrColor::MAX_LEN == "2;255;255;255".len());
+	assert!(
+		Sgr::UnderlineColor(SgrColor::Rgb(u8::MAX, u8::MAX, u8::MAX))
+			.encoded_len() == Sgr::MAX_LEN
+	);
+};
+
+/// Numeric code of an [`Sgr`], which is the first of its parameters.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SgrCode(u8);
+
+impl SgrCode {
+	/// Return the code as a number.
+	#[inline(always)]
+	pub const fn get(self) -> u8 {
+		self.0
+	}
+}
+
+impl From<SgrCode> for u16 {
+	#[inline(always)]
+	fn from(value: SgrCode) -> Self {
+		value.0 as _
+	}
+}
+
+impl fmt::Display for SgrCode {
+	#[inline(always)]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+/// Error returned when a code isn't an [`Sgr`] that can be created from its
+/// code alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownSgrCode {
+	/// Code that was converted.
+	pub code: u16,
+}
+
+impl fmt::Display for UnknownSgrCode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "unknown SGR code {}", self.code)
+	}
+}
+
+impl TryFrom<u16> for Sgr {
+	type Error = UnknownSgrCode;
+	/// Convert a code that doesn't carry a color, as in
+	/// [`Sgr::from_simple_param`].
+	#[inline(always)]
+	fn try_from(value: u16) -> Result<Self, Self::Error> {
+		Self::from_simple_param(value).ok_or(UnknownSgrCode { code: value })
+	}
+}
+
+impl Sgr {
+	/// Return an iterator over the SGR parameters of `csi`, or `None` if it
+	/// isn't an SGR sequence.
+	pub fn parse_csi<'a>(csi: &Csi<'a>) -> Option<SgrParams<'a>> {
+		let is_sgr = csi.final_byte.byte() == b'm'
+			&& csi.intermediate_bytes.is_empty()
+			&& csi.private_marker().is_none();
+		is_sgr.then(move || SgrParams::new(CsiParam::slice_as_bytes(
+			csi.parameter_bytes
+		)))
+	}
+
+	/// Return the [`Sgr`] for a parameter that doesn't carry a color.
+	/// 
+	/// Bright colors (`90`-`97` and `100`-`107`) are returned as entries
+	/// `8`-`15` of the 256-color table.
+	pub const fn from_simple_param(param: u16) -> Option<Self> {
+		Some(match param {
+			0 => Self::Reset,
+			1 => Self::WeightBoldOn,
+			2 => Self::WeightThinOn,
+			3 => Self::ItalicOn,
+			4 => Self::UnderlineSingle,
+			5 => Self::BlinkSlow,
+			6 => Self::BlinkRapid,
+			7 => Self::Invert,
+			8 => Self::ConcealOn,
+			9 => Self::StrikethroughOn,
+			10 => Self::FontPrimary,
+			11 => Self::Font1,
+			12 => Self::Font2,
+			13 => Self::Font3,
+			14 => Self::Font4,
+			15 => Self::Font5,
+			16 => Self::Font6,
+			17 => Self::Font7,
+			18 => Self::Font8,
+			19 => Self::Font9,
+			20 => Self::Fraktur,
+			21 => Self::UnderlineDouble,
+			22 => Self::WeightAllOff,
+			23 => Self::ItalicOff,
+			24 => Self::UnderlineNone,
+			25 => Self::BlinkNone,
+			26 => Self::ProportionalSpacingOn,
+			27 => Self::ReversedOff,
+			28 => Self::ConcealOff,
+			29 => Self::StrikethroughOff,
+			30 => Self::Foreground1,
+			31 => Self::Foreground2,
+			32 => Self::Foreground3,
+			33 => Self::Foreground4,
+			34 => Self::Foreground5,
+			35 => Self::Foreground6,
+			36 => Self::Foreground7,
+			37 => Self::Foreground8,
+			39 => Self::ForegroundDefault,
+			40 => Self::Background1,
+			41 => Self::Background2,
+			42 => Self::Background3,
+			43 => Self::Background4,
+			44 => Self::Background5,
+			45 => Self::Background6,
+			46 => Self::Background7,
+			47 => Self::Background8,
+			49 => Self::BackgroundDefault,
+			50 => Self::ProportionalSpacingOff,
+			51 => Self::FrameFramed,
+			52 => Self::FrameEncircled,
+			53 => Self::OverlinedOn,
+			54 => Self::FrameNone,
+			55 => Self::OverlinedOff,
+			59 => Self::UnderlineColorDefault,
+			90..=97 => Self::Foreground(SgrColor::Table((param - 90 + 8) as u8)),
+			100..=107 => {
+				Self::Background(SgrColor::Table((param - 100 + 8) as u8))
+			}
+			_ => return None,
+		})
+	}
+}
+
+/// Iterator over the [`Sgr`]s in the parameters of an SGR sequence.
+/// 
+/// Both `;` and `:` are accepted as separators for extended colors. Unknown
+/// parameters are skipped.
+#[derive(Debug, Clone)]
+pub struct SgrParams<'a> {
+	params: CsiParams<'a>,
+	is_first: bool,
+}
+
+impl<'a> SgrParams<'a> {
+	/// Create an iterator over the SGR parameters in `bytes`.
+	#[inline(always)]
+	pub const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			params: CsiParams::new(bytes),
+			is_first: true,
+		}
+	}
+
+	/// Parse the color that follows an extended color parameter.
+	fn color(&mut self, sub: &[u8]) -> Option<SgrColor> {
+		let mut subs = sub.split(move |&b| b == b':').skip(1).map(parse_u16);
+		if let Some(kind) = subs.next() {
+			// Colon-separated form, such as `38:2::255:0:0` or `38:5:208`.
+			let mut rest = [0u16; 4];
+			let mut len = 0;
+			for value in subs.take(4) {
+				rest[len] = value.unwrap_or(0);
+				len += 1;
+			}
+			return match (kind, &rest[..len]) {
+				(Some(5), [n, ..]) => Some(SgrColor::Table(*n as u8)),
+				(Some(2), [_, r, g, b]) | (Some(2), [r, g, b]) => {
+					Some(SgrColor::Rgb(*r as u8, *g as u8, *b as u8))
+				}
+				_ => None,
+			}
+		}
+
+		let mut next = move || self.params.next().map(move |p| p.unwrap_or(0));
+		match next()? {
+			5 => Some(SgrColor::Table(next()? as u8)),
+			2 => Some(SgrColor::Rgb(next()? as u8, next()? as u8, next()? as u8)),
+			_ => None,
+		}
+	}
+}
+
+fn parse_u16(bytes: &[u8]) -> Option<u16> {
+	CsiParams::new(bytes).next().flatten()
+}
+
+impl<'a> Iterator for SgrParams<'a> {
+	type Item = Sgr;
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let raw = self.params.remaining();
+			let param = match self.params.next() {
+				Some(param) => param,
+				// An empty sequence is a reset.
+				None if self.is_first => {
+					self.is_first = false;
+					return Some(Sgr::Reset)
+				}
+				None => return None,
+			};
+			self.is_first = false;
+			let sub_len = raw.iter().position(|&b| b == b';').unwrap_or(raw.len());
+			let sub = &raw[..sub_len];
+
+			let sgr = match param.unwrap_or(0) {
+				code @ (38 | 48 | 58) => {
+					let Some(color) = self.color(sub) else { continue };
+					match code {
+						38 => Sgr::Foreground(color),
+						48 => Sgr::Background(color),
+						_ => Sgr::UnderlineColor(color),
+					}
+				}
+				4 if sub.contains(&b':') => match parse_u16(&sub[2..]) {
+					Some(0) => Sgr::UnderlineNone,
+					Some(2) => Sgr::UnderlineDouble,
+					_ => Sgr::UnderlineSingle,
+				},
+				code => match Sgr::from_simple_param(code) {
+					Some(sgr) => sgr,
+					None => continue,
+				},
+			};
+			return Some(sgr)
+		}
+	}
+}
@@ -0,0 +1,719 @@
+use crate::{
+	capabilities::{
+		Capabilities, Feature
+	},
+	csi::Csi,
+	fe_seq::ESC_STR,
+	pos::Pos,
+};
+
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::io::{
+	self, Write,
+};
+
+/// When a terminal writer writes buffered output to the terminal.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlushPolicy {
+	/// After every write, for the lowest latency.
+	Immediate,
+	/// At the end of every frame, so that a frame is written at once.
+	#[default]
+	OnFrame,
+	/// Only when the terminal is flushed explicitly.
+	Manual,
+}
+
+/// State of the frames and modes of a terminal writer, which writes the
+/// sequences that change it to the buffered output of the writer.
+#[derive(Debug)]
+struct Modes {
+	policy: FlushPolicy,
+	synchronized: bool,
+	in_frame: bool,
+	alternate_scroll: bool,
+	color_scheme_updates: bool,
+	scroll_region: Option<(u16, u16)>,
+	origin_mode: bool,
+}
+
+impl Modes {
+	#[inline(always)]
+	const fn new(policy: FlushPolicy) -> Self {
+		Self {
+			policy,
+			synchronized: false,
+			in_frame: false,
+			alternate_scroll: false,
+			color_scheme_updates: false,
+			scroll_region: None,
+			origin_mode: false,
+		}
+	}
+
+	/// Return `true` if output should be flushed as soon as it's written.
+	#[inline(always)]
+	fn is_immediate(&self) -> bool {
+		self.policy == FlushPolicy::Immediate
+	}
+
+	fn begin_frame(&mut self, buf: &mut impl fmt::Write) {
+		if self.in_frame {
+			return
+		}
+		self.in_frame = true;
+		if self.synchronized {
+			write_mode(buf, 2026, true);
+		}
+	}
+
+	/// End the frame, returning `true` if the output should be flushed.
+	fn end_frame(&mut self, buf: &mut impl fmt::Write) -> bool {
+		if !self.in_frame {
+			return false
+		}
+		self.in_frame = false;
+		if self.synchronized {
+			write_mode(buf, 2026, false);
+		}
+		self.policy != FlushPolicy::Manual
+	}
+
+	#[inline(always)]
+	fn set_alternate_scroll(&mut self, buf: &mut impl fmt::Write, enabled: bool) {
+		write_mode(buf, 1007, enabled);
+		self.alternate_scroll = enabled;
+	}
+
+	#[inline(always)]
+	fn set_color_scheme_updates(&mut self, buf: &mut impl fmt::Write, enabled: bool) {
+		write_mode(buf, 2031, enabled);
+		self.color_scheme_updates = enabled;
+	}
+
+	fn set_scroll_region(&mut self, buf: &mut impl fmt::Write, top: u16, bottom: u16) {
+		// Writing to a buffer can't fail.
+		let _ = write!(
+			buf, "{ESC_STR}{}{};{}r",
+			Csi::INTRO_STR, top as u32 + 1, bottom as u32 + 1,
+		);
+		self.scroll_region = Some((top, bottom));
+	}
+
+	fn reset_scroll_region(&mut self, buf: &mut impl fmt::Write) {
+		// Writing to a buffer can't fail.
+		let _ = write!(buf, "{ESC_STR}{}r", Csi::INTRO_STR);
+		self.scroll_region = None;
+	}
+
+	#[inline(always)]
+	fn set_origin_mode(&mut self, buf: &mut impl fmt::Write, enabled: bool) {
+		write_mode(buf, 6, enabled);
+		self.origin_mode = enabled;
+	}
+
+	/// Return the row that cursor positions are counted from, which is the
+	/// top of the scrolling region in origin mode.
+	#[inline(always)]
+	const fn origin_row(&self) -> u16 {
+		match (self.origin_mode, self.scroll_region) {
+			(true, Some((top, _))) => top,
+			_ => 0,
+		}
+	}
+
+	const fn to_cursor_address(&self, pos: Pos) -> Option<Pos> {
+		match (self.origin_mode, self.scroll_region) {
+			(true, Some((top, bottom))) => {
+				if pos.row < top || pos.row > bottom {
+					return None
+				}
+				Some(Pos::new(pos.row - top, pos.col))
+			}
+			_ => Some(pos),
+		}
+	}
+
+	#[inline(always)]
+	const fn to_screen(&self, pos: Pos) -> Pos {
+		Pos::new(pos.row.saturating_add(self.origin_row()), pos.col)
+	}
+
+	fn move_to(&self, buf: &mut impl fmt::Write, pos: Pos) {
+		let pos = match (self.origin_mode, self.scroll_region) {
+			(true, Some((top, bottom))) => {
+				Pos::new(pos.row.clamp(top, bottom.max(top)) - top, pos.col)
+			}
+			_ => pos,
+		};
+		let (row, col) = pos.to_one_based();
+		// Writing to a buffer can't fail.
+		let _ = write!(buf, "{ESC_STR}{}{row};{col}H", Csi::INTRO_STR);
+	}
+
+	/// End the frame and reset the modes that were changed, like writers do
+	/// on drop.
+	fn reset(&mut self, buf: &mut impl fmt::Write) {
+		self.end_frame(buf);
+		if self.alternate_scroll {
+			self.set_alternate_scroll(buf, false);
+		}
+		if self.color_scheme_updates {
+			self.set_color_scheme_updates(buf, false);
+		}
+		if self.origin_mode {
+			self.set_origin_mode(buf, false);
+		}
+		if self.scroll_region.is_some() {
+			self.reset_scroll_region(buf);
+		}
+	}
+}
+
+/// Write the sequence that sets or resets the private mode `mode` to `buf`.
+fn write_mode(buf: &mut impl fmt::Write, mode: u16, set: bool) {
+	let suffix = if set { 'h' } else { 'l' };
+	// Writing to a buffer can't fail.
+	let _ = write!(buf, "{ESC_STR}{}?{mode}{suffix}", Csi::INTRO_STR);
+}
+
+/// Write the sequence that clears the lines scrolled off the top of the screen
+/// to `buf`.
+fn write_clear_scrollback(buf: &mut impl fmt::Write) {
+	// Writing to a buffer can't fail.
+	let _ = write!(buf, "{ESC_STR}{}3J", Csi::INTRO_STR);
+}
+
+/// Buffer of output bytes that sequences can be formatted into.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct ByteBuf(Vec<u8>);
+
+#[cfg(feature = "std")]
+impl fmt::Write for ByteBuf {
+	#[inline(always)]
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.0.extend_from_slice(s.as_bytes());
+		Ok(())
+	}
+}
+
+/// Buffered writer to a terminal that groups output into frames.
+/// 
+/// Output between [`Self::begin_frame`] and [`Self::end_frame`] is wrapped in
+/// synchronized output, or mode 2026, if the terminal supports it, so that the
+/// terminal displays the frame at once instead of while it's being drawn.
+/// When the buffered output is written depends on the [`FlushPolicy`].
+/// 
+/// Buffered output is flushed, synchronized output is ended, and alternate
+/// scroll mode, color scheme updates, the scrolling region and origin mode
+/// are reset on drop.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Terminal<W: Write> {
+	inner: W,
+	buf: ByteBuf,
+	modes: Modes,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Terminal<W> {
+	/// Create a [`Terminal`] that writes to `inner` with `policy`, without
+	/// synchronized output.
+	#[inline(always)]
+	pub const fn new(inner: W, policy: FlushPolicy) -> Self {
+		Self {
+			inner,
+			buf: ByteBuf(Vec::new()),
+			modes: Modes::new(policy),
+		}
+	}
+
+	/// Create a [`Terminal`] that writes to `inner` with `policy`, using
+	/// synchronized output if `capabilities` says that it's supported.
+	#[inline(always)]
+	pub fn with_capabilities(
+		inner: W, policy: FlushPolicy, capabilities: &mut Capabilities,
+	) -> Self {
+		let mut terminal = Self::new(inner, policy);
+		terminal.modes.synchronized = capabilities.supports(Feature::SynchronizedOutput);
+		terminal
+	}
+
+	/// Return the flush policy.
+	#[inline(always)]
+	pub const fn policy(&self) -> FlushPolicy {
+		self.modes.policy
+	}
+
+	/// Change the flush policy.
+	#[inline(always)]
+	pub fn set_policy(&mut self, policy: FlushPolicy) {
+		self.modes.policy = policy;
+	}
+
+	/// Return `true` if frames are wrapped in synchronized output.
+	#[inline(always)]
+	pub const fn is_synchronized(&self) -> bool {
+		self.modes.synchronized
+	}
+
+	/// Set whether frames are wrapped in synchronized output.
+	/// 
+	/// This takes effect at the next frame.
+	#[inline(always)]
+	pub fn set_synchronized(&mut self, synchronized: bool) {
+		self.modes.synchronized = synchronized;
+	}
+
+	/// Return `true` if a frame was begun and not ended.
+	#[inline(always)]
+	pub const fn in_frame(&self) -> bool {
+		self.modes.in_frame
+	}
+
+	/// Return the output that wasn't written to the inner writer yet.
+	#[inline(always)]
+	pub fn buffered(&self) -> &[u8] {
+		&self.buf.0
+	}
+
+	/// Begin a frame, which does nothing if a frame was already begun.
+	#[inline(always)]
+	pub fn begin_frame(&mut self) -> io::Result<()> {
+		self.modes.begin_frame(&mut self.buf);
+		Ok(())
+	}
+
+	/// End the frame, flushing the output unless the policy is
+	/// [`FlushPolicy::Manual`].
+	pub fn end_frame(&mut self) -> io::Result<()> {
+		match self.modes.end_frame(&mut self.buf) {
+			true => self.flush(),
+			false => Ok(()),
+		}
+	}
+
+	/// Return `true` if alternate scroll mode was turned on.
+	#[inline(always)]
+	pub const fn is_alternate_scroll(&self) -> bool {
+		self.modes.alternate_scroll
+	}
+
+	/// Turn xterm's alternate scroll mode, or private mode `1007`, on or off,
+	/// which makes the mouse wheel scroll the alternate screen with arrow keys.
+	pub fn set_alternate_scroll(&mut self, enabled: bool) -> io::Result<()> {
+		self.modes.set_alternate_scroll(&mut self.buf, enabled);
+		self.written()
+	}
+
+	/// Return `true` if color scheme updates were turned on.
+	#[inline(always)]
+	pub const fn is_color_scheme_updates(&self) -> bool {
+		self.modes.color_scheme_updates
+	}
+
+	/// Turn the color scheme updates of contour and kitty, or private mode
+	/// `2031`, on or off, which makes the terminal report
+	/// [`Event::ColorSchemeChanged`](crate::Event::ColorSchemeChanged) whenever
+	/// the color scheme that the user prefers changes.
+	pub fn set_color_scheme_updates(&mut self, enabled: bool) -> io::Result<()> {
+		self.modes.set_color_scheme_updates(&mut self.buf, enabled);
+		self.written()
+	}
+
+	/// Clear the lines that were scrolled off the top of the screen, without
+	/// changing the screen.
+	#[inline(always)]
+	pub fn clear_scrollback(&mut self) -> io::Result<()> {
+		write_clear_scrollback(&mut self.buf);
+		self.written()
+	}
+
+	/// Return the first and last rows of the scrolling region, which are
+	/// 0-based and inclusive, or [`None`] if it's the whole screen.
+	#[inline(always)]
+	pub const fn scroll_region(&self) -> Option<(u16, u16)> {
+		self.modes.scroll_region
+	}
+
+	/// Limit scrolling to the rows from `top` to `bottom`, which are 0-based
+	/// and inclusive, or **DECSTBM**.
+	/// 
+	/// The terminal also moves the cursor to the top left of the screen, or
+	/// of the region in origin mode.
+	pub fn set_scroll_region(&mut self, top: u16, bottom: u16) -> io::Result<()> {
+		self.modes.set_scroll_region(&mut self.buf, top, bottom);
+		self.written()
+	}
+
+	/// Let the whole screen scroll again.
+	pub fn reset_scroll_region(&mut self) -> io::Result<()> {
+		self.modes.reset_scroll_region(&mut self.buf);
+		self.written()
+	}
+
+	/// Return `true` if origin mode was turned on.
+	#[inline(always)]
+	pub const fn origin_mode(&self) -> bool {
+		self.modes.origin_mode
+	}
+
+	/// Turn origin mode, or **DECOM**, on or off.
+	/// 
+	/// While it's on, the terminal counts cursor positions from the top of
+	/// the scrolling region, and keeps the cursor inside of it.
+	/// [`Self::move_to`], [`Self::to_cursor_address`] and [`Self::to_screen`]
+	/// take it into account, so positions given to and returned by them are
+	/// always relative to the screen.
+	pub fn set_origin_mode(&mut self, enabled: bool) -> io::Result<()> {
+		self.modes.set_origin_mode(&mut self.buf, enabled);
+		self.written()
+	}
+
+	/// Translate `pos` on the screen to the position that the terminal
+	/// expects in cursor movements, or [`None`] if the cursor can't be moved
+	/// there because of origin mode.
+	#[inline(always)]
+	pub const fn to_cursor_address(&self, pos: Pos) -> Option<Pos> {
+		self.modes.to_cursor_address(pos)
+	}
+
+	/// Translate `pos` reported by the terminal, such as in a
+	/// [`CursorPositionQuery`](crate::CursorPositionQuery) response, to a
+	/// position on the screen.
+	#[inline(always)]
+	pub const fn to_screen(&self, pos: Pos) -> Pos {
+		self.modes.to_screen(pos)
+	}
+
+	/// Move the cursor to `pos` on the screen, or **CUP**.
+	/// 
+	/// In origin mode, a row outside of the scrolling region is moved to the
+	/// nearest row inside of it, like the terminal would.
+	pub fn move_to(&mut self, pos: Pos) -> io::Result<()> {
+		self.modes.move_to(&mut self.buf, pos);
+		self.written()
+	}
+
+	/// Return a reference to the inner writer.
+	#[inline(always)]
+	pub fn get_ref(&self) -> &W {
+		&self.inner
+	}
+
+	/// Return a mutable reference to the inner writer.
+	/// 
+	/// Writing to it directly bypasses the buffered output.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut W {
+		&mut self.inner
+	}
+
+	/// Flush the output that was just buffered if the policy is
+	/// [`FlushPolicy::Immediate`].
+	#[inline(always)]
+	fn written(&mut self) -> io::Result<()> {
+		match self.modes.is_immediate() {
+			true => self.flush(),
+			false => Ok(()),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for Terminal<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.buf.0.extend_from_slice(buf);
+		self.written()?;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.write_all(&self.buf.0)?;
+		self.buf.0.clear();
+		self.inner.flush()
+	}
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Drop for Terminal<W> {
+	fn drop(&mut self) {
+		self.modes.reset(&mut self.buf);
+		// Errors can't be reported from `drop`.
+		let _ = self.flush();
+	}
+}
+
+/// Terminal that writes output to a callback and decodes input that is fed to
+/// it, for terminals that aren't connected through `std::io`, such as
+/// xterm.js in a browser.
+/// 
+/// Output is buffered and passed to the callback, such as one that calls
+/// xterm.js's `write`, as decided by the [`FlushPolicy`]. Frames and modes
+/// work like those of
+#[cfg_attr(feature = "std", doc = "[`Terminal`].")]
+#[cfg_attr(not(feature = "std"), doc = "`Terminal`.")]
+/// Input, such as data from xterm.js's `onData`, is given to
+/// [`Self::feed_input`].
+/// 
+/// Buffered output is flushed, synchronized output is ended, and alternate
+/// scroll mode, color scheme updates, the scrolling region and origin mode
+/// are reset on drop.
+#[cfg(feature = "wasm")]
+pub struct CallbackTerminal<F: FnMut(&str)> {
+	output: F,
+	buf: alloc::string::String,
+	modes: Modes,
+	parser: crate::event::Parser,
+}
+
+#[cfg(feature = "wasm")]
+impl<F: FnMut(&str)> fmt::Debug for CallbackTerminal<F> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CallbackTerminal")
+			.field("buf", &self.buf)
+			.field("modes", &self.modes)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(feature = "wasm")]
+impl<F: FnMut(&str)> CallbackTerminal<F> {
+	/// Create a [`CallbackTerminal`] that passes output to `output` with
+	/// `policy`, without synchronized output.
+	#[inline(always)]
+	pub const fn new(output: F, policy: FlushPolicy) -> Self {
+		Self {
+			output,
+			buf: alloc::string::String::new(),
+			modes: Modes::new(policy),
+			parser: crate::event::Parser::new(),
+		}
+	}
+
+	/// Create a [`CallbackTerminal`] that passes output to `output` with
+	/// `policy`, using synchronized output if `capabilities` says that it's
+	/// supported.
+	#[inline(always)]
+	pub fn with_capabilities(
+		output: F, policy: FlushPolicy, capabilities: &mut Capabilities,
+	) -> Self {
+		let mut terminal = Self::new(output, policy);
+		terminal.modes.synchronized = capabilities.supports(Feature::SynchronizedOutput);
+		terminal
+	}
+
+	/// Return the flush policy.
+	#[inline(always)]
+	pub const fn policy(&self) -> FlushPolicy {
+		self.modes.policy
+	}
+
+	/// Change the flush policy.
+	#[inline(always)]
+	pub fn set_policy(&mut self, policy: FlushPolicy) {
+		self.modes.policy = policy;
+	}
+
+	/// Return `true` if frames are wrapped in synchronized output.
+	#[inline(always)]
+	pub const fn is_synchronized(&self) -> bool {
+		self.modes.synchronized
+	}
+
+	/// Set whether frames are wrapped in synchronized output.
+	/// 
+	/// This takes effect at the next frame.
+	#[inline(always)]
+	pub fn set_synchronized(&mut self, synchronized: bool) {
+		self.modes.synchronized = synchronized;
+	}
+
+	/// Return `true` if a frame was begun and not ended.
+	#[inline(always)]
+	pub const fn in_frame(&self) -> bool {
+		self.modes.in_frame
+	}
+
+	/// Return the output that wasn't passed to the callback yet.
+	#[inline(always)]
+	pub fn buffered(&self) -> &str {
+		&self.buf
+	}
+
+	/// Begin a frame, which does nothing if a frame was already begun.
+	#[inline(always)]
+	pub fn begin_frame(&mut self) {
+		self.modes.begin_frame(&mut self.buf);
+	}
+
+	/// End the frame, flushing the output unless the policy is
+	/// [`FlushPolicy::Manual`].
+	pub fn end_frame(&mut self) {
+		if self.modes.end_frame(&mut self.buf) {
+			self.flush();
+		}
+	}
+
+	/// Return `true` if alternate scroll mode was turned on.
+	#[inline(always)]
+	pub const fn is_alternate_scroll(&self) -> bool {
+		self.modes.alternate_scroll
+	}
+
+	/// Turn xterm's alternate scroll mode, or private mode `1007`, on or off,
+	/// which makes the mouse wheel scroll the alternate screen with arrow keys.
+	pub fn set_alternate_scroll(&mut self, enabled: bool) {
+		self.modes.set_alternate_scroll(&mut self.buf, enabled);
+		self.written();
+	}
+
+	/// Return `true` if color scheme updates were turned on.
+	#[inline(always)]
+	pub const fn is_color_scheme_updates(&self) -> bool {
+		self.modes.color_scheme_updates
+	}
+
+	/// Turn the color scheme updates of contour and kitty, or private mode
+	/// `2031`, on or off, which makes the terminal report
+	/// [`Event::ColorSchemeChanged`](crate::Event::ColorSchemeChanged) whenever
+	/// the color scheme that the user prefers changes.
+	pub fn set_color_scheme_updates(&mut self, enabled: bool) {
+		self.modes.set_color_scheme_updates(&mut self.buf, enabled);
+		self.written();
+	}
+
+	/// Clear the lines that were scrolled off the top of the screen, without
+	/// changing the screen.
+	#[inline(always)]
+	pub fn clear_scrollback(&mut self) {
+		write_clear_scrollback(&mut self.buf);
+		self.written();
+	}
+
+	/// Return the first and last rows of the scrolling region, which are
+	/// 0-based and inclusive, or [`None`] if it's the whole screen.
+	#[inline(always)]
+	pub const fn scroll_region(&self) -> Option<(u16, u16)> {
+		self.modes.scroll_region
+	}
+
+	/// Limit scrolling to the rows from `top` to `bottom`, which are 0-based
+	/// and inclusive, or **DECSTBM**.
+	/// 
+	/// The terminal also moves the cursor to the top left of the screen, or
+	/// of the region in origin mode.
+	pub fn set_scroll_region(&mut self, top: u16, bottom: u16) {
+		self.modes.set_scroll_region(&mut self.buf, top, bottom);
+		self.written();
+	}
+
+	/// Let the whole screen scroll again.
+	pub fn reset_scroll_region(&mut self) {
+		self.modes.reset_scroll_region(&mut self.buf);
+		self.written();
+	}
+
+	/// Return `true` if origin mode was turned on.
+	#[inline(always)]
+	pub const fn origin_mode(&self) -> bool {
+		self.modes.origin_mode
+	}
+
+	/// Turn origin mode, or **DECOM**, on or off.
+	/// 
+	/// While it's on, the terminal counts cursor positions from the top of
+	/// the scrolling region, and keeps the cursor inside of it.
+	/// [`Self::move_to`], [`Self::to_cursor_address`] and [`Self::to_screen`]
+	/// take it into account, so positions given to and returned by them are
+	/// always relative to the screen.
+	pub fn set_origin_mode(&mut self, enabled: bool) {
+		self.modes.set_origin_mode(&mut self.buf, enabled);
+		self.written();
+	}
+
+	/// Translate `pos` on the screen to the position that the terminal
+	/// expects in cursor movements, or [`None`] if the cursor can't be moved
+	/// there because of origin mode.
+	#[inline(always)]
+	pub const fn to_cursor_address(&self, pos: Pos) -> Option<Pos> {
+		self.modes.to_cursor_address(pos)
+	}
+
+	/// Translate `pos` reported by the terminal, such as in a
+	/// [`CursorPositionQuery`](crate::CursorPositionQuery) response, to a
+	/// position on the screen.
+	#[inline(always)]
+	pub const fn to_screen(&self, pos: Pos) -> Pos {
+		self.modes.to_screen(pos)
+	}
+
+	/// Move the cursor to `pos` on the screen, or **CUP**.
+	/// 
+	/// In origin mode, a row outside of the scrolling region is moved to the
+	/// nearest row inside of it, like the terminal would.
+	pub fn move_to(&mut self, pos: Pos) {
+		self.modes.move_to(&mut self.buf, pos);
+		self.written();
+	}
+
+	/// Pass the buffered output to the callback.
+	pub fn flush(&mut self) {
+		if !self.buf.is_empty() {
+			(self.output)(&self.buf);
+			self.buf.clear();
+		}
+	}
+
+	/// Decode `bytes` of input, calling `f` with every complete event.
+	/// 
+	/// A lone escape character is kept until more input arrives, or until
+	/// [`Self::flush_input`] is called, which should be done after a short
+	/// timeout.
+	#[inline(always)]
+	pub fn feed_input(&mut self, bytes: &[u8], f: impl FnMut(crate::event::Event<'_>)) {
+		self.parser.advance(bytes, f);
+	}
+
+	/// Deliver the buffered incomplete input, calling `f` with every event.
+	#[inline(always)]
+	pub fn flush_input(&mut self, f: impl FnMut(crate::event::Event<'_>)) {
+		self.parser.flush(f);
+	}
+
+	/// Return `true` if incomplete input is buffered, so that
+	/// [`Self::flush_input`] should be called if no more input arrives soon.
+	#[inline(always)]
+	pub fn has_pending_input(&self) -> bool {
+		self.parser.has_pending()
+	}
+
+	/// Pass the output that was just buffered to the callback if the policy is
+	/// [`FlushPolicy::Immediate`].
+	#[inline(always)]
+	fn written(&mut self) {
+		if self.modes.is_immediate() {
+			self.flush();
+		}
+	}
+}
+
+#[cfg(feature = "wasm")]
+impl<F: FnMut(&str)> fmt::Write for CallbackTerminal<F> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.buf.push_str(s);
+		self.written();
+		Ok(())
+	}
+}
+
+#[cfg(feature = "wasm")]
+impl<F: FnMut(&str)> Drop for CallbackTerminal<F> {
+	fn drop(&mut self) {
+		self.modes.reset(&mut self.buf);
+		self.flush();
+	}
+}
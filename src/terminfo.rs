@@ -0,0 +1,384 @@
+//! Reader of compiled terminfo entries.
+
+use std::{
+	fs,
+	io,
+	path::PathBuf,
+};
+
+/// Magic number of the legacy format, with 16-bit numbers.
+const MAGIC_16: i16 = 0o432;
+/// Magic number of the extended number format, with 32-bit numbers.
+const MAGIC_32: i16 = 0o1036;
+
+/// Error that occurs while parsing a compiled terminfo entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminfoError {
+	/// The entry doesn't start with a known magic number.
+	BadMagic,
+	/// The entry ends before the sizes in its header say it does.
+	Truncated,
+	/// The entry has a negative size or an offset out of bounds.
+	Invalid,
+}
+
+impl core::fmt::Display for TerminfoError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(match self {
+			Self::BadMagic => "not a compiled terminfo entry",
+			Self::Truncated => "truncated terminfo entry",
+			Self::Invalid => "invalid terminfo entry",
+		})
+	}
+}
+
+impl std::error::Error for TerminfoError {}
+
+impl From<TerminfoError> for io::Error {
+	#[inline(always)]
+	fn from(value: TerminfoError) -> Self {
+		io::Error::new(io::ErrorKind::InvalidData, value)
+	}
+}
+
+/// Capabilities of a terminal, as described by its terminfo entry.
+/// 
+/// Capabilities are looked up by their short names, such as `"colors"` or
+/// `"setaf"`. Extended capabilities, such as `"RGB"`, `"Tc"` and `"Sync"`, are
+/// looked up the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Terminfo {
+	names: String,
+	flags: Vec<String>,
+	numbers: Vec<(String, i32)>,
+	strings: Vec<(String, Vec<u8>)>,
+}
+
+impl Terminfo {
+	/// Load the entry for the terminal named by the `TERM` environment
+	/// variable.
+	pub fn from_env() -> io::Result<Self> {
+		let term = std::env::var("TERM")
+			.map_err(|_| io::Error::new(io::ErrorKind::NotFound, "TERM is not set"))?;
+		Self::load(&term)
+	}
+
+	/// Load the entry for the terminal named `term` from the terminfo
+	/// directories.
+	/// 
+	/// The directories are searched in the same order as ncurses does: the
+	/// `TERMINFO` environment variable, `~/.terminfo`, the directories in
+	/// `TERMINFO_DIRS`, and then the usual system directories.
+	pub fn load(term: &str) -> io::Result<Self> {
+		let first = term.chars().next()
+			.filter(move |_| !term.contains(['/', '\\']) && term != "..")
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid terminal name"))?;
+		let hex = format!("{:02x}", first as u32);
+		for dir in search_dirs() {
+			for subdir in [first.encode_utf8(&mut [0; 4]) as &str, &hex] {
+				match fs::read(dir.join(subdir).join(term)) {
+					Ok(bytes) => return Ok(Self::parse(&bytes)?),
+					Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+					Err(e) => return Err(e),
+				}
+			}
+		}
+		Err(io::Error::new(io::ErrorKind::NotFound, "no terminfo entry found"))
+	}
+
+	/// Parse a compiled terminfo entry.
+	pub fn parse(bytes: &[u8]) -> Result<Self, TerminfoError> {
+		let mut r = Reader {
+			bytes,
+			pos: 0,
+		};
+		let number_size = match r.i16()? {
+			MAGIC_16 => 2,
+			MAGIC_32 => 4,
+			_ => return Err(TerminfoError::BadMagic),
+		};
+		let names_size = r.size()?;
+		let bool_count = r.size()?;
+		let number_count = r.size()?;
+		let string_count = r.size()?;
+		let table_size = r.size()?;
+
+		let names = r.take(names_size)?;
+		let names = names.strip_suffix(b"\0").unwrap_or(names);
+		let mut result = Self {
+			names: String::from_utf8_lossy(names).into_owned(),
+			flags: Vec::new(),
+			numbers: Vec::new(),
+			strings: Vec::new(),
+		};
+
+		let bools = r.take(bool_count)?;
+		for (&value, name) in bools.iter().zip(BOOL_NAMES) {
+			if value == 1 {
+				result.flags.push(name.into());
+			}
+		}
+		r.align();
+		for i in 0..number_count {
+			let value = r.number(number_size)?;
+			if let Some(name) = NUMBER_NAMES.get(i).filter(|_| value >= 0) {
+				result.numbers.push(((*name).into(), value));
+			}
+		}
+		let offsets = r.take(string_count * 2)?;
+		let table = r.take(table_size)?;
+		for (offset, name) in offsets.chunks(2).zip(STRING_NAMES) {
+			let offset = i16::from_le_bytes([offset[0], offset[1]]);
+			if let Some(value) = string_at(table, offset)? {
+				result.strings.push(((*name).into(), value.into()));
+			}
+		}
+
+		r.align();
+		if r.pos < bytes.len() {
+			result.parse_extended(&mut r, number_size)?;
+		}
+		Ok(result)
+	}
+
+	/// Parse the extended capabilities that follow the standard ones.
+	fn parse_extended(
+		&mut self, r: &mut Reader<'_>, number_size: usize,
+	) -> Result<(), TerminfoError> {
+		let bool_count = r.size()?;
+		let number_count = r.size()?;
+		let string_count = r.size()?;
+		let _item_count = r.size()?;
+		let table_size = r.size()?;
+
+		let bools = r.take(bool_count)?;
+		r.align();
+		let mut numbers = Vec::with_capacity(number_count);
+		for _ in 0..number_count {
+			numbers.push(r.number(number_size)?);
+		}
+		let value_offsets = r.take(string_count * 2)?;
+		let name_offsets = r.take((bool_count + number_count + string_count) * 2)?;
+		let table = r.take(table_size)?;
+
+		// Names are stored after the last string value.
+		let mut values = Vec::with_capacity(string_count);
+		let mut names_start = 0;
+		for offset in value_offsets.chunks(2) {
+			let offset = i16::from_le_bytes([offset[0], offset[1]]);
+			let value = string_at(table, offset)?;
+			if let Some(value) = value {
+				names_start = names_start.max(offset as usize + value.len() + 1);
+			}
+			values.push(value);
+		}
+		let names_table = table.get(names_start..).ok_or(TerminfoError::Invalid)?;
+		let mut names = name_offsets.chunks(2).map(move |offset| {
+			let offset = i16::from_le_bytes([offset[0], offset[1]]);
+			string_at(names_table, offset)?
+				.map(move |name| String::from_utf8_lossy(name).into_owned())
+				.ok_or(TerminfoError::Invalid)
+		});
+
+		for &value in bools {
+			let name = names.next().ok_or(TerminfoError::Invalid)??;
+			if value == 1 {
+				self.flags.push(name);
+			}
+		}
+		for value in numbers {
+			let name = names.next().ok_or(TerminfoError::Invalid)??;
+			if value >= 0 {
+				self.numbers.push((name, value));
+			}
+		}
+		for value in values {
+			let name = names.next().ok_or(TerminfoError::Invalid)??;
+			if let Some(value) = value {
+				self.strings.push((name, value.into()));
+			}
+		}
+		Ok(())
+	}
+
+	/// Return the names of the terminal, the first of which is its primary
+	/// name and the last of which is usually a description.
+	#[inline(always)]
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.names.split('|')
+	}
+
+	/// Return `true` if the boolean capability `name` is present.
+	#[inline(always)]
+	pub fn flag(&self, name: &str) -> bool {
+		self.flags.iter().any(move |flag| flag == name)
+	}
+
+	/// Return the value of the numeric capability `name`.
+	#[inline(always)]
+	pub fn number(&self, name: &str) -> Option<i32> {
+		self.numbers.iter()
+			.find(move |(n, _)| n == name)
+			.map(|&(_, value)| value)
+	}
+
+	/// Return the value of the string capability `name`, with its escapes
+	/// already interpreted.
+	/// 
+	/// Parameterized capabilities still contain their `%` directives.
+	#[inline(always)]
+	pub fn string(&self, name: &str) -> Option<&[u8]> {
+		self.strings.iter()
+			.find(move |(n, _)| n == name)
+			.map(|(_, value)| value.as_slice())
+	}
+}
+
+/// Return the directories that terminfo entries are searched in, in order.
+fn search_dirs() -> Vec<PathBuf> {
+	const SYSTEM_DIRS: [&str; 4] = [
+		"/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo",
+		"/usr/lib/terminfo",
+	];
+
+	let mut dirs = Vec::new();
+	if let Some(dir) = std::env::var_os("TERMINFO") {
+		dirs.push(dir.into());
+	}
+	if let Some(home) = std::env::var_os("HOME") {
+		dirs.push(PathBuf::from(home).join(".terminfo"));
+	}
+	if let Ok(list) = std::env::var("TERMINFO_DIRS") {
+		for dir in list.split(':') {
+			match dir {
+				"" => dirs.extend(SYSTEM_DIRS.map(PathBuf::from)),
+				dir => dirs.push(dir.into()),
+			}
+		}
+	}
+	dirs.extend(SYSTEM_DIRS.map(PathBuf::from));
+	dirs
+}
+
+/// Return the NUL-terminated string at `offset` in `table`, or `None` if the
+/// capability is absent or cancelled.
+fn string_at(table: &[u8], offset: i16) -> Result<Option<&[u8]>, TerminfoError> {
+	if offset < 0 {
+		return Ok(None)
+	}
+	let rest = table.get(offset as usize..).ok_or(TerminfoError::Invalid)?;
+	let len = rest.iter().position(|&b| b == 0).ok_or(TerminfoError::Invalid)?;
+	Ok(Some(&rest[..len]))
+}
+
+/// Reader of the little-endian fields of a compiled entry.
+struct Reader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Result<&'a [u8], TerminfoError> {
+		let bytes = self.bytes.get(self.pos..self.pos + len)
+			.ok_or(TerminfoError::Truncated)?;
+		self.pos += len;
+		Ok(bytes)
+	}
+
+	fn i16(&mut self) -> Result<i16, TerminfoError> {
+		let bytes = self.take(2)?;
+		Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+	}
+
+	fn size(&mut self) -> Result<usize, TerminfoError> {
+		usize::try_from(self.i16()?).map_err(|_| TerminfoError::Invalid)
+	}
+
+	fn number(&mut self, size: usize) -> Result<i32, TerminfoError> {
+		let bytes = self.take(size)?;
+		Ok(match *bytes {
+			[a, b] => i16::from_le_bytes([a, b]) as i32,
+			[a, b, c, d] => i32::from_le_bytes([a, b, c, d]),
+			_ => unreachable!(),
+		})
+	}
+
+	/// Skip a byte to align the position to 2 bytes.
+	#[inline(always)]
+	fn align(&mut self) {
+		if self.pos % 2 == 1 {
+			self.pos += 1;
+		}
+	}
+}
+
+/// Names of the standard boolean capabilities, in the order of the compiled
+/// format.
+const BOOL_NAMES: [&str; 44] = [
+	"bw", "am", "xsb", "xhp", "xenl", "eo", "gn", "hc", "km", "hs", "in", "da",
+	"db", "mir", "msgr", "os", "eslok", "xt", "hz", "ul", "xon", "nxon",
+	"mc5i", "chts", "nrrmc", "npc", "ndscr", "ccc", "bce", "hls", "xhpa",
+	"crxm", "daisy", "xvpa", "sam", "cpix", "lpix", "OTbs", "OTns", "OTnc",
+	"OTMT", "OTNL", "OTpt", "OTxr",
+];
+
+/// Names of the standard numeric capabilities, in the order of the compiled
+/// format.
+const NUMBER_NAMES: [&str; 39] = [
+	"cols", "it", "lines", "lm", "xmc", "pb", "vt", "wsl", "nlab", "lh", "lw",
+	"ma", "wnum", "colors", "pairs", "ncv", "bufsz", "spinv", "spinh", "maddr",
+	"mjump", "mcs", "mls", "npins", "orc", "orl", "orhi", "orvi", "cps",
+	"widcs", "btns", "bitwin", "bitype", "OTug", "OTdC", "OTdN", "OTdB",
+	"OTdT", "OTkn",
+];
+
+/// Names of the standard string capabilities, in the order of the compiled
+/// format.
+const STRING_NAMES: [&str; 414] = [
+	"cbt", "bel", "cr", "csr", "tbc", "clear", "el", "ed", "hpa", "cmdch",
+	"cup", "cud1", "home", "civis", "cub1", "mrcup", "cnorm", "cuf1", "ll",
+	"cuu1", "cvvis", "dch1", "dl1", "dsl", "hd", "smacs", "blink", "bold",
+	"smcup", "smdc", "dim", "smir", "invis", "prot", "rev", "smso", "smul",
+	"ech", "rmacs", "sgr0", "rmcup", "rmdc", "rmir", "rmso", "rmul", "flash",
+	"ff", "fsl", "is1", "is2", "is3", "if", "ich1", "il1", "ip", "kbs", "ktbc",
+	"kclr", "kctab", "kdch1", "kdl1", "kcud1", "krmir", "kel", "ked", "kf0",
+	"kf1", "kf10", "kf2", "kf3", "kf4", "kf5", "kf6", "kf7", "kf8", "kf9",
+	"khome", "kich1", "kil1", "kcub1", "kll", "knp", "kpp", "kcuf1", "kind",
+	"kri", "khts", "kcuu1", "rmkx", "smkx", "lf0", "lf1", "lf10", "lf2", "lf3",
+	"lf4", "lf5", "lf6", "lf7", "lf8", "lf9", "rmm", "smm", "nel", "pad",
+	"dch", "dl", "cud", "ich", "indn", "il", "cub", "cuf", "rin", "cuu",
+	"pfkey", "pfloc", "pfx", "mc0", "mc4", "mc5", "rep", "rs1", "rs2", "rs3",
+	"rf", "rc", "vpa", "sc", "ind", "ri", "sgr", "hts", "wind", "ht", "tsl",
+	"uc", "hu", "iprog", "ka1", "ka3", "kb2", "kc1", "kc3", "mc5p", "rmp",
+	"acsc", "pln", "kcbt", "smxon", "rmxon", "smam", "rmam", "xonc", "xoffc",
+	"enacs", "smln", "rmln", "kbeg", "kcan", "kclo", "kcmd", "kcpy", "kcrt",
+	"kend", "kent", "kext", "kfnd", "khlp", "kmrk", "kmsg", "kmov", "knxt",
+	"kopn", "kopt", "kprv", "kprt", "krdo", "kref", "krfr", "krpl", "krst",
+	"kres", "ksav", "kspd", "kund", "kBEG", "kCAN", "kCMD", "kCPY", "kCRT",
+	"kDC", "kDL", "kslt", "kEND", "kEOL", "kEXT", "kFND", "kHLP", "kHOM",
+	"kIC", "kLFT", "kMSG", "kMOV", "kNXT", "kOPT", "kPRV", "kPRT", "kRDO",
+	"kRPL", "kRIT", "kRES", "kSAV", "kSPD", "kUND", "rfi", "kf11", "kf12",
+	"kf13", "kf14", "kf15", "kf16", "kf17", "kf18", "kf19", "kf20", "kf21",
+	"kf22", "kf23", "kf24", "kf25", "kf26", "kf27", "kf28", "kf29", "kf30",
+	"kf31", "kf32", "kf33", "kf34", "kf35", "kf36", "kf37", "kf38", "kf39",
+	"kf40", "kf41", "kf42", "kf43", "kf44", "kf45", "kf46", "kf47", "kf48",
+	"kf49", "kf50", "kf51", "kf52", "kf53", "kf54", "kf55", "kf56", "kf57",
+	"kf58", "kf59", "kf60", "kf61", "kf62", "kf63", "el1", "mgc", "smgl",
+	"smgr", "fln", "sclk", "dclk", "rmclk", "cwin", "wingo", "hup", "dial",
+	"qdial", "tone", "pulse", "hook", "pause", "wait", "u0", "u1", "u2", "u3",
+	"u4", "u5", "u6", "u7", "u8", "u9", "op", "oc", "initc", "initp", "scp",
+	"setf", "setb", "cpi", "lpi", "chr", "cvr", "defc", "swidm", "sdrfq",
+	"sitm", "slm", "smicm", "snlq", "snrmq", "sshm", "ssubm", "ssupm", "sum",
+	"rwidm", "ritm", "rlm", "rmicm", "rshm", "rsubm", "rsupm", "rum", "mhpa",
+	"mcud1", "mcub1", "mcuf1", "mvpa", "mcuu1", "porder", "mcud", "mcub",
+	"mcuf", "mcuu", "scs", "smgb", "smgbp", "smglp", "smgrp", "smgt", "smgtp",
+	"sbim", "scsd", "rbim", "rcsd", "subcs", "supcs", "docr", "zerom", "csnm",
+	"kmous", "minfo", "reqmp", "getm", "setaf", "setab", "pfxl", "devt",
+	"csin", "s0ds", "s1ds", "s2ds", "s3ds", "smglr", "smgtb", "birep", "binel",
+	"bicr", "colornm", "defbi", "endbi", "setcolor", "slines", "dispc",
+	"smpch", "rmpch", "smsc", "rmsc", "pctrm", "scesc", "scesa", "ehhlm",
+	"elhlm", "elohlm", "erhlm", "ethlm", "evhlm", "sgr1", "slength", "OTi2",
+	"OTrs", "OTnl", "OTbc", "OTko", "OTma", "OTG2", "OTG3", "OTG1", "OTG4",
+	"OTGR", "OTGL", "OTGU", "OTGD", "OTGH", "OTGV", "OTGC", "meml", "memu",
+	"box1",
+];
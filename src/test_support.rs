@@ -0,0 +1,257 @@
+//! Helpers for testing sequences against the parsers of this crate.
+//! 
+//! These are meant for tests, including those of crates that add their own
+//! sequences, and panic when a check fails.
+
+use crate::parse::FromSequence;
+
+use alloc::string::String;
+
+use core::fmt::{
+	Debug, Display, Write,
+};
+
+/// Assert that `value` is parsed back from the bytes that it's displayed as,
+/// consuming all of them.
+/// 
+/// `T` can't borrow from the bytes. See [`roundtrip_in`] for types that do.
+#[track_caller]
+pub fn roundtrip<T>(value: &T)
+where
+	T: for<'b> FromSequence<'b> + Display + Debug + PartialEq,
+{
+	roundtrip_in(value, &mut String::new())
+}
+
+/// Assert that `value` is parsed back from the bytes that it's displayed as,
+/// consuming all of them, displaying it into `buf`.
+/// 
+/// Unlike [`roundtrip`], the parsed value may borrow from `buf`.
+#[track_caller]
+pub fn roundtrip_in<'a, T>(value: &T, buf: &'a mut String)
+where
+	T: FromSequence<'a> + Display + Debug + PartialEq,
+{
+	buf.clear();
+	write!(buf, "{value}").expect("`Display` implementation returned an error");
+	let buf: &'a String = buf;
+	match T::parse_sequence(buf.as_bytes()) {
+		Ok((parsed, len)) => {
+			assert_eq!(&parsed, value, "{buf:?} was parsed as a different value");
+			assert_eq!(len, buf.len(), "{buf:?} wasn't parsed completely");
+		}
+		Err(e) => panic!("failed to parse {buf:?}, displayed by {value:?}: {e}"),
+	}
+}
+
+/// Assert [`roundtrip`] for every value in `values`.
+#[track_caller]
+pub fn roundtrip_all<T>(values: impl IntoIterator<Item = T>)
+where
+	T: for<'b> FromSequence<'b> + Display + Debug + PartialEq,
+{
+	let mut buf = String::new();
+	for value in values {
+		roundtrip_in(&value, &mut buf);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		Csi, Sgr, SgrColor, SingleCharacter, SingleGraphicCharacter, Terminator,
+	};
+
+	#[test]
+	fn roundtrips_sequences() {
+		roundtrip_all([Terminator::St, Terminator::Bel]);
+		roundtrip_all([Sgr::Reset, Sgr::WeightBoldOn, Sgr::UnderlineDouble]);
+		roundtrip_all([
+			Sgr::Foreground(SgrColor::Table(200)),
+			Sgr::Background(SgrColor::Rgb(1, 2, 3)),
+			Sgr::UnderlineColor(SgrColor::Table(4)),
+		]);
+		roundtrip(&SingleGraphicCharacter('é'));
+		roundtrip(&SingleCharacter::new('A').unwrap());
+
+		let mut buf = String::new();
+		for bytes in [&b"\x1b[?25h"[..], b"\x1b[1;2 q", b"\x1b[>c"] {
+			let (csi, _) = Csi::parse(bytes).unwrap();
+			roundtrip_in(&csi, &mut buf);
+		}
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn writes_parsed_fe_sequences() {
+		use crate::FeSeq;
+
+		for bytes in [&b"\x1b]0;title\x1b\\"[..], b"\x1bD", b"\x1b[3A"] {
+			let (seq, len) = FeSeq::parse_sequence(bytes).unwrap();
+			assert_eq!(len, bytes.len());
+			let mut written = alloc::vec::Vec::new();
+			seq.write_to(&mut written).unwrap();
+			assert_eq!(written, bytes);
+		}
+	}
+
+	#[cfg(feature = "helpers")]
+	#[test]
+	fn roundtrips_helpers() {
+		use crate::*;
+		use core::num::{
+			NonZeroU8, NonZeroU16,
+		};
+
+		roundtrip_all([
+			EraseDisplay::CurToEnd, EraseDisplay::CurToBegin, EraseDisplay::All,
+			EraseDisplay::XtermAllNoScrollback,
+		]);
+		roundtrip_all([
+			EraseLine::CurToEnd, EraseLine::CurToBegin, EraseLine::All,
+		]);
+		roundtrip_all([Protection::Unprotected, Protection::Protected]);
+		roundtrip_all([
+			SelectiveEraseDisplay::CurToEnd, SelectiveEraseDisplay::CurToBegin,
+			SelectiveEraseDisplay::All,
+		]);
+		roundtrip_all([
+			SelectiveEraseLine::CurToEnd, SelectiveEraseLine::CurToBegin,
+			SelectiveEraseLine::All,
+		]);
+		roundtrip(&ScreenAlignment);
+		roundtrip_all([
+			LineSize::DoubleHeightTop, LineSize::DoubleHeightBottom,
+			LineSize::SingleWidth, LineSize::DoubleWidth,
+		]);
+		roundtrip(&Bell);
+		for volume in [BellVolume::Off, BellVolume::Low, BellVolume::High] {
+			roundtrip(&WarningBellVolume(volume));
+			roundtrip(&MarginBellVolume(volume));
+		}
+		let rep = Profile::new().with_repeat(true);
+		roundtrip_all([
+			Repeated::new('\0', 0),
+			Repeated::new('-', 1),
+			Repeated::new('-', 3),
+			Repeated::new('─', 80).with_profile(&rep),
+			Repeated::new('=', 100).with_profile(&rep),
+		]);
+		roundtrip_all([
+			CursorShape::Default, CursorShape::BlinkingBlock,
+			CursorShape::SteadyBlock, CursorShape::BlinkingUnderline,
+			CursorShape::SteadyUnderline, CursorShape::BlinkingBar,
+			CursorShape::SteadyBar,
+		]);
+		roundtrip_all([CursorBlink::On, CursorBlink::Off]);
+		roundtrip_all([AlternateScroll::On, AlternateScroll::Off]);
+		roundtrip_all([ColorSchemeUpdates::On, ColorSchemeUpdates::Off]);
+		let encodings = [
+			MouseEncoding::Sgr, MouseEncoding::SgrPixels, MouseEncoding::Urxvt,
+		];
+		for encoding in encodings {
+			roundtrip(&MouseEncodingChange::Enable(encoding));
+			roundtrip(&MouseEncodingChange::Disable(encoding));
+		}
+		roundtrip_all([Weight::Bold, Weight::Thin, Weight::Regular]);
+		roundtrip_all([Underline::Single, Underline::Double, Underline::None]);
+		roundtrip_all([Italic::On, Italic::Off]);
+		roundtrip_all([Strikethrough::On, Strikethrough::Off]);
+		roundtrip_all([Invert::On, Invert::Off]);
+
+		let one = NonZeroU8::MIN;
+		let far = NonZeroU8::new(200).unwrap();
+		roundtrip_all([
+			CursorMove::Rows(-128), CursorMove::Rows(-1), CursorMove::Rows(127),
+			CursorMove::Columns(-5), CursorMove::Columns(1),
+			CursorMove::To { row: one, column: far },
+			CursorMove::Column(NonZeroU16::MAX),
+		]);
+		roundtrip_all([
+			Movement::Relative { rows: None, columns: None },
+			Movement::Relative { rows: Some(-3), columns: None },
+			Movement::Relative { rows: None, columns: Some(4) },
+			Movement::Relative { rows: Some(2), columns: Some(-7) },
+			Movement::Absolute { row: far, column: one },
+		]);
+
+		let style = Style::new()
+			.with_weight(Weight::Bold)
+			.with_underline(Underline::Double)
+			.with_foreground(Color::Rgb(10, 20, 30))
+			.with_background(Color::Table(100));
+		roundtrip_all([Style::new(), style]);
+		roundtrip_all([
+			StateChange::new(),
+			style.to_state_change(),
+			Style::new().diff_to(&style),
+		]);
+
+		let mut buf = String::new();
+		for terminator in [Terminator::St, Terminator::Bel] {
+			let notify = Notify {
+				title: Some("Build"),
+				body: "done; 0 errors",
+				style: NotifyStyle::Osc777,
+				terminator,
+			};
+			roundtrip_in(&notify, &mut buf);
+			let notify = Notify {
+				title: None,
+				style: NotifyStyle::Osc9,
+				..notify
+			};
+			roundtrip_in(&notify, &mut buf);
+
+			let dir = WorkingDirectory {
+				terminator,
+				..WorkingDirectory::new("host", "/home/user/src")
+			};
+			roundtrip_in(&dir, &mut buf);
+			let link = LinkedPath {
+				terminator,
+				..LinkedPath::new("", "/tmp/report.txt")
+			};
+			roundtrip_in(&link, &mut buf);
+		}
+	}
+
+	#[cfg(feature = "helpers")]
+	#[test]
+	fn parses_common_forms() {
+		use crate::*;
+		use core::num::NonZeroU8;
+
+		let parse = |bytes: &[u8]| CursorMove::parse_sequence(bytes).unwrap().0;
+		assert_eq!(parse(b"\x1b[A"), CursorMove::Rows(-1));
+		assert_eq!(parse(b"\x1b[0B"), CursorMove::Rows(1));
+		assert_eq!(parse(b"\x1b[C"), CursorMove::Columns(1));
+		assert_eq!(parse(b"\x1b[D"), CursorMove::Columns(-1));
+		assert_eq!(parse(b"\x1b[H"), CursorMove::To {
+			row: NonZeroU8::MIN,
+			column: NonZeroU8::MIN,
+		});
+		assert_eq!(
+			Movement::parse_sequence(b"\x1b[A\x1b[C").unwrap(),
+			(Movement::Relative { rows: Some(-1), columns: Some(1) }, 6),
+		);
+		assert_eq!(Sgr::parse_sequence(b"\x1b[m").unwrap().0, Sgr::Reset);
+		assert_eq!(
+			EraseDisplay::parse_sequence(b"\x1b[J").unwrap().0,
+			EraseDisplay::CurToEnd,
+		);
+		assert_eq!(
+			EraseLine::parse_sequence(b"\x1b[K").unwrap().0,
+			EraseLine::CurToEnd,
+		);
+		assert_eq!(
+			Repeated::parse_sequence(b"-\x1b[b").unwrap(),
+			(Repeated { ch: '-', count: 2, use_rep: true }, 4),
+		);
+		assert_eq!(
+			Terminator::parse_sequence(b"\x1b"),
+			Err(ParseError::Incomplete),
+		);
+	}
+}
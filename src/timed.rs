@@ -0,0 +1,207 @@
+use crate::event::{
+	Event, Parser
+};
+
+use std::time::{
+	Duration, Instant,
+};
+
+/// [`Parser`] that delivers a lone escape character as the Escape key if no
+/// more bytes arrive within a timeout.
+/// 
+/// Terminals send the Escape key as the same byte that starts escape
+/// sequences, so the only way to tell them apart is that the bytes of a
+/// sequence arrive together. After feeding bytes with [`Self::advance`], wait
+/// for input until [`Self::deadline`] (for example, with `poll`), and call
+/// [`Self::expire`] if none arrives.
+#[derive(Debug, Clone)]
+pub struct TimedParser {
+	parser: Parser,
+	timeout: Duration,
+	deadline: Option<Instant>,
+}
+
+impl Default for TimedParser {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new(Self::DEFAULT_TIMEOUT)
+	}
+}
+
+impl TimedParser {
+	/// Timeout that is long enough for sequences sent over slow connections,
+	/// and short enough that the Escape key doesn't feel delayed.
+	pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(50);
+
+	/// Create a parser that waits for `timeout` after an incomplete sequence.
+	#[inline(always)]
+	pub const fn new(timeout: Duration) -> Self {
+		Self {
+			parser: Parser::new(),
+			timeout,
+			deadline: None,
+		}
+	}
+
+	/// Return the time to wait after an incomplete sequence.
+	#[inline(always)]
+	pub const fn timeout(&self) -> Duration {
+		self.timeout
+	}
+
+	/// Set the time to wait after an incomplete sequence.
+	#[inline(always)]
+	pub fn set_timeout(&mut self, timeout: Duration) {
+		self.timeout = timeout;
+	}
+
+	/// Return a mutable reference to the inner [`Parser`].
+	#[inline(always)]
+	pub fn parser_mut(&mut self) -> &mut Parser {
+		&mut self.parser
+	}
+
+	/// Return the time at which the buffered incomplete sequence is
+	/// delivered, if there is one.
+	#[inline(always)]
+	pub const fn deadline(&self) -> Option<Instant> {
+		self.deadline
+	}
+
+	/// Return how long to wait for more input from `now` before calling
+	/// [`Self::expire`], or [`None`] to wait indefinitely.
+	#[inline(always)]
+	pub fn remaining(&self, now: Instant) -> Option<Duration> {
+		self.deadline.map(move |deadline| deadline.saturating_duration_since(now))
+	}
+
+	/// Feed `bytes` that were received at `now` to the parser, calling `f`
+	/// with every complete event.
+	pub fn advance(&mut self, bytes: &[u8], now: Instant, f: impl FnMut(Event<'_>)) {
+		self.parser.advance(bytes, f);
+		// A timeout too long to be an `Instant` never expires.
+		self.deadline = self.parser.has_pending()
+			.then(|| now.checked_add(self.timeout))
+			.flatten();
+	}
+
+	/// Deliver the buffered incomplete sequence if the deadline has passed at
+	/// `now`, calling `f` with every event.
+	/// 
+	/// Returns `true` if the sequence was delivered.
+	pub fn expire(&mut self, now: Instant, f: impl FnMut(Event<'_>)) -> bool {
+		if self.deadline.is_none_or(move |deadline| now < deadline) {
+			return false
+		}
+		self.parser.flush(f);
+		self.deadline = None;
+		true
+	}
+
+	/// Read once from `input`, calling `f` with every event.
+	/// 
+	/// If an incomplete sequence is buffered, the escape key is delivered
+	/// when no input arrives before the deadline, and then this keeps waiting
+	/// for input. Returns the number of bytes that were read, which is `0` at
+	/// the end of the input.
+	#[cfg(unix)]
+	pub fn read_from(
+		&mut self,
+		input: &mut (impl std::io::Read + std::os::fd::AsFd),
+		mut f: impl FnMut(Event<'_>),
+	) -> std::io::Result<usize> {
+		if let Some(timeout) = self.remaining(Instant::now()) {
+			if !poll_readable(input.as_fd(), timeout)? {
+				self.parser.flush(&mut f);
+				self.deadline = None;
+			}
+		}
+		let mut buf = [0; Parser::CAPACITY];
+		let len = input.read(&mut buf)?;
+		self.advance(&buf[..len], Instant::now(), f);
+		Ok(len)
+	}
+
+	/// Read once from `input` unless `deadline` passes first, calling `f`
+	/// with every event.
+	/// 
+	/// This is like [`Self::read_from`], but stops waiting at `deadline`, such
+	/// as the [`FrameLimiter::deadline`](crate::FrameLimiter::deadline) of the
+	/// next frame, so that input and drawing can share one loop. Returns the
+	/// number of bytes that were read, or [`None`] if the deadline passed.
+	#[cfg(unix)]
+	pub fn read_until(
+		&mut self,
+		input: &mut (impl std::io::Read + std::os::fd::AsFd),
+		deadline: Option<Instant>,
+		mut f: impl FnMut(Event<'_>),
+	) -> std::io::Result<Option<usize>> {
+		loop {
+			let now = Instant::now();
+			let wake = match (self.deadline, deadline) {
+				(Some(a), Some(b)) => Some(a.min(b)),
+				(a, b) => a.or(b),
+			};
+			if let Some(wake) = wake {
+				if !poll_readable(input.as_fd(), wake.saturating_duration_since(now))? {
+					let now = Instant::now();
+					self.expire(now, &mut f);
+					if deadline.is_some_and(move |deadline| now >= deadline) {
+						return Ok(None)
+					}
+					continue
+				}
+			}
+			let mut buf = [0; Parser::CAPACITY];
+			let len = input.read(&mut buf)?;
+			self.advance(&buf[..len], Instant::now(), f);
+			return Ok(Some(len))
+		}
+	}
+}
+
+/// Wait until `fd` can be read from without blocking, or `timeout` passes.
+/// 
+/// Returns `false` if the timeout passed.
+#[cfg(unix)]
+pub(crate) fn poll_readable(fd: std::os::fd::BorrowedFd<'_>, timeout: Duration) -> std::io::Result<bool> {
+	use core::ffi::{
+		c_int, c_short,
+	};
+	use std::os::fd::AsRawFd;
+
+	#[repr(C)]
+	struct PollFd {
+		fd: c_int,
+		events: c_short,
+		revents: c_short,
+	}
+	#[cfg(any(target_os = "linux", target_os = "android"))]
+	type NFds = core::ffi::c_ulong;
+	#[cfg(not(any(target_os = "linux", target_os = "android")))]
+	type NFds = core::ffi::c_uint;
+	extern "C" {
+		fn poll(fds: *mut PollFd, count: NFds, timeout: c_int) -> c_int;
+	}
+	const POLLIN: c_short = 0x1;
+
+	let mut poll_fd = PollFd {
+		fd: fd.as_raw_fd(),
+		events: POLLIN,
+		revents: 0,
+	};
+	// Round up, so that the deadline has passed when this returns.
+	let millis = timeout.as_nanos().div_ceil(1_000_000).min(c_int::MAX as u128) as c_int;
+	loop {
+		// SAFETY: `poll_fd` is valid for one element.
+		match unsafe { poll(&mut poll_fd, 1, millis) } {
+			-1 => {
+				let e = std::io::Error::last_os_error();
+				if e.kind() != std::io::ErrorKind::Interrupted {
+					return Err(e)
+				}
+			}
+			ready => return Ok(ready > 0),
+		}
+	}
+}
@@ -0,0 +1,121 @@
+use core::fmt::{
+	Display, Formatter, Result as FmtResult, Write,
+};
+
+/// Way in which [`Visible`] shows control characters.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ControlNotation {
+	/// Caret notation, such as `^C` for ETX and `^?` for DEL.
+	#[default]
+	Caret,
+	/// Symbols from the Unicode Control Pictures block, such as `␃` for ETX
+	/// and `␡` for DEL.
+	Pictures,
+}
+
+/// Adapter that displays arbitrary data without letting the terminal
+/// interpret any of it, such as text read from files or sockets.
+/// 
+/// Control characters, including the escape character, are shown with
+/// [`ControlNotation`]. C1 control characters are shown like
+/// `cat -v` shows them, such as `M-^[` for CSI. Bytes that aren't valid UTF-8
+/// are shown as [`char::REPLACEMENT_CHARACTER`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Visible<'a> {
+	pub bytes: &'a [u8],
+	pub notation: ControlNotation,
+	/// Whether tabs and line feeds are written as they are.
+	pub keep_whitespace: bool,
+}
+
+impl<'a> Visible<'a> {
+	/// Create a [`Visible`] for `bytes` that uses caret notation for every
+	/// control character.
+	#[inline(always)]
+	pub const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			bytes,
+			notation: ControlNotation::Caret,
+			keep_whitespace: false,
+		}
+	}
+
+	/// Return `self` with control characters shown with `notation`.
+	#[inline(always)]
+	pub const fn with_notation(mut self, notation: ControlNotation) -> Self {
+		self.notation = notation;
+		self
+	}
+
+	/// Return `self` with tabs and line feeds written as they are, or shown
+	/// like other control characters.
+	#[inline(always)]
+	pub const fn with_keep_whitespace(mut self, keep_whitespace: bool) -> Self {
+		self.keep_whitespace = keep_whitespace;
+		self
+	}
+
+	/// Write the control character `c` with the notation of `self`.
+	fn write_control(&self, f: &mut Formatter<'_>, c: char) -> FmtResult {
+		let code = c as u32;
+		match (code, self.notation) {
+			(0x80..=0x9f, ..) => {
+				f.write_str("M-^")?;
+				f.write_char(caret_char(code - 0x80))
+			}
+			(.., ControlNotation::Caret) => {
+				f.write_char('^')?;
+				f.write_char(caret_char(code))
+			}
+			(0x7f, ControlNotation::Pictures) => f.write_char('\u{2421}'),
+			(.., ControlNotation::Pictures) => {
+				let picture = char::from_u32(0x2400 + code);
+				f.write_char(picture.unwrap_or(char::REPLACEMENT_CHARACTER))
+			}
+		}
+	}
+}
+
+impl<'a> From<&'a [u8]> for Visible<'a> {
+	#[inline(always)]
+	fn from(value: &'a [u8]) -> Self {
+		Self::new(value)
+	}
+}
+
+impl<'a> From<&'a str> for Visible<'a> {
+	#[inline(always)]
+	fn from(value: &'a str) -> Self {
+		Self::new(value.as_bytes())
+	}
+}
+
+impl Display for Visible<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		for chunk in self.bytes.utf8_chunks() {
+			let text = chunk.valid();
+			let mut start = 0;
+			for (idx, c) in text.char_indices() {
+				let is_kept = self.keep_whitespace && matches!(c, '\t' | '\n');
+				if !c.is_control() || is_kept {
+					continue
+				}
+				f.write_str(&text[start..idx])?;
+				self.write_control(f, c)?;
+				start = idx + c.len_utf8();
+			}
+			f.write_str(&text[start..])?;
+			if !chunk.invalid().is_empty() {
+				f.write_char(char::REPLACEMENT_CHARACTER)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Return the character after the caret for the C0 control character or DEL
+/// with the code `code`.
+#[inline(always)]
+const fn caret_char(code: u32) -> char {
+	(code as u8 ^ 0x40) as char
+}
@@ -0,0 +1,305 @@
+use crate::scan::Token;
+
+/// Rules for the number of cells that characters occupy, which differ between
+/// terminals and locales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidthPolicy {
+	/// Whether characters of ambiguous East Asian width occupy two cells, as
+	/// they do on terminals that are set up for legacy CJK locales.
+	pub ambiguous_wide: bool,
+	/// Whether emoji sequences occupy two cells, as they do on terminals that
+	/// render them as one emoji.
+	/// 
+	/// If this is `false`, then sequences joined with a zero width joiner are
+	/// measured as their separate emoji, and emoji presentation selectors
+	/// don't make a character wide.
+	pub emoji_wide: bool,
+}
+
+impl Default for WidthPolicy {
+	#[inline(always)]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl WidthPolicy {
+	/// Create a [`WidthPolicy`] with narrow ambiguous characters and wide
+	/// emoji sequences.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			ambiguous_wide: false,
+			emoji_wide: true,
+		}
+	}
+
+	/// Return the number of cells that `c` occupies on a terminal.
+	/// 
+	/// Without the `unicode-width` feature, every character other than a
+	/// control character is assumed to occupy one cell.
+	#[inline(always)]
+	pub fn char_width(&self, c: char) -> usize {
+		#[cfg(feature = "unicode-width")]
+		{
+			use unicode_width::UnicodeWidthChar;
+			let width = if self.ambiguous_wide { c.width_cjk() } else { c.width() };
+			width.unwrap_or(0)
+		}
+		#[cfg(not(feature = "unicode-width"))]
+		{
+			if c.is_control() { 0 } else { 1 }
+		}
+	}
+
+	/// Return the number of cells that the grapheme cluster `g` occupies.
+	/// 
+	/// The width of a cluster is the width of its first character, except that
+	/// clusters with an emoji presentation selector are wide if
+	/// [`Self::emoji_wide`] is `true`.
+	pub fn grapheme_width(&self, g: &str) -> usize {
+		let Some(first) = g.chars().next() else {
+			return 0
+		};
+		if !self.emoji_wide && g.contains('\u{200d}') {
+			return g.chars().map(move |c| self.char_width(c)).sum()
+		}
+		match self.char_width(first) {
+			1 if self.emoji_wide && g.contains('\u{fe0f}') => 2,
+			0 => g.chars().map(move |c| self.char_width(c)).max().unwrap_or(0),
+			width => width,
+		}
+	}
+
+	/// Return the number of cells that the text `s` occupies.
+	/// 
+	/// Escape sequences aren't recognized. See [`Self::visible_width`].
+	#[inline(always)]
+	pub fn str_width(&self, s: &str) -> usize {
+		graphemes(s).map(move |g| self.grapheme_width(g)).sum()
+	}
+
+	/// Return the number of cells that the text in `bytes` occupies, ignoring
+	/// escape sequences and control characters.
+	pub fn visible_width(&self, bytes: &[u8]) -> usize {
+		let mut width = 0;
+		let mut rest = bytes;
+		while let Ok((token, len)) = Token::parse(rest) {
+			if let Token::Text(text) = token {
+				width += self.str_width(text);
+			}
+			rest = &rest[len..];
+		}
+		width
+	}
+
+	/// Return the longest start of `s` that occupies at most `width` cells,
+	/// ignoring escape sequences.
+	/// 
+	/// `s` is only cut between grapheme clusters. Escape sequences after the
+	/// cut are dropped, so a style that is set in the kept part may need to be
+	/// reset.
+	pub fn truncate<'a>(&self, s: &'a str, width: usize) -> &'a str {
+		let bytes = s.as_bytes();
+		let mut used = 0;
+		let mut idx = 0;
+		while let Ok((token, len)) = Token::parse(&bytes[idx..]) {
+			if let Token::Text(text) = token {
+				for g in graphemes(text) {
+					used += self.grapheme_width(g);
+					if used > width {
+						return &s[..idx]
+					}
+					idx += g.len();
+				}
+			} else {
+				idx += len;
+			}
+		}
+		s
+	}
+
+	/// Return the longest end of `s` that occupies at most `width` cells,
+	/// ignoring escape sequences.
+	/// 
+	/// `s` is only cut between grapheme clusters. Escape sequences before the
+	/// cut are dropped, so a style that is set in the dropped part may need to
+	/// be set again.
+	pub fn truncate_start<'a>(&self, s: &'a str, width: usize) -> &'a str {
+		let Some(mut excess) = self.visible_width(s.as_bytes()).checked_sub(width)
+		else {
+			return s
+		};
+		let bytes = s.as_bytes();
+		let mut idx = 0;
+		while excess > 0 {
+			let Ok((token, len)) = Token::parse(&bytes[idx..]) else {
+				break
+			};
+			if let Token::Text(text) = token {
+				for g in graphemes(text) {
+					excess = excess.saturating_sub(self.grapheme_width(g));
+					idx += g.len();
+					if excess == 0 {
+						break
+					}
+				}
+			} else {
+				idx += len;
+			}
+		}
+		&s[idx..]
+	}
+
+	/// Return an iterator over the lines of `s` wrapped to at most `width`
+	/// cells, ignoring escape sequences.
+	/// 
+	/// Lines are broken at spaces where possible, and between grapheme
+	/// clusters otherwise. Newlines in `s` always end a line.
+	#[inline(always)]
+	pub const fn wrap<'a>(&self, s: &'a str, width: usize) -> Wrap<'a> {
+		Wrap {
+			rest: s,
+			width,
+			policy: *self,
+		}
+	}
+}
+
+/// Return the number of cells that `c` occupies on a terminal, with the
+/// default [`WidthPolicy`].
+#[inline(always)]
+pub fn char_width(c: char) -> usize {
+	WidthPolicy::new().char_width(c)
+}
+
+/// Return an iterator over the grapheme clusters of `s`, which are the units
+/// of text that occupy cells on a terminal.
+/// 
+/// Without the `unicode-width` feature, every character is its own cluster.
+#[inline(always)]
+pub fn graphemes(s: &str) -> impl Iterator<Item = &str> + '_ {
+	#[cfg(feature = "unicode-width")]
+	{
+		unicode_segmentation::UnicodeSegmentation::graphemes(s, true)
+	}
+	#[cfg(not(feature = "unicode-width"))]
+	{
+		s.char_indices().map(move |(idx, c)| &s[idx..idx + c.len_utf8()])
+	}
+}
+
+/// Return the number of cells that the grapheme cluster `g` occupies, with
+/// the default [`WidthPolicy`].
+#[inline(always)]
+pub fn grapheme_width(g: &str) -> usize {
+	WidthPolicy::new().grapheme_width(g)
+}
+
+/// Return the number of cells that the text `s` occupies, with the default
+/// [`WidthPolicy`].
+/// 
+/// Escape sequences aren't recognized. See [`visible_width`].
+#[inline(always)]
+pub fn str_width(s: &str) -> usize {
+	WidthPolicy::new().str_width(s)
+}
+
+/// Return the number of cells that the text in `bytes` occupies, ignoring
+/// escape sequences and control characters, with the default
+/// [`WidthPolicy`].
+#[inline(always)]
+pub fn visible_width(bytes: &[u8]) -> usize {
+	WidthPolicy::new().visible_width(bytes)
+}
+
+/// Return the longest start of `s` that occupies at most `width` cells with
+/// the default [`WidthPolicy`], ignoring escape sequences.
+/// 
+/// See [`WidthPolicy::truncate`].
+#[inline(always)]
+pub fn truncate(s: &str, width: usize) -> &str {
+	WidthPolicy::new().truncate(s, width)
+}
+
+/// Return the longest end of `s` that occupies at most `width` cells with the
+/// default [`WidthPolicy`], ignoring escape sequences.
+/// 
+/// See [`WidthPolicy::truncate_start`].
+#[inline(always)]
+pub fn truncate_start(s: &str, width: usize) -> &str {
+	WidthPolicy::new().truncate_start(s, width)
+}
+
+/// Return an iterator over the lines of `s` wrapped to at most `width` cells
+/// with the default [`WidthPolicy`], ignoring escape sequences.
+/// 
+/// See [`WidthPolicy::wrap`].
+#[inline(always)]
+pub const fn wrap(s: &str, width: usize) -> Wrap<'_> {
+	WidthPolicy::new().wrap(s, width)
+}
+
+/// Iterator over wrapped lines, returned by [`WidthPolicy::wrap`].
+#[derive(Debug, Clone)]
+pub struct Wrap<'a> {
+	rest: &'a str,
+	width: usize,
+	policy: WidthPolicy,
+}
+
+impl<'a> Wrap<'a> {
+	/// Return `s[..end]` as a line, continuing at `next`.
+	fn split(&mut self, end: usize, next: usize) -> &'a str {
+		let s = self.rest;
+		self.rest = &s[next..];
+		&s[..end]
+	}
+}
+
+impl<'a> Iterator for Wrap<'a> {
+	type Item = &'a str;
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.rest.is_empty() {
+			return None
+		}
+
+		let bytes = self.rest.as_bytes();
+		let mut used = 0;
+		let mut idx = 0;
+		// End of the line and start of the next one if broken at a space.
+		let mut last_space = None;
+		while let Ok((token, len)) = Token::parse(&bytes[idx..]) {
+			match token {
+				Token::Control(b'\n') => return Some(self.split(idx, idx + 1)),
+				Token::Text(text) => for g in graphemes(text) {
+					let width = self.policy.grapheme_width(g);
+					if g == " " {
+						if used + width > self.width {
+							let next = idx + count_spaces(&bytes[idx..]);
+							return Some(self.split(idx, next))
+						}
+						let next = idx + count_spaces(&bytes[idx..]);
+						// Break before the first space of a run.
+						if last_space.is_none_or(move |(_, n)| n != next) {
+							last_space = Some((idx, next));
+						}
+					} else if used + width > self.width && used > 0 {
+						let (end, next) = last_space.unwrap_or((idx, idx));
+						return Some(self.split(end, next))
+					}
+					used += width;
+					idx += g.len();
+				},
+				_ => idx += len,
+			}
+		}
+		Some(self.split(bytes.len(), bytes.len()))
+	}
+}
+
+/// Return the number of spaces at the start of `bytes`.
+#[inline(always)]
+fn count_spaces(bytes: &[u8]) -> usize {
+	bytes.iter().take_while(move |&&b| b == b' ').count()
+}